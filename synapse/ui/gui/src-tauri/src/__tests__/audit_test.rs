@@ -0,0 +1,442 @@
+//! Tests for Audit Log Aggregation
+//!
+//! Verifies grouping and windowing over seeded audit entries
+
+#[cfg(test)]
+mod tests {
+    use crate::audit::*;
+    use crate::commands::AuditLogEntry;
+    use chrono::{Duration, Utc};
+
+    fn entry(action: &str, user: &str, result: &str, resource: Option<&str>, age_hours: i64) -> AuditLogEntry {
+        AuditLogEntry {
+            id: format!("audit-{}-{}", action, age_hours),
+            timestamp: Utc::now() - Duration::hours(age_hours),
+            action: action.to_string(),
+            user_id: user.to_string(),
+            result: result.to_string(),
+            resource: resource.map(str::to_string),
+            details: None,
+            prev_hash: String::new(),
+            entry_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_summarize_groups_within_window() {
+        let entries = vec![
+            entry(ACTION_SKILL_EXECUTE, "alice", "success", Some("read_file"), 1),
+            entry(ACTION_SKILL_EXECUTE, "alice", "success", Some("read_file"), 2),
+            entry(ACTION_SKILL_EXECUTE, "bob", "failure", Some("web_search"), 3),
+            entry("config_update", "bob", "success", None, 4),
+            entry(ACTION_SKILL_EXECUTE, "carol", "success", Some("write_file"), 48),
+        ];
+
+        let until = Utc::now();
+        let summary = summarize(&entries, until - Duration::hours(24), until);
+
+        assert_eq!(summary.total_entries, 4);
+        assert_eq!(summary.by_action[ACTION_SKILL_EXECUTE], 3);
+        assert_eq!(summary.by_user["bob"], 2);
+        assert!(!summary.by_user.contains_key("carol"));
+        assert_eq!(summary.success_count, 3);
+        assert_eq!(summary.failure_count, 1);
+        assert_eq!(summary.success_ratio, Some(0.75));
+        assert_eq!(summary.top_skills[0], RankedCount { name: "read_file".to_string(), count: 2 });
+        assert_eq!(summary.top_users.len(), 2);
+    }
+
+    #[test]
+    fn test_summarize_empty_window() {
+        let until = Utc::now();
+        let summary = summarize(&[], until - Duration::hours(1), until);
+
+        assert_eq!(summary.total_entries, 0);
+        assert!(summary.success_ratio.is_none());
+        assert!(summary.top_users.is_empty());
+    }
+
+    #[test]
+    fn test_burst_coalesces_but_approval_is_written() {
+        let mut log = AuditLog::new(5);
+        let start = Utc::now();
+
+        for i in 0..52 {
+            let mut execution = entry(ACTION_SKILL_EXECUTE, "agent", "success", Some("read_file"), 0);
+            execution.id = String::new();
+            execution.timestamp = start + Duration::milliseconds(i * 200);
+            log.record(execution);
+        }
+        assert!(log.status().active);
+
+        let mut approval = entry("skill_approve", "admin", "success", Some("web_search"), 0);
+        approval.id = String::new();
+        approval.timestamp = start + Duration::seconds(11);
+        log.record(approval);
+        for (i, action) in ["session_start", "session_end"].into_iter().enumerate() {
+            let mut session = entry(action, "admin", "success", None, 0);
+            session.id = String::new();
+            session.timestamp = start + Duration::seconds(12 + i as i64);
+            log.record(session);
+        }
+
+        let entries = log.entries();
+        assert_eq!(entries.len(), 9);
+        assert_eq!(entries[5].action, ACTION_SKILL_EXECUTE);
+        assert_eq!(entries[5].details.as_deref(), Some("skill_execute x47 in 9s"));
+        assert_eq!(entries[6].action, "skill_approve");
+        assert_eq!(entries[7].action, "session_start");
+        assert_eq!(entries[8].action, "session_end");
+    }
+
+    fn recorded(action: &str, user: &str, age_hours: i64) -> AuditLogEntry {
+        let mut recorded = entry(action, user, "success", None, age_hours);
+        recorded.id = String::new();
+        recorded
+    }
+
+    #[test]
+    fn test_file_backed_log_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE);
+
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        log.record(recorded("config_update", "admin", 1));
+        log.open(&path).unwrap();
+        log.record(recorded("skill_approve", "admin", 0));
+        assert_eq!(read_log_file(&path).unwrap().len(), 2);
+
+        let mut reopened = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        reopened.open(&path).unwrap();
+        reopened.record(recorded("skill_reject", "bob", 0));
+        let ids: Vec<&str> = reopened.entries().iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["audit-000001", "audit-000002", "audit-000003"]);
+    }
+
+    #[test]
+    fn test_summary_groups_persisted_log_by_action_result_user_and_hour() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE);
+        let start = chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 3, 1, 9, 0, 0).unwrap();
+        let at = |minutes: i64, action: &str, user: &str, result: &str| AuditLogEntry {
+            id: String::new(),
+            timestamp: start + Duration::minutes(minutes),
+            ..entry(action, user, result, None, 0)
+        };
+
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        log.open(&path).unwrap();
+        log.record(at(5, "skill_approve", "admin", "success"));
+        log.record(at(70, "skill_reject", "admin", "success"));
+        log.record(at(75, "login", "bob", "failure"));
+        log.record(at(110, "login", "bob", "success"));
+        log.record(at(200, "login", "carol", "denied"));
+        log.record(at(24 * 60, "login", "dave", "success"));
+
+        // Summaries read what was persisted, not a live sample
+        let mut reopened = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        reopened.open(&path).unwrap();
+        let summary = summarize(&reopened.history().unwrap(), start, start + Duration::hours(12));
+
+        assert_eq!(summary.total_entries, 5);
+        assert_eq!(summary.by_action["login"], 3);
+        assert_eq!(summary.by_action["skill_approve"], 1);
+        assert_eq!(summary.by_result["success"], 3);
+        assert_eq!(summary.by_result["failure"], 1);
+        assert_eq!(summary.by_result["denied"], 1);
+        assert_eq!(summary.by_user["admin"], 2);
+        assert!(!summary.by_user.contains_key("dave"));
+        assert_eq!(summary.busiest_hour, Some(HourlyCount { hour: start + Duration::hours(1), count: 3 }));
+
+        let empty = summarize(&reopened.history().unwrap(), start - Duration::days(2), start - Duration::days(1));
+        assert_eq!(empty.total_entries, 0);
+        assert_eq!((empty.by_result["success"], empty.by_result["failure"]), (0, 0));
+        assert!(empty.by_action.is_empty());
+        assert!(empty.busiest_hour.is_none());
+    }
+
+    fn hashes(entries: &[AuditLogEntry]) -> Vec<String> {
+        entries.iter().map(|e| e.entry_hash.clone()).collect()
+    }
+
+    /// File-backed log with entries 40, 20, 5 and 0 days old
+    fn aged_log(path: &std::path::Path) -> AuditLog {
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        log.open(path).unwrap();
+        for (action, days) in [("config_update", 40), ("skill_approve", 20), ("skill_reject", 5), ("config_update", 0)] {
+            log.record(recorded(action, "admin", days * 24));
+        }
+        log
+    }
+
+    #[test]
+    fn test_rotation_archives_old_entries_and_keeps_recent() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE);
+        let mut log = aged_log(&path);
+        let before = log.history().unwrap();
+
+        let policy = RetentionPolicy { max_age_days: Some(30), max_file_bytes: None };
+        let report = log.rotate(&policy, Utc::now()).unwrap();
+        assert_eq!((report.archived_entries, report.kept_entries), (1, 3));
+        let segment = std::path::PathBuf::from(report.segment.unwrap());
+        assert_eq!(archived_segments(&path), vec![segment.clone()]);
+        assert_eq!(hashes(&read_log_file(&segment).unwrap()), hashes(&before[..1]));
+        assert_eq!(hashes(&read_log_file(&path).unwrap()), hashes(&before[1..]));
+
+        // The active log alone starts mid-chain; with its archive it verifies
+        assert!(!verify_chain(log.entries()).valid);
+        let history = log.history().unwrap();
+        assert_eq!(hashes(&history), hashes(&before));
+        assert!(verify_chain(&history).valid);
+
+        // Archived entries stay queryable
+        let query = AuditQuery { action: Some("config_update".to_string()), ..Default::default() };
+        assert_eq!(crate::audit::query(&history, &query).len(), 2);
+
+        let again = log.rotate(&policy, Utc::now()).unwrap();
+        assert_eq!(again.archived_entries, 0);
+        assert!(again.segment.is_none());
+    }
+
+    #[test]
+    fn test_size_rotation_keeps_newest_entries_within_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE);
+        let mut log = aged_log(&path);
+        let raw = std::fs::read_to_string(&path).unwrap();
+        let newest_two: u64 = raw.lines().skip(2).map(|line| line.len() as u64 + 1).sum();
+
+        let policy = RetentionPolicy { max_age_days: None, max_file_bytes: Some(newest_two) };
+        let report = log.rotate(&policy, Utc::now()).unwrap();
+        assert_eq!((report.archived_entries, report.kept_entries), (2, 2));
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), newest_two);
+        assert_eq!(log.entries()[0].action, "skill_reject");
+        assert!(verify_chain(&log.history().unwrap()).valid);
+    }
+
+    #[test]
+    fn test_chain_continues_after_reopening_rotated_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE);
+        let mut log = aged_log(&path);
+        let everything = RetentionPolicy { max_age_days: None, max_file_bytes: Some(1) };
+        assert_eq!(log.rotate(&everything, Utc::now()).unwrap().kept_entries, 0);
+        assert!(read_log_file(&path).unwrap().is_empty());
+
+        let mut reopened = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        reopened.open(&path).unwrap();
+        reopened.record(recorded("skill_approve", "bob", 0));
+        let history = reopened.history().unwrap();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[4].id, "audit-000005");
+        assert!(verify_chain(&history).valid);
+    }
+
+    #[test]
+    fn test_in_memory_log_cannot_rotate() {
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        log.record(recorded("config_update", "admin", 24 * 90));
+        let policy = RetentionPolicy { max_age_days: Some(30), max_file_bytes: None };
+        assert!(log.rotate(&policy, Utc::now()).unwrap_err().contains("in memory only"));
+        assert_eq!(log.entries().len(), 1);
+    }
+
+    #[test]
+    fn test_query_by_date_range_and_action() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(AUDIT_LOG_FILE);
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        log.open(&path).unwrap();
+        for (action, user, age_hours) in [
+            ("skill_approve", "alice", 72),
+            ("skill_approve", "bob", 30),
+            ("config_update", "alice", 20),
+            ("skill_approve", "alice", 10),
+            ("skill_reject", "alice", 5),
+            ("skill_approve", "carol", 1),
+        ] {
+            log.record(recorded(action, user, age_hours));
+        }
+        let entries = read_log_file(&path).unwrap();
+
+        let now = Utc::now();
+        let approvals = query(
+            &entries,
+            &AuditQuery {
+                since: Some(now - Duration::hours(48)),
+                until: Some(now - Duration::hours(2)),
+                action: Some("skill_approve".to_string()),
+                ..Default::default()
+            },
+        );
+        let users: Vec<&str> = approvals.iter().map(|e| e.user_id.as_str()).collect();
+        assert_eq!(users, vec!["alice", "bob"]);
+
+        let latest_by_alice = query(
+            &entries,
+            &AuditQuery {
+                user_id: Some("alice".to_string()),
+                limit: Some(2),
+                ..Default::default()
+            },
+        );
+        let actions: Vec<&str> = latest_by_alice.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["skill_reject", "skill_approve"]);
+    }
+
+    fn chained_log() -> Vec<AuditLogEntry> {
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        for (action, user, age_hours) in [
+            ("config_update", "admin", 4),
+            ("skill_approve", "admin", 3),
+            ("capability_grant", "bob", 2),
+            ("skill_reject", "carol", 1),
+        ] {
+            log.record(recorded(action, user, age_hours));
+        }
+        log.entries().to_vec()
+    }
+
+    #[test]
+    fn test_valid_chain_verifies() {
+        let entries = chained_log();
+        assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+        assert_eq!(entries[1].prev_hash, entries[0].entry_hash);
+
+        let verification = verify_chain(&entries);
+        assert!(verification.valid);
+        assert_eq!(verification.entries_checked, 4);
+        assert!(verification.broken_at.is_none());
+    }
+
+    #[test]
+    fn test_edited_entry_breaks_chain_at_its_index() {
+        let mut entries = chained_log();
+        entries[2].user_id = "mallory".to_string();
+        let verification = verify_chain(&entries);
+        assert!(!verification.valid);
+        assert_eq!(verification.broken_at, Some(2));
+        assert!(verification.reason.unwrap().contains("modified"));
+
+        // Recomputing the edited entry's hash moves the break to the next link
+        entries[2].entry_hash = compute_entry_hash(&entries[2]);
+        assert_eq!(verify_chain(&entries).broken_at, Some(3));
+    }
+
+    #[test]
+    fn test_inserted_and_reordered_entries_break_chain() {
+        let mut inserted = chained_log();
+        let mut forged = recorded("capability_grant", "mallory", 2);
+        forged.prev_hash = inserted[0].entry_hash.clone();
+        forged.entry_hash = compute_entry_hash(&forged);
+        inserted.insert(1, forged);
+        assert_eq!(verify_chain(&inserted).broken_at, Some(2));
+
+        let mut reordered = chained_log();
+        reordered.swap(1, 2);
+        assert_eq!(verify_chain(&reordered).broken_at, Some(1));
+    }
+
+    /// Minimal RFC 4180 reader for checking exports
+    fn parse_csv(raw: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = raw.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') => quoted = true,
+                (false, ',') => row.push(std::mem::take(&mut field)),
+                (false, '\r') => {}
+                (false, '\n') => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (false, c) => field.push(c),
+            }
+        }
+        rows
+    }
+
+    fn export_sample() -> Vec<AuditLogEntry> {
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        let mut tricky = recorded("config_update", "admin", 2);
+        tricky.details = Some("Changed \"mode\", language\nand paths".to_string());
+        log.record(tricky);
+        log.record(recorded("skill_approve", "bob", 1));
+        log.entries().to_vec()
+    }
+
+    #[test]
+    fn test_csv_export_round_trips() {
+        let entries = export_sample();
+        let rows = parse_csv(&export_entries(&entries, "csv").unwrap());
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], CSV_COLUMNS.iter().map(|c| c.to_string()).collect::<Vec<_>>());
+        assert_eq!(rows[1][6], "Changed \"mode\", language\nand paths");
+        assert_eq!(rows[2][2], "skill_approve");
+        assert_eq!(rows[2][8], entries[1].entry_hash);
+        assert!(rows.iter().all(|row| row.len() == CSV_COLUMNS.len()));
+    }
+
+    #[test]
+    fn test_json_export_round_trips_and_unknown_format_fails() {
+        let entries = export_sample();
+        let parsed: Vec<AuditLogEntry> = serde_json::from_str(&export_entries(&entries, "json").unwrap()).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].details, entries[0].details);
+        assert!(verify_chain(&parsed).valid);
+
+        assert!(export_entries(&entries, "xml").unwrap_err().contains("Unknown export format 'xml'"));
+    }
+
+    fn stream_filter(action: &str, user: &str) -> AuditQuery {
+        AuditQuery {
+            action: Some(action.to_string()),
+            user_id: Some(user.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_receives_only_matching_entries() {
+        let mut receiver = entry_sink().subscribe();
+        let filter = stream_filter("stream_match_test", "alice");
+
+        let mut log = AuditLog::new(DEFAULT_MAX_ENTRIES_PER_MINUTE);
+        log.record(recorded("stream_match_test", "bob", 0));
+        log.record(recorded("stream_other_test", "alice", 0));
+        log.record(recorded("stream_match_test", "alice", 0));
+
+        let streamed = tokio::time::timeout(std::time::Duration::from_secs(1), next_matching(&mut receiver, &filter))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((streamed.action.as_str(), streamed.user_id.as_str()), ("stream_match_test", "alice"));
+        // Streamed entries are the written ones, id and hash included
+        assert_eq!(streamed.id, log.entries()[2].id);
+        assert_eq!(streamed.entry_hash, log.entries()[2].entry_hash);
+    }
+
+    #[tokio::test]
+    async fn test_shared_record_reaches_subscribers() {
+        let mut receiver = entry_sink().subscribe();
+        let filter = stream_filter("stream_shared_test", "carol");
+        record("stream_shared_test", "carol", "success", Some("skill-001"), None);
+
+        let streamed = tokio::time::timeout(std::time::Duration::from_secs(1), next_matching(&mut receiver, &filter))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(streamed.resource.as_deref(), Some("skill-001"));
+    }
+}
@@ -5,11 +5,12 @@
 #[cfg(test)]
 mod tests {
     use crate::commands::*;
+    use crate::state::AppState;
     use crate::{PROTOCOL_VERSION, SPEC_VERSION};
 
     #[tokio::test]
     async fn test_get_config_returns_protocol_version() {
-        let result = get_config().await.unwrap();
+        let result = get_config(None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert_eq!(result.base.spec_version, SPEC_VERSION);
         assert!(result.success);
@@ -17,20 +18,41 @@ mod tests {
 
     #[tokio::test]
     async fn test_save_config_returns_protocol_version() {
+        let dir = tempfile::tempdir().unwrap();
         let config = SynapseConfig {
             language: "en".to_string(),
-            mode: "supervised".to_string(),
+            mode: "offline".to_string(),
             llm_providers: vec![],
-            data_paths: std::collections::HashMap::new(),
+            routing_mode: "priority".to_string(),
+            data_paths: std::collections::HashMap::from([
+                ("config".to_string(), dir.path().to_string_lossy().to_string()),
+            ]),
             security_settings: SecuritySettings {
                 require_approval_for_risk: 3,
                 isolation_policy: "container".to_string(),
                 audit_enabled: true,
                 trusted_users: vec![],
+                rate_limit_per_minute: 60,
+                session_timeout_minutes: 30,
+                idempotency_window_minutes: 60,
+                skill_runs_per_hour: None,
+                skill_run_quotas: std::collections::HashMap::new(),
+                user_roles: std::collections::HashMap::new(),
+                token_signing_key: None,
+                trusted_signing_keys: vec![],
+                webhook_urls: vec![],
+                audit_webhook_failures: false,
+                audit_retention_days: None,
+                audit_max_file_bytes: None,
+            audit_max_entries_per_minute: None,
             },
+            token_prices: vec![],
+            model_capabilities: vec![],
+            log_level: "info".to_string(),
+            allow_local_providers: false,
         };
         
-        let result = save_config(config).await.unwrap();
+        let result = save_config_in(dir.path(), config, None, false, None, None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
     }
@@ -42,6 +64,7 @@ mod tests {
             "test-key".to_string(),
             Some("https://api.openai.com/v1".to_string()),
             "gpt-4o".to_string(),
+            Some(1),
         ).await.unwrap();
         
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
@@ -50,51 +73,127 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_skills_returns_protocol_version() {
-        let result = get_skills().await.unwrap();
+        let result = get_skills(None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
         
         // Verify skills data structure
         if let Some(data) = &result.data {
-            let skills: Vec<SkillInfo> = serde_json::from_value(data.clone()).unwrap();
-            assert!(!skills.is_empty());
+            let page: Page<SkillInfo> = serde_json::from_value(data.clone()).unwrap();
+            assert!(!page.items.is_empty());
+            assert_eq!(page.total, page.items.len() as u64);
+            assert!(!page.has_more);
         }
     }
 
     #[tokio::test]
     async fn test_get_skill_details_returns_protocol_version() {
-        let result = get_skill_details("skill-001".to_string()).await.unwrap();
+        let result = get_skill_details_in(crate::state::app_state(), "skill-001".to_string()).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
+        let skill = skill_registry().into_iter().find(|s| s.id == "skill-001").unwrap();
+        let details = result.data.unwrap();
+        assert_eq!(details["name"], skill.name);
+        assert_eq!(details["isolation_type"], skill.isolation_type);
+        assert_eq!(details["required_capabilities"], serde_json::json!(skill.required_capabilities));
+
+        let missing = get_skill_details_in(crate::state::app_state(), "skill-404".to_string()).await.unwrap();
+        assert!(matches!(missing.error, Some(SynapseError::NotFound(_))));
     }
 
     #[tokio::test]
     async fn test_approve_skill_returns_protocol_version() {
-        let result = approve_skill(
+        let result = approve_skill_in(
+            crate::state::app_state(),
             "skill-001".to_string(),
             "test-user".to_string(),
+            None,
+            None,
+            None,
+            None,
         ).await.unwrap();
         
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
-        assert!(result.success);
+        assert_eq!(result.error, Some(SynapseError::Unauthorized("No session; sign in first".to_string())));
     }
 
     #[tokio::test]
     async fn test_reject_skill_returns_protocol_version() {
-        let result = reject_skill(
+        let result = reject_skill_in(
+            crate::state::app_state(),
             "skill-001".to_string(),
             "Test reason".to_string(),
+            None,
+            None,
+            None,
         ).await.unwrap();
         
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
-        assert!(result.success);
+        assert_eq!(result.error, Some(SynapseError::Unauthorized("No session; sign in first".to_string())));
     }
 
     #[tokio::test]
     async fn test_archive_skill_returns_protocol_version() {
-        let result = archive_skill("skill-001".to_string()).await.unwrap();
+        let state = AppState::default();
+        let denied = archive_skill_in(&state, "skill-001".to_string(), None, None, None).await.unwrap();
+        assert!(matches!(denied.error, Some(SynapseError::Unauthorized(_))));
+
+        let result = archive_skill_as(&state, "test-user", "skill-001", None);
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
+        assert_eq!(skill_registry_in(&state).into_iter().find(|s| s.id == "skill-001").unwrap().status, "archived");
+        assert_ne!(registry_skill("skill-001").status, "archived");
+        
+        let denied = unarchive_skill_in(&state, "skill-001".to_string(), None, None).await.unwrap();
+        assert!(matches!(denied.error, Some(SynapseError::Unauthorized(_))));
+
+        let result = unarchive_skill_as(&state, "test-user", "skill-001");
+        assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(result.data.unwrap()["status"], "active");
+    }
+
+    #[tokio::test]
+    async fn test_archive_skill_replays_response_for_repeated_idempotency_key() {
+        let state = AppState::default();
+        let key = Some(uuid::Uuid::new_v4().to_string());
+        let first = archive_skill_as(&state, "test-user", "skill-003", key.as_deref());
+        assert!(first.success);
+
+        // Archiving twice would fail, so a success with the original
+        // request id shows the retry wasn't applied again
+        let retry = archive_skill_as(&state, "test-user", "skill-003", key.as_deref());
+        assert!(retry.success);
+        assert_eq!(retry.base.request_id, first.base.request_id);
+        assert_eq!(retry.data, first.data);
+
+        // Another caller presenting the key doesn't get the cached response
+        let stolen = archive_skill_as(&state, "mallory", "skill-003", key.as_deref());
+        assert!(matches!(stolen.error, Some(SynapseError::Conflict(_))));
+
+        let reused = archive_skill_as(&state, "test-user", "skill-002", key.as_deref());
+        assert!(matches!(reused.error, Some(SynapseError::Conflict(_))));
+
+        // A failed request doesn't hold its key
+        let missing_key = Some(uuid::Uuid::new_v4().to_string());
+        let missing = archive_skill_as(&state, "test-user", "skill-404", missing_key.as_deref());
+        assert!(matches!(missing.error, Some(SynapseError::NotFound(_))));
+        let missing = archive_skill_as(&state, "test-user", "skill-404", missing_key.as_deref());
+        assert!(matches!(missing.error, Some(SynapseError::NotFound(_))));
+
+        assert!(unarchive_skill_as(&state, "test-user", "skill-003").success);
+    }
+
+    #[tokio::test]
+    async fn test_get_llm_usage_by_day_validates_dates() {
+        let state = AppState::default();
+        let reversed = get_llm_usage_by_day_in(&state, "2026-03-05".to_string(), "2026-03-04".to_string()).await.unwrap();
+        assert!(matches!(reversed.error, Some(SynapseError::Validation(message)) if message.contains("before start")));
+
+        let malformed = get_llm_usage_by_day_in(&state, "March 1".to_string(), "2026-03-04".to_string()).await.unwrap();
+        assert!(matches!(malformed.error, Some(SynapseError::Validation(message)) if message.contains("YYYY-MM-DD")));
+
+        let week = get_llm_usage_by_day_in(&state, "2026-03-01".to_string(), "2026-03-07".to_string()).await.unwrap();
+        assert_eq!(week.data.unwrap().as_array().unwrap().len(), 7);
     }
 
     #[tokio::test]
@@ -104,32 +203,67 @@ mod tests {
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_metrics_snapshot_bundles_every_section() {
+        let state = AppState::default();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("support").join("metrics.json");
+        let result = export_metrics_snapshot_in(&state, Some(path.to_string_lossy().to_string()), None, None).await.unwrap();
+        assert!(result.success);
+
+        let data = result.data.unwrap();
+        assert!(data["path"].as_str().unwrap().ends_with("metrics.json"));
+        let snapshot = &data["snapshot"];
+        for section in ["system", "llm_usage", "skills", "history"] {
+            assert!(!snapshot[section].is_null(), "missing {}", section);
+        }
+        assert!(snapshot["hostname"].is_null());
+        assert_eq!(snapshot["protocol_version"], PROTOCOL_VERSION);
+
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["captured_at"], snapshot["captured_at"]);
+
+        let unknown = Some(vec!["incident-../../secrets".to_string()]);
+        let result = export_metrics_snapshot_in(&state, None, None, unknown).await.unwrap();
+        assert!(matches!(result.error, Some(SynapseError::NotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_get_llm_usage_returns_protocol_version() {
-        let result = get_llm_usage().await.unwrap();
+        let result = get_llm_usage_in(crate::state::app_state(), None, None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
     }
 
     #[tokio::test]
     async fn test_get_skill_metrics_returns_protocol_version() {
-        let result = get_skill_metrics(None).await.unwrap();
+        let result = get_skill_metrics_in(crate::state::app_state(), None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
     }
 
     #[tokio::test]
     async fn test_get_capabilities_returns_protocol_version() {
-        let result = get_capabilities(None).await.unwrap();
+        let result = get_capabilities(None, None, None, None, None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
     }
 
     #[tokio::test]
     async fn test_get_audit_log_returns_protocol_version() {
-        let result = get_audit_log(None, None, None).await.unwrap();
+        let result = get_audit_log(None, None, None, None, None, None).await.unwrap();
+        assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_get_audit_summary_returns_protocol_version() {
+        let result = get_audit_summary(None, None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
         assert!(result.success);
+
+        let inverted = get_audit_summary(Some("2026-03-02T00:00:00Z".to_string()), Some("2026-03-01T00:00:00Z".to_string()));
+        assert!(matches!(inverted.await.unwrap().error, Some(SynapseError::Validation(_))));
     }
 
     #[tokio::test]
@@ -146,11 +280,53 @@ mod tests {
             isolation_policy: "container".to_string(),
             audit_enabled: true,
             trusted_users: vec![],
+            rate_limit_per_minute: 60,
+            session_timeout_minutes: 30,
+            idempotency_window_minutes: 60,
+            skill_runs_per_hour: None,
+            skill_run_quotas: std::collections::HashMap::new(),
+            user_roles: std::collections::HashMap::new(),
+            token_signing_key: None,
+            trusted_signing_keys: vec![],
+            webhook_urls: vec![],
+            audit_webhook_failures: false,
+            audit_retention_days: None,
+            audit_max_file_bytes: None,
+            audit_max_entries_per_minute: None,
         };
         
-        let result = update_security_settings(settings).await.unwrap();
+        let result = update_security_settings(settings, None, None, None).await.unwrap();
         assert_eq!(result.base.protocol_version, PROTOCOL_VERSION);
-        assert!(result.success);
+        assert_eq!(result.error, Some(SynapseError::Unauthorized("No session; sign in first".to_string())));
+    }
+
+    #[test]
+    fn test_apply_security_settings_persists_to_the_active_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = crate::state::app_state();
+        let mut settings = SynapseConfig::default().security_settings;
+        settings.require_approval_for_risk = 4;
+        settings.audit_enabled = false;
+        settings.audit_max_entries_per_minute = Some(1000);
+        let saved = apply_security_settings(dir.path(), settings.clone(), false, "admin").unwrap();
+        assert_eq!(saved, settings);
+        assert_eq!(state.config_in(dir.path()).unwrap().security_settings, settings);
+        assert_eq!(state.audit_log().lock().unwrap().status().max_entries_per_minute, 1000);
+
+        // Refused changes leave the saved settings alone
+        let mut downgrade = settings.clone();
+        downgrade.isolation_policy = "subprocess".to_string();
+        let refused = apply_security_settings(dir.path(), downgrade, false, "admin");
+        assert!(matches!(refused, Err(SynapseError::Validation(_))));
+        let mut invalid = settings.clone();
+        invalid.session_timeout_minutes = 0;
+        let refused = apply_security_settings(dir.path(), invalid, false, "admin");
+        assert!(matches!(refused, Err(SynapseError::Validation(message)) if message.contains("session_timeout_minutes")));
+        let mut invalid = settings.clone();
+        invalid.audit_max_entries_per_minute = Some(0);
+        let refused = apply_security_settings(dir.path(), invalid, false, "admin");
+        assert!(matches!(refused, Err(SynapseError::Validation(message)) if message.contains("audit_max_entries_per_minute")));
+        assert_eq!(state.config_in(dir.path()).unwrap().security_settings, settings);
     }
 
     #[test]
@@ -162,4 +338,1253 @@ mod tests {
     fn test_spec_version_constant() {
         assert_eq!(SPEC_VERSION, "3.1");
     }
+
+    #[test]
+    fn test_plan_orders_dependencies_and_flags_blockers() {
+        let registry = skill_registry();
+        let config = SynapseConfig::default();
+        let granted = vec!["fs:read".to_string(), "fs:write".to_string()];
+
+        let plan = plan_execution("skill-002", &serde_json::Value::Null, "admin", &granted, None, &registry, &config).unwrap();
+        let order: Vec<&str> = plan.steps.iter().map(|s| s.skill_id.as_str()).collect();
+        assert_eq!(order, vec!["skill-001", "skill-002"]);
+        assert!(!plan.will_fail);
+
+        let plan = plan_execution("skill-002", &serde_json::Value::Null, "nobody", &[], None, &registry, &config).unwrap();
+        assert!(plan.will_fail);
+        assert_eq!(plan.steps[0].blockers, vec!["Missing capability 'fs:read'"]);
+    }
+
+    #[tokio::test]
+    async fn test_save_then_load_config_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        let mut config = SynapseConfig::default();
+        config.language = "ru".to_string();
+        // Offline, so saving doesn't check models against a live catalog
+        config.mode = "offline".to_string();
+        // Profiles go where they're loaded from, not to the config's own path
+        config.data_paths.insert("config".to_string(), elsewhere.path().to_string_lossy().to_string());
+
+        let saved = save_config_in(dir.path(), config.clone(), None, false, None, None).await.unwrap();
+        assert!(saved.success, "{:?}", saved.error);
+        let path = std::path::PathBuf::from(saved.data.unwrap()["path"].as_str().unwrap());
+        assert!(path.is_absolute());
+        assert_eq!(path, std::fs::canonicalize(profile_path(dir.path(), DEFAULT_PROFILE).unwrap()).unwrap());
+        assert!(!elsewhere.path().join("profiles").exists());
+
+        let state = crate::state::app_state();
+        assert_eq!(state.config_in(dir.path()).unwrap(), config);
+
+        // Cloned providers are saved to the active profile
+        update_active_profile(dir.path(), |c| clone_provider_config(c, "OpenAI GPT-4", "OpenAI backup")).unwrap();
+        let loaded = state.config_in(dir.path()).unwrap();
+        assert_eq!(loaded.llm_providers.len(), 2);
+        assert_eq!(loaded.llm_providers[1].name, "OpenAI backup");
+
+        // Reads served from the shared state pick up each save
+        for level in ["debug", "warn"] {
+            config.log_level = level.to_string();
+            assert!(save_config_in(dir.path(), config.clone(), None, false, None, None).await.unwrap().success);
+            assert_eq!(state.config_in(dir.path()).unwrap().log_level, level);
+        }
+
+        // Security settings in force only change with an admin's session
+        config.security_settings.user_roles.insert("mallory".to_string(), "admin".to_string());
+        let denied = save_config_in(dir.path(), config.clone(), None, false, None, None).await.unwrap();
+        assert!(matches!(denied.error, Some(SynapseError::Unauthorized(_))));
+        assert!(state.config_in(dir.path()).unwrap().security_settings.user_roles.is_empty());
+        config.security_settings.user_roles.clear();
+        config.security_settings.isolation_policy = "none".to_string();
+        assert!(!save_config_in(dir.path(), config.clone(), None, true, None, None).await.unwrap().success);
+        assert_eq!(state.config_in(dir.path()).unwrap().security_settings.isolation_policy, "container");
+    }
+
+    #[test]
+    fn test_security_settings_changed_keeps_missing_signing_key() {
+        let mut current = SynapseConfig::default().security_settings;
+        current.token_signing_key = Some("00ff".to_string());
+        let mut incoming = current.clone();
+        incoming.token_signing_key = None;
+        assert!(!security_settings_changed(&current, &incoming));
+
+        incoming.trusted_users = vec!["mallory".to_string()];
+        assert!(security_settings_changed(&current, &incoming));
+        incoming.trusted_users.clear();
+        incoming.token_signing_key = Some("ff00".to_string());
+        assert!(security_settings_changed(&current, &incoming));
+    }
+
+    #[test]
+    fn test_offline_mode_refuses_network_features() {
+        let mut config = SynapseConfig::default();
+        let remote = config.llm_providers[0].clone();
+        let mut ollama = remote.clone();
+        ollama.provider_type = "ollama".to_string();
+        ollama.base_url = None;
+
+        assert!(!config.is_offline());
+        assert!(config.require_network("Webhook delivery").is_ok());
+        assert!(config.require_provider_network("Testing an LLM connection", &remote).is_ok());
+
+        config.mode = "offline".to_string();
+        match config.require_network("Importing a skill from a URL") {
+            Err(SynapseError::Offline(message)) => {
+                assert_eq!(message, "Importing a skill from a URL is disabled in offline mode")
+            }
+            other => panic!("expected an offline error, got {:?}", other),
+        }
+        assert!(config.require_provider_network("Testing an LLM connection", &ollama).is_err());
+
+        // Local providers may opt back in; remote ones stay refused
+        config.allow_local_providers = true;
+        assert!(config.require_provider_network("Testing an LLM connection", &ollama).is_ok());
+        ollama.base_url = Some("http://[::1]:11434".to_string());
+        assert!(config.require_provider_network("Testing an LLM connection", &ollama).is_ok());
+        let refused = config.require_provider_network("Testing an LLM connection", &remote);
+        assert!(matches!(refused, Err(SynapseError::Offline(_))));
+        assert!(config.require_network("Webhook delivery").is_err());
+    }
+
+    #[test]
+    fn test_update_active_profile_leaves_unreadable_profile_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = profile_path(dir.path(), DEFAULT_PROFILE).unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let clone_backup = |config: &mut SynapseConfig| clone_provider_config(config, "OpenAI GPT-4", "Backup");
+        let result = update_active_profile(dir.path(), clone_backup);
+        assert!(matches!(result, Err(SynapseError::Internal(message)) if message.contains("was not changed")));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{ not json");
+
+        std::fs::remove_file(&path).unwrap();
+        let (clone, saved) = update_active_profile(dir.path(), clone_backup).unwrap();
+        assert_eq!(clone.name, "Backup");
+        assert_eq!(load_profile(dir.path(), DEFAULT_PROFILE).unwrap(), Some(saved));
+    }
+
+    #[test]
+    fn test_model_requirements_checked_against_every_weighted_provider() {
+        let mut skill = skill_registry().into_iter().find(|s| s.id == "skill-001").unwrap();
+        skill.min_model_capabilities.requires_tools = true;
+        let mut config = SynapseConfig {
+            routing_mode: "weighted".to_string(),
+            ..SynapseConfig::default()
+        };
+        let mut local = config.llm_providers[0].clone();
+        (local.name, local.provider_type, local.model) = ("Weighted llama".into(), "ollama".into(), "llama3".into());
+        (local.priority, local.weight) = (2, Some(10));
+        config.llm_providers[0].name = "Weighted gpt".to_string();
+        config.llm_providers[0].weight = Some(90);
+        config.llm_providers.push(local);
+
+        // The tools-capable provider takes most traffic, but the skill could
+        // still land on the one without tools
+        let error = check_skill_model_requirements(&skill, &config).unwrap_err();
+        assert!(error.contains("Weighted llama") && error.contains("tool use"));
+
+        config.llm_providers[1].weight = Some(0);
+        assert!(check_skill_model_requirements(&skill, &config).is_ok());
+    }
+
+    #[test]
+    fn test_clone_provider_copies_settings_without_the_key() {
+        let mut config = SynapseConfig::default();
+        config.llm_providers[0].api_key = Some("sk-live-abcdef".to_string());
+        config.llm_providers[0].weight = Some(30);
+
+        let clone = clone_provider_config(&mut config, "OpenAI GPT-4", " OpenAI mini ").unwrap();
+        assert_eq!(clone.name, "OpenAI mini");
+        assert_eq!((clone.priority, clone.is_active, clone.api_key.as_deref()), (2, false, None));
+        assert_eq!((clone.model.as_str(), clone.weight), ("gpt-4o", Some(30)));
+        assert_eq!(config.llm_providers.len(), 2);
+        assert_eq!(config.llm_providers[0].api_key.as_deref(), Some("sk-live-abcdef"));
+        assert!(validate_config(&config).is_empty());
+    }
+
+    #[test]
+    fn test_clone_provider_rejects_missing_source_and_taken_name() {
+        let mut config = SynapseConfig::default();
+        let missing = clone_provider_config(&mut config, "Nope", "Copy").unwrap_err();
+        assert_eq!(missing, SynapseError::NotFound("Provider 'Nope' not found".to_string()));
+
+        let taken = clone_provider_config(&mut config, "OpenAI GPT-4", "OpenAI GPT-4").unwrap_err();
+        assert!(matches!(taken, SynapseError::Conflict(_)));
+        assert_eq!(config.llm_providers.len(), 1);
+    }
+
+    fn invalid_fields(config: &SynapseConfig) -> Vec<String> {
+        validate_config(config).into_iter().map(|issue| issue.field).collect()
+    }
+
+    #[test]
+    fn test_validate_config_accepts_defaults() {
+        assert!(validate_config(&SynapseConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_config_requires_providers_unless_offline() {
+        let mut config = SynapseConfig::default();
+        config.llm_providers.clear();
+        assert_eq!(invalid_fields(&config), vec!["llm_providers"]);
+
+        config.mode = "offline".to_string();
+        assert!(validate_config(&config).is_empty());
+    }
+
+    #[test]
+    fn test_validate_config_rejects_duplicate_priorities() {
+        let mut config = SynapseConfig::default();
+        let mut second = config.llm_providers[0].clone();
+        second.name = "Backup".to_string();
+        config.llm_providers.push(second);
+        assert_eq!(invalid_fields(&config), vec!["llm_providers[1].priority"]);
+    }
+
+    #[test]
+    fn test_validate_config_requires_base_url_for_custom_provider() {
+        let mut config = SynapseConfig::default();
+        config.llm_providers[0].provider_type = "custom_openai".to_string();
+        config.llm_providers[0].base_url = None;
+        assert_eq!(invalid_fields(&config), vec!["llm_providers[0].base_url"]);
+
+        config.llm_providers[0].base_url = Some("http://localhost:8000/v1".to_string());
+        assert!(validate_config(&config).is_empty());
+    }
+
+    #[test]
+    fn test_validate_config_rejects_non_http_webhooks() {
+        let mut config = SynapseConfig::default();
+        config.security_settings.webhook_urls =
+            vec!["https://hooks.slack.com/services/T0/B0/x".to_string(), "ftp://example.com/hook".to_string(), "not a url".to_string()];
+        assert_eq!(
+            invalid_fields(&config),
+            vec!["security_settings.webhook_urls[1]", "security_settings.webhook_urls[2]"]
+        );
+    }
+
+    #[test]
+    fn test_skill_quota_limit_prefers_per_skill_quota() {
+        let mut config = SynapseConfig::default();
+        assert_eq!(skill_quota_limit(&config.security_settings, "skill-001"), None);
+
+        config.security_settings.skill_runs_per_hour = Some(20);
+        config.security_settings.skill_run_quotas.insert("skill-003".to_string(), 5);
+        assert_eq!(skill_quota_limit(&config.security_settings, "skill-001"), Some(20));
+        assert_eq!(skill_quota_limit(&config.security_settings, "skill-003"), Some(5));
+
+        config.security_settings.skill_run_quotas.insert("skill-002".to_string(), 0);
+        assert_eq!(invalid_fields(&config), vec!["security_settings.skill_run_quotas.skill-002"]);
+    }
+
+    #[test]
+    fn test_validate_config_rejects_risk_out_of_range() {
+        let mut config = SynapseConfig::default();
+        config.security_settings.require_approval_for_risk = 6;
+        assert_eq!(invalid_fields(&config), vec!["security_settings.require_approval_for_risk"]);
+    }
+
+    #[test]
+    fn test_validate_config_rejects_unknown_isolation_policy() {
+        let mut config = SynapseConfig::default();
+        config.security_settings.isolation_policy = "vm".to_string();
+        assert_eq!(invalid_fields(&config), vec!["security_settings.isolation_policy"]);
+    }
+
+    #[test]
+    fn test_validate_config_rejects_unsupported_language() {
+        let mut config = SynapseConfig::default();
+        config.language = "xx".to_string();
+        assert_eq!(invalid_fields(&config), vec!["language"]);
+    }
+
+    #[tokio::test]
+    async fn test_save_config_returns_field_errors() {
+        let mut config = SynapseConfig::default();
+        config.language = "xx".to_string();
+
+        let result = save_config(config, None, None, None, None).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.data.unwrap()["errors"][0]["field"], "language");
+    }
+
+    fn fake_env(name: &str) -> Option<String> {
+        match name {
+            "HOME" | "USERPROFILE" => Some("/home/alice".to_string()),
+            "SYNAPSE_DATA" => Some("/srv/synapse".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_path_template_tilde() {
+        assert_eq!(expand_path_template("~/.synapse/skills", &fake_env).unwrap(), "/home/alice/.synapse/skills");
+        assert_eq!(expand_path_template("~", &fake_env).unwrap(), "/home/alice");
+        assert_eq!(expand_path_template("/tmp/~x", &fake_env).unwrap(), "/tmp/~x");
+        assert!(expand_path_template("~/x", &|_: &str| None).unwrap_err().contains("is not set"));
+    }
+
+    #[test]
+    fn test_expand_path_template_defined_var() {
+        assert_eq!(expand_path_template("$SYNAPSE_DATA/memory", &fake_env).unwrap(), "/srv/synapse/memory");
+        assert_eq!(expand_path_template("${SYNAPSE_DATA}_old", &fake_env).unwrap(), "/srv/synapse_old");
+    }
+
+    #[test]
+    fn test_expand_path_template_undefined_var() {
+        let error = expand_path_template("$NOPE/data", &fake_env).unwrap_err();
+        assert!(error.contains("'NOPE'"));
+    }
+
+    #[test]
+    fn test_profiles_switch_without_deleting_others() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut work = SynapseConfig::default();
+        work.mode = "supervised".to_string();
+        let mut home = SynapseConfig::default();
+        home.mode = "autonomous".to_string();
+
+        write_config_file(&profile_path(dir.path(), "work").unwrap(), &work).unwrap();
+        write_config_file(&profile_path(dir.path(), "home").unwrap(), &home).unwrap();
+        assert_eq!(saved_profiles(dir.path()), vec!["home", "work"]);
+        assert_eq!(active_profile(dir.path()), DEFAULT_PROFILE);
+
+        set_active_profile(dir.path(), "work").unwrap();
+        let active = active_profile(dir.path());
+        assert_eq!(load_profile(dir.path(), &active).unwrap().unwrap(), work);
+
+        set_active_profile(dir.path(), "home").unwrap();
+        let active = active_profile(dir.path());
+        assert_eq!(load_profile(dir.path(), &active).unwrap().unwrap(), home);
+        assert_eq!(saved_profiles(dir.path()).len(), 2);
+
+        assert!(set_active_profile(dir.path(), "missing").is_err());
+        assert!(profile_path(dir.path(), "../escape").is_err());
+    }
+
+    #[test]
+    fn test_reset_backs_up_and_restore_brings_config_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut custom = SynapseConfig::default();
+        custom.language = "ru".to_string();
+        write_config_file(&profile_path(dir.path(), DEFAULT_PROFILE).unwrap(), &custom).unwrap();
+
+        let backup = reset_config(dir.path()).unwrap().unwrap();
+        assert!(backup.starts_with(dir.path().join("backups")));
+        assert_eq!(load_profile(dir.path(), DEFAULT_PROFILE).unwrap().unwrap(), SynapseConfig::default());
+        assert_eq!(config_backups(dir.path()), vec![backup.clone()]);
+
+        restore_backup(dir.path(), &backup).unwrap();
+        assert_eq!(load_profile(dir.path(), DEFAULT_PROFILE).unwrap().unwrap(), custom);
+    }
+
+    #[test]
+    fn test_restore_rejects_files_outside_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("backups")).unwrap();
+        let stray = dir.path().join("stray.json");
+        std::fs::write(&stray, serde_json::to_string(&SynapseConfig::default()).unwrap()).unwrap();
+        assert!(restore_backup(dir.path(), &stray).is_err());
+
+        let broken = dir.path().join("backups").join("config-broken.json");
+        std::fs::write(&broken, "{not json").unwrap();
+        assert!(restore_backup(dir.path(), &broken).is_err());
+        assert!(load_profile(dir.path(), DEFAULT_PROFILE).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_data_backup_round_trips() {
+        let root = tempfile::tempdir().unwrap();
+        let (config_dir, skills_dir) = (root.path().join("config"), root.path().join("skills"));
+        std::fs::create_dir_all(skills_dir.join("web-search")).unwrap();
+        std::fs::write(skills_dir.join("web-search").join("manifest.json"), "{\"v\": 1}").unwrap();
+        let approved = r#"{"status": "active", "trust_level": "verified"}"#;
+        std::fs::write(skills_dir.join("web-search").join("state.json"), approved).unwrap();
+        write_config_file(&profile_path(&config_dir, DEFAULT_PROFILE).unwrap(), &SynapseConfig::default()).unwrap();
+        std::fs::write(config_dir.join("audit.jsonl"), "{\"action\": \"before\"}\n").unwrap();
+
+        let now = chrono::Utc::now();
+        let backup = backup_data(&config_dir, &skills_dir, now).unwrap();
+        assert!(backup.size_bytes > 0);
+
+        std::fs::remove_dir_all(&skills_dir).unwrap();
+        let changed = SynapseConfig { language: "ru".to_string(), ..SynapseConfig::default() };
+        write_config_file(&profile_path(&config_dir, DEFAULT_PROFILE).unwrap(), &changed).unwrap();
+        std::fs::write(config_dir.join("audit.jsonl"), "{\"action\": \"after\"}\n").unwrap();
+
+        let report = restore_data(std::path::Path::new(&backup.archive_path), &config_dir, &skills_dir, now).unwrap();
+        assert_eq!(report.restored_folders, DATA_BACKUP_FOLDERS);
+        assert_eq!(load_profile(&config_dir, DEFAULT_PROFILE).unwrap().unwrap(), SynapseConfig::default());
+        assert_eq!(std::fs::read_to_string(skills_dir.join("web-search").join("manifest.json")).unwrap(), "{\"v\": 1}");
+        // Restored skills wait for review again, whatever the archive says
+        let state = std::fs::read_to_string(skills_dir.join("web-search").join("state.json")).unwrap();
+        let state: serde_json::Value = serde_json::from_str(&state).unwrap();
+        assert_eq!((state["status"].as_str(), state["trust_level"].as_str()), (Some("pending"), Some("unverified")));
+
+        // The live log keeps its entries; the archived one lands beside it
+        assert!(std::fs::read_to_string(config_dir.join("audit.jsonl")).unwrap().contains("after"));
+        let archived = report.archived_audit_log.unwrap();
+        assert!(std::fs::read_to_string(archived).unwrap().contains("before"));
+        assert_eq!(std::fs::read_dir(config_dir.join("backups")).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_data_restore_refuses_archive_missing_folders() {
+        let root = tempfile::tempdir().unwrap();
+        let archive_path = root.path().join("partial.tar.gz");
+        let file = std::fs::File::create(&archive_path).unwrap();
+        let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(file, flate2::Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive.append_data(&mut header, "config/settings.json", &b"{}"[..]).unwrap();
+        archive.into_inner().unwrap().finish().unwrap();
+
+        let config_dir = root.path().join("config");
+        let err = restore_data(&archive_path, &config_dir, &root.path().join("skills"), chrono::Utc::now()).unwrap_err();
+        assert_eq!(err, "Archive is missing top-level folder(s): skills, audit");
+        assert!(!config_dir.exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_repair_creates_missing_paths_and_reports_read_only() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = tempfile::tempdir().unwrap();
+        let read_only = root.path().join("read-only");
+        std::fs::create_dir(&read_only).unwrap();
+        std::fs::set_permissions(&read_only, std::fs::Permissions::from_mode(0o555)).unwrap();
+        let blocker = root.path().join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+        let dirs = vec![
+            ("config".to_string(), root.path().to_path_buf()),
+            ("memory".to_string(), blocker.join("memory")),
+            ("read_only".to_string(), read_only.clone()),
+            ("skills".to_string(), root.path().join("moved/skills")),
+        ];
+
+        let before: Vec<DataPathStatus> = dirs.iter().map(|(key, path)| inspect_data_path(key, path)).collect();
+        assert_eq!(before[0].status, "ok");
+        assert_eq!(before[1].status, "error");
+        assert_eq!(before[3].status, "missing");
+        assert!(!before[3].exists);
+
+        let (created, after) = repair_data_dirs(&dirs);
+        assert_eq!(created, vec!["skills"]);
+        assert!(root.path().join("moved/skills").is_dir());
+        assert_eq!(after[3].status, "ok");
+        assert!(!blocker.join("memory").exists());
+
+        // Root ignores permission bits, so only check when the mode bites
+        if tempfile::tempfile_in(&read_only).is_err() {
+            assert_eq!(after[2].status, "error");
+            assert!(after[2].exists && after[2].is_dir && !after[2].writable);
+            assert!(after[2].message.as_deref().unwrap().ends_with("is not writable"));
+        }
+        std::fs::set_permissions(&read_only, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_data_path_symlinks_are_resolved() {
+        let root = tempfile::tempdir().unwrap();
+        let target = root.path().join("real");
+        std::fs::create_dir(&target).unwrap();
+        let link = root.path().join("link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let status = inspect_data_path("skills", &link);
+        assert_eq!(status.status, "ok");
+        assert_eq!(status.symlink_target, Some(std::fs::canonicalize(&target).unwrap().to_string_lossy().to_string()));
+
+        let dangling = root.path().join("dangling");
+        std::os::unix::fs::symlink(root.path().join("gone"), &dangling).unwrap();
+        let (created, statuses) = repair_data_dirs(&[("memory".to_string(), dangling.clone())]);
+        assert!(created.is_empty());
+        assert_eq!(statuses[0].status, "error");
+        assert!(statuses[0].message.as_deref().unwrap().contains("symlink to missing"));
+        assert_eq!(inspect_data_path("config", root.path()).symlink_target, None);
+    }
+
+    #[tokio::test]
+    async fn test_bundled_skill_schemas_are_valid() {
+        for id in ["skill-001", "skill-002", "skill-003"] {
+            let result = validate_skill_schema(id.to_string()).await.unwrap();
+            assert_eq!(result.data.unwrap()["valid"], true, "{}", id);
+        }
+        let missing = validate_skill_schema("skill-404".to_string()).await.unwrap();
+        assert!(matches!(missing.error, Some(SynapseError::NotFound(_))));
+    }
+
+    /// Registry skills with distinct, ordered timestamps
+    fn registry_skill(id: &str) -> SkillInfo {
+        skill_registry().into_iter().find(|s| s.id == id).unwrap()
+    }
+
+    #[test]
+    fn test_acquire_warm_sandbox_only_for_keep_warm_skills() {
+        let mut skill = registry_skill("skill-001");
+        skill.isolation_type = "warm-test".to_string();
+        crate::isolation::warm_pool().lock().unwrap().add("warm-test", "sandbox-1".to_string());
+
+        skill.keep_warm = false;
+        assert_eq!(acquire_warm_sandbox(&skill), None);
+        skill.keep_warm = true;
+        assert_eq!(acquire_warm_sandbox(&skill), Some("sandbox-1".to_string()));
+        assert_eq!(acquire_warm_sandbox(&skill), None);
+    }
+
+    #[test]
+    fn test_dry_run_ready_with_valid_inputs() {
+        let granted = vec!["fs:write".to_string()];
+        let inputs = serde_json::json!({"path": "notes.txt", "content": "hi", "append": true});
+        let report = dry_run(&registry_skill("skill-002"), &inputs, &granted);
+        assert!(report.ready, "{:?}", report.blockers);
+        assert!(report.approved && report.enabled);
+        assert!(report.inputs.is_ok());
+        assert_eq!(report.capabilities.effective, vec!["fs:write"]);
+    }
+
+    #[test]
+    fn test_dry_run_reports_missing_and_mistyped_inputs() {
+        let granted = vec!["fs:write".to_string()];
+        let inputs = serde_json::json!({"path": 42, "append": "yes"});
+        let report = dry_run(&registry_skill("skill-002"), &inputs, &granted);
+        assert!(!report.ready);
+        assert_eq!(report.inputs.missing, vec!["content"]);
+        let mistyped: Vec<(&str, &str, &str)> = report
+            .inputs
+            .type_mismatches
+            .iter()
+            .map(|m| (m.field.as_str(), m.expected.as_str(), m.actual.as_str()))
+            .collect();
+        assert_eq!(mistyped, vec![("append", "boolean", "string"), ("path", "string", "integer")]);
+        assert_eq!(
+            report.blockers,
+            vec![
+                "Missing required input 'content'",
+                "Input 'append' should be boolean but is string",
+                "Input 'path' should be string but is integer"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dry_run_checks_approval_and_capabilities() {
+        let mut skill = registry_skill("skill-003");
+        skill.enabled = false;
+        let report = dry_run(&skill, &serde_json::json!({"query": "rust", "max_results": 5}), &[]);
+        assert!(report.inputs.is_ok());
+        assert_eq!(
+            report.blockers,
+            vec!["Skill is not approved (status 'pending')", "Skill is disabled", "Missing capability 'network:http'"]
+        );
+    }
+
+    fn dated_skills() -> Vec<SkillInfo> {
+        let base = chrono::Utc::now();
+        skill_registry()
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut skill)| {
+                skill.created_at = base - chrono::Duration::days(10 - i as i64);
+                skill.last_used = skill.last_used.map(|_| base - chrono::Duration::hours(i as i64));
+                skill
+            })
+            .collect()
+    }
+
+    fn skill_ids(query: &SkillQuery) -> Vec<String> {
+        query_skills(dated_skills(), query).unwrap().items.into_iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn test_query_skills_filters() {
+        let by_status = SkillQuery { status: Some("pending".to_string()), ..Default::default() };
+        assert_eq!(skill_ids(&by_status), vec!["skill-003"]);
+
+        let by_trust = SkillQuery { trust_level: Some("verified".to_string()), ..Default::default() };
+        assert_eq!(skill_ids(&by_trust), vec!["skill-002"]);
+
+        let by_risk = SkillQuery { min_risk: Some(2), max_risk: Some(2), ..Default::default() };
+        assert_eq!(skill_ids(&by_risk), vec!["skill-002"]);
+
+        let by_name = SkillQuery { name_contains: Some("FILE".to_string()), ..Default::default() };
+        assert_eq!(skill_ids(&by_name), vec!["skill-001", "skill-002"]);
+    }
+
+    #[test]
+    fn test_query_skills_by_tags_any_or_all() {
+        let tags = |mode: Option<&str>| SkillQuery {
+            tags: vec!["Filesystem".to_string(), "network".to_string()],
+            tag_mode: mode.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(skill_ids(&tags(None)), vec!["skill-001", "skill-002", "skill-003"]);
+        assert!(skill_ids(&tags(Some("all"))).is_empty());
+
+        let both = SkillQuery {
+            tags: vec!["network".to_string(), "experimental".to_string()],
+            tag_mode: Some("all".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(skill_ids(&both), vec!["skill-003"]);
+
+        let bad_mode = SkillQuery { tag_mode: Some("most".to_string()), ..Default::default() };
+        assert!(query_skills(dated_skills(), &bad_mode).err().unwrap().contains("any, all"));
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_skill_tag() {
+        let state = AppState::default();
+        let denied = add_skill_tag_in(&state, "skill-001".to_string(), "reviewed".to_string(), None, None).await.unwrap();
+        assert!(matches!(denied.error, Some(SynapseError::Unauthorized(_))));
+
+        let added = edit_skill_tag(&state, "test-user", "skill-001", " Reviewed ", true).data.unwrap();
+        assert_eq!(added["tags"], serde_json::json!(["filesystem", "reviewed"]));
+        assert_eq!(added["changed"], true);
+
+        let again = edit_skill_tag(&state, "test-user", "skill-001", "REVIEWED", true).data.unwrap();
+        assert_eq!(again["changed"], false);
+        let counts = count_tags(&skill_registry_in(&state));
+        assert!(counts.contains(&TagCount { tag: "reviewed".to_string(), count: 1 }));
+
+        let removed = edit_skill_tag(&state, "test-user", "skill-001", "reviewed", false).data.unwrap();
+        assert_eq!(removed["tags"], serde_json::json!(["filesystem"]));
+        assert_eq!(removed["changed"], true);
+
+        let invalid = edit_skill_tag(&state, "test-user", "skill-001", "two words", true);
+        assert!(matches!(invalid.error, Some(SynapseError::Validation(_))));
+        let missing = edit_skill_tag(&state, "test-user", "skill-404", "x", true);
+        assert!(matches!(missing.error, Some(SynapseError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_count_tags_orders_by_use() {
+        let mut skills = skill_registry();
+        for (skill, tags) in skills.iter_mut().zip([vec!["network"], vec!["fs", "network"], vec!["fs", "beta", "network"]]) {
+            skill.tags = tags.into_iter().map(str::to_string).collect();
+        }
+        let counts: Vec<(String, usize)> = count_tags(&skills).into_iter().map(|t| (t.tag, t.count)).collect();
+        assert_eq!(counts, vec![("network".to_string(), 3), ("fs".to_string(), 2), ("beta".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_rename_skill_keeps_id_and_remaps_metrics() {
+        let mut skills = skill_registry();
+        let mut renames = crate::skills::SkillRenames::default();
+        let (new_name, old_name) = check_skill_rename(&skills, &renames, "skill-001", "  read_text  ").unwrap();
+        assert_eq!((new_name.as_str(), old_name.as_str()), ("read_text", skills[0].name.as_str()));
+        let rename = renames.rename("skill-001", &old_name, &new_name, "admin", chrono::Utc::now());
+        assert_eq!(rename.previous_names, vec![old_name.clone()]);
+        assert_eq!(renames.by_previous_name(&old_name).map(|(id, _)| id), Some("skill-001"));
+
+        // Metrics are kept by id, so they follow the skill to its new name
+        skills[0].name = new_name;
+        let mut history = crate::metrics::ExecutionHistory::default();
+        let now = chrono::Utc::now();
+        history.record("skill-001", now, true, 10.0);
+        history.record("skill-001", now, false, 30.0);
+        history.record("skill-gone", now, true, 5.0);
+        let metrics = skill_metrics_from(&history.totals(), &skills);
+        let names: Vec<&str> = metrics.iter().map(|m| m.skill_name.as_str()).collect();
+        assert_eq!(names, vec!["read_text", "skill-gone"]);
+        assert_eq!((metrics[0].total_executions, metrics[0].failed_executions), (2, 1));
+        assert_eq!((metrics[0].avg_latency_ms, metrics[0].success_rate), (20.0, 50.0));
+
+        // The old name stays reserved for the renamed skill
+        let reuse = check_skill_rename(&skills, &renames, "skill-002", &old_name.to_uppercase());
+        assert!(matches!(reuse, Err(SynapseError::Conflict(_))));
+        assert!(matches!(check_skill_rename(&skills, &renames, "skill-001", " "), Err(SynapseError::Validation(_))));
+        assert!(matches!(check_skill_rename(&skills, &renames, "skill-404", "x"), Err(SynapseError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rename_skill_rejects_name_collision() {
+        let state = AppState::default();
+        let denied = rename_skill_in(&state, "skill-001".to_string(), "renamed".to_string(), None, None).await.unwrap();
+        assert!(matches!(denied.error, Some(SynapseError::Unauthorized(_))));
+
+        let taken = registry_skill("skill-002").name;
+        let result = rename_skill_as(&state, "test-user", "skill-001", &taken.to_uppercase());
+        assert!(!result.success);
+        assert!(matches!(result.error, Some(SynapseError::Conflict(_))));
+        assert_eq!(skill_registry_in(&state).into_iter().find(|s| s.id == "skill-001").unwrap().name, "read_file");
+    }
+
+    #[test]
+    fn test_query_skills_sorting() {
+        let by_risk = SkillQuery { sort_by: Some("risk_level".to_string()), descending: true, ..Default::default() };
+        assert_eq!(skill_ids(&by_risk), vec!["skill-003", "skill-002", "skill-001"]);
+
+        let by_name = SkillQuery { sort_by: Some("name".to_string()), ..Default::default() };
+        assert_eq!(skill_ids(&by_name), vec!["skill-001", "skill-003", "skill-002"]);
+
+        let by_created = SkillQuery { sort_by: Some("created_at".to_string()), descending: true, ..Default::default() };
+        assert_eq!(skill_ids(&by_created), vec!["skill-003", "skill-002", "skill-001"]);
+
+        // Never-used skill-003 stays last in both directions
+        let mut by_last_used = SkillQuery { sort_by: Some("last_used".to_string()), ..Default::default() };
+        assert_eq!(skill_ids(&by_last_used), vec!["skill-002", "skill-001", "skill-003"]);
+        by_last_used.descending = true;
+        assert_eq!(skill_ids(&by_last_used), vec!["skill-001", "skill-002", "skill-003"]);
+
+        let unknown = SkillQuery { sort_by: Some("size".to_string()), ..Default::default() };
+        assert!(query_skills(dated_skills(), &unknown).is_err());
+    }
+
+    #[test]
+    fn test_query_skills_combined_with_paging() {
+        let query = SkillQuery {
+            max_risk: Some(3),
+            name_contains: Some("_".to_string()),
+            sort_by: Some("risk_level".to_string()),
+            limit: Some(1),
+            offset: 1,
+            ..Default::default()
+        };
+        let page = query_skills(dated_skills(), &query).unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "skill-002");
+        assert!(page.has_more);
+    }
+
+    #[test]
+    fn test_page_has_more_across_two_pages() {
+        let first = Page::from_items((0..5).collect(), Some(3), None);
+        assert_eq!(first.items, vec![0, 1, 2]);
+        assert_eq!((first.total, first.limit, first.offset), (5, 3, 0));
+        assert!(first.has_more);
+
+        let second = Page::from_items((0..5).collect(), Some(3), Some(3));
+        assert_eq!(second.items, vec![3, 4]);
+        assert_eq!(second.total, 5);
+        assert!(!second.has_more);
+
+        let past_end: Page<u32> = Page::from_items((0..5).collect(), None, Some(9));
+        assert!(past_end.items.is_empty() && !past_end.has_more);
+        assert_eq!(past_end.limit, DEFAULT_PAGE_LIMIT);
+
+        let value = serde_json::to_value(&first).unwrap();
+        assert_eq!(value["protocol_version"], PROTOCOL_VERSION);
+        assert_eq!(value["has_more"], true);
+    }
+
+    fn search_ids(query: &str) -> Vec<String> {
+        search_skill_list(skill_registry(), query).into_iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn test_search_skills_ranks_name_hits_first() {
+        // "file" is in both file skills' names; "workspace" only in descriptions
+        assert_eq!(search_ids("FILE"), vec!["skill-001", "skill-002"]);
+        assert_eq!(search_ids("web"), vec!["skill-003"]);
+
+        let mut skills = skill_registry();
+        skills[2].description = "Fetch a file over HTTP".to_string();
+        let ids: Vec<String> = search_skill_list(skills, "file").into_iter().map(|s| s.id).collect();
+        assert_eq!(ids.last().unwrap(), "skill-003");
+    }
+
+    #[test]
+    fn test_search_skills_description_only_hit() {
+        assert_eq!(search_ids("summaries"), vec!["skill-003"]);
+        assert_eq!(search_ids("workspace"), vec!["skill-001", "skill-002"]);
+    }
+
+    #[test]
+    fn test_search_skills_multi_word_requires_all_words() {
+        assert_eq!(search_ids("write workspace"), vec!["skill-002"]);
+        assert!(search_ids("write summaries").is_empty());
+        assert!(search_ids("   ").is_empty());
+    }
+
+    fn ids(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn audit_results(action: &str, resource: &str) -> Vec<String> {
+        crate::audit::entries()
+            .into_iter()
+            .filter(|e| e.action == action && e.resource.as_deref() == Some(resource))
+            .map(|e| e.result)
+            .collect()
+    }
+
+    #[test]
+    fn test_bulk_approve_is_best_effort() {
+        let mut registry = skill_registry();
+        registry[0].id = "bulk-approve-ok".to_string();
+        registry[0].status = "pending".to_string();
+
+        let request = ids(&["bulk-approve-ok", "skill-002", "bulk-missing", "bulk-approve-ok"]);
+        let results = bulk_approve(&request, "reviewer", &registry, &SynapseConfig::default());
+
+        assert_eq!(results.iter().map(|r| r.success).collect::<Vec<_>>(), vec![true, false, false, false]);
+        assert!(results[1].error.as_ref().unwrap().contains("already approved"));
+        assert!(results[2].error.as_ref().unwrap().contains("not found"));
+        assert!(results[3].error.as_ref().unwrap().contains("more than once"));
+        assert_eq!(audit_results("skill_approve", "bulk-approve-ok"), vec!["success"]);
+    }
+
+    #[test]
+    fn test_approval_gate_ignores_a_lowered_risk_override() {
+        let settings = SynapseConfig::default().security_settings;
+        let mut skill = skill_registry().into_iter().find(|s| s.id == "skill-001").unwrap();
+        skill.id = "gate-lowered".to_string();
+        skill.required_capabilities = vec!["process:spawn".to_string()];
+        skill.risk_level = 0;
+        let gated = check_approval_gate(&skill, &settings, "reviewer", false).unwrap_err();
+        assert!(gated.contains("at or above the threshold of 3"));
+    }
+
+    #[test]
+    fn test_approval_gate_passes_below_threshold_and_gates_risky_skills() {
+        let mut settings = SynapseConfig::default().security_settings;
+        settings.user_roles.insert("root".to_string(), "admin".to_string());
+        let mut skill = skill_registry().into_iter().find(|s| s.id == "skill-001").unwrap();
+        skill.id = "gate-check".to_string();
+        skill.risk_level = 2;
+        assert_eq!(check_approval_gate(&skill, &settings, "reviewer", false), Ok("below_threshold"));
+
+        skill.risk_level = 3;
+        let gated = check_approval_gate(&skill, &settings, "reviewer", false).unwrap_err();
+        assert!(gated.starts_with("Elevated approval required"));
+        assert!(gated.contains("risk level 3, at or above the threshold of 3"));
+        assert_eq!(check_approval_gate(&skill, &settings, "reviewer", true), Ok("confirmed"));
+        assert_eq!(check_approval_gate(&skill, &settings, "root", false), Ok("elevated_role"));
+
+        let decisions: Vec<(String, Option<String>)> = crate::audit::entries()
+            .into_iter()
+            .filter(|e| e.action == "skill_approval_gate" && e.resource.as_deref() == Some("gate-check"))
+            .map(|e| (e.result, e.details))
+            .collect();
+        assert_eq!(decisions.len(), 4);
+        assert_eq!(decisions[1], ("failure".to_string(), Some("Risk level 3, threshold 3: elevated approval required".to_string())));
+    }
+
+    #[test]
+    fn test_bulk_approve_gates_high_risk_skills() {
+        let mut registry = skill_registry();
+        registry[0].id = "bulk-gated".to_string();
+        registry[0].status = "pending".to_string();
+        registry[0].risk_level = 5;
+
+        let results = bulk_approve(&ids(&["bulk-gated"]), "reviewer", &registry, &SynapseConfig::default());
+        assert!(results[0].error.as_ref().unwrap().starts_with("Elevated approval required"));
+        assert_eq!(audit_results("skill_approve", "bulk-gated"), vec!["failure"]);
+    }
+
+    #[test]
+    fn test_bulk_reject_is_best_effort() {
+        let mut registry = skill_registry();
+        registry[0].id = "bulk-reject-ok".to_string();
+        registry[0].status = "pending".to_string();
+
+        let results = bulk_reject(&ids(&["bulk-missing", "bulk-reject-ok"]), "Not needed", &registry);
+        assert!(!results[0].success);
+        assert!(results[1].success);
+        assert_eq!(results[1].error, None);
+        assert_eq!(audit_results("skill_reject", "bulk-reject-ok"), vec!["success"]);
+    }
+
+    #[test]
+    fn test_rolled_back_skill_reports_active_version() {
+        let mut history = crate::skills::SkillVersionHistory::default();
+        let skill = skill_registry().into_iter().find(|s| s.id == "skill-002").unwrap();
+        for v in skill_versions(&skill, &crate::skills::SkillStore::default().versions) {
+            history.record("skill-002", v);
+        }
+        history.rollback("skill-002", "0.9.0").unwrap();
+
+        let rolled_back = with_active_version(skill, &history);
+        assert_eq!(rolled_back.version, "0.9.0");
+        assert_eq!(rolled_back.risk_level, 3);
+        assert_eq!(rolled_back.trust_level, "unverified");
+    }
+
+    #[test]
+    fn test_disable_then_enable_skill_round_trips() {
+        let mut toggles = crate::skills::SkillToggles::default();
+        let skill = skill_registry().into_iter().find(|s| s.id == "skill-002").unwrap();
+
+        assert!(toggles.set_enabled("skill-002", false));
+        let disabled = with_enabled_flag(skill.clone(), &toggles);
+        assert!(!disabled.enabled);
+        assert_eq!((disabled.status.as_str(), disabled.trust_level.as_str()), ("active", "verified"));
+        assert!(execution_eligible(vec![disabled.clone()]).is_empty());
+
+        let json = serde_json::to_value(&disabled).unwrap();
+        assert_eq!(json["enabled"], false);
+        let parsed: SkillInfo = serde_json::from_value(json).unwrap();
+        assert!(!parsed.enabled);
+
+        assert!(toggles.set_enabled("skill-002", true));
+        assert!(!toggles.set_enabled("skill-002", true));
+        let enabled = with_enabled_flag(skill, &toggles);
+        assert!(enabled.enabled);
+        assert_eq!(execution_eligible(vec![enabled]).len(), 1);
+    }
+
+    const SIGNING_KEY: &[u8] = b"commands-test-signing-key";
+
+    fn token(user_id: &str, capabilities: &[&str], expires_in_hours: Option<i64>) -> crate::security::IssuedCapabilityToken {
+        let now = chrono::Utc::now();
+        let capabilities = capabilities.iter().map(|c| c.to_string()).collect();
+        let mut token = crate::security::issue_capability_token(SIGNING_KEY, user_id, capabilities, vec![], None, now).unwrap();
+        token.expires_at = expires_in_hours.map(|hours| now + chrono::Duration::hours(hours));
+        token.signature = crate::security::sign_capability_token(SIGNING_KEY, &token);
+        token
+    }
+
+    #[test]
+    fn test_skill_capabilities_fully_covered() {
+        let skill = skill_registry().into_iter().find(|s| s.id == "skill-002").unwrap();
+        let tokens = vec![token("admin", &["fs:read", "fs:write"], None)];
+        let granted = capabilities_granted_by(&tokens, SIGNING_KEY, "admin", chrono::Utc::now());
+        let check = skill_capability_check(&skill, "admin", &granted);
+        assert!(check.is_covered());
+        assert_eq!(check.granted, vec!["fs:write"]);
+    }
+
+    #[test]
+    fn test_skill_capabilities_ignores_expired_and_revoked_tokens() {
+        let mut skill = skill_registry().into_iter().find(|s| s.id == "skill-002").unwrap();
+        skill.required_capabilities.extend(["network:http".to_string(), "process:spawn".to_string()]);
+        let mut revoked = token("dev", &["process:spawn"], Some(1));
+        revoked.revoked_at = Some(chrono::Utc::now());
+        let tokens = vec![token("dev", &["fs:write"], None), token("dev", &["network:http"], Some(-1)), revoked];
+        let granted = capabilities_granted_by(&tokens, SIGNING_KEY, "dev", chrono::Utc::now());
+
+        let check = skill_capability_check(&skill, "dev", &granted);
+        assert!(!check.is_covered());
+        assert_eq!(check.granted, vec!["fs:write"]);
+        assert_eq!(check.missing, vec!["network:http", "process:spawn"]);
+
+        let infos = capability_infos(tokens, Some(SIGNING_KEY), chrono::Utc::now());
+        assert_eq!(infos.iter().map(|t| t.is_valid).collect::<Vec<_>>(), vec![true, false, false]);
+        // Without a signing key nothing can be verified
+        assert!(!capability_infos(vec![token("dev", &["fs:read"], None)], None, chrono::Utc::now())[0].is_valid);
+    }
+
+    #[test]
+    fn test_capability_listing_filters_by_user_and_state() {
+        let now = chrono::Utc::now();
+        let mut revoked = token("dev", &["process:spawn"], None);
+        revoked.revoked_at = Some(now);
+        let tokens = vec![
+            token("dev", &["fs:read"], None),
+            token("ops", &["fs:write"], Some(2)),
+            token("dev", &["network:http"], Some(-1)),
+            revoked,
+        ];
+        let grants = |selected: Vec<crate::security::IssuedCapabilityToken>| {
+            selected.into_iter().map(|t| format!("{}:{}", t.user_id, t.capabilities[0])).collect::<Vec<_>>()
+        };
+
+        assert_eq!(grants(select_capability_tokens(tokens.clone(), Some("dev"), false, false, now)), vec!["dev:fs:read"]);
+        assert_eq!(
+            grants(select_capability_tokens(tokens.clone(), Some("dev"), true, false, now)),
+            vec!["dev:fs:read", "dev:network:http"]
+        );
+        assert_eq!(grants(select_capability_tokens(tokens.clone(), None, false, false, now)), vec!["dev:fs:read", "ops:fs:write"]);
+        assert_eq!(select_capability_tokens(tokens.clone(), None, true, true, now).len(), 4);
+
+        let listed = capability_infos(select_capability_tokens(tokens, None, true, true, now), Some(SIGNING_KEY), now);
+        let page = Page::from_items(listed, Some(3), Some(2));
+        assert_eq!((page.total, page.items.len(), page.has_more), (4, 2, false));
+        assert!(!page.items[1].is_valid);
+        assert_eq!(page.items[1].revoked_at, Some(now));
+    }
+
+    #[tokio::test]
+    async fn test_exported_skill_imports_as_identical_pending_skill() {
+        let exported = export_skill("skill-003".to_string()).await.unwrap().data.unwrap();
+        for field in SKILL_RUNTIME_FIELDS {
+            assert!(exported.get(field).is_none(), "{} should be redacted", field);
+        }
+        assert_eq!(exported["tags"], serde_json::json!(["experimental", "network"]));
+
+        let dir = tempfile::tempdir().unwrap();
+        let duplicate = import_skill_definition(dir.path(), &skill_registry(), &exported).unwrap_err();
+        assert_eq!(duplicate, SynapseError::Conflict("Skill 'web_search' 1.0.0 is already installed as 'skill-003'".to_string()));
+
+        let mut shared = exported.clone();
+        shared["version"] = serde_json::json!("1.1.0");
+        let id = import_skill_definition(dir.path(), &skill_registry(), &shared).unwrap();
+        let imported = imported_skills(dir.path());
+        let skill = imported.iter().find(|s| s.id == id).unwrap();
+        assert_eq!((skill.status.as_str(), skill.trust_level.as_str()), ("pending", "unverified"));
+        assert_eq!(skill_definition(skill), shared);
+
+        let again = import_skill_definition(dir.path(), &imported, &shared).unwrap_err();
+        assert!(matches!(again, SynapseError::Conflict(message) if message.contains(&id)));
+    }
+
+    #[test]
+    fn test_imported_skill_joins_registry_as_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = serde_json::json!({
+            "name": "summarize",
+            "version": "0.1.0",
+            "inputs": {},
+            "outputs": {},
+            "required_capabilities": ["fs:read"],
+            "risk_level": 1
+        });
+        let id = crate::skills::install_skill(dir.path(), &manifest).unwrap();
+
+        let imported = imported_skills(dir.path());
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].id, id);
+        assert_eq!((imported[0].status.as_str(), imported[0].trust_level.as_str()), ("pending", "unverified"));
+        assert_eq!(imported[0].isolation_type, "container");
+        assert!(imported[0].enabled);
+    }
+
+    #[test]
+    fn test_understated_risk_warns_unless_overridden() {
+        let mut skill = skill_registry().into_iter().find(|s| s.id == "skill-003").unwrap();
+        assert!(assess_skill_risk(&skill, None).warning.is_none());
+
+        skill.risk_level = 1;
+        let assessment = assess_skill_risk(&skill, None);
+        assert_eq!(assessment.computed_risk_level, 3);
+        assert!(assessment.warning.unwrap().contains("lower than 3"));
+
+        let risk_override = crate::skills::RiskOverride {
+            risk_level: 1,
+            set_by: "admin".to_string(),
+            reason: "Only queries an internal index".to_string(),
+            set_at: chrono::Utc::now(),
+        };
+        let assessment = assess_skill_risk(&skill, Some(&risk_override));
+        assert!(assessment.warning.is_none());
+        assert_eq!(assessment.risk_override.unwrap().set_by, "admin");
+    }
+
+    #[test]
+    fn test_diff_config_reports_added_provider_and_policy_change() {
+        let old = SynapseConfig::default();
+        let mut new = old.clone();
+        let mut local = old.llm_providers[0].clone();
+        local.name = "Local".to_string();
+        local.provider_type = "ollama".to_string();
+        local.api_key = Some("sk-secret-value".to_string());
+        local.priority = 2;
+        new.llm_providers.push(local);
+        new.security_settings.isolation_policy = "subprocess".to_string();
+
+        let changes = diff_configs(&old, &new);
+        assert_eq!(changes.len(), 2);
+
+        assert_eq!(changes[0].section, "llm_providers");
+        assert_eq!(changes[0].key, "Local");
+        assert_eq!(changes[0].change, "added");
+        assert_eq!(changes[0].new.as_ref().unwrap()["api_key"], "***");
+
+        assert_eq!(changes[1].section, "security_settings");
+        assert_eq!(changes[1].key, "isolation_policy");
+        assert_eq!(changes[1].old, Some(serde_json::json!("container")));
+        assert_eq!(changes[1].new, Some(serde_json::json!("subprocess")));
+        assert!(!serde_json::to_string(&changes).unwrap().contains("sk-secret-value"));
+    }
+
+    #[test]
+    fn test_redacted_config_hides_secrets_and_saves_back_unchanged() {
+        let mut config = SynapseConfig::default();
+        config.llm_providers[0].api_key = Some("sk-secret-value".to_string());
+        config.security_settings.token_signing_key = Some("ab".repeat(32));
+
+        let shown = redacted_config(&config);
+        let text = shown.to_string();
+        assert!(!text.contains("sk-secret-value"));
+        assert!(!text.contains(&"ab".repeat(32)));
+        assert!(shown["security_settings"].get("token_signing_key").is_none());
+        assert_eq!(shown["llm_providers"][0]["api_key"], "***");
+
+        // The UI saving back what it was shown keeps the stored key
+        let mut echoed: SynapseConfig = serde_json::from_value(shown).unwrap();
+        restore_masked_api_keys(&mut echoed, Some(&config));
+        assert_eq!(echoed.llm_providers, config.llm_providers);
+        assert!(!security_settings_changed(&config.security_settings, &echoed.security_settings));
+    }
+
+    #[tokio::test]
+    async fn test_reload_org_policy_requires_admin() {
+        let denied = reload_org_policy(None, None).await.unwrap();
+        assert!(matches!(denied.error, Some(SynapseError::Unauthorized(_))));
+    }
+
+    fn role_settings() -> SecuritySettings {
+        let mut settings = SynapseConfig::default().security_settings;
+        settings.user_roles = std::collections::HashMap::from([
+            ("alice".to_string(), "admin".to_string()),
+            ("olga".to_string(), "operator".to_string()),
+            ("vic".to_string(), "viewer".to_string()),
+        ]);
+        settings
+    }
+
+    #[test]
+    fn test_each_role_against_protected_commands() {
+        let settings = role_settings();
+        let tokens = vec![token("alice", &["fs:read"], None), token("olga", &["fs:read"], None), token("vic", &["fs:read"], None)];
+        let now = chrono::Utc::now();
+        let authorize = |index: usize, required: &str| {
+            let bearer = crate::security::bearer_token(&tokens[index]);
+            authorize_caller(&tokens, Some(SIGNING_KEY), &settings, Some(&bearer), required, now)
+        };
+
+        // Security settings need admin; approving skills needs operator
+        assert_eq!(authorize(0, "admin").unwrap(), "alice");
+        assert_eq!(authorize(0, "operator").unwrap(), "alice");
+        assert_eq!(
+            authorize(1, "admin").unwrap_err(),
+            "Forbidden: requires role 'admin'; user 'olga' has role 'operator'"
+        );
+        assert_eq!(authorize(1, "operator").unwrap(), "olga");
+        assert!(authorize(2, "admin").unwrap_err().contains("requires role 'admin'"));
+        assert_eq!(
+            authorize(2, "operator").unwrap_err(),
+            "Forbidden: requires role 'operator'; user 'vic' has role 'viewer'"
+        );
+    }
+
+    #[test]
+    fn test_authorize_rejects_missing_and_revoked_tokens() {
+        let settings = role_settings();
+        let mut revoked = token("alice", &["fs:read"], None);
+        revoked.revoked_at = Some(chrono::Utc::now());
+        let tokens = vec![revoked];
+        let now = chrono::Utc::now();
+
+        let missing = authorize_caller(&tokens, Some(SIGNING_KEY), &settings, None, "admin", now).unwrap_err();
+        assert_eq!(missing, "Forbidden: requires role 'admin'; no capability token was presented");
+        let bearer = crate::security::bearer_token(&tokens[0]);
+        let error = authorize_caller(&tokens, Some(SIGNING_KEY), &settings, Some(&bearer), "admin", now).unwrap_err();
+        assert!(error.contains("was revoked"));
+
+        let mut invalid = SynapseConfig::default();
+        invalid.security_settings.user_roles.insert("bob".to_string(), "root".to_string());
+        assert!(validate_config(&invalid).iter().any(|e| e.field == "security_settings.user_roles.bob"));
+    }
+
+    #[test]
+    fn test_token_id_alone_is_not_a_credential() {
+        let settings = role_settings();
+        let tokens = vec![token("alice", &["fs:read"], None)];
+        let now = chrono::Utc::now();
+        let authorize = |bearer: &str| authorize_caller(&tokens, Some(SIGNING_KEY), &settings, Some(bearer), "admin", now);
+
+        // The id is listed and logged, so it must not be enough to sign in
+        assert!(authorize(&tokens[0].token_id).unwrap_err().contains("malformed"));
+        let forged = format!("{}.{}", tokens[0].token_id, "00".repeat(32));
+        assert!(authorize(&forged).unwrap_err().contains("invalid signature"));
+        assert_eq!(authorize(&crate::security::bearer_token(&tokens[0])).unwrap(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_create_session_requires_a_valid_token() {
+        let result = create_session_in(crate::state::app_state(), "cap-unknown".to_string()).await.unwrap();
+        assert!(!result.success);
+        assert!(matches!(result.error, Some(SynapseError::Unauthorized(message)) if message.starts_with("Cannot start a session")));
+    }
+
+    #[tokio::test]
+    async fn test_finalize_wizard_saves_valid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_dir = dir.path().to_string_lossy().to_string();
+        let answers: crate::wizard::WizardAnswers = serde_json::from_value(serde_json::json!({
+            "language": "en",
+            "llm_providers": [{
+                "name": "Local",
+                "provider_type": "ollama",
+                "api_key": null,
+                "base_url": "http://127.0.0.1:9",
+                "model": "llama3",
+                "priority": 1,
+                "is_active": true
+            }],
+            "data_paths": {"config": config_dir},
+            "security_preset": "supervised"
+        }))
+        .unwrap();
+
+        let result = finalize_wizard_in(dir.path(), answers.clone()).await.unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let saved = read_config_file(&profile_path(dir.path(), DEFAULT_PROFILE).unwrap()).unwrap().unwrap();
+        assert_eq!(saved.mode, "supervised");
+        assert_eq!(saved.llm_providers[0].model, "llama3");
+        assert!(validate_config(&saved).is_empty());
+        let state = crate::wizard::load_wizard_state(&dir.path().join(crate::wizard::WIZARD_STATE_FILE)).unwrap();
+        assert!(state.finished);
+
+        let mut invalid = answers;
+        invalid.language = "xx".to_string();
+        let result = finalize_wizard_in(dir.path(), invalid).await.unwrap();
+        assert!(!result.success);
+        assert!(result.data.unwrap()["errors"].to_string().contains("language"));
+    }
+
+    #[test]
+    fn test_error_variants_serialize_with_stable_codes() {
+        let cases = [
+            (SynapseError::NotFound("Skill 'x' not found".to_string()), "not_found"),
+            (SynapseError::Validation("Risk level 9 is outside 0-5".to_string()), "validation"),
+            (SynapseError::Unauthorized("No session; sign in first".to_string()), "unauthorized"),
+            (SynapseError::RateLimited("Rate limit exceeded".to_string()), "rate_limited"),
+            (SynapseError::Upstream("No active, reachable LLM provider".to_string()), "upstream"),
+            (SynapseError::Internal("Disk full".to_string()), "internal"),
+            (SynapseError::Conflict("Idempotency key 'k' was already used".to_string()), "conflict"),
+        ];
+        for (error, code) in cases {
+            let value = serde_json::to_value(&error).unwrap();
+            assert_eq!(value, serde_json::json!({"code": code, "message": error.message()}));
+            assert_eq!(serde_json::from_value::<SynapseError>(value).unwrap(), error);
+        }
+
+        let response = serde_json::to_value(ApiResponse::error(SynapseError::NotFound("gone".to_string()))).unwrap();
+        assert_eq!(response["success"], false);
+        assert_eq!(response["error"], serde_json::json!({"code": "not_found", "message": "gone"}));
+    }
+
+    #[tokio::test]
+    async fn test_responses_carry_distinct_request_ids() {
+        let first = get_supported_languages().await.unwrap();
+        let second = get_supported_languages().await.unwrap();
+        for response in [&first, &second] {
+            let id = uuid::Uuid::parse_str(&response.base.request_id).unwrap();
+            assert_eq!(id.get_version(), Some(uuid::Version::Random));
+        }
+        assert_ne!(first.base.request_id, second.base.request_id);
+
+        let failed = serde_json::to_value(ApiResponse::error(SynapseError::NotFound("gone".to_string()))).unwrap();
+        assert!(uuid::Uuid::parse_str(failed["request_id"].as_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_one_request_id_per_command_invocation() {
+        tracing::subscriber::with_default(tracing_subscriber::registry(), || {
+            let span = command_span("list_skills", &serde_json::json!({}));
+            let request_id = {
+                let _entered = span.enter();
+                let page = Page::from_items(vec![1, 2, 3], Some(2), None);
+                let response = ApiResponse::success(serde_json::to_value(&page).unwrap());
+                // Spans Tauri opens for the command future nest under it
+                let inner = tracing::info_span!("ipc");
+                let _inner = inner.enter();
+                let failed = ApiResponse::error(SynapseError::Internal("disk full".to_string()));
+                assert_eq!(page.base.request_id, response.base.request_id);
+                assert_eq!(failed.base.request_id, response.base.request_id);
+                response.base.request_id
+            };
+
+            let next = command_span("list_skills", &serde_json::json!({}));
+            let _entered = next.enter();
+            assert_ne!(ApiResponse::success(serde_json::json!(null)).base.request_id, request_id);
+        });
+    }
 }
@@ -0,0 +1,24 @@
+//! Tests for Localization
+//!
+//! Verifies per-language lookups and the English fallback
+
+#[cfg(test)]
+mod tests {
+    use crate::i18n::*;
+
+    #[test]
+    fn test_message_differs_by_language() {
+        let english = localize("config_saved", "en");
+        let russian = localize("config_saved", "ru");
+        assert_eq!(english, "Configuration saved successfully");
+        assert_eq!(russian, "Конфигурация успешно сохранена");
+        assert_ne!(english, russian);
+    }
+
+    #[test]
+    fn test_missing_translation_falls_back_to_english() {
+        assert_eq!(localize("connection_successful", "de"), "Connection successful");
+        assert_eq!(localize("connection_successful", ""), "Connection successful");
+        assert_eq!(localize("no_such_message", "ru"), "no_such_message");
+    }
+}
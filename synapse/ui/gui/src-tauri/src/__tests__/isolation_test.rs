@@ -0,0 +1,50 @@
+//! Tests for Isolation Backends
+//!
+//! Verifies warm pool accounting and cold-start fallback
+
+#[cfg(test)]
+mod tests {
+    use crate::isolation::*;
+
+    #[test]
+    fn test_warm_pool_hits_then_degrades_to_cold_start() {
+        let mut pool = WarmPool::default();
+        pool.add("container", "c1".to_string());
+
+        assert_eq!(pool.acquire("container").as_deref(), Some("c1"));
+        assert!(pool.acquire("container").is_none());
+
+        let stats = pool.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].hits, 1);
+        assert_eq!(stats[0].misses, 1);
+        assert_eq!(stats[0].available, 0);
+    }
+
+    #[tokio::test]
+    async fn test_no_isolation_has_no_cold_start() {
+        assert_eq!(measure_cold_start("none").await.unwrap(), std::time::Duration::ZERO);
+        assert!(measure_cold_start("vm").await.is_err());
+    }
+
+    #[test]
+    fn test_isolation_upgrade_is_allowed() {
+        assert_eq!(check_policy_transition("none", "container", false), Ok(false));
+        assert_eq!(check_policy_transition("subprocess", "subprocess", false), Ok(false));
+    }
+
+    #[test]
+    fn test_isolation_downgrade_is_blocked_without_confirmation() {
+        let error = check_policy_transition("container", "none", false).unwrap_err();
+        assert!(error.contains("from 'container' to 'none'"));
+        assert!(error.contains("confirm_downgrade"));
+        assert!(check_policy_transition("container", "subprocess", false).is_err());
+        assert!(check_policy_transition("container", "vm", true).unwrap_err().contains("Unknown isolation policy"));
+    }
+
+    #[test]
+    fn test_confirmed_isolation_downgrade_is_flagged() {
+        assert_eq!(check_policy_transition("container", "none", true), Ok(true));
+        assert_eq!(check_policy_transition("subprocess", "none", true), Ok(true));
+    }
+}
@@ -0,0 +1,98 @@
+//! Tests for the Developer Log
+//!
+//! Verifies tailing, level filtering, following across rotation, and that
+//! secrets never reach emitted lines
+
+#[cfg(test)]
+mod tests {
+    use crate::logs::*;
+    use std::fs;
+    use std::io::Write;
+
+    #[test]
+    fn test_read_log_tail_returns_last_n_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("synapse.log");
+        let lines: Vec<String> = (1..=10).map(|i| format!("2026-01-01 INFO line {}", i)).collect();
+        fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        assert_eq!(read_log_tail(&path, 3).unwrap(), lines[7..].to_vec());
+        assert_eq!(read_log_tail(&path, 50).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_level_filter() {
+        assert!(passes_level("2026-01-01 ERROR boom", Some("warn")));
+        assert!(!passes_level("2026-01-01 DEBUG noise", Some("info")));
+        assert!(passes_level("no level here", Some("error")));
+        assert_eq!(line_level("[WARN] disk almost full"), Some("warn"));
+    }
+
+    #[test]
+    fn test_follower_continues_across_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("synapse.log");
+        fs::write(&path, "old INFO before follow\n").unwrap();
+
+        let mut follower = LogFollower::new(&path);
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "INFO first\nINFO part").unwrap();
+        assert_eq!(follower.poll(), vec!["INFO first"]);
+
+        fs::rename(&path, dir.path().join("synapse.log.1")).unwrap();
+        fs::write(&path, "INFO after roll\n").unwrap();
+        assert_eq!(follower.poll(), vec!["INFO after roll"]);
+    }
+
+    /// Log lines written by a subscriber, shared with the test
+    #[derive(Clone, Default)]
+    struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_api_key_never_appears_in_log_lines() {
+        let secret = "sk-live-0123456789abcdefghij";
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .fmt_fields(redacting_fields())
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("command", name = "save_config", api_key = secret);
+            let _entered = span.enter();
+            tracing::info!(api_key = secret, provider = "openai", "saving provider");
+            tracing::warn!(config = ?serde_json::json!({"api_key": secret}), "probe failed with key {}", secret);
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 3, "{}", output);
+        assert!(!output.contains(secret), "{}", output);
+        assert!(output.contains("api_key=[REDACTED]"));
+        assert!(output.contains("provider=\"openai\""));
+    }
+
+    #[test]
+    fn test_current_log_file_picks_newest_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(current_log_file(dir.path()), dir.path().join(LEGACY_LOG_FILE));
+
+        for name in ["synapse.log", "synapse.2026-03-01.log", "synapse.2026-03-02.log", "other.2026-03-09.log"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        assert_eq!(current_log_file(dir.path()), dir.path().join("synapse.2026-03-02.log"));
+    }
+}
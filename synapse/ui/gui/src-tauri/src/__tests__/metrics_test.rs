@@ -0,0 +1,593 @@
+//! Tests for Metrics Collection
+//!
+//! Verifies incident snapshots are written, listed newest first, and read back
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::*;
+    use crate::providers::{ModelPrice, PriceTable};
+    use chrono::{Duration, Utc};
+
+    fn snapshot(note: &str, age_minutes: i64) -> IncidentSnapshot {
+        let captured_at = Utc::now() - Duration::minutes(age_minutes);
+        IncidentSnapshot {
+            id: incident_snapshot_id(captured_at),
+            captured_at,
+            note: note.to_string(),
+            system: get_system_metrics(std::path::Path::new(".")),
+            process: None,
+            recent_errors: vec![],
+            providers: vec![],
+            history: vec![],
+            running_executions: vec![],
+            protocol_version: "1.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_incident_snapshots_listed_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        write_incident_snapshot(dir.path(), &snapshot("older", 10)).unwrap();
+        let path = write_incident_snapshot(dir.path(), &snapshot("newer", 1)).unwrap();
+        std::fs::write(dir.path().join("unrelated.json"), "{}").unwrap();
+
+        let listed = list_incident_snapshots(dir.path());
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].note, "newer");
+        assert_eq!(listed[0].path, path.to_string_lossy());
+        assert_eq!(listed[1].note, "older");
+    }
+
+    #[test]
+    fn test_incident_snapshot_reads_back_for_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut captured = snapshot("disk full", 0);
+        captured.running_executions.push(RunningExecution {
+            execution_id: "exec-1".to_string(),
+            skill_id: "skill-001".to_string(),
+            user_id: "alice".to_string(),
+            sandbox_id: None,
+            started_at: Utc::now(),
+        });
+        write_incident_snapshot(dir.path(), &captured).unwrap();
+
+        let read = read_incident_snapshot(dir.path(), &captured.id).unwrap();
+        assert_eq!(read.note, "disk full");
+        assert_eq!(read.running_executions, captured.running_executions);
+
+        assert!(read_incident_snapshot(dir.path(), "incident-../../etc/passwd").is_err());
+        assert!(read_incident_snapshot(dir.path(), "unrelated").is_err());
+        assert!(read_incident_snapshot(dir.path(), &incident_snapshot_id(Utc::now() - Duration::days(1))).is_err());
+    }
+
+    #[test]
+    fn test_compare_skill_metrics_reports_p95_drop() {
+        let now = Utc::now();
+        let mut history = ExecutionHistory::default();
+        for i in 0..10 {
+            history.record("skill-001", now - Duration::hours(30), true, 100.0 + i as f64);
+            history.record("skill-001", now - Duration::hours(2), i != 0, 50.0 + i as f64);
+        }
+
+        let a = parse_time_window("previous_24h", now).unwrap();
+        let b = parse_time_window("last_24h", now).unwrap();
+        let comparison = history.compare("skill-001", a, b);
+
+        assert_eq!(comparison.window_a.execution_count, 10);
+        assert_eq!(comparison.window_b.success_rate, 90.0);
+        assert_eq!(comparison.window_a.p95_latency_ms, 109.0);
+        assert_eq!(comparison.window_b.p95_latency_ms, 59.0);
+        assert!(comparison.delta.p95_latency_change_percent.unwrap() < -45.0);
+        assert!(!comparison.insufficient_data);
+    }
+
+    #[test]
+    fn test_compare_skill_metrics_empty_window_is_flagged() {
+        let now = Utc::now();
+        let history = ExecutionHistory::default();
+        let window = parse_time_window("last_7d", now).unwrap();
+
+        let comparison = history.compare("skill-404", window, window);
+        assert_eq!(comparison.window_a.execution_count, 0);
+        assert_eq!(comparison.window_a.success_rate, 0.0);
+        assert!(comparison.delta.p95_latency_change_percent.is_none());
+        assert!(comparison.insufficient_data);
+        assert!(parse_time_window("yesterday", now).is_err());
+        assert!(parse_time_window("last_24é", now).is_err());
+        assert!(parse_time_window("last_", now).is_err());
+    }
+
+    #[test]
+    fn test_running_executions_are_tracked_until_finished() {
+        let now = Utc::now();
+        let mut running = RunningExecutions::default();
+        for (id, age) in [("exec-2", 1), ("exec-1", 5)] {
+            running.start(RunningExecution {
+                execution_id: id.to_string(),
+                skill_id: "skill-001".to_string(),
+                user_id: "alice".to_string(),
+                sandbox_id: None,
+                started_at: now - Duration::minutes(age),
+            });
+        }
+
+        let ids: Vec<String> = running.list().into_iter().map(|e| e.execution_id).collect();
+        assert_eq!(ids, vec!["exec-1", "exec-2"]);
+        assert_eq!(running.get("exec-1").unwrap().user_id, "alice");
+        assert_eq!(running.finish("exec-1").unwrap().skill_id, "skill-001");
+        assert!(running.finish("exec-1").is_none());
+        assert!(running.get("exec-1").is_none());
+        assert!(running.get("exec-2").is_some());
+    }
+
+    /// History with executions yesterday and today, plus noon today as "now"
+    fn two_day_history() -> (ExecutionHistory, chrono::DateTime<Utc>) {
+        use chrono::DurationRound;
+        let today = Utc::now().duration_trunc(Duration::days(1)).unwrap();
+        let mut history = ExecutionHistory::default();
+        for success in [true, true, false] {
+            history.record("skill-001", today - Duration::hours(14), success, 40.0);
+        }
+        history.record("skill-001", today + Duration::hours(9), true, 40.0);
+        history.record("skill-002", today + Duration::hours(9), false, 60.0);
+        (history, today + Duration::hours(12))
+    }
+
+    #[test]
+    fn test_daily_trend_fills_empty_days() {
+        let (history, now) = two_day_history();
+        let skill = vec!["skill-001".to_string()];
+        let points = history.success_trend(Some(&skill), "day", now).unwrap();
+
+        assert_eq!(points.len(), 7);
+        assert!(points.windows(2).all(|pair| pair[1].start - pair[0].start == Duration::days(1)));
+        assert!(points[..5].iter().all(|p| p.execution_count == 0 && p.success_rate.is_none()));
+        assert_eq!((points[5].execution_count, points[5].success_count), (3, 2));
+        assert!((points[5].success_rate.unwrap() - 66.67).abs() < 0.01);
+        assert_eq!(points[6].success_rate, Some(100.0));
+
+        let all = history.success_trend(None, "day", now).unwrap();
+        assert_eq!((all[6].execution_count, all[6].success_rate), (2, Some(50.0)));
+    }
+
+    #[test]
+    fn test_hourly_and_weekly_trend_buckets() {
+        use chrono::Datelike;
+        let (history, now) = two_day_history();
+        let hours = history.success_trend(None, "hour", now).unwrap();
+        assert_eq!(hours.len(), 24);
+        assert_eq!(hours.iter().map(|p| p.execution_count).sum::<u64>(), 2);
+        assert_eq!(hours[23].start, now);
+
+        let weeks = history.success_trend(None, "week", now).unwrap();
+        assert_eq!(weeks.len(), 4);
+        assert!(weeks.iter().all(|p| p.start.weekday() == chrono::Weekday::Mon));
+        assert_eq!(weeks.iter().map(|p| p.execution_count).sum::<u64>(), 5);
+
+        assert!(history.success_trend(None, "month", now).unwrap_err().contains("Unknown bucket 'month'"));
+    }
+
+    #[test]
+    fn test_latency_percentiles_from_known_distribution() {
+        let now = Utc::now();
+        let mut history = ExecutionHistory::default();
+        // 1..=100 ms, recorded out of order
+        for i in (1..=100).rev() {
+            history.record("skill-001", now, true, i as f64);
+        }
+
+        let summary = history.latency_summary("skill-001");
+        assert_eq!(summary.sample_count, 100);
+        assert_eq!(summary.p50_latency_ms, Some(50.0));
+        assert_eq!(summary.p95_latency_ms, Some(95.0));
+        assert_eq!(summary.p99_latency_ms, Some(99.0));
+        assert_eq!(summary.max_latency_ms, Some(100.0));
+
+        // The window keeps only the most recent samples
+        for _ in 0..LATENCY_SAMPLE_WINDOW {
+            history.record("skill-001", now, true, 10.0);
+        }
+        assert_eq!(history.latency_summary("skill-001").max_latency_ms, Some(10.0));
+    }
+
+    #[test]
+    fn test_latency_percentiles_need_enough_samples() {
+        let mut history = ExecutionHistory::default();
+        for latency in [20.0, 40.0] {
+            history.record("skill-001", Utc::now(), true, latency);
+        }
+
+        let summary = history.latency_summary("skill-001");
+        assert_eq!(summary.p50_latency_ms, None);
+        assert_eq!(summary.p99_latency_ms, None);
+        assert_eq!(summary.max_latency_ms, Some(40.0));
+        assert_eq!(history.latency_summary("skill-404"), LatencySummary::default());
+    }
+
+    #[test]
+    fn test_system_metrics_disk_usage_in_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let metrics = get_system_metrics(dir.path());
+        assert!((0.0..=100.0).contains(&metrics.disk_percent));
+        if let Some(disk) = metrics.disk {
+            assert!(!disk.mount_point.is_empty());
+            assert!(disk.total_bytes > 0);
+            assert!(disk.used_bytes <= disk.total_bytes);
+            assert_eq!(disk.percent, metrics.disk_percent);
+        }
+    }
+
+    #[test]
+    fn test_gpu_metrics_serialize() {
+        let gpu = GpuMetrics {
+            name: "NVIDIA GeForce RTX 4090".to_string(),
+            utilization_percent: 87.0,
+            memory_used_mb: 18_432,
+            memory_total_mb: 24_564,
+            temperature_celsius: None,
+        };
+        let value = serde_json::to_value(&gpu).unwrap();
+        assert_eq!(value["memory_total_mb"], 24_564);
+        assert!(value["temperature_celsius"].is_null());
+        assert_eq!(serde_json::from_value::<GpuMetrics>(value).unwrap(), gpu);
+
+        let dir = tempfile::tempdir().unwrap();
+        let metrics = serde_json::to_value(get_system_metrics(dir.path())).unwrap();
+        assert!(metrics["gpus"].is_array());
+    }
+
+    #[test]
+    #[cfg(not(feature = "gpu"))]
+    fn test_no_gpus_without_feature() {
+        assert!(gpu_metrics().is_empty());
+        let dir = tempfile::tempdir().unwrap();
+        assert!(get_system_metrics(dir.path()).gpus.is_empty());
+    }
+
+    #[test]
+    fn test_select_disk_prefers_deepest_mount() {
+        let root = std::fs::canonicalize("/").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let data = std::fs::canonicalize(dir.path()).unwrap();
+        let disks = vec![(root.clone(), 1000, 750), (data.clone(), 200, 50)];
+
+        let chosen = select_disk(&data.join("config"), &disks).unwrap();
+        assert_eq!(chosen.mount_point, data.to_string_lossy());
+        assert_eq!((chosen.used_bytes, chosen.total_bytes, chosen.percent), (150, 200, 75.0));
+        assert!(!chosen.fallback);
+
+        // An unresolvable path falls back to the root filesystem
+        let chosen = select_disk(std::path::Path::new("no-such-dir/config"), &disks).unwrap();
+        assert_eq!((chosen.percent, chosen.fallback), (25.0, true));
+        assert!(select_disk(std::path::Path::new("no-such-dir/config"), &disks[1..]).is_none());
+    }
+
+    #[test]
+    fn test_system_metrics_per_core_breakdown() {
+        let metrics = get_system_metrics(std::path::Path::new("."));
+        assert!(metrics.core_count > 0);
+        assert_eq!(metrics.per_core_percent.len(), metrics.core_count);
+        assert!(metrics.physical_core_count.map_or(true, |physical| physical <= metrics.core_count));
+    }
+
+    fn interface(name: &str, received: u64, transmitted: u64) -> InterfaceMetrics {
+        InterfaceMetrics {
+            name: name.to_string(),
+            bytes_received: received,
+            bytes_transmitted: transmitted,
+        }
+    }
+
+    #[test]
+    fn test_network_metrics_skip_loopback_and_serialize() {
+        let interfaces = vec![interface("lo", 500, 500), interface("eth0", 100, 40), interface("wlan0", 10, 2)];
+        let metrics = summarize_network(interfaces.clone(), false);
+        assert_eq!((metrics.bytes_received, metrics.bytes_transmitted), (110, 42));
+        assert_eq!(metrics.interfaces.len(), 2);
+        assert_eq!(summarize_network(interfaces, true).bytes_received, 610);
+
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["protocol_version"], "1.0");
+        assert_eq!(json["interfaces"][0]["name"], "eth0");
+    }
+
+    #[test]
+    fn test_process_metrics_for_current_process() {
+        let metrics = get_process_metrics(Some(std::process::id())).unwrap();
+        assert_eq!(metrics.pid, std::process::id());
+        assert!(metrics.resident_memory_bytes > 0);
+        assert!(metrics.virtual_memory_bytes >= metrics.resident_memory_bytes);
+        if cfg!(target_os = "linux") {
+            assert!(metrics.open_files.unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_process_metrics_unknown_pid_errors() {
+        assert!(get_process_metrics(Some(u32::MAX - 1)).unwrap_err().contains("No process"));
+    }
+
+    #[test]
+    fn test_metrics_history_is_bounded_and_filters_by_time() {
+        let system = get_system_metrics(std::path::Path::new("."));
+        let start = Utc::now();
+        let mut history = MetricsHistory::new(3);
+        for minute in 0..5 {
+            history.push(MetricsSample {
+                timestamp: start + Duration::minutes(minute),
+                metrics: system.clone(),
+            });
+        }
+        assert_eq!(history.since(None).len(), 3);
+        assert_eq!(history.since(None)[0].timestamp, start + Duration::minutes(2));
+
+        let recent = history.since(Some(start + Duration::minutes(3)));
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].timestamp, start + Duration::minutes(3));
+
+        history.set_capacity(1);
+        assert_eq!(history.since(None)[0].timestamp, start + Duration::minutes(4));
+    }
+
+    #[test]
+    fn test_prometheus_exposition_format() {
+        let system = get_system_metrics(std::path::Path::new("."));
+        let name = |id: &str| if id == "skill-001" { "read_file".to_string() } else { id.to_string() };
+        let text = render_prometheus(&system, &get_llm_usage_stats(&crate::providers::PriceTable::default()), &get_skill_execution_metrics(None), &name);
+
+        assert!(text.contains("# TYPE synapse_cpu_usage_percent gauge\n"));
+        assert!(text.contains("# TYPE synapse_llm_tokens_total counter\n"));
+        assert!(text.contains("synapse_llm_tokens_total{type=\"prompt\"} 60000\n"));
+        assert!(text.contains("synapse_skill_executions_total{skill=\"read_file\",skill_id=\"skill-001\"} 100\n"));
+        assert!(text.contains("synapse_cpu_core_usage_percent{core=\"0\"} "));
+
+        // Every sample follows its family's HELP and TYPE lines
+        let mut declared = Vec::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                declared.push(rest.split(' ').next().unwrap().to_string());
+            } else if !line.starts_with("# HELP ") {
+                let (series, value) = line.rsplit_once(' ').unwrap();
+                let name = series.split('{').next().unwrap();
+                assert_eq!(declared.last().map(String::as_str), Some(name), "{}", line);
+                assert!(value.parse::<f64>().is_ok(), "{}", line);
+            }
+        }
+    }
+
+    fn usage(model: &str, hours_ago: i64, prompt_tokens: u64, completion_tokens: u64) -> UsageRecord {
+        UsageRecord {
+            timestamp: Utc::now() - Duration::hours(hours_ago),
+            provider: "openai".to_string(),
+            model: model.to_string(),
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    #[test]
+    fn test_usage_breakdown_prices_models_separately() {
+        let records = vec![usage("gpt-4o", 1, 600, 400), usage("gpt-3.5-turbo", 1, 1500, 500), usage("gpt-4o", 1, 0, 1000)];
+        let mut prices = PriceTable::default();
+        prices.set(&price("gpt-4o", 0.01, 0.01));
+        prices.set(&price("gpt-3.5-turbo", 0.001, 0.001));
+        let breakdown = usage_breakdown(&records, &prices);
+
+        let gpt4o = &breakdown.models["gpt-4o"];
+        assert_eq!((gpt4o.provider.as_str(), gpt4o.total_tokens), ("openai", 2000));
+        assert!((gpt4o.estimated_cost_usd - 0.02).abs() < 1e-9);
+        assert!((breakdown.models["gpt-3.5-turbo"].estimated_cost_usd - 0.002).abs() < 1e-9);
+        assert!((breakdown.estimated_cost_usd - 0.022).abs() < 1e-9);
+        assert_eq!(breakdown.providers["openai"], 4000);
+    }
+
+    fn price(model: &str, input_per_1k: f64, output_per_1k: f64) -> ModelPrice {
+        ModelPrice {
+            provider: "openai".to_string(),
+            model: model.to_string(),
+            input_per_1k,
+            output_per_1k,
+        }
+    }
+
+    #[test]
+    fn test_price_change_recalculates_cost() {
+        let records = vec![usage("gpt-4o", 1, 2000, 1000), usage("my-finetune", 1, 500, 500)];
+        let mut prices = PriceTable::default();
+
+        // Default gpt-4o: 2 * 0.0025 + 1 * 0.01
+        let before = usage_breakdown(&records, &prices);
+        assert!((before.models["gpt-4o"].estimated_cost_usd - 0.015).abs() < 1e-9);
+        assert!(before.models["gpt-4o"].has_price);
+        assert_eq!(before.models["my-finetune"].estimated_cost_usd, 0.0);
+        assert!(!before.models["my-finetune"].has_price);
+
+        prices.set(&price("gpt-4o", 0.005, 0.02));
+        prices.set(&price("my-finetune", 0.002, 0.004));
+        let after = usage_breakdown(&records, &prices);
+        assert!((after.models["gpt-4o"].estimated_cost_usd - 0.03).abs() < 1e-9);
+        assert!((after.models["my-finetune"].estimated_cost_usd - 0.003).abs() < 1e-9);
+        assert!(after.models["my-finetune"].has_price);
+        assert!((after.estimated_cost_usd - 0.033).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_small_budget_is_exceeded() {
+        let records = vec![usage("gpt-4o", 1, 2000, 1000)];
+        let prices = PriceTable::default();
+        let mut budgets = BudgetTracker::default();
+        budgets.set_budget(None, 0.01).unwrap();
+        budgets.set_budget(Some("anthropic".to_string()), 1.0).unwrap();
+        assert!(budgets.set_budget(None, 0.0).is_err());
+
+        // gpt-4o costs 2 * 0.0025 + 1 * 0.01 = 0.015
+        let statuses = budgets.statuses(&records, &prices);
+        let overall = statuses.iter().find(|s| s.provider.is_none()).unwrap();
+        assert!((overall.used_percent - 150.0).abs() < 1e-9);
+        assert!(overall.exceeded);
+        let anthropic = statuses.iter().find(|s| s.provider.is_some()).unwrap();
+        assert_eq!((anthropic.used_percent, anthropic.exceeded), (0.0, false));
+    }
+
+    fn budget_status(used_percent: f64) -> BudgetStatus {
+        BudgetStatus {
+            provider: None,
+            monthly_limit_usd: 10.0,
+            spent_usd: used_percent / 10.0,
+            used_percent,
+            exceeded: used_percent >= 100.0,
+        }
+    }
+
+    #[test]
+    fn test_budget_alerts_fire_once_per_month() {
+        let mut budgets = BudgetTracker::default();
+        let now = "2026-03-10T12:00:00Z".parse::<chrono::DateTime<Utc>>().unwrap();
+        let thresholds = |alerts: Vec<BudgetAlert>| alerts.iter().map(|a| a.threshold_percent).collect::<Vec<_>>();
+
+        assert!(budgets.check_alerts(&[budget_status(50.0)], now).is_empty());
+        assert_eq!(thresholds(budgets.check_alerts(&[budget_status(85.0)], now)), vec![80]);
+        assert!(budgets.check_alerts(&[budget_status(90.0)], now).is_empty());
+        assert_eq!(thresholds(budgets.check_alerts(&[budget_status(120.0)], now)), vec![100]);
+        assert!(budgets.check_alerts(&[budget_status(130.0)], now).is_empty());
+
+        let next_month = now + Duration::days(30);
+        assert_eq!(month_start(next_month).to_rfc3339(), "2026-04-01T00:00:00+00:00");
+        assert_eq!(thresholds(budgets.check_alerts(&[budget_status(85.0)], next_month)), vec![80]);
+    }
+
+    #[test]
+    fn test_usage_ledger_filters_by_period() {
+        let mut ledger = UsageLedger::default();
+        ledger.record(usage("gpt-4o", 48, 100, 100));
+        ledger.record(usage("gpt-4o", 2, 100, 100));
+        let now = Utc::now();
+
+        assert_eq!(ledger.records(Some(now - Duration::hours(24)), None).len(), 1);
+        assert_eq!(ledger.records(None, Some(now - Duration::hours(24))).len(), 1);
+        assert_eq!(ledger.records(None, None).len(), 2);
+    }
+
+    #[test]
+    fn test_usage_by_day_lists_empty_days_with_zeros() {
+        let day = |d: u32| chrono::NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let at = |d: u32, hour: u32| day(d).and_hms_opt(hour, 0, 0).unwrap().and_utc();
+        let records = vec![
+            UsageRecord { timestamp: at(1, 9), ..usage("gpt-4o", 0, 1000, 500) },
+            UsageRecord { timestamp: at(1, 23), ..usage("gpt-4o", 0, 1000, 500) },
+            UsageRecord { timestamp: at(3, 0), ..usage("gpt-4o-mini", 0, 2000, 0) },
+            UsageRecord { timestamp: at(4, 0), ..usage("gpt-4o", 0, 9999, 9999) },
+        ];
+
+        let days = usage_by_day(&records, &PriceTable::default(), day(1), day(3)).unwrap();
+        assert_eq!(days.iter().map(|d| d.date).collect::<Vec<_>>(), vec![day(1), day(2), day(3)]);
+        assert_eq!((days[0].prompt_tokens, days[0].completion_tokens, days[0].total_tokens), (2000, 1000, 3000));
+        assert!((days[0].estimated_cost_usd - 0.015).abs() < 1e-9);
+        assert_eq!((days[1].total_tokens, days[1].estimated_cost_usd), (0, 0.0));
+        assert_eq!(days[2].total_tokens, 2000);
+    }
+
+    #[test]
+    fn test_usage_by_day_rejects_reversed_and_overlong_ranges() {
+        let day = |m: u32, d: u32| chrono::NaiveDate::from_ymd_opt(2026, m, d).unwrap();
+        let reversed = usage_by_day(&[], &PriceTable::default(), day(3, 5), day(3, 4)).unwrap_err();
+        assert!(reversed.contains("before start"));
+
+        let year = usage_by_day(&[], &PriceTable::default(), day(1, 1), day(12, 31)).unwrap();
+        assert_eq!(year.len(), 365);
+        let too_long = usage_by_day(&[], &PriceTable::default(), day(1, 1), day(12, 31) + Duration::days(2)).unwrap_err();
+        assert!(too_long.contains("at most 366"));
+    }
+
+    #[test]
+    fn test_usage_reset_zeroes_counters() {
+        let mut ledger = UsageLedger::default();
+        ledger.record(usage("gpt-4o", 2, 1000, 500));
+        ledger.record(usage("gpt-4o-mini", 1, 300, 200));
+        ledger.record(UsageRecord { provider: "anthropic".to_string(), ..usage("claude-3.5-sonnet", 1, 400, 100) });
+        let prices = PriceTable::default();
+
+        let now = Utc::now();
+        let removed = ledger.reset(Some("openai"), now);
+        assert_eq!(usage_breakdown(&removed, &prices).prompt_tokens, 1300);
+        assert_eq!(ledger.records(None, None).len(), 1);
+        assert_eq!(ledger.last_reset_at(), Some(now));
+
+        ledger.reset(None, now);
+        let after = usage_breakdown(&ledger.records(None, None), &prices);
+        assert_eq!((after.prompt_tokens, after.completion_tokens), (0, 0));
+        assert_eq!(after.estimated_cost_usd, 0.0);
+    }
+
+    #[test]
+    fn test_metrics_stream_toggles_and_rejects_zero_interval() {
+        let (sender, received) = std::sync::mpsc::channel();
+        let emit = move |metrics: SystemMetrics| {
+            let _ = sender.send(metrics.protocol_version);
+        };
+        let dir = std::path::PathBuf::from(".");
+
+        assert!(start_metrics_stream(dir.clone(), std::time::Duration::ZERO, |_| {}).is_err());
+        assert!(metrics_stream_interval().is_none());
+
+        assert!(start_metrics_stream(dir.clone(), std::time::Duration::from_millis(50), emit).unwrap());
+        assert!(received.recv_timeout(std::time::Duration::from_secs(5)).is_ok());
+
+        // A second start retunes the running stream instead of spawning another
+        assert!(!start_metrics_stream(dir, std::time::Duration::from_millis(20), |_| {}).unwrap());
+        assert_eq!(metrics_stream_interval(), Some(std::time::Duration::from_millis(20)));
+        assert!(received.recv_timeout(std::time::Duration::from_secs(5)).is_ok());
+
+        assert!(stop_metrics_stream());
+        assert!(metrics_stream_interval().is_none());
+        assert!(!stop_metrics_stream());
+    }
+
+    #[test]
+    fn test_unwritable_data_dir_degrades_health() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+        let dirs = vec![
+            ("config".to_string(), dir.path().to_path_buf()),
+            ("skills".to_string(), blocker.join("skills")),
+        ];
+
+        let data_dirs = check_data_dirs(&dirs);
+        assert_eq!(data_dirs.status, "failed");
+        assert!(data_dirs.reason.contains("skills"));
+        assert!(!data_dirs.reason.contains("config"));
+
+        let audit = check_appendable("audit_log", Some(&dir.path().join("audit.jsonl"))).critical();
+        let report = summarize_health(vec![SubsystemHealth::ok("config", "Profile loaded").critical(), data_dirs, audit], Utc::now());
+        assert_eq!(report.status, "degraded");
+    }
+
+    #[test]
+    fn test_critical_failure_makes_health_unhealthy() {
+        let healthy = summarize_health(vec![SubsystemHealth::ok("config", "Profile loaded")], Utc::now());
+        assert_eq!(healthy.status, "healthy");
+
+        let report = summarize_health(
+            vec![SubsystemHealth::failed("config", "Failed to parse").critical(), SubsystemHealth::degraded("audit_log", "in memory")],
+            Utc::now(),
+        );
+        assert_eq!(report.status, "unhealthy");
+        assert_eq!(check_appendable("audit_log", None).status, "degraded");
+    }
+
+    #[test]
+    fn test_disk_headroom_threshold() {
+        let disk = |used: u64| DiskUsage {
+            mount_point: "/".to_string(),
+            used_bytes: used,
+            total_bytes: 100,
+            percent: used as f32,
+            fallback: false,
+        };
+        assert_eq!(check_disk_headroom(Some(&disk(50)), MIN_DISK_HEADROOM_PERCENT).status, "ok");
+        assert_eq!(check_disk_headroom(Some(&disk(95)), MIN_DISK_HEADROOM_PERCENT).status, "degraded");
+        assert_eq!(check_disk_headroom(Some(&disk(100)), MIN_DISK_HEADROOM_PERCENT).status, "failed");
+        assert_eq!(check_disk_headroom(None, MIN_DISK_HEADROOM_PERCENT).status, "degraded");
+    }
+}
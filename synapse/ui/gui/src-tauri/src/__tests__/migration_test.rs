@@ -0,0 +1,117 @@
+//! Tests for Configuration Migration
+//!
+//! Verifies rollback and resumability of interrupted migrations
+
+#[cfg(test)]
+mod tests {
+    use crate::migration::*;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Writes a marker file, counting how many times it was applied
+    struct MarkerStep {
+        name: &'static str,
+        applied: Arc<AtomicUsize>,
+    }
+
+    impl MigrationStep for MarkerStep {
+        fn name(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn apply(&self, root: &Path) -> Result<(), String> {
+            self.applied.fetch_add(1, Ordering::SeqCst);
+            fs::write(root.join(self.name), "done").map_err(|e| e.to_string())
+        }
+
+        fn rollback(&self, root: &Path) {
+            let _ = fs::remove_file(root.join(self.name));
+        }
+    }
+
+    /// Writes a partial file and then fails while `fail` is set
+    struct FlakyStep {
+        fail: Arc<AtomicBool>,
+    }
+
+    impl MigrationStep for FlakyStep {
+        fn name(&self) -> String {
+            "flaky".to_string()
+        }
+
+        fn apply(&self, root: &Path) -> Result<(), String> {
+            fs::write(root.join("flaky"), "partial").map_err(|e| e.to_string())?;
+            if self.fail.load(Ordering::SeqCst) {
+                return Err("simulated failure".to_string());
+            }
+            Ok(())
+        }
+
+        fn rollback(&self, root: &Path) {
+            let _ = fs::remove_file(root.join("flaky"));
+        }
+    }
+
+    #[test]
+    fn test_failed_step_rolls_back_and_resumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let first_applied = Arc::new(AtomicUsize::new(0));
+        let fail = Arc::new(AtomicBool::new(true));
+
+        let steps: Vec<Box<dyn MigrationStep + Send + Sync>> = vec![
+            Box::new(MarkerStep { name: "first", applied: first_applied.clone() }),
+            Box::new(FlakyStep { fail: fail.clone() }),
+            Box::new(MarkerStep { name: "last", applied: Arc::new(AtomicUsize::new(0)) }),
+        ];
+
+        let mut events = Vec::new();
+        let summary = run_migration(root, &steps, &mut |p| events.push(p.status.clone())).unwrap();
+
+        assert!(!summary.completed);
+        assert_eq!(summary.stopped_at.as_deref(), Some("flaky"));
+        assert_eq!(events, vec!["completed", "failed"]);
+        assert!(root.join("first").exists());
+        assert!(!root.join("flaky").exists(), "failed step must be rolled back");
+        assert!(!root.join("last").exists());
+
+        let journal = load_journal(root);
+        assert_eq!(journal.completed_steps, vec!["first"]);
+        assert_eq!(journal.failed_step.as_deref(), Some("flaky"));
+
+        fail.store(false, Ordering::SeqCst);
+        let summary = run_migration(root, &steps, &mut |_| {}).unwrap();
+
+        assert!(summary.completed);
+        assert_eq!(summary.skipped_steps, vec!["first"]);
+        assert_eq!(summary.applied_steps, vec!["flaky", "last"]);
+        assert_eq!(first_applied.load(Ordering::SeqCst), 1, "completed steps must not re-run");
+        assert!(load_journal(root).failed_step.is_none());
+    }
+
+    #[test]
+    fn test_legacy_layout_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("config.json"),
+            r#"{"language": "ru", "mode": "supervised", "llm_providers": [{"name": "a"}, {"name": "b"}]}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("data/skills")).unwrap();
+
+        let steps = plan_legacy_migration(root).unwrap();
+        let summary = run_migration(root, &steps, &mut |_| {}).unwrap();
+
+        assert!(summary.completed);
+        assert!(root.join("skills").is_dir());
+        assert!(!root.join("config.json").exists());
+
+        let config: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(root.join("config/config.json")).unwrap()).unwrap();
+        assert_eq!(config["language"], "ru");
+        assert_eq!(config["llm_providers"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,485 @@
+//! Tests for LLM Provider Helpers
+//!
+//! Verifies model-name suggestions used by catalog validation and the
+//! per-provider request formatting of connection probes, cost estimates
+//! for planned calls, offline provider config checks, and model capability
+//! hints
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{LLMProviderConfig, SynapseConfig};
+    use crate::providers::*;
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("gpt-4o", "gpt-4o"), 0);
+        assert_eq!(edit_distance("gpt4o", "gpt-4o"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_closest_model_suggests_nearest_catalog_entry() {
+        let catalog = vec!["gpt-4o".to_string(), "gpt-4o-mini".to_string(), "gpt-3.5-turbo".to_string()];
+
+        assert_eq!(closest_model("gpt4o", &catalog).as_deref(), Some("gpt-4o"));
+        assert_eq!(closest_model("gpt-3.5", &catalog).as_deref(), Some("gpt-3.5-turbo"));
+        assert!(closest_model("gpt-4o", &[]).is_none());
+    }
+
+    fn provider(name: &str, priority: u8, weight: Option<u32>) -> LLMProviderConfig {
+        LLMProviderConfig {
+            name: name.to_string(),
+            provider_type: "openai".to_string(),
+            api_key: None,
+            base_url: None,
+            model: "gpt-4o".to_string(),
+            priority,
+            is_active: true,
+            weight,
+        }
+    }
+
+    fn weighted_config() -> SynapseConfig {
+        SynapseConfig {
+            llm_providers: vec![provider("local", 2, Some(70)), provider("premium", 1, Some(30))],
+            routing_mode: "weighted".to_string(),
+            ..SynapseConfig::default()
+        }
+    }
+
+    fn failover_config(providers: Vec<LLMProviderConfig>) -> SynapseConfig {
+        SynapseConfig {
+            llm_providers: providers,
+            ..SynapseConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_active_provider_by_priority() {
+        let config = failover_config(vec![provider("backup", 2, None), provider("primary", 1, None), provider("last", 3, None)]);
+        assert_eq!(resolve_active_provider(&config).unwrap().name, "primary");
+        assert_eq!(next_provider_after(&config, "primary").unwrap().name, "backup");
+        assert_eq!(next_provider_after(&config, "backup").unwrap().name, "last");
+        assert!(next_provider_after(&config, "last").is_none());
+        assert_eq!(next_provider_after(&config, "removed").unwrap().name, "primary");
+    }
+
+    #[test]
+    fn test_priority_ties_break_by_name() {
+        let config = failover_config(vec![provider("zeta", 1, None), provider("alpha", 1, None), provider("beta", 1, None)]);
+        assert_eq!(resolve_active_provider(&config).unwrap().name, "alpha");
+        assert_eq!(next_provider_after(&config, "alpha").unwrap().name, "beta");
+        assert_eq!(next_provider_after(&config, "beta").unwrap().name, "zeta");
+    }
+
+    #[test]
+    fn test_inactive_providers_are_skipped() {
+        let inactive = |name, priority| LLMProviderConfig {
+            is_active: false,
+            ..provider(name, priority, None)
+        };
+        let config = failover_config(vec![inactive("primary", 1), provider("backup", 2, None), inactive("spare", 3)]);
+        assert_eq!(resolve_active_provider(&config).unwrap().name, "backup");
+        assert!(next_provider_after(&config, "backup").is_none());
+        assert_eq!(next_provider_after(&config, "primary").unwrap().name, "backup");
+
+        let config = failover_config(vec![inactive("primary", 1), inactive("backup", 2)]);
+        assert!(resolve_active_provider(&config).is_none());
+        assert!(next_provider_after(&config, "primary").is_none());
+    }
+
+    #[test]
+    fn test_weighted_routing_splits_by_weight() {
+        let config = weighted_config();
+
+        // Candidates are ordered by priority: premium (30) then local (70)
+        assert_eq!(route_llm_request(&config, |_| true, 0.0).unwrap().name, "premium");
+        assert_eq!(route_llm_request(&config, |_| true, 0.29).unwrap().name, "premium");
+        assert_eq!(route_llm_request(&config, |_| true, 0.31).unwrap().name, "local");
+        assert_eq!(route_llm_request(&config, |_| true, 0.99).unwrap().name, "local");
+    }
+
+    #[test]
+    fn test_routing_shares_report_the_weighted_split() {
+        let mut config = weighted_config();
+        let shares: Vec<(&str, f64)> =
+            routing_shares(&config, |_| true).into_iter().map(|(p, share)| (p.name.as_str(), share)).collect();
+        assert_eq!(shares, vec![("premium", 30.0), ("local", 70.0)]);
+
+        let shares = routing_shares(&config, |p| p.name == "local");
+        assert_eq!((shares[0].0.name.as_str(), shares[0].1), ("local", 100.0));
+
+        config.routing_mode = "priority".to_string();
+        let shares = routing_shares(&config, |_| true);
+        assert_eq!(shares.len(), 1);
+        assert_eq!((shares[0].0.name.as_str(), shares[0].1), ("premium", 100.0));
+    }
+
+    #[test]
+    fn test_weighted_routing_falls_back_to_priority() {
+        let mut config = weighted_config();
+        config.llm_providers.push(provider("backup", 3, None));
+
+        let chosen = route_llm_request(&config, |p| p.name == "backup", 0.5).unwrap();
+        assert_eq!(chosen.name, "backup");
+        assert!(route_llm_request(&config, |_| false, 0.5).is_none());
+    }
+
+    #[test]
+    fn test_weighted_mode_rejects_all_zero_weights() {
+        let mut config = weighted_config();
+        for p in config.llm_providers.iter_mut() {
+            p.weight = Some(0);
+        }
+        assert_eq!(validate_routing(&config).len(), 1);
+        assert!(validate_routing(&weighted_config()).is_empty());
+    }
+
+    #[test]
+    fn test_rate_limited_provider_is_skipped_until_cooldown_elapses() {
+        let config = SynapseConfig {
+            llm_providers: vec![provider("primary", 1, None), provider("secondary", 2, None)],
+            ..SynapseConfig::default()
+        };
+        let now = Utc::now();
+        let mut state = RouterState::default();
+
+        let cooldown = rate_limit_cooldown(429, Some("30"), None, now).unwrap();
+        state.mark_rate_limited("primary", now + cooldown);
+
+        let during = now + Duration::seconds(10);
+        assert_eq!(route_llm_request(&config, |p| state.is_available(&p.name, during), 0.0).unwrap().name, "secondary");
+        assert_eq!(state.cooldowns(during).len(), 1);
+
+        let after = now + Duration::seconds(31);
+        assert_eq!(route_llm_request(&config, |p| state.is_available(&p.name, after), 0.0).unwrap().name, "primary");
+        assert!(state.cooldowns(after).is_empty());
+    }
+
+    #[test]
+    fn test_rate_limit_cooldown_parsing() {
+        let now = Utc::now();
+        assert!(rate_limit_cooldown(200, None, Some("5"), now).is_none());
+        assert_eq!(rate_limit_cooldown(200, None, Some("0"), now), Some(Duration::seconds(60)));
+        assert_eq!(rate_limit_cooldown(429, Some("bogus"), None, now), Some(Duration::seconds(60)));
+        assert_eq!(rate_limit_cooldown(429, Some("5"), None, now), Some(Duration::seconds(5)));
+    }
+
+    fn planned(provider: &str, model: &str, prompt_tokens: u64, expected_completion_tokens: u64) -> PlannedLlmCall {
+        PlannedLlmCall {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_tokens,
+            expected_completion_tokens,
+        }
+    }
+
+    #[test]
+    fn test_estimate_known_model_splits_prompt_and_completion() {
+        let estimate = estimate_call_costs(&PriceTable::default(), &[planned("openai", "gpt-4o", 2000, 500)]);
+        let call = &estimate.calls[0];
+        assert!(call.has_price);
+        assert!((call.prompt_cost_usd.unwrap() - 0.005).abs() < 1e-9);
+        assert!((call.completion_cost_usd.unwrap() - 0.005).abs() < 1e-9);
+        assert!((estimate.total_cost_usd - 0.01).abs() < 1e-9);
+        assert!(estimate.unpriced_models.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_unknown_model_is_flagged_not_free() {
+        let estimate = estimate_call_costs(&PriceTable::default(), &[planned("openai", "gpt-9", 1000, 1000)]);
+        let call = &estimate.calls[0];
+        assert!(!call.has_price);
+        assert_eq!(call.total_cost_usd, None);
+        assert_eq!(estimate.unpriced_models, vec!["openai/gpt-9".to_string()]);
+    }
+
+    #[test]
+    fn test_estimate_batch_sums_priced_calls() {
+        let calls = [
+            planned("openai", "gpt-4o", 2000, 500),
+            planned("anthropic", "claude-3-opus", 1000, 1000),
+            planned("ollama", "llama3", 5000, 5000),
+            planned("openai", "gpt-9", 1000, 1000),
+        ];
+        let estimate = estimate_call_costs(&PriceTable::default(), &calls);
+        assert_eq!(estimate.calls.len(), 4);
+        assert!((estimate.prompt_cost_usd - 0.02).abs() < 1e-9);
+        assert!((estimate.completion_cost_usd - 0.08).abs() < 1e-9);
+        assert!((estimate.total_cost_usd - 0.1).abs() < 1e-9);
+        assert_eq!(estimate.unpriced_models, vec!["openai/gpt-9".to_string()]);
+    }
+
+    /// Serve one canned HTTP response on a local port and return its base URL
+    async fn mock_server(status_line: &'static str, body: &'static str) -> String {
+        recording_server(status_line, body).await.0
+    }
+
+    /// Like `mock_server`, also handing back the raw request head it received
+    async fn recording_server(
+        status_line: &'static str,
+        body: &'static str,
+    ) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = [0u8; 4096];
+            let read = socket.read(&mut buffer).await.unwrap_or(0);
+            let _ = sender.send(String::from_utf8_lossy(&buffer[..read]).to_lowercase());
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status_line,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{}", addr), receiver)
+    }
+
+    fn probe_target(base_url: String) -> LLMProviderConfig {
+        LLMProviderConfig {
+            base_url: Some(base_url),
+            api_key: Some("sk-test".to_string()),
+            ..provider("mock", 1, None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_connection_success() {
+        let base = mock_server("200 OK", r#"{"data": [{"id": "gpt-4o"}]}"#).await;
+        let probe = probe_connection(&probe_target(base), std::time::Duration::from_secs(5)).await.unwrap();
+        assert!(probe.connected);
+        assert!(probe.auth_ok);
+        assert_eq!(probe.status, Some(200));
+        assert_eq!(probe.available_models, vec!["gpt-4o"]);
+        assert!(probe.models_listable);
+    }
+
+    #[test]
+    fn test_parse_ollama_model_list() {
+        let body = serde_json::json!({"models": [{"name": "llama3"}, {"name": "mistral"}]});
+        assert_eq!(parse_model_list("ollama", &body).unwrap(), vec!["llama3", "mistral"]);
+        assert!(parse_model_list("openai", &body).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_probe_connection_auth_failure() {
+        let base = mock_server("401 Unauthorized", r#"{"error": "bad key"}"#).await;
+        let probe = probe_connection(&probe_target(base), std::time::Duration::from_secs(5)).await.unwrap();
+        assert!(!probe.connected);
+        assert!(!probe.auth_ok);
+        assert!(probe.message.starts_with("Authentication failed"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_connection_network_failure() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let probe = probe_connection(&probe_target(base), std::time::Duration::from_secs(5)).await.unwrap();
+        assert!(!probe.connected);
+        assert_eq!(probe.status, None);
+        assert!(probe.message.starts_with("Network error"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_results_update_router_reachability() {
+        let reachable = |config: &SynapseConfig| provider_reachability(config)[0].reachable;
+        let target = |base: String| LLMProviderConfig { name: "reachability-mock".to_string(), ..probe_target(base) };
+        let timeout = std::time::Duration::from_secs(5);
+
+        let down = target(mock_server("503 Service Unavailable", "{}").await);
+        let config = failover_config(vec![down.clone()]);
+        assert!(!probe_connection(&down, timeout).await.unwrap().connected);
+        assert!(!reachable(&config));
+        assert!(current_provider(&config).is_none());
+
+        let up = target(mock_server("200 OK", r#"{"data": []}"#).await);
+        assert!(probe_connection(&up, timeout).await.unwrap().connected);
+        assert!(reachable(&config));
+        assert_eq!(current_provider(&config).unwrap().name, "reachability-mock");
+    }
+
+    fn typed_target(provider_type: &str, base_url: String) -> LLMProviderConfig {
+        LLMProviderConfig {
+            provider_type: provider_type.to_string(),
+            model: "test-model".to_string(),
+            ..probe_target(base_url)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openai_probe_sends_bearer_auth() {
+        let (base, request) = recording_server("200 OK", r#"{"data": []}"#).await;
+        let probe = probe_connection(&typed_target("openai", base), std::time::Duration::from_secs(5)).await.unwrap();
+        assert!(probe.connected);
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("get /models "));
+        assert!(request.contains("authorization: bearer sk-test\r\n"));
+        assert!(!request.contains("x-api-key"));
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_probe_sends_api_key_and_version() {
+        let (base, request) = recording_server("200 OK", r#"{"id": "msg_1"}"#).await;
+        let probe = probe_connection(&typed_target("anthropic", base), std::time::Duration::from_secs(5)).await.unwrap();
+        assert!(probe.connected);
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("post /messages "));
+        assert!(request.contains("x-api-key: sk-test\r\n"));
+        assert!(request.contains(&format!("anthropic-version: {}\r\n", ANTHROPIC_VERSION)));
+        assert!(!request.contains("authorization:"));
+    }
+
+    #[tokio::test]
+    async fn test_ollama_probe_sends_no_auth() {
+        let (base, request) = recording_server("200 OK", r#"{"models": [{"name": "llama3"}]}"#).await;
+        let probe = probe_connection(&typed_target("ollama", base), std::time::Duration::from_secs(5)).await.unwrap();
+        assert_eq!(probe.available_models, vec!["llama3"]);
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("get /api/tags "));
+        assert!(!request.contains("authorization:"));
+        assert!(!request.contains("x-api-key"));
+    }
+
+    #[tokio::test]
+    async fn test_custom_provider_probes_arbitrary_base_url() {
+        let (base, request) = recording_server("200 OK", r#"{"data": [{"id": "mistral-7b"}]}"#).await;
+        let provider = typed_target("custom_openai", format!("{}/vllm/v1/", base));
+        let probe = probe_connection(&provider, std::time::Duration::from_secs(5)).await.unwrap();
+        assert!(probe.connected);
+        assert_eq!(probe.available_models, vec!["mistral-7b"]);
+        assert!(probe.models_listable);
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("get /vllm/v1/models "));
+        assert!(request.contains("authorization: bearer sk-test\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_custom_provider_tolerates_missing_model_listing() {
+        let (base, request) = recording_server("404 Not Found", r#"{"detail": "Not Found"}"#).await;
+        let provider = LLMProviderConfig {
+            api_key: None,
+            ..typed_target("custom_openai", base)
+        };
+        let probe = probe_connection(&provider, std::time::Duration::from_secs(5)).await.unwrap();
+        assert!(probe.connected);
+        assert!(probe.available_models.is_empty());
+        assert!(!probe.models_listable);
+        assert!(!request.await.unwrap().contains("authorization:"));
+
+        // The same 404 from a real OpenAI endpoint is a failure
+        let base = mock_server("404 Not Found", "{}").await;
+        assert!(!probe_connection(&typed_target("openai", base), std::time::Duration::from_secs(5)).await.unwrap().connected);
+
+        let without_base = LLMProviderConfig {
+            base_url: None,
+            ..typed_target("custom_openai", String::new())
+        };
+        let error = probe_connection(&without_base, std::time::Duration::from_secs(1)).await.unwrap_err();
+        assert!(error.starts_with("No base_url configured"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_provider_is_unsupported() {
+        let provider = typed_target("cohere", "http://127.0.0.1:9".to_string());
+        let error = probe_connection(&provider, std::time::Duration::from_secs(1)).await.unwrap_err();
+        assert_eq!(error, "Unsupported provider 'cohere'; expected one of openai, anthropic, ollama, custom_openai");
+        assert_eq!(list_provider_models(&provider).await.unwrap_err(), error);
+    }
+
+    #[test]
+    fn test_known_model_capability_hints() {
+        let hints = model_capability_hints(&SynapseConfig::default(), "openai", "gpt-4o");
+        assert!(hints.known);
+        assert_eq!(hints.context_window, Some(128_000));
+        assert_eq!((hints.supports_vision, hints.supports_tools), (Some(true), Some(true)));
+
+        let llama = model_capability_hints(&SynapseConfig::default(), "ollama", "llama3");
+        assert_eq!((llama.supports_vision, llama.supports_tools), (Some(false), Some(false)));
+    }
+
+    #[test]
+    fn test_unknown_model_falls_back_to_config_then_nulls() {
+        let unknown = model_capability_hints(&SynapseConfig::default(), "custom_openai", "qwen2-vl");
+        assert!(!unknown.known);
+        assert_eq!((unknown.context_window, unknown.supports_vision, unknown.supports_tools), (None, None, None));
+
+        let config = SynapseConfig {
+            model_capabilities: vec![ModelCapabilityOverride {
+                provider: "custom_openai".to_string(),
+                model: "qwen2-vl".to_string(),
+                capabilities: ModelCapabilities { context_window: 32_768, supports_tools: false, supports_vision: true },
+            }],
+            ..SynapseConfig::default()
+        };
+        let custom = model_capability_hints(&config, "custom_openai", "qwen2-vl");
+        assert!(custom.known);
+        assert_eq!((custom.context_window, custom.supports_vision), (Some(32_768), Some(true)));
+        // Overrides only apply to the provider they name
+        assert!(!model_capability_hints(&config, "ollama", "qwen2-vl").known);
+    }
+
+    /// Fields `check_provider_fields` rejects for a provider of `provider_type`
+    fn invalid_fields(provider_type: &str, api_key: Option<&str>, base_url: Option<&str>) -> Vec<String> {
+        let provider = LLMProviderConfig {
+            provider_type: provider_type.to_string(),
+            api_key: api_key.map(str::to_string),
+            base_url: base_url.map(str::to_string),
+            ..provider("candidate", 1, None)
+        };
+        check_provider_fields(&provider).into_iter().filter(|f| !f.valid).map(|f| f.field).collect()
+    }
+
+    #[test]
+    fn test_hosted_providers_require_an_api_key() {
+        for provider_type in ["openai", "anthropic"] {
+            assert_eq!(invalid_fields(provider_type, None, None), vec!["api_key"]);
+            assert_eq!(invalid_fields(provider_type, Some("  "), None), vec!["api_key"]);
+            assert!(invalid_fields(provider_type, Some("sk-test"), None).is_empty());
+        }
+        let checks = check_provider_fields(&provider("premium", 1, None));
+        assert_eq!(checks.len(), 6);
+        assert_eq!(checks[2].message.as_deref(), Some("An API key is required for openai providers"));
+    }
+
+    #[test]
+    fn test_local_providers_require_a_base_url_only_without_a_default() {
+        assert!(invalid_fields("ollama", None, None).is_empty());
+        assert!(invalid_fields("ollama", None, Some("http://gpu-box:11434")).is_empty());
+        assert_eq!(invalid_fields("ollama", None, Some("ftp://gpu-box")), vec!["base_url"]);
+
+        assert_eq!(invalid_fields("custom_openai", None, None), vec!["base_url"]);
+        assert_eq!(invalid_fields("custom_openai", None, Some("")), vec!["base_url"]);
+        assert!(invalid_fields("custom_openai", None, Some("http://localhost:8000/v1")).is_empty());
+
+        assert_eq!(invalid_fields("cohere", Some("key"), None), vec!["provider_type"]);
+    }
+
+    #[test]
+    fn test_provider_priority_and_model_are_checked() {
+        let unranked = LLMProviderConfig {
+            api_key: Some("sk-test".to_string()),
+            model: " ".to_string(),
+            ..provider("premium", 0, None)
+        };
+        let invalid: Vec<_> = check_provider_fields(&unranked).into_iter().filter(|f| !f.valid).collect();
+        assert_eq!(invalid.iter().map(|f| f.field.as_str()).collect::<Vec<_>>(), vec!["model", "priority"]);
+        assert_eq!(invalid[1].message.as_deref(), Some("Priority 0 is outside 1-100"));
+
+        let edge = LLMProviderConfig { priority: 100, ..unranked };
+        assert!(check_provider_fields(&edge).iter().any(|f| f.field == "priority" && f.valid));
+    }
+}
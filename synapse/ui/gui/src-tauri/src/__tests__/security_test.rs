@@ -0,0 +1,437 @@
+//! Tests for Security Helpers
+//!
+//! Verifies key exposure checks never depend on or reveal the full key
+
+#[cfg(test)]
+mod tests {
+    use crate::security::*;
+
+    #[tokio::test]
+    async fn test_placeholder_key_is_flagged_for_rotation() {
+        let status = check_key_exposure("openai", Some("sk-test-your-api-key"), None).await;
+        assert_eq!(status.status, "exposed");
+        assert!(status.rotate_now);
+        assert!(!status.online_checked);
+        assert_eq!(status.key_hint.as_deref(), Some("...-key"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_and_clean_keys() {
+        let missing = check_key_exposure("ollama", None, None).await;
+        assert_eq!(missing.status, "no_key");
+
+        let clean = check_key_exposure("anthropic", Some("sk-ant-a8F3kQ9zL2"), None).await;
+        assert_eq!(clean.status, "ok");
+        assert!(!clean.rotate_now);
+    }
+
+    #[test]
+    fn test_key_fingerprint_is_sha256_hex() {
+        let fingerprint = key_fingerprint("abc");
+        assert_eq!(fingerprint.len(), 64);
+        assert!(fingerprint.starts_with("BA7816BF"));
+    }
+
+    fn caps(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_effective_capabilities_narrow_to_grant_scope() {
+        let declared = caps(&["fs:read", "network:http:api.example.com", "process:spawn"]);
+        let granted = caps(&["fs:write:/home/user/docs", "network:http:example.com"]);
+
+        let result = effective_capabilities(&declared, &granted);
+        assert_eq!(result.effective, caps(&["fs:read:/home/user/docs", "network:http:api.example.com"]));
+        assert_eq!(result.missing, caps(&["process:spawn"]));
+        assert_eq!(result.declared, declared);
+        assert_eq!(result.available, granted);
+    }
+
+    #[test]
+    fn test_disjoint_scopes_do_not_grant() {
+        let result = effective_capabilities(&caps(&["fs:read:/etc"]), &caps(&["fs:read:/home/user"]));
+        assert!(result.effective.is_empty());
+        assert_eq!(result.missing, caps(&["fs:read:/etc"]));
+    }
+
+    #[test]
+    fn test_parent_dir_scope_does_not_escape_grant() {
+        let granted = caps(&["fs:read:/data"]);
+        let escaping = effective_capabilities(&caps(&["fs:read:/data/../etc"]), &granted);
+        assert!(escaping.effective.is_empty());
+        assert_eq!(escaping.missing, caps(&["fs:read:/data/../etc"]));
+
+        let above_root = effective_capabilities(&caps(&["fs:read:/../data"]), &granted);
+        assert!(above_root.effective.is_empty());
+        let staying = effective_capabilities(&caps(&["fs:read:/data/tmp/../reports"]), &granted);
+        assert_eq!(staying.effective, caps(&["fs:read:/data/tmp/../reports"]));
+    }
+
+    #[test]
+    fn test_redact_secrets_masks_known_and_key_shaped_words() {
+        let text = "auth failed for sk-proj-abcdef1234567890 using my-own-secret, retry";
+        let redacted = redact_secrets(text, &["my-own-secret"]);
+        assert_eq!(redacted, "auth failed for [REDACTED] using [REDACTED], retry");
+        assert_eq!(redact_secrets("sk-short is fine", &[]), "sk-short is fine");
+    }
+
+    fn write_policy(dir: &std::path::Path, raw: &str) -> std::path::PathBuf {
+        let path = dir.join(ORG_POLICY_FILE);
+        std::fs::write(&path, raw).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_org_policy_denies_and_cites_rule() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_policy(dir.path(), r#"{
+            "user_roles": {"alice": ["engineer"]},
+            "rules": [
+                {"id": "eng-fs", "subjects": ["role:engineer"], "allow": ["fs:write:/srv"], "deny": ["fs:write:/srv/secrets"]},
+                {"id": "no-shell", "subjects": ["*"], "deny": ["process:*"]}
+            ]
+        }"#);
+        let state = load_org_policy(&path);
+
+        assert!(state.check_grant("alice", "fs:read:/srv/app").is_ok());
+        assert_eq!(state.check_grant("alice", "fs:write:/srv/secrets/db").unwrap_err().rule.as_deref(), Some("eng-fs"));
+        assert_eq!(state.check_grant("alice", "process:spawn").unwrap_err().rule.as_deref(), Some("no-shell"));
+        // Unscoped fs:write includes /srv/secrets, so the deny fires first
+        assert_eq!(state.check_grant("alice", "fs:write").unwrap_err().rule.as_deref(), Some("eng-fs"));
+        assert!(state.check_grant("bob", "fs:read:/srv").is_err());
+    }
+
+    #[test]
+    fn test_org_policy_scoped_deny_blocks_unscoped_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_policy(dir.path(), r#"{
+            "rules": [{"id": "no-etc", "subjects": ["*"], "allow": ["fs:*"], "deny": ["fs:write:/etc"]}]
+        }"#);
+        let state = load_org_policy(&path);
+
+        assert_eq!(state.check_grant("dev", "fs:write").unwrap_err().rule.as_deref(), Some("no-etc"));
+        assert!(state.check_grant("dev", "fs:write:/etc/hosts").is_err());
+        assert!(state.check_grant("dev", "fs:write:/srv").is_ok());
+        assert!(state.check_grant("dev", "fs:read:/etc").is_ok());
+    }
+
+    #[test]
+    fn test_org_policy_load_errors_fail_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(load_org_policy(&dir.path().join("missing.json")), OrgPolicyState::Absent));
+
+        let broken = load_org_policy(&write_policy(dir.path(), "{ not json"));
+        assert!(matches!(broken, OrgPolicyState::Invalid(_)));
+        assert!(broken.check_grant("admin", "fs:read").is_err());
+    }
+
+    #[test]
+    fn test_secret_encryption_round_trip() {
+        let key = [7u8; 32];
+        let stored = encrypt_secret_with(&key, "sk-live-123").unwrap();
+        assert!(is_encrypted(&stored));
+        assert!(!stored.contains("sk-live-123"));
+        assert_eq!(decrypt_secret_with(&key, &stored).unwrap(), "sk-live-123");
+        assert!(decrypt_secret_with(&[8u8; 32], &stored).is_err());
+        assert_eq!(decrypt_secret_with(&key, "legacy-plaintext").unwrap(), "legacy-plaintext");
+    }
+
+    #[test]
+    fn test_config_keys_encrypted_with_mock_keychain() {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        let mut config = crate::commands::SynapseConfig::default();
+        config.llm_providers[0].api_key = Some("sk-live-abcdef".to_string());
+        crate::commands::write_config_file(&path, &config).unwrap();
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(raw.contains(ENCRYPTED_PREFIX));
+        assert!(!raw.contains("sk-live-abcdef"));
+        assert_eq!(crate::commands::read_config_file(&path).unwrap().unwrap(), config);
+    }
+
+    fn issue(key: &[u8], ttl_seconds: Option<u64>) -> IssuedCapabilityToken {
+        let capabilities = vec!["fs:read:/workspace".to_string(), "network:http".to_string()];
+        issue_capability_token(key, "dev", capabilities, vec![], ttl_seconds, chrono::Utc::now()).unwrap()
+    }
+
+    #[test]
+    fn test_issued_token_signature_verifies() {
+        let key = hex::decode(generate_signing_key()).unwrap();
+        let token = issue(&key, Some(3600));
+        assert!(token.token_id.starts_with("cap-"));
+        assert_eq!(token.expires_at.unwrap() - token.issued_at, chrono::Duration::seconds(3600));
+        assert!(verify_capability_token(&key, &token));
+        assert!(capability_token_is_valid(&key, &token, chrono::Utc::now()));
+        assert!(!capability_token_is_valid(&key, &token, token.issued_at + chrono::Duration::hours(2)));
+        assert!(!verify_capability_token(b"some other key", &token));
+    }
+
+    #[test]
+    fn test_tampered_token_is_rejected() {
+        let key = hex::decode(generate_signing_key()).unwrap();
+        let token = issue(&key, None);
+
+        let mut escalated = token.clone();
+        escalated.capabilities.push("process:spawn".to_string());
+        assert!(!verify_capability_token(&key, &escalated));
+
+        let mut extended = issue(&key, Some(60));
+        extended.expires_at = None;
+        assert!(!verify_capability_token(&key, &extended));
+
+        let mut reassigned = token.clone();
+        reassigned.user_id = "admin".to_string();
+        assert!(!verify_capability_token(&key, &reassigned));
+
+        let mut garbled = token;
+        garbled.signature = "not hex".to_string();
+        assert!(!verify_capability_token(&key, &garbled));
+    }
+
+    #[test]
+    fn test_issue_rejects_unknown_capabilities() {
+        let key = hex::decode(generate_signing_key()).unwrap();
+        let now = chrono::Utc::now();
+        let error = issue_capability_token(&key, "dev", vec!["fs:read".to_string(), "db:drop".to_string()], vec![], None, now).unwrap_err();
+        assert_eq!(error, "Unknown capability 'db:drop'");
+        assert!(issue_capability_token(&key, "dev", vec![], vec![], None, now).is_err());
+        assert!(issue_capability_token(&key, "dev", vec!["fs:read".to_string()], vec![], Some(0), now).is_err());
+    }
+
+    #[test]
+    fn test_capability_tokens_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CAPABILITY_TOKENS_FILE);
+        assert!(load_capability_tokens(&path).unwrap().is_empty());
+        assert!(capability_tokens_missing(&path));
+
+        let key = hex::decode(generate_signing_key()).unwrap();
+        let first = issue(&key, None);
+        let second = issue(&key, Some(60));
+        save_capability_token(&path, &first).unwrap();
+        save_capability_token(&path, &second).unwrap();
+        assert_eq!(load_capability_tokens(&path).unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_skill_scoped_token_only_authorizes_skills_in_scope() {
+        let key = hex::decode(generate_signing_key()).unwrap();
+        let now = chrono::Utc::now();
+        let scope = vec!["skill-002".to_string(), " skill-001 ".to_string(), "skill-002".to_string()];
+        let token = issue_capability_token(&key, "dev", vec!["network:http".to_string()], scope, None, now).unwrap();
+        assert_eq!(token.skill_scope, vec!["skill-001", "skill-002"]);
+
+        assert!(validate_capability(&key, &token, "network:http", Some("skill-001"), now).is_ok());
+        assert_eq!(
+            validate_capability(&key, &token, "network:http", Some("skill-003"), now).unwrap_err(),
+            format!("Token '{}' is not scoped to skill 'skill-003'", token.token_id)
+        );
+        assert!(validate_capability(&key, &token, "network:http", None, now).is_err());
+        assert!(validate_capability(&key, &token, "fs:read", Some("skill-001"), now).is_err());
+
+        // An unscoped token is global, and the scope is covered by the signature
+        let global = issue(&key, None);
+        assert!(validate_capability(&key, &global, "network:http", Some("skill-003"), now).is_ok());
+        let mut widened = token;
+        widened.skill_scope.push("skill-003".to_string());
+        assert!(!verify_capability_token(&key, &widened));
+        let mut unscoped = widened;
+        unscoped.skill_scope.clear();
+        assert!(!verify_capability_token(&key, &unscoped));
+
+        let blank = issue_capability_token(&key, "dev", vec!["fs:read".to_string()], vec![" ".to_string()], None, now);
+        assert!(blank.is_err());
+    }
+
+    #[test]
+    fn test_validate_capability_for_valid_expired_and_revoked_tokens() {
+        let key = hex::decode(generate_signing_key()).unwrap();
+        let now = chrono::Utc::now();
+        let valid = issue(&key, Some(3600));
+        assert!(validate_capability(&key, &valid, "fs:read:/workspace/src", None, now).is_ok());
+        assert!(validate_capability(&key, &valid, "network:http", None, now).is_ok());
+        assert_eq!(
+            validate_capability(&key, &valid, "fs:write", None, now).unwrap_err(),
+            format!("Token '{}' does not grant 'fs:write'", valid.token_id)
+        );
+
+        let later = now + chrono::Duration::hours(2);
+        assert!(validate_capability(&key, &valid, "network:http", None, later).unwrap_err().contains("expired at"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CAPABILITY_TOKENS_FILE);
+        save_capability_token(&path, &valid).unwrap();
+        let revoked = revoke_capability_token(&path, &valid.token_id, "admin", now).unwrap();
+        assert_eq!(revoked.revoked_by.as_deref(), Some("admin"));
+        assert!(validate_capability(&key, &revoked, "network:http", None, now).unwrap_err().contains("was revoked by admin"));
+        assert!(!capability_token_is_valid(&key, &load_capability_tokens(&path).unwrap()[0], now));
+    }
+
+    #[test]
+    fn test_corrupt_token_file_is_not_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CAPABILITY_TOKENS_FILE);
+        std::fs::write(&path, "{ not json").unwrap();
+
+        // A broken file must not reopen first-token bootstrap
+        assert!(load_capability_tokens(&path).is_err());
+        assert!(!capability_tokens_missing(&path));
+    }
+
+    #[test]
+    fn test_revoking_twice_keeps_first_revocation() {
+        let key = hex::decode(generate_signing_key()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CAPABILITY_TOKENS_FILE);
+        let token = issue(&key, None);
+        save_capability_token(&path, &token).unwrap();
+
+        let now = chrono::Utc::now();
+        let first = revoke_capability_token(&path, &token.token_id, "admin", now).unwrap();
+        let second = revoke_capability_token(&path, &token.token_id, "auditor", now + chrono::Duration::minutes(5)).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(second.revoked_by.as_deref(), Some("admin"));
+        assert!(revoke_capability_token(&path, "cap-missing", "admin", now).is_err());
+    }
+
+    #[test]
+    fn test_add_trusted_user_rejects_duplicates() {
+        let mut trusted = vec!["alice".to_string()];
+        add_trusted_user(&mut trusted, " bob ").unwrap();
+        assert_eq!(trusted, vec!["alice", "bob"]);
+
+        assert_eq!(add_trusted_user(&mut trusted, "bob").unwrap_err(), "User 'bob' is already trusted");
+        assert!(add_trusted_user(&mut trusted, "  ").is_err());
+        assert_eq!(trusted.len(), 2);
+    }
+
+    #[test]
+    fn test_remove_missing_trusted_user_is_noop() {
+        let mut trusted = vec!["alice".to_string(), "bob".to_string()];
+        assert!(remove_trusted_user(&mut trusted, "alice"));
+        assert!(!remove_trusted_user(&mut trusted, "carol"));
+        assert_eq!(trusted, vec!["bob"]);
+    }
+
+    #[test]
+    fn test_idempotency_cache_replays_until_window_expires() {
+        let mut cache = IdempotencyCache::default();
+        let start = chrono::Utc::now();
+        let response = serde_json::json!({"success": true, "data": {"approved": true}});
+        assert_eq!(cache.begin("k1", "skill_approve:skill-001:alice", 60, start), Ok(None));
+        cache.store("k1", "skill_approve:skill-001:alice", response.clone(), start);
+
+        let retry = start + chrono::Duration::minutes(59);
+        assert_eq!(cache.begin("k1", "skill_approve:skill-001:alice", 60, retry), Ok(Some(response)));
+        let conflict = cache.begin("k1", "skill_reject:skill-001:alice", 60, retry).unwrap_err();
+        assert!(conflict.contains("skill_approve:skill-001"));
+        assert!(cache.begin("k1", "skill_approve:skill-001:mallory", 60, retry).is_err());
+
+        let expired = start + chrono::Duration::minutes(60);
+        assert_eq!(cache.begin("k1", "skill_reject:skill-001:alice", 60, expired), Ok(None));
+    }
+
+    #[test]
+    fn test_idempotency_cache_reserves_key_while_in_flight() {
+        let mut cache = IdempotencyCache::default();
+        let now = chrono::Utc::now();
+        assert_eq!(cache.begin("k1", "skill_archive:skill-003:alice", 60, now), Ok(None));
+
+        // A concurrent first call must not apply the action a second time
+        let concurrent = cache.begin("k1", "skill_archive:skill-003:alice", 60, now).unwrap_err();
+        assert!(concurrent.contains("still in progress"));
+
+        // A failed request frees the key for a retry
+        cache.release("k1", "skill_archive:skill-003:alice");
+        assert_eq!(cache.begin("k1", "skill_archive:skill-003:alice", 60, now), Ok(None));
+        cache.store("k1", "skill_archive:skill-003:alice", serde_json::json!({"success": true}), now);
+        cache.release("k1", "skill_archive:skill-003:alice");
+        assert!(cache.begin("k1", "skill_archive:skill-003:alice", 60, now).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_rate_limiter_throttles_until_window_passes() {
+        let mut limiter = RateLimiter::default();
+        let start = chrono::Utc::now();
+        for i in 0..3 {
+            assert!(limiter.check("alice", 3, start + chrono::Duration::seconds(i)).is_ok());
+        }
+
+        let blocked = start + chrono::Duration::seconds(10);
+        assert_eq!(limiter.check("alice", 3, blocked), Err(50));
+        assert!(limiter.check("bob", 3, blocked).is_ok());
+
+        // The first request leaves the window after 60s, freeing one slot
+        let reopened = start + chrono::Duration::seconds(RATE_LIMIT_WINDOW_SECONDS);
+        assert!(limiter.check("alice", 3, reopened).is_ok());
+        assert_eq!(limiter.check("alice", 3, reopened), Err(1));
+    }
+
+    #[test]
+    fn test_skill_quota_blocks_runs_until_window_rolls_over() {
+        let mut ledger = SkillQuotaLedger::default();
+        let start = chrono::Utc::now();
+        for i in 0..2 {
+            let quota = ledger.consume("alice", "skill-001", Some(2), start + chrono::Duration::minutes(i)).unwrap();
+            assert_eq!(quota.remaining, Some(1 - i as u32));
+        }
+
+        let blocked = start + chrono::Duration::minutes(10);
+        let exceeded = ledger.consume("alice", "skill-001", Some(2), blocked).unwrap_err();
+        assert_eq!((exceeded.used, exceeded.remaining), (2, Some(0)));
+        assert_eq!(exceeded.resets_at, Some(start + chrono::Duration::seconds(SKILL_QUOTA_WINDOW_SECONDS)));
+        assert_eq!(exceeded.retry_after_seconds(blocked), 50 * 60);
+        assert_eq!(ledger.status("alice", "skill-001", Some(2), blocked).used, 2);
+        // Other skills and other users count separately
+        assert!(ledger.consume("alice", "skill-002", Some(2), blocked).is_ok());
+        assert!(ledger.consume("bob", "skill-001", Some(2), blocked).is_ok());
+
+        // The first run leaves the window after an hour, freeing one slot
+        let reopened = start + chrono::Duration::seconds(SKILL_QUOTA_WINDOW_SECONDS);
+        assert_eq!(ledger.consume("alice", "skill-001", Some(2), reopened).unwrap().remaining, Some(0));
+        assert!(ledger.consume("alice", "skill-001", Some(2), reopened).is_err());
+
+        let unlimited = ledger.consume("carol", "skill-001", None, start).unwrap();
+        assert_eq!((unlimited.limit, unlimited.remaining, unlimited.retry_after_seconds(start)), (None, None, 0));
+    }
+
+    #[test]
+    fn test_session_expires_after_idle_window() {
+        let mut registry = SessionRegistry::default();
+        let start = chrono::Utc::now();
+        let session = registry.create("alice", start);
+
+        let late = start + chrono::Duration::minutes(30);
+        match registry.touch(&session.session_id, 30, late) {
+            Err(SessionError::Expired(expired)) => assert_eq!(expired.user_id, "alice"),
+            other => panic!("expected an expired session, got {:?}", other),
+        }
+        // Once expired the session is gone
+        assert_eq!(
+            registry.touch(&session.session_id, 30, late),
+            Err(SessionError::NotFound(session.session_id.clone()))
+        );
+    }
+
+    #[test]
+    fn test_session_activity_refreshes_idle_window() {
+        let mut registry = SessionRegistry::default();
+        let start = chrono::Utc::now();
+        let session = registry.create("alice", start);
+        let idle = registry.create("bob", start);
+
+        for minutes in [20, 40, 60] {
+            let now = start + chrono::Duration::minutes(minutes);
+            assert_eq!(registry.touch(&session.session_id, 30, now).unwrap().last_active_at, now);
+        }
+
+        let expired = registry.expire_idle(30, start + chrono::Duration::minutes(61));
+        assert_eq!(expired, vec![idle]);
+        assert_eq!(registry.end(&session.session_id).unwrap().user_id, "alice");
+        assert!(registry.end(&session.session_id).is_none());
+    }
+}
@@ -0,0 +1,459 @@
+//! Tests for Skills Management
+//!
+//! Verifies registry backup/restore, lifecycle rules and manifest signatures
+
+#[cfg(test)]
+mod tests {
+    use crate::skills::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+
+    fn write_skill(skills_dir: &Path, name: &str, manifest: serde_json::Value) {
+        let dir = skills_dir.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        fs::write(dir.join(STATE_FILE), r#"{"status": "active", "trust_level": "trusted"}"#).unwrap();
+    }
+
+    #[test]
+    fn test_skills_backup_restores_as_pending() {
+        let source = tempfile::tempdir().unwrap();
+        let backups = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+
+        write_skill(source.path(), "read_file", serde_json::json!({
+            "name": "read_file",
+            "version": "1.0.0",
+            "required_capabilities": ["fs:read"],
+            "risk_level": 1
+        }));
+        write_skill(source.path(), "broken", serde_json::json!({
+            "name": "broken",
+            "required_capabilities": []
+        }));
+
+        let mut history = SkillVersionHistory::default();
+        let version = SkillVersion {
+            version: "0.9.0".to_string(),
+            created_at: chrono::Utc::now(),
+            risk_level: 1,
+            trust_level: "verified".to_string(),
+        };
+        history.record("read_file", version.clone());
+        let backup = backup_skills(source.path(), backups.path(), &history).unwrap();
+        assert_eq!(backup.skills_backed_up, 2);
+        assert_eq!(backup.versions_backed_up, 1);
+
+        // A skill already there is replaced by the restored one
+        write_skill(target.path(), "read_file", serde_json::json!({ "name": "stale" }));
+        let mut restored_history = SkillVersionHistory::default();
+        let report = restore_skills(Path::new(&backup.archive_path), target.path(), &mut restored_history).unwrap();
+        assert_eq!(report.restored, vec!["read_file"]);
+        assert_eq!(report.versions_restored, 1);
+        assert_eq!(restored_history.versions("read_file"), vec![version]);
+        let manifest = fs::read_to_string(target.path().join("read_file").join(MANIFEST_FILE)).unwrap();
+        assert!(manifest.contains("1.0.0"));
+        // Nothing is left behind from staging the copy
+        assert_eq!(fs::read_dir(target.path()).unwrap().count(), 1);
+        assert!(report.failed.contains_key("broken"));
+        assert!(!target.path().join("broken").exists());
+
+        let state: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(target.path().join("read_file").join(STATE_FILE)).unwrap()).unwrap();
+        assert_eq!(state["status"], "pending");
+        assert_eq!(state["trust_level"], "unverified");
+    }
+
+    #[test]
+    fn test_skills_restore_rejects_escaping_names_and_bad_signatures() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = |name: &str| root.path().join(name);
+        let (source, backups, target) = (dir("source"), dir("backups"), dir("target"));
+        let mut tampered = signed(signing_manifest(), &ed25519_dalek::SigningKey::from_bytes(&[7; 32]));
+        tampered["risk_level"] = serde_json::json!(1);
+        write_skill(&source, "tampered", tampered);
+        let backup = backup_skills(&source, &backups, &SkillVersionHistory::default()).unwrap();
+
+        // Re-pack the archive with an index entry that walks out of the skills directory
+        let staging = tempfile::tempdir().unwrap();
+        let archive = fs::File::open(&backup.archive_path).unwrap();
+        tar::Archive::new(flate2::read::GzDecoder::new(archive)).unpack(staging.path()).unwrap();
+        let mut index: HashMap<String, String> =
+            serde_json::from_slice(&fs::read(staging.path().join("skills_index.json")).unwrap()).unwrap();
+        index.insert("../escaped".to_string(), index["tampered"].clone());
+        fs::write(staging.path().join("skills_index.json"), serde_json::to_vec(&index).unwrap()).unwrap();
+        fs::create_dir_all(dir("escaped")).unwrap();
+        fs::write(dir("escaped").join("keep.txt"), "untouched").unwrap();
+        let crafted = dir("crafted.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(fs::File::create(&crafted).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder.append_dir_all(".", staging.path()).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let report = restore_skills(&crafted, &target, &mut SkillVersionHistory::default()).unwrap();
+        assert!(report.restored.is_empty());
+        assert!(report.failed["../escaped"].contains("not a valid skill directory name"));
+        assert!(report.failed["tampered"].starts_with("Signature does not match"));
+        assert_eq!(fs::read_to_string(dir("escaped").join("keep.txt")).unwrap(), "untouched");
+        assert!(!target.join("tampered").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restored_skill_refuses_symlinks() {
+        let root = tempfile::tempdir().unwrap();
+        let staged = root.path().join("staged");
+        let skills_dir = root.path().join("skills");
+        fs::create_dir_all(&skills_dir).unwrap();
+        write_skill(root.path(), "staged", serde_json::json!({ "name": "linked" }));
+        fs::write(root.path().join("secret.txt"), "outside").unwrap();
+        std::os::unix::fs::symlink(root.path().join("secret.txt"), staged.join("secret.txt")).unwrap();
+        write_skill(&skills_dir, "linked", serde_json::json!({ "name": "previous" }));
+
+        let error = install_restored_skill(&staged, &skills_dir, "linked").unwrap_err();
+        assert!(error.contains("symbolic link"));
+        let kept = fs::read_to_string(skills_dir.join("linked").join(MANIFEST_FILE)).unwrap();
+        assert!(kept.contains("previous"));
+        assert_eq!(fs::read_dir(&skills_dir).unwrap().count(), 1);
+    }
+
+    fn manifest_with_inputs(inputs: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "name": "example",
+            "version": "1.0.0",
+            "required_capabilities": [],
+            "risk_level": 1,
+            "inputs": inputs
+        })
+    }
+
+    #[test]
+    fn test_manifest_schema_with_undefined_type_is_rejected() {
+        let manifest = manifest_with_inputs(serde_json::json!({
+            "query": {"type": "text", "required": true}
+        }));
+
+        let errors = validate_manifest(&manifest);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("inputs.query"));
+        assert!(!lint_skill_manifest(&manifest).valid);
+    }
+
+    #[test]
+    fn test_manifest_schema_required_must_be_declared() {
+        let manifest = manifest_with_inputs(serde_json::json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}},
+            "required": ["query", "limit"]
+        }));
+
+        let errors = validate_manifest(&manifest);
+        assert_eq!(errors, vec!["inputs: required property 'limit' is not declared"]);
+    }
+
+    #[test]
+    fn test_manifest_schema_valid_shorthand() {
+        let manifest = manifest_with_inputs(serde_json::json!({
+            "query": {"type": "string", "required": true, "description": "Search terms"}
+        }));
+        assert!(validate_manifest(&manifest).is_empty());
+    }
+
+    #[test]
+    fn test_skill_schema_valid_inputs_and_outputs() {
+        let inputs = serde_json::json!({
+            "type": "object",
+            "properties": {"query": {"type": "string"}, "limit": {"type": "integer", "minimum": 1}},
+            "required": ["query"]
+        });
+        let outputs = serde_json::json!({
+            "results": {"type": "array", "items": {"type": "object", "properties": {"url": {"type": "string"}}}}
+        });
+        let report = validate_skill_schemas("skill-x", &inputs, &outputs);
+        assert!(report.valid, "{:?}", report.errors);
+        assert!(validate_skill_schemas("skill-x", &serde_json::Value::Null, &serde_json::Value::Null).valid);
+    }
+
+    #[test]
+    fn test_skill_schema_suggests_known_types() {
+        let inputs = serde_json::json!({
+            "name": {"type": "str"},
+            "count": {"type": "Int"},
+            "ratio": {"type": "numbr"},
+            "blob": {"type": "bytes"}
+        });
+        let report = validate_skill_schemas("skill-x", &inputs, &serde_json::Value::Null);
+        assert!(!report.valid);
+        assert_eq!(
+            report.errors,
+            vec![
+                "inputs.blob: unknown type 'bytes'",
+                "inputs.count: unknown type 'Int'; did you mean 'integer'?",
+                "inputs.name: unknown type 'str'; did you mean 'string'?",
+                "inputs.ratio: unknown type 'numbr'; did you mean 'number'?",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skill_schema_flags_malformed_definitions() {
+        let outputs = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "items": {"type": "array", "items": {"type": ["string"]}},
+                "note": {"type": "string", "requried": true, "nullable": true}
+            },
+            "required": ["items", "total"]
+        });
+        let report = validate_skill_schemas("skill-x", &serde_json::json!({"path": "string"}), &outputs);
+        assert_eq!(
+            report.errors,
+            vec![
+                "inputs.path: schema must be an object",
+                "outputs.items[]: 'type' must be a string, not [\"string\"]",
+                "outputs.note: unknown keyword 'nullable'",
+                "outputs.note: unknown keyword 'requried'; did you mean 'required'?",
+                "outputs: required property 'total' is not declared",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tools_skill_is_blocked_on_non_tools_model() {
+        let required = MinModelCapabilities {
+            requires_tools: true,
+            ..Default::default()
+        };
+        let llama = crate::providers::get_model_capabilities("ollama", "llama3");
+        let gpt = crate::providers::get_model_capabilities("openai", "gpt-4o");
+
+        let error = check_model_requirements(&required, "llama3", llama.as_ref()).unwrap_err();
+        assert!(error.contains("tool use"));
+        assert!(check_model_requirements(&required, "gpt-4o", gpt.as_ref()).is_ok());
+        assert!(check_model_requirements(&MinModelCapabilities::default(), "unknown", None).is_ok());
+    }
+
+    fn graph(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(id, deps)| (id.to_string(), deps.iter().map(|d| d.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_dependency_order_is_deterministic() {
+        let deps = graph(&[("report", &["search", "fetch"]), ("search", &["fetch"]), ("fetch", &[])]);
+        assert_eq!(resolve_dependency_order("report", &deps).unwrap(), vec!["fetch", "search", "report"]);
+
+        let cyclic = graph(&[("a", &["b"]), ("b", &["a"])]);
+        assert_eq!(resolve_dependency_order("a", &cyclic).unwrap_err(), "Dependency cycle: a -> b -> a");
+        assert!(resolve_dependency_order("missing", &deps).is_err());
+    }
+
+    fn version(version: &str, days_ago: i64, risk_level: u8) -> SkillVersion {
+        SkillVersion {
+            version: version.to_string(),
+            created_at: chrono::Utc::now() - chrono::Duration::days(days_ago),
+            risk_level,
+            trust_level: "verified".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rollback_pins_earlier_version() {
+        let mut history = SkillVersionHistory::default();
+        history.record("fetch", version("2.0.0", 1, 4));
+        history.record("fetch", version("1.0.0", 10, 2));
+        assert_eq!(history.versions("fetch").iter().map(|v| v.version.as_str()).collect::<Vec<_>>(), vec!["1.0.0", "2.0.0"]);
+        assert!(history.active_version("fetch").is_none());
+
+        let active = history.rollback("fetch", "1.0.0").unwrap();
+        assert_eq!(active.risk_level, 2);
+        assert_eq!(history.active_version("fetch").unwrap().version, "1.0.0");
+    }
+
+    #[test]
+    fn test_rollback_to_unknown_version_fails() {
+        let mut history = SkillVersionHistory::default();
+        history.record("fetch", version("1.0.0", 1, 2));
+
+        let error = history.rollback("fetch", "9.9.9").unwrap_err();
+        assert_eq!(error, "Skill 'fetch' has no version '9.9.9'; known versions: 1.0.0");
+        assert!(history.rollback("other", "1.0.0").unwrap_err().ends_with("known versions: none"));
+        assert!(history.active_version("fetch").is_none());
+    }
+
+    fn import_manifest() -> serde_json::Value {
+        serde_json::json!({
+            "name": "summarize",
+            "version": "0.1.0",
+            "description": "Summarize a document",
+            "inputs": {"text": {"type": "string", "required": true}},
+            "outputs": {"summary": {"type": "string"}},
+            "required_capabilities": ["fs:read:/docs"],
+            "risk_level": 1
+        })
+    }
+
+    #[test]
+    fn test_install_skill_stores_pending_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = install_skill(dir.path(), &import_manifest()).unwrap();
+        assert!(id.starts_with("skill-"));
+
+        let installed = installed_skills(dir.path());
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].id, id);
+        assert_eq!(installed[0].manifest["name"], "summarize");
+        assert_eq!(installed[0].state["status"], "pending");
+        assert_eq!(installed[0].state["trust_level"], "unverified");
+    }
+
+    #[test]
+    fn test_install_skill_rejects_malformed_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut missing = import_manifest();
+        missing.as_object_mut().unwrap().remove("outputs");
+        assert!(install_skill(dir.path(), &missing).unwrap_err().contains("'outputs' is missing"));
+
+        let mut unknown = import_manifest();
+        unknown["required_capabilities"] = serde_json::json!(["fs:read", "db:drop"]);
+        assert_eq!(install_skill(dir.path(), &unknown).unwrap_err(), "Unknown capability 'db:drop'");
+        assert!(installed_skills(dir.path()).is_empty());
+    }
+
+    fn risk(capabilities: &[&str]) -> u8 {
+        compute_risk_level(&capabilities.iter().map(|c| c.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_compute_risk_level() {
+        assert_eq!(risk(&[]), 0);
+        assert_eq!(risk(&["fs:read"]), 1);
+        assert_eq!(risk(&["fs:read", "fs:write:/tmp"]), 2);
+        assert_eq!(risk(&["network:http"]), 3);
+        assert_eq!(risk(&["fs:read", "network:http"]), 3);
+        assert_eq!(risk(&["fs:write", "network:http"]), 4);
+        assert_eq!(risk(&["process:spawn", "network:http"]), 5);
+        assert_eq!(risk(&["db:drop"]), 5);
+    }
+
+    #[test]
+    fn test_unarchive_restores_previous_status() {
+        let mut archive = SkillArchive::default();
+        let record = archive.archive("fetch", "pending", "admin").unwrap();
+        assert_eq!(record.archived_by, "admin");
+        assert!(archive.archive("fetch", "archived", "admin").unwrap_err().contains("already archived"));
+
+        assert_eq!(archive.unarchive("fetch").unwrap().previous_status, "pending");
+        assert!(archive.get("fetch").is_none());
+        assert!(archive.unarchive("fetch").unwrap_err().contains("not archived"));
+    }
+
+    #[test]
+    fn test_check_inputs_against_full_schema() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {"url": {"type": "string"}, "timeout": {"type": "number"}},
+            "required": ["url"]
+        });
+        assert!(check_inputs(&schema, &serde_json::json!({"url": "https://example.com", "timeout": 5})).is_ok());
+
+        let check = check_inputs(&schema, &serde_json::json!({"url": null, "timeout": "5s"}));
+        assert_eq!(check.missing, vec!["url"]);
+        assert_eq!(
+            check.type_mismatches,
+            vec![InputTypeMismatch {
+                field: "timeout".to_string(),
+                expected: "number".to_string(),
+                actual: "string".to_string()
+            }]
+        );
+
+        let not_an_object = check_inputs(&schema, &serde_json::json!(["url"]));
+        assert_eq!(not_an_object.type_mismatches[0].field, "inputs");
+        assert!(check_inputs(&serde_json::Value::Null, &serde_json::Value::Null).is_ok());
+    }
+
+    /// Sign `manifest` with `key`, as a skill publisher would
+    fn signed(mut manifest: serde_json::Value, key: &ed25519_dalek::SigningKey) -> serde_json::Value {
+        use ed25519_dalek::Signer;
+
+        manifest[SIGNER_KEY_FIELD] = serde_json::json!(hex::encode(key.verifying_key().as_bytes()));
+        let signature = key.sign(&signed_manifest_bytes(&manifest));
+        manifest[SIGNATURE_FIELD] = serde_json::json!(hex::encode(signature.to_bytes()));
+        manifest
+    }
+
+    fn signing_manifest() -> serde_json::Value {
+        serde_json::json!({
+            "name": "fetch",
+            "version": "1.0.0",
+            "required_capabilities": ["network:http"],
+            "risk_level": 3,
+            "inputs": {"url": {"type": "string", "required": true}},
+            "outputs": {}
+        })
+    }
+
+    #[test]
+    fn test_signature_from_trusted_key_verifies() {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let manifest = signed(signing_manifest(), &key);
+        let public_key = hex::encode(key.verifying_key().as_bytes());
+
+        let check = verify_manifest_signature(&manifest, &[public_key.to_uppercase()]);
+        assert_eq!((check.status.as_str(), check.trusted), ("valid", true));
+        assert_eq!(check.trust_level(), "verified");
+
+        // Field order doesn't change the signed bytes
+        let reordered: serde_json::Value = serde_json::from_str(&canonical_json(&manifest)).unwrap();
+        assert_eq!(verify_manifest_signature(&reordered, &[public_key]).status, "valid");
+
+        let untrusted = verify_manifest_signature(&manifest, &[]);
+        assert_eq!((untrusted.status.as_str(), untrusted.trusted), ("valid", false));
+        assert_eq!(untrusted.trust_level(), "unverified");
+        let unsigned = verify_manifest_signature(&signing_manifest(), &[]);
+        assert_eq!((unsigned.status.as_str(), unsigned.trust_level()), ("missing", "unverified"));
+    }
+
+    #[test]
+    fn test_signature_with_wrong_key_is_invalid() {
+        let signer = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let impostor = ed25519_dalek::SigningKey::from_bytes(&[9; 32]);
+        let mut manifest = signed(signing_manifest(), &signer);
+        let impostor_key = hex::encode(impostor.verifying_key().as_bytes());
+        manifest[SIGNER_KEY_FIELD] = serde_json::json!(impostor_key.clone());
+
+        let check = verify_manifest_signature(&manifest, &[impostor_key]);
+        assert_eq!((check.status.as_str(), check.trusted), ("invalid", false));
+        assert_eq!(check.trust_level(), "unverified");
+    }
+
+    #[test]
+    fn test_tampered_manifest_fails_verification() {
+        let key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let trusted = [hex::encode(key.verifying_key().as_bytes())];
+        let mut manifest = signed(signing_manifest(), &key);
+        manifest["risk_level"] = serde_json::json!(1);
+
+        let check = verify_manifest_signature(&manifest, &trusted);
+        assert_eq!(check.status, "invalid");
+        assert!(check.message.contains("altered"));
+
+        manifest[SIGNATURE_FIELD] = serde_json::json!("not-hex");
+        assert!(verify_manifest_signature(&manifest, &trusted).message.contains("hex-encoded"));
+    }
+
+    #[test]
+    fn test_set_installed_trust_level_keeps_other_state() {
+        let dir = tempfile::tempdir().unwrap();
+        write_skill(dir.path(), "skill-signed", signing_manifest());
+        set_installed_trust_level(dir.path(), "skill-signed", "verified").unwrap();
+
+        let installed = installed_skills(dir.path());
+        assert_eq!(installed[0].state["trust_level"], "verified");
+        assert_eq!(installed[0].state["status"], "active");
+    }
+}
@@ -0,0 +1,53 @@
+//! Tests for Shared Application State
+//!
+//! Verifies that review decisions recorded through the shared state are
+//! seen by later reads, including from concurrent callers
+
+#[cfg(test)]
+mod tests {
+    use crate::state::*;
+    use chrono::Utc;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_recorded_review_is_visible_to_later_reads() {
+        let state = AppState::default();
+        assert_eq!(state.skill_review("skill-003"), None);
+
+        let now = Utc::now();
+        state.record_review("skill-003", "active", "alice", now);
+        let review = state.skill_review("skill-003").unwrap();
+        assert_eq!((review.status.as_str(), review.reviewed_by.as_str(), review.reviewed_at), ("active", "alice", now));
+
+        state.record_review("skill-003", "rejected", "bob", now);
+        assert_eq!(state.skill_review("skill-003").unwrap().status, "rejected");
+    }
+
+    #[test]
+    fn test_concurrent_reviews_are_all_kept() {
+        let state = Arc::new(AppState::default());
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let state = Arc::clone(&state);
+                std::thread::spawn(move || state.record_review(&format!("skill-{}", i), "active", "alice", Utc::now()))
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!((0..8).all(|i| state.skill_review(&format!("skill-{}", i)).is_some()));
+    }
+
+    #[test]
+    fn test_unreadable_profile_is_an_error_not_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = crate::commands::profile_path(dir.path(), crate::commands::DEFAULT_PROFILE).unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let state = AppState::default();
+        assert!(state.config_in(dir.path()).unwrap_err().contains("Failed to parse"));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(state.config_in(dir.path()).unwrap(), crate::commands::SynapseConfig::default());
+    }
+}
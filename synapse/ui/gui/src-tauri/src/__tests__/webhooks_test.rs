@@ -0,0 +1,122 @@
+//! Tests for Webhook Notifications
+//!
+//! Verifies the skill event payload and that delivery never waits on the
+//! receiving end
+
+#[cfg(test)]
+mod tests {
+    use crate::webhooks::*;
+    use crate::PROTOCOL_VERSION;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Accept one request, send its body to the receiver and answer with
+    /// `status_line`
+    async fn webhook_server(status_line: &'static str) -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            // Read until the whole body named by Content-Length has arrived
+            let body = loop {
+                let read = socket.read(&mut buffer).await.unwrap_or(0);
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if body.len() >= length || read == 0 {
+                        break format!("{}\n{}", head.lines().next().unwrap_or(""), body);
+                    }
+                }
+                if read == 0 {
+                    break text;
+                }
+            };
+            let _ = sender.send(body);
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status_line);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (format!("http://{}/hook", addr), receiver)
+    }
+
+    fn event() -> SkillEvent {
+        SkillEvent::new("skill-001", "approved", "alice", chrono::Utc::now())
+    }
+
+    #[tokio::test]
+    async fn test_delivery_posts_skill_event_json() {
+        let (url, received) = webhook_server("200 OK").await;
+        deliver(&url, &event()).await.unwrap();
+
+        let request = received.await.unwrap();
+        let (request_line, body) = request.split_once('\n').unwrap();
+        assert!(request_line.starts_with("POST /hook "));
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        let mut keys: Vec<&str> = payload.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["action", "actor", "protocol_version", "skill_id", "timestamp"]);
+        assert_eq!(payload["skill_id"], "skill-001");
+        assert_eq!(payload["action"], "approved");
+        assert_eq!(payload["actor"], "alice");
+        assert_eq!(payload["protocol_version"], PROTOCOL_VERSION);
+        assert!(chrono::DateTime::parse_from_rfc3339(payload["timestamp"].as_str().unwrap()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_notify_reaches_every_url() {
+        let (first, first_received) = webhook_server("204 No Content").await;
+        let (second, second_received) = webhook_server("200 OK").await;
+        let event = SkillEvent::new("skill-002", "rejected", "olga", chrono::Utc::now());
+
+        for handle in notify(&[first, second], false, event) {
+            handle.await.unwrap();
+        }
+        for received in [first_received.await.unwrap(), second_received.await.unwrap()] {
+            assert!(received.contains("\"action\":\"rejected\""));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_an_error() {
+        let (url, _received) = webhook_server("500 Internal Server Error").await;
+        assert!(deliver(&url, &event()).await.unwrap_err().contains("500"));
+
+        // Nothing listens on a port that was just released
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+        assert!(deliver(&closed, &event()).await.unwrap_err().contains("unreachable"));
+    }
+
+    #[tokio::test]
+    async fn test_notify_does_not_wait_for_slow_webhooks() {
+        // Accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let hold = tokio::spawn(async move {
+            let _socket = listener.accept().await;
+            tokio::time::sleep(WEBHOOK_TIMEOUT * 2).await;
+        });
+
+        let started = std::time::Instant::now();
+        let handles = notify(&[url], false, event());
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        for handle in handles {
+            handle.abort();
+        }
+        hold.abort();
+    }
+
+    #[test]
+    fn test_webhook_url_must_be_http() {
+        assert!(validate_webhook_url("https://outlook.office.com/webhook/abc").is_ok());
+        assert!(validate_webhook_url("http://localhost:9000/hook").is_ok());
+        assert!(validate_webhook_url("file:///etc/passwd").unwrap_err().contains("unsupported scheme 'file'"));
+        assert!(validate_webhook_url("hooks.slack.com").is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Tests for the Configuration Wizard
+//!
+//! Verifies wizard progress survives a reload and per-step validation
+
+#[cfg(test)]
+mod tests {
+    use crate::wizard::*;
+
+    #[test]
+    fn test_completed_step_persists_across_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WIZARD_STATE_FILE);
+        assert_eq!(load_wizard_state(&path).unwrap(), WizardState::default());
+
+        let now = chrono::Utc::now();
+        save_wizard_progress(&path, "welcome", true, now).unwrap();
+        save_wizard_progress(&path, "language", true, now).unwrap();
+
+        let reloaded = load_wizard_state(&path).unwrap();
+        assert_eq!(reloaded.completed_steps, vec!["welcome", "language"]);
+        assert_eq!(reloaded.current_step, "llm");
+        assert!(!reloaded.finished);
+
+        let steps = get_wizard_steps(&reloaded);
+        let complete: Vec<&str> = steps.iter().filter(|s| s.is_complete).map(|s| s.id.as_str()).collect();
+        assert_eq!(complete, vec!["welcome", "language"]);
+    }
+
+    #[test]
+    fn test_reopening_a_step_makes_it_current() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WIZARD_STATE_FILE);
+        let now = chrono::Utc::now();
+        save_wizard_progress(&path, "welcome", true, now).unwrap();
+        save_wizard_progress(&path, "language", true, now).unwrap();
+
+        let state = save_wizard_progress(&path, "welcome", false, now).unwrap();
+        assert_eq!(state.completed_steps, vec!["language"]);
+        assert_eq!(state.current_step, "welcome");
+        assert!(save_wizard_progress(&path, "plugins", true, now).unwrap_err().contains("Unknown wizard step"));
+    }
+
+    #[test]
+    fn test_completing_review_finishes_wizard() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WIZARD_STATE_FILE);
+        let now = chrono::Utc::now();
+        for step in WIZARD_STEP_IDS {
+            save_wizard_progress(&path, step, true, now).unwrap();
+        }
+
+        let state = load_wizard_state(&path).unwrap();
+        assert!(state.finished);
+        assert_eq!(state.current_step, "review");
+        assert!(get_wizard_steps(&state).iter().all(|s| s.is_complete));
+    }
+
+    #[test]
+    fn test_language_step_validation() {
+        assert!(validate_wizard_step("language", &serde_json::json!({"language": "ru"})).unwrap().is_empty());
+        let errors = validate_wizard_step("language", &serde_json::json!({"language": "fr"})).unwrap();
+        assert!(errors.is_empty());
+        let errors = validate_wizard_step("language", &serde_json::json!({"language": "pt"})).unwrap();
+        assert_eq!(errors, vec!["language: 'pt' is not supported; expected one of en, ru, de, es, fr, zh"]);
+        assert_eq!(validate_wizard_step("language", &serde_json::json!({})).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_llm_step_requires_model_and_key() {
+        let valid = serde_json::json!({"llm_providers": [
+            {"provider_type": "openai", "model": "gpt-4o", "api_key": "sk-test"},
+            {"provider_type": "ollama", "model": "llama3"}
+        ]});
+        assert!(validate_wizard_step("llm", &valid).unwrap().is_empty());
+
+        let invalid = serde_json::json!({"llm_providers": [{"provider_type": "anthropic", "model": " "}]});
+        assert_eq!(
+            validate_wizard_step("llm", &invalid).unwrap(),
+            vec!["llm_providers[0].model: a model is required", "llm_providers[0].api_key: an API key is required"]
+        );
+        assert_eq!(
+            validate_wizard_step("llm", &serde_json::json!({"llm_providers": []})).unwrap(),
+            vec!["llm_providers: at least one provider is required"]
+        );
+    }
+
+    #[test]
+    fn test_custom_provider_needs_base_url_not_key() {
+        let custom = serde_json::json!({"llm_providers": [{"provider_type": "custom_openai", "model": "mistral-7b"}]});
+        assert_eq!(
+            validate_wizard_step("llm", &custom).unwrap(),
+            vec!["llm_providers[0].base_url: a base URL is required for OpenAI-compatible providers"]
+        );
+        let custom = serde_json::json!({"llm_providers": [
+            {"provider_type": "custom_openai", "model": "mistral-7b", "base_url": "http://localhost:8000/v1"}
+        ]});
+        assert!(validate_wizard_step("llm", &custom).unwrap().is_empty());
+
+        let listed = get_supported_llm_providers().into_iter().find(|p| p["id"] == "custom_openai").unwrap();
+        assert!(listed.contains_key("note"));
+        assert!(static_models("custom_openai").is_empty());
+    }
+
+    #[test]
+    fn test_storage_step_requires_writable_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let not_a_dir = dir.path().join("file");
+        std::fs::write(&not_a_dir, "").unwrap();
+
+        let valid = serde_json::json!({"data_paths": {
+            "config": dir.path().to_string_lossy(),
+            "skills": dir.path().join("not/yet/created").to_string_lossy()
+        }});
+        assert!(validate_wizard_step("storage", &valid).unwrap().is_empty());
+
+        let invalid = serde_json::json!({"data_paths": {
+            "config": "",
+            "memory": not_a_dir.join("memory").to_string_lossy()
+        }});
+        let errors = validate_wizard_step("storage", &invalid).unwrap();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0], "data_paths.config: a path is required");
+        assert!(errors[1].starts_with("data_paths.memory:") && errors[1].ends_with("is not writable"));
+    }
+
+    #[test]
+    fn test_review_step_reruns_all_validations() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = serde_json::json!({
+            "language": "en",
+            "llm_providers": [{"provider_type": "ollama", "model": "llama3"}],
+            "data_paths": {"config": dir.path().to_string_lossy()},
+            "security_settings": {"isolation_policy": "container"}
+        });
+        assert!(validate_wizard_step("review", &config).unwrap().is_empty());
+        assert!(validate_wizard_step("welcome", &serde_json::json!({})).unwrap().is_empty());
+
+        config["language"] = serde_json::json!("xx");
+        config["security_settings"]["isolation_policy"] = serde_json::json!("vm");
+        let errors = validate_wizard_step("review", &config).unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("language:"));
+        assert!(errors[1].starts_with("security_settings.isolation_policy:"));
+        assert!(validate_wizard_step("plugins", &config).is_err());
+    }
+
+    fn answers(preset: &str) -> WizardAnswers {
+        serde_json::from_value(serde_json::json!({
+            "language": "ru",
+            "llm_providers": [{
+                "name": "Local",
+                "provider_type": "ollama",
+                "api_key": null,
+                "base_url": null,
+                "model": "llama3",
+                "priority": 1,
+                "is_active": true
+            }],
+            "data_paths": {"skills": "/srv/synapse/skills"},
+            "security_preset": preset
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_security_preset_maps_to_mode() {
+        let config = config_from_answers(&answers("safe")).unwrap();
+        assert_eq!(config.mode, "safe");
+        assert_eq!(config.language, "ru");
+        assert_eq!(config.security_settings.require_approval_for_risk, 1);
+        assert_eq!(config.data_paths["skills"], "/srv/synapse/skills");
+        assert_eq!(config.data_paths["memory"], "~/.synapse/memory");
+
+        let mut autonomous = answers("autonomous");
+        autonomous.isolation_policy = Some("container".to_string());
+        let config = config_from_answers(&autonomous).unwrap();
+        assert_eq!(config.mode, "autonomous");
+        assert_eq!(config.security_settings.isolation_policy, "container");
+
+        assert!(config_from_answers(&answers("yolo")).unwrap_err().contains("Unknown security preset 'yolo'"));
+    }
+
+    fn probe(connected: bool, models: &[&str]) -> crate::providers::ConnectionProbe {
+        crate::providers::ConnectionProbe {
+            connected,
+            status: Some(if connected { 200 } else { 401 }),
+            auth_ok: connected,
+            latency_ms: 12,
+            message: if connected { "Connected".to_string() } else { "Authentication failed".to_string() },
+            available_models: models.iter().map(|m| m.to_string()).collect(),
+            models_listable: !models.is_empty(),
+        }
+    }
+
+    #[test]
+    fn test_successful_probe_overrides_static_models() {
+        let list = model_list_from_probe("openai", Ok(&probe(true, &["gpt-4o", "o3-mini"])));
+        assert_eq!(list.models, vec!["gpt-4o", "o3-mini"]);
+        assert_eq!(list.source, "live");
+        assert!(list.error.is_none());
+
+        let mut cache = ModelListCache::default();
+        let key = ModelListCache::key("openai", None, "sk-test");
+        cache.insert(key.clone(), &list);
+        assert_eq!(cache.get(&key), Some(&list));
+        assert!(cache.get(&ModelListCache::key("openai", None, "sk-other")).is_none());
+    }
+
+    #[test]
+    fn test_failed_probe_falls_back_to_static_models() {
+        let failed = model_list_from_probe("openai", Ok(&probe(false, &[])));
+        assert_eq!(failed.models, vec!["gpt-4o", "gpt-4-turbo", "gpt-3.5-turbo"]);
+        assert_eq!(failed.source, "static");
+        assert_eq!(failed.error.as_deref(), Some("Authentication failed"));
+
+        let unlisted = model_list_from_probe("ollama", Ok(&probe(true, &[])));
+        assert_eq!(unlisted.models, vec!["llama3", "mistral", "codellama"]);
+        assert!(unlisted.error.is_some());
+
+        let errored = model_list_from_probe("anthropic", Err("Invalid base URL"));
+        assert_eq!(errored.error.as_deref(), Some("Invalid base URL"));
+
+        // Fallbacks aren't cached so the next attempt probes again
+        let mut cache = ModelListCache::default();
+        cache.insert("k".to_string(), &failed);
+        assert!(cache.get("k").is_none());
+    }
+
+    #[test]
+    fn test_every_language_has_code_and_name() {
+        let languages = get_supported_languages();
+        assert!(languages.len() >= 6);
+        for language in &languages {
+            assert!(!language.code.is_empty());
+            assert!(!language.name.is_empty());
+            assert!(!language.native_name.is_empty());
+        }
+        for code in ["de", "es", "fr", "zh"] {
+            assert!(supported_language_codes().contains(&code));
+        }
+    }
+
+    #[test]
+    fn test_wizard_next_is_blocked_on_invalid_step() {
+        let now = chrono::Utc::now();
+        let mut state = WizardState::default();
+        let status = wizard_next(&mut state, &serde_json::Value::Null, now).unwrap();
+        assert_eq!(status.current_step, "language");
+        assert!(!status.can_go_next);
+        assert!(status.can_go_back);
+
+        let invalid = serde_json::json!({"language": "xx"});
+        assert!(wizard_next(&mut state, &invalid, now).unwrap_err().starts_with("Step 'language' is incomplete"));
+        assert_eq!(state.current_step, "language");
+
+        let status = wizard_next(&mut state, &serde_json::json!({"language": "de"}), now).unwrap();
+        assert_eq!(status.current_step, "llm");
+
+        let status = wizard_back(&mut state, &serde_json::Value::Null, now).unwrap();
+        assert_eq!(status.current_step, "language");
+        assert_eq!(state.completed_steps, vec!["welcome", "language"]);
+    }
+
+    #[test]
+    fn test_wizard_status_percentage() {
+        let now = chrono::Utc::now();
+        let mut state = WizardState::default();
+        let status = wizard_status(&state, &serde_json::Value::Null);
+        assert_eq!((status.step_index, status.total_steps, status.percent_complete), (0, 6, 0));
+        assert!(!status.can_go_back);
+        assert!(status.can_go_next);
+
+        for step in ["welcome", "language", "llm"] {
+            state.set_step_complete(step, true, now).unwrap();
+        }
+        let status = wizard_status(&state, &serde_json::Value::Null);
+        assert_eq!(status.current_step, "storage");
+        assert_eq!((status.step_index, status.percent_complete), (3, 50));
+
+        state.set_step_complete("storage", true, now).unwrap();
+        assert_eq!(wizard_status(&state, &serde_json::Value::Null).percent_complete, 66);
+        assert!(wizard_back(&mut WizardState::default(), &serde_json::Value::Null, now).is_err());
+    }
+}
@@ -0,0 +1,741 @@
+//! Audit Log Module
+//!
+//! Aggregation and storage helpers for the security audit log.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::commands::AuditLogEntry;
+
+/// Protocol version constant for audit responses
+const AUDIT_PROTOCOL_VERSION: &str = "1.0";
+
+/// Number of entries reported in each top-N ranking
+const TOP_N: usize = 5;
+
+/// Action recorded for skill executions
+pub const ACTION_SKILL_EXECUTE: &str = "skill_execute";
+
+/// Default cap on entries written per minute before bursts are coalesced
+pub const DEFAULT_MAX_ENTRIES_PER_MINUTE: u32 = 600;
+
+/// Security-relevant actions that are always written individually
+pub const CRITICAL_ACTIONS: &[&str] = &[
+    "skill_approve",
+    "skill_reject",
+    "skill_rollback",
+    "capability_grant",
+    "capability_revoke",
+    "config_update",
+    "security_update",
+    "isolation_downgrade",
+    "session_start",
+    "session_end",
+];
+
+/// A name with its occurrence count
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RankedCount {
+    pub name: String,
+    pub count: u64,
+}
+
+/// Entries written within one clock hour
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct HourlyCount {
+    /// Start of the hour
+    pub hour: DateTime<Utc>,
+    pub count: u64,
+}
+
+/// Aggregate view of audit activity over a time window
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditSummary {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub total_entries: u64,
+    pub by_action: HashMap<String, u64>,
+    pub by_user: HashMap<String, u64>,
+    /// Entries per `result`; "success" and "failure" are always present
+    pub by_result: HashMap<String, u64>,
+    /// Hour with the most entries, the earliest on a tie; null when there are none
+    pub busiest_hour: Option<HourlyCount>,
+    pub success_count: u64,
+    pub failure_count: u64,
+    /// Successes over all entries with a success/failure result; null when there are none
+    pub success_ratio: Option<f64>,
+    pub top_users: Vec<RankedCount>,
+    pub top_skills: Vec<RankedCount>,
+    pub protocol_version: String,
+}
+
+/// Append-only audit log file (JSON lines), relative to the config directory
+pub const AUDIT_LOG_FILE: &str = "audit.jsonl";
+
+/// Directory beside the audit log that rotated segments are moved into
+pub const AUDIT_ARCHIVE_DIR: &str = "audit-archive";
+
+/// How often the rotation schedule checks the retention settings
+pub const ROTATION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Filters for `query`; every field is optional
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AuditQuery {
+    /// Inclusive lower bound on `timestamp`
+    pub since: Option<DateTime<Utc>>,
+    /// Inclusive upper bound on `timestamp`
+    pub until: Option<DateTime<Utc>>,
+    pub action: Option<String>,
+    pub user_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Whether `entry` passes every filter; `limit` doesn't apply
+    pub fn matches(&self, entry: &AuditLogEntry) -> bool {
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+            && self.action.as_ref().is_none_or(|action| &entry.action == action)
+            && self.user_id.as_ref().is_none_or(|user| &entry.user_id == user)
+    }
+}
+
+/// Entries matching `query`, newest first
+pub fn query(entries: &[AuditLogEntry], query: &AuditQuery) -> Vec<AuditLogEntry> {
+    let mut matched: Vec<AuditLogEntry> = entries.iter().filter(|e| query.matches(e)).cloned().collect();
+    matched.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    if let Some(limit) = query.limit {
+        matched.truncate(limit);
+    }
+    matched
+}
+
+/// Entries stored at `path`, oldest first. A torn last line from a crash
+/// mid-write is skipped.
+pub fn read_log_file(path: &Path) -> Result<Vec<AuditLogEntry>, String> {
+    match fs::read_to_string(path) {
+        Ok(raw) => Ok(raw.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// `prev_hash` of the first entry in a chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// `sha256(prev_hash + canonical_json(entry))`, where the canonical JSON
+/// leaves out both hash fields and has its keys sorted
+pub fn compute_entry_hash(entry: &AuditLogEntry) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut value = serde_json::to_value(entry).unwrap();
+    if let Some(fields) = value.as_object_mut() {
+        fields.remove("prev_hash");
+        fields.remove("entry_hash");
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(entry.prev_hash.as_bytes());
+    hasher.update(value.to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Result of walking the audit hash chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChainVerification {
+    pub valid: bool,
+    pub entries_checked: usize,
+    /// Index of the first entry that doesn't chain correctly
+    pub broken_at: Option<usize>,
+    pub reason: Option<String>,
+    pub protocol_version: String,
+}
+
+/// Check that every entry links to its predecessor and that its hash
+/// matches its contents. Edits, insertions and reordering all break the
+/// chain at or before the altered entry.
+pub fn verify_chain(entries: &[AuditLogEntry]) -> ChainVerification {
+    let mut expected_prev = GENESIS_HASH;
+    for (index, entry) in entries.iter().enumerate() {
+        let reason = if entry.prev_hash != expected_prev {
+            Some(format!("Entry '{}' does not link to the entry before it", entry.id))
+        } else if entry.entry_hash != compute_entry_hash(entry) {
+            Some(format!("Entry '{}' was modified after it was written", entry.id))
+        } else {
+            None
+        };
+        if reason.is_some() {
+            return ChainVerification {
+                valid: false,
+                entries_checked: index + 1,
+                broken_at: Some(index),
+                reason,
+                protocol_version: AUDIT_PROTOCOL_VERSION.to_string(),
+            };
+        }
+        expected_prev = &entry.entry_hash;
+    }
+
+    ChainVerification {
+        valid: true,
+        entries_checked: entries.len(),
+        broken_at: None,
+        reason: None,
+        protocol_version: AUDIT_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+/// Formats accepted by `export_entries`
+pub const EXPORT_FORMATS: &[&str] = &["csv", "json"];
+
+/// Column order of CSV exports
+pub const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "timestamp",
+    "action",
+    "user_id",
+    "result",
+    "resource",
+    "details",
+    "prev_hash",
+    "entry_hash",
+];
+
+/// Quote a CSV field when it contains a delimiter, quote, or line break
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Serialize entries as "csv" (RFC 4180, header row first) or "json"
+pub fn export_entries(entries: &[AuditLogEntry], format: &str) -> Result<String, String> {
+    match format {
+        "json" => serde_json::to_string_pretty(entries).map_err(|e| e.to_string()),
+        "csv" => {
+            let mut out = CSV_COLUMNS.join(",");
+            out.push_str("\r\n");
+            for entry in entries {
+                let row = [
+                    entry.id.clone(),
+                    entry.timestamp.to_rfc3339(),
+                    entry.action.clone(),
+                    entry.user_id.clone(),
+                    entry.result.clone(),
+                    entry.resource.clone().unwrap_or_default(),
+                    entry.details.clone().unwrap_or_default(),
+                    entry.prev_hash.clone(),
+                    entry.entry_hash.clone(),
+                ];
+                out.push_str(&row.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+                out.push_str("\r\n");
+            }
+            Ok(out)
+        }
+        other => Err(format!("Unknown export format '{}'; expected one of {}", other, EXPORT_FORMATS.join(", "))),
+    }
+}
+
+/// Limits past which `AuditLog::rotate` moves entries into the archive;
+/// `None` leaves that limit off
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RetentionPolicy {
+    pub max_age_days: Option<u32>,
+    pub max_file_bytes: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.max_age_days.is_none() && self.max_file_bytes.is_none()
+    }
+}
+
+/// What a rotation moved into the archive
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RotationReport {
+    pub archived_entries: usize,
+    pub kept_entries: usize,
+    /// Segment the archived entries were written to; `None` when nothing was due
+    pub segment: Option<String>,
+    pub protocol_version: String,
+}
+
+/// Archived segments of the log at `log_path`, oldest first
+pub fn archived_segments(log_path: &Path) -> Vec<PathBuf> {
+    let dir = log_path.parent().unwrap_or(Path::new(".")).join(AUDIT_ARCHIVE_DIR);
+    let mut segments: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    // Segment names are rotation timestamps, so name order is age order
+    segments.sort();
+    segments
+}
+
+/// Entries of every archived segment of the log at `log_path`, oldest first
+pub fn read_archived_entries(log_path: &Path) -> Result<Vec<AuditLogEntry>, String> {
+    let mut entries = Vec::new();
+    for segment in archived_segments(log_path) {
+        entries.extend(read_log_file(&segment)?);
+    }
+    Ok(entries)
+}
+
+fn write_entries(path: &Path, entries: &[AuditLogEntry]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    for entry in entries {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+    }
+    file.sync_data()
+}
+
+fn line_len(entry: &AuditLogEntry) -> u64 {
+    serde_json::to_string(entry).map(|line| line.len() as u64 + 1).unwrap_or(0)
+}
+
+fn append_to_file(path: &Path, entry: &AuditLogEntry) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    file.sync_data()
+}
+
+fn top_n(counts: &HashMap<String, u64>) -> Vec<RankedCount> {
+    let mut ranked: Vec<RankedCount> = counts
+        .iter()
+        .map(|(name, count)| RankedCount { name: name.clone(), count: *count })
+        .collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    ranked.truncate(TOP_N);
+    ranked
+}
+
+/// Summarize entries with `since <= timestamp <= until` in a single pass.
+/// An empty window gives zero counts rather than an error.
+pub fn summarize(entries: &[AuditLogEntry], since: DateTime<Utc>, until: DateTime<Utc>) -> AuditSummary {
+    let mut total_entries = 0;
+    let mut by_action: HashMap<String, u64> = HashMap::new();
+    let mut by_user: HashMap<String, u64> = HashMap::new();
+    let mut by_skill: HashMap<String, u64> = HashMap::new();
+    let mut by_result: HashMap<String, u64> = ["success", "failure"].iter().map(|r| (r.to_string(), 0)).collect();
+    let mut by_hour: HashMap<DateTime<Utc>, u64> = HashMap::new();
+    let mut success_count = 0;
+    let mut failure_count = 0;
+
+    for entry in entries.iter().filter(|e| e.timestamp >= since && e.timestamp <= until) {
+        total_entries += 1;
+        *by_action.entry(entry.action.clone()).or_default() += 1;
+        *by_user.entry(entry.user_id.clone()).or_default() += 1;
+        *by_result.entry(entry.result.clone()).or_default() += 1;
+        let hour = entry.timestamp.duration_trunc(Duration::hours(1)).unwrap_or(entry.timestamp);
+        *by_hour.entry(hour).or_default() += 1;
+
+        match entry.result.as_str() {
+            "success" => success_count += 1,
+            "failure" | "error" | "denied" => failure_count += 1,
+            _ => {}
+        }
+
+        if entry.action == ACTION_SKILL_EXECUTE {
+            if let Some(skill) = &entry.resource {
+                *by_skill.entry(skill.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    let decided = success_count + failure_count;
+    let busiest_hour = by_hour
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map(|(hour, count)| HourlyCount { hour, count });
+
+    AuditSummary {
+        since,
+        until,
+        total_entries,
+        top_users: top_n(&by_user),
+        top_skills: top_n(&by_skill),
+        by_action,
+        by_user,
+        by_result,
+        busiest_hour,
+        success_count,
+        failure_count,
+        success_ratio: if decided == 0 { None } else { Some(success_count as f64 / decided as f64) },
+        protocol_version: AUDIT_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+// ============================================================================
+// Audit Log Store
+// ============================================================================
+
+/// Identical non-critical entries held back while the rate guard is tripped
+struct Burst {
+    template: AuditLogEntry,
+    count: u64,
+    last: DateTime<Utc>,
+}
+
+/// Whether the rate guard is currently coalescing entries
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CoalescingStatus {
+    pub active: bool,
+    pub max_entries_per_minute: u32,
+    pub entries_last_minute: u32,
+    pub pending_coalesced: u64,
+    pub protocol_version: String,
+}
+
+/// Audit log with a per-minute growth guard, optionally backed by an
+/// append-only file
+pub struct AuditLog {
+    entries: Vec<AuditLogEntry>,
+    max_entries_per_minute: u32,
+    recent_writes: VecDeque<DateTime<Utc>>,
+    burst: Option<Burst>,
+    next_id: u64,
+    path: Option<PathBuf>,
+    /// `entry_hash` of the newest entry, which the next entry links to
+    last_hash: String,
+}
+
+fn is_critical(action: &str) -> bool {
+    CRITICAL_ACTIONS.contains(&action)
+}
+
+fn same_kind(a: &AuditLogEntry, b: &AuditLogEntry) -> bool {
+    a.action == b.action && a.user_id == b.user_id && a.resource == b.resource && a.result == b.result
+}
+
+impl AuditLog {
+    pub fn new(max_entries_per_minute: u32) -> Self {
+        Self {
+            entries: Vec::new(),
+            max_entries_per_minute,
+            recent_writes: VecDeque::new(),
+            burst: None,
+            next_id: 1,
+            path: None,
+            last_hash: GENESIS_HASH.to_string(),
+        }
+    }
+
+    /// Load the entries stored at `path` and append every later entry to
+    /// it. Entries recorded before this call are written after the stored
+    /// ones.
+    pub fn open(&mut self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let stored = read_log_file(path)?;
+        // A log emptied by rotation continues the chain and ids of its
+        // newest archived entry
+        let archived_tail = match archived_segments(path).last() {
+            Some(segment) if stored.is_empty() => read_log_file(segment)?.pop(),
+            _ => None,
+        };
+        let pending = std::mem::replace(&mut self.entries, stored);
+        self.next_id = self
+            .entries
+            .iter()
+            .chain(archived_tail.iter())
+            .filter_map(|e| e.id.strip_prefix("audit-").and_then(|n| n.parse::<u64>().ok()))
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.last_hash = self
+            .entries
+            .last()
+            .or(archived_tail.as_ref())
+            .map_or_else(|| GENESIS_HASH.to_string(), |e| e.entry_hash.clone());
+        self.path = Some(path.to_path_buf());
+        for mut entry in pending {
+            entry.id.clear();
+            self.write(entry);
+        }
+        Ok(())
+    }
+
+    pub fn set_max_entries_per_minute(&mut self, max: u32) {
+        self.max_entries_per_minute = max;
+    }
+
+    /// Entries written so far, oldest first
+    pub fn entries(&self) -> &[AuditLogEntry] {
+        &self.entries
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        while let Some(oldest) = self.recent_writes.front() {
+            if now - *oldest >= Duration::minutes(1) {
+                self.recent_writes.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn write(&mut self, mut entry: AuditLogEntry) {
+        if entry.id.is_empty() {
+            entry.id = format!("audit-{:06}", self.next_id);
+            self.next_id += 1;
+        }
+        entry.prev_hash = std::mem::take(&mut self.last_hash);
+        entry.entry_hash = compute_entry_hash(&entry);
+        self.last_hash = entry.entry_hash.clone();
+        if let Some(path) = &self.path {
+            // The entry is kept in memory even if the file can't be written
+            let _ = append_to_file(path, &entry);
+        }
+        // No receivers just means nobody is subscribed
+        let _ = entry_sink().send(entry.clone());
+        self.recent_writes.push_back(entry.timestamp);
+        self.entries.push(entry);
+    }
+
+    /// Write any held-back burst as a single summarized entry
+    pub fn flush(&mut self) {
+        if let Some(burst) = self.burst.take() {
+            let mut entry = burst.template;
+            if burst.count > 1 {
+                let seconds = (burst.last - entry.timestamp).num_seconds();
+                entry.details = Some(format!("{} x{} in {}s", entry.action, burst.count, seconds));
+            }
+            self.write(entry);
+        }
+    }
+
+    /// Record an entry, coalescing bursts of identical non-critical actions
+    /// once more than `max_entries_per_minute` entries were written in the
+    /// last minute. Critical actions are never coalesced.
+    pub fn record(&mut self, entry: AuditLogEntry) {
+        self.prune(entry.timestamp);
+
+        if is_critical(&entry.action) {
+            self.flush();
+            self.write(entry);
+            return;
+        }
+
+        if let Some(burst) = self.burst.as_mut() {
+            if same_kind(&burst.template, &entry) {
+                burst.count += 1;
+                burst.last = entry.timestamp;
+                return;
+            }
+            self.flush();
+        }
+
+        if (self.recent_writes.len() as u32) < self.max_entries_per_minute {
+            self.write(entry);
+        } else {
+            self.burst = Some(Burst {
+                last: entry.timestamp,
+                template: entry,
+                count: 1,
+            });
+        }
+    }
+
+    /// Move entries past the retention limits into a new archive segment and
+    /// trim them from the active file. Only the oldest entries move, as one
+    /// unbroken run, so the archive followed by the active log is still a
+    /// single hash chain.
+    pub fn rotate(&mut self, policy: &RetentionPolicy, now: DateTime<Utc>) -> Result<RotationReport, String> {
+        let path = self.path.clone().ok_or_else(|| "The audit log is in memory only; there is no file to rotate".to_string())?;
+        self.flush();
+        
+        let cutoff = policy.max_age_days.map(|days| now - Duration::days(days as i64));
+        let mut split = self.entries.iter().take_while(|e| cutoff.is_some_and(|cutoff| e.timestamp < cutoff)).count();
+        if let Some(max_bytes) = policy.max_file_bytes {
+            let sizes: Vec<u64> = self.entries.iter().map(line_len).collect();
+            let mut remaining: u64 = sizes[split..].iter().sum();
+            while remaining > max_bytes && split < sizes.len() {
+                remaining -= sizes[split];
+                split += 1;
+            }
+        }
+        if split == 0 {
+            return Ok(RotationReport {
+                archived_entries: 0,
+                kept_entries: self.entries.len(),
+                segment: None,
+                protocol_version: AUDIT_PROTOCOL_VERSION.to_string(),
+            });
+        }
+        
+        let archive_dir = path.parent().unwrap_or(Path::new(".")).join(AUDIT_ARCHIVE_DIR);
+        fs::create_dir_all(&archive_dir).map_err(|e| format!("Failed to create {}: {}", archive_dir.display(), e))?;
+        let segment = archive_dir.join(format!("audit-{}.jsonl", now.format("%Y%m%dT%H%M%S%.3fZ")));
+        if segment.exists() {
+            return Err(format!("Archive segment {} already exists", segment.display()));
+        }
+        write_entries(&segment, &self.entries[..split]).map_err(|e| format!("Failed to write {}: {}", segment.display(), e))?;
+        // Swap the trimmed log in whole so a crash never leaves it half written
+        let staged = path.with_extension("jsonl.tmp");
+        write_entries(&staged, &self.entries[split..])
+            .and_then(|_| fs::rename(&staged, &path))
+            .map_err(|e| format!("Failed to trim {}: {}", path.display(), e))?;
+        self.entries.drain(..split);
+        
+        Ok(RotationReport {
+            archived_entries: split,
+            kept_entries: self.entries.len(),
+            segment: Some(segment.to_string_lossy().to_string()),
+            protocol_version: AUDIT_PROTOCOL_VERSION.to_string(),
+        })
+    }
+
+    /// Archived entries followed by the active ones, oldest first
+    pub fn history(&mut self) -> Result<Vec<AuditLogEntry>, String> {
+        self.flush();
+        let mut history = match &self.path {
+            Some(path) => read_archived_entries(path)?,
+            None => Vec::new(),
+        };
+        history.extend(self.entries.iter().cloned());
+        Ok(history)
+    }
+
+    /// File the log appends to; `None` when it lives in memory only
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn status(&self) -> CoalescingStatus {
+        let now = Utc::now();
+        let entries_last_minute = self
+            .recent_writes
+            .iter()
+            .filter(|t| now - **t < Duration::minutes(1))
+            .count() as u32;
+
+        CoalescingStatus {
+            active: self.burst.is_some() || entries_last_minute >= self.max_entries_per_minute,
+            max_entries_per_minute: self.max_entries_per_minute,
+            entries_last_minute,
+            pending_coalesced: self.burst.as_ref().map(|b| b.count).unwrap_or(0),
+            protocol_version: AUDIT_PROTOCOL_VERSION.to_string(),
+        }
+    }
+}
+
+/// Back the shared audit log with the file at `path`
+pub fn open_log(path: &Path) -> Result<(), String> {
+    crate::state::app_state().audit_log().lock().unwrap().open(path)
+}
+
+/// Append an entry to the shared audit log
+pub fn record(action: &str, user_id: &str, result: &str, resource: Option<&str>, details: Option<String>) {
+    let entry = AuditLogEntry {
+        id: String::new(),
+        timestamp: Utc::now(),
+        action: action.to_string(),
+        user_id: user_id.to_string(),
+        result: result.to_string(),
+        resource: resource.map(str::to_string),
+        details,
+        prev_hash: String::new(),
+        entry_hash: String::new(),
+    };
+    crate::state::app_state().audit_log().lock().unwrap().record(entry);
+}
+
+/// The shared log's full history: archived segments, then active entries.
+/// Falls back to the active entries alone when the archive can't be read.
+pub fn history() -> Vec<AuditLogEntry> {
+    let mut log = crate::state::app_state().audit_log().lock().unwrap();
+    log.history().unwrap_or_else(|_| log.entries().to_vec())
+}
+
+/// Running rotation schedule
+struct RotationSchedule {
+    stop: std::sync::mpsc::Sender<()>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+fn rotation_schedule() -> &'static Mutex<Option<RotationSchedule>> {
+    static SCHEDULE: OnceLock<Mutex<Option<RotationSchedule>>> = OnceLock::new();
+    SCHEDULE.get_or_init(|| Mutex::new(None))
+}
+
+/// Rotate the shared log every `interval` under the policy `policy` returns
+/// at that moment, replacing any schedule already running
+pub fn start_rotation_schedule(interval: std::time::Duration, policy: impl Fn() -> RetentionPolicy + Send + 'static) {
+    stop_rotation_schedule();
+    let (stop, stopped) = std::sync::mpsc::channel::<()>();
+    let thread = std::thread::spawn(move || loop {
+        let policy = policy();
+        if !policy.is_empty() {
+            if let Err(e) = crate::state::app_state().audit_log().lock().unwrap().rotate(&policy, Utc::now()) {
+                tracing::warn!(error = %e, "scheduled audit log rotation failed");
+            }
+        }
+        match stopped.recv_timeout(interval) {
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            _ => break,
+        }
+    });
+    *rotation_schedule().lock().unwrap() = Some(RotationSchedule { stop, thread });
+}
+
+/// Stop the rotation schedule and wait for it to finish
+pub fn stop_rotation_schedule() {
+    if let Some(RotationSchedule { stop, thread }) = rotation_schedule().lock().unwrap().take() {
+        let _ = stop.send(());
+        let _ = thread.join();
+    }
+}
+
+/// Entries buffered per subscriber before the slowest one starts missing some
+const SINK_CAPACITY: usize = 256;
+
+/// Every entry written by any `AuditLog`, as it is written
+pub fn entry_sink() -> &'static tokio::sync::broadcast::Sender<AuditLogEntry> {
+    static SINK: OnceLock<tokio::sync::broadcast::Sender<AuditLogEntry>> = OnceLock::new();
+    SINK.get_or_init(|| tokio::sync::broadcast::channel(SINK_CAPACITY).0)
+}
+
+/// Wait for the next written entry that matches `filter`. Entries missed
+/// because the receiver fell behind are skipped; `None` once the sink closes.
+pub async fn next_matching(
+    receiver: &mut tokio::sync::broadcast::Receiver<AuditLogEntry>,
+    filter: &AuditQuery,
+) -> Option<AuditLogEntry> {
+    loop {
+        match receiver.recv().await {
+            Ok(entry) if filter.matches(&entry) => return Some(entry),
+            Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// Streaming subscriptions by id, holding the task that forwards entries
+pub fn subscriptions() -> &'static Mutex<HashMap<String, tokio::task::JoinHandle<()>>> {
+    static SUBSCRIPTIONS: OnceLock<Mutex<HashMap<String, tokio::task::JoinHandle<()>>>> = OnceLock::new();
+    SUBSCRIPTIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Snapshot of the shared audit log, including any pending burst
+pub fn entries() -> Vec<AuditLogEntry> {
+    let mut log = crate::state::app_state().audit_log().lock().unwrap();
+    log.flush();
+    log.entries().to_vec()
+}
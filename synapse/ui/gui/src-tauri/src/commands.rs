@@ -3,8 +3,11 @@
 //! All responses include protocol_version="1.0" and spec_version="3.1"
 
 use serde::{Deserialize, Serialize};use std::collections::HashMap;
+use std::path::PathBuf;
 use chrono::{DateTime, Utc};
 
+use crate::{audit, i18n, isolation, logs, metrics, migration, providers, security, skills, state, webhooks, wizard};
+use crate::state::{AppState, SharedState};
 use crate::{PROTOCOL_VERSION, SPEC_VERSION};
 
 // ============================================================================
@@ -16,6 +19,9 @@ use crate::{PROTOCOL_VERSION, SPEC_VERSION};
 pub struct BaseResponse {
     pub protocol_version: String,
     pub spec_version: String,
+    /// One per command invocation, shared by everything it responds with
+    /// and by its log lines, so a user can quote it in a bug report
+    pub request_id: String,
 }
 
 impl Default for BaseResponse {
@@ -23,10 +29,71 @@ impl Default for BaseResponse {
         Self {
             protocol_version: PROTOCOL_VERSION.to_string(),
             spec_version: SPEC_VERSION.to_string(),
+            request_id: current_request_id(),
         }
     }
 }
 
+/// Command failure with a stable machine-readable code, serialized as
+/// `{ "code": "not_found", "message": "..." }` so the UI can branch on
+/// and localize errors without matching message text
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "code", content = "message", rename_all = "snake_case")]
+pub enum SynapseError {
+    /// The skill, profile, token or other resource doesn't exist
+    NotFound(String),
+    /// The request's input was rejected
+    Validation(String),
+    /// No valid session or token, or the caller's role is insufficient
+    Unauthorized(String),
+    /// Too many requests; `data.retry_after_seconds` says when to retry
+    RateLimited(String),
+    /// An LLM provider or other remote service failed
+    Upstream(String),
+    /// Local I/O or other unexpected failure
+    Internal(String),
+    /// The request clashes with earlier state, such as a reused idempotency key
+    Conflict(String),
+    /// A user has used up a skill's run quota; `data.retry_after_seconds` says
+    /// when a run frees up
+    QuotaExceeded(String),
+    /// The command needs the network, which offline mode switches off
+    Offline(String),
+}
+
+impl SynapseError {
+    pub fn message(&self) -> &str {
+        match self {
+            SynapseError::NotFound(message)
+            | SynapseError::Validation(message)
+            | SynapseError::Unauthorized(message)
+            | SynapseError::RateLimited(message)
+            | SynapseError::Upstream(message)
+            | SynapseError::Internal(message)
+            | SynapseError::Conflict(message)
+            | SynapseError::QuotaExceeded(message)
+            | SynapseError::Offline(message) => message,
+        }
+    }
+}
+
+impl std::fmt::Display for SynapseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+/// Unwrap `$result` inside a command, answering with an `Internal` error
+/// rather than handing the UI an untyped `Err(String)`
+macro_rules! try_internal {
+    ($result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(e) => return Ok(ApiResponse::error(SynapseError::Internal(e.to_string()))),
+        }
+    };
+}
+
 /// Generic response wrapper
 #[derive(Serialize, Deserialize)]
 pub struct ApiResponse {
@@ -34,27 +101,154 @@ pub struct ApiResponse {
     pub base: BaseResponse,
     pub success: bool,
     pub data: Option<serde_json::Value>,
-    pub error: Option<String>,
+    pub error: Option<SynapseError>,
 }
 
 impl ApiResponse {
     pub fn success(data: serde_json::Value) -> Self {
+        let base = BaseResponse::default();
+        tracing::debug!(request_id = %base.request_id, "command succeeded");
         Self {
-            base: BaseResponse::default(),
+            base,
             success: true,
             data: Some(data),
             error: None,
         }
     }
     
-    pub fn error(message: &str) -> Self {
+    pub fn error(error: SynapseError) -> Self {
+        let base = BaseResponse::default();
+        tracing::warn!(request_id = %base.request_id, error = ?error, "command failed");
         Self {
-            base: BaseResponse::default(),
+            base,
             success: false,
             data: None,
-            error: Some(message.to_string()),
+            error: Some(error),
+        }
+    }
+}
+
+/// Page size listing commands use when the caller doesn't pass a limit
+pub const DEFAULT_PAGE_LIMIT: u32 = 100;
+
+/// One page of a listing command's results. `total` counts every match
+/// before paging, and `has_more` is `offset + items.len() < total`.
+#[derive(Serialize, Deserialize)]
+pub struct Page<T> {
+    #[serde(flatten)]
+    pub base: BaseResponse,
+    pub items: Vec<T>,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u32,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Cut the page at `offset` out of the full listing `all`; `limit`
+    /// defaults to `DEFAULT_PAGE_LIMIT`
+    pub fn from_items(all: Vec<T>, limit: Option<u32>, offset: Option<u32>) -> Self {
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        let offset = offset.unwrap_or(0);
+        let total = all.len() as u64;
+        let items: Vec<T> = all.into_iter().skip(offset as usize).take(limit as usize).collect();
+        let has_more = offset as u64 + (items.len() as u64) < total;
+        Self {
+            base: BaseResponse::default(),
+            items,
+            total,
+            limit,
+            offset,
+            has_more,
+        }
+    }
+}
+
+// ============================================================================
+// Request Tracing
+// ============================================================================
+
+/// Log to stderr (info and above, plus this crate's debug output) and,
+/// when `log_dir` is given, to a file there rotated daily at `log_level`.
+/// Each command span's duration is logged when it closes, and sensitive
+/// fields are redacted. Hold the returned guard until exit so buffered file
+/// lines are flushed.
+pub fn init_tracing(log_dir: Option<&std::path::Path>, log_level: &str) -> Option<tracing_appender::non_blocking::WorkerGuard> {
+    use tracing_subscriber::fmt::format::FmtSpan;
+    use tracing_subscriber::prelude::*;
+    let stderr_filter = tracing_subscriber::filter::Targets::new()
+        .with_default(tracing::Level::INFO)
+        .with_target(env!("CARGO_CRATE_NAME"), tracing::Level::DEBUG);
+    let appender = log_dir.and_then(|dir| {
+        tracing_appender::rolling::Builder::new()
+            .rotation(tracing_appender::rolling::Rotation::DAILY)
+            .filename_prefix(logs::LOG_FILE_PREFIX)
+            .filename_suffix(logs::LOG_FILE_SUFFIX)
+            .build(dir)
+            .ok()
+    });
+    let (file_layer, guard) = match appender {
+        Some(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let level = log_level.parse().unwrap_or(tracing::Level::INFO);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .fmt_fields(logs::redacting_fields())
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(writer)
+                .with_filter(tracing_subscriber::filter::LevelFilter::from_level(level));
+            (Some(layer), Some(guard))
         }
+        None => (None, None),
+    };
+    let _ = tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .fmt_fields(logs::redacting_fields())
+                .with_span_events(FmtSpan::CLOSE)
+                .with_filter(stderr_filter),
+        )
+        .with(file_layer)
+        .try_init();
+    guard
+}
+
+/// Request id of one command invocation, kept with its span
+struct RequestId(String);
+
+/// Span for one command invocation. Tauri instruments async command
+/// futures as its children, so response events nest under it and it
+/// closes only once the command has responded. The invocation's request
+/// id is minted here, logged on the span and kept for `current_request_id`.
+pub fn command_span(command: &str, payload: &serde_json::Value) -> tracing::Span {
+    use tracing_subscriber::registry::LookupSpan;
+    let user = ["userId", "user"]
+        .iter()
+        .find_map(|key| payload.get(key).and_then(|v| v.as_str()))
+        .unwrap_or("anonymous");
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let span = tracing::info_span!("command", name = command, user = user, request_id = %request_id);
+    if let Some(id) = span.id() {
+        tracing::dispatcher::get_default(|dispatch| {
+            if let Some(span) = dispatch.downcast_ref::<tracing_subscriber::Registry>().and_then(|r| r.span(&id)) {
+                span.extensions_mut().insert(RequestId(request_id.clone()));
+            }
+        });
     }
+    span
+}
+
+/// Request id of the command invocation running now, found on the nearest
+/// enclosing command span; a fresh one outside any command
+pub fn current_request_id() -> String {
+    use tracing_subscriber::registry::LookupSpan;
+    let found = tracing::Span::current().id().and_then(|id| {
+        tracing::dispatcher::get_default(|dispatch| {
+            let span = dispatch.downcast_ref::<tracing_subscriber::Registry>()?.span(&id)?;
+            span.scope().find_map(|span| span.extensions().get::<RequestId>().map(|r| r.0.clone()))
+        })
+    });
+    found.unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
 }
 
 // ============================================================================
@@ -62,7 +256,7 @@ impl ApiResponse {
 // ============================================================================
 
 /// LLM Provider configuration
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct LLMProviderConfig {
     pub name: String,
     pub provider_type: String,
@@ -71,402 +265,5322 @@ pub struct LLMProviderConfig {
     pub model: String,
     pub priority: u8,
     pub is_active: bool,
+    /// Relative share of traffic in `"weighted"` routing mode
+    #[serde(default)]
+    pub weight: Option<u32>,
 }
 
 /// Full configuration
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct SynapseConfig {
     pub language: String,
     pub mode: String,
     pub llm_providers: Vec<LLMProviderConfig>,
+    /// Provider selection strategy: "priority" or "weighted"
+    #[serde(default = "default_routing_mode")]
+    pub routing_mode: String,
     pub data_paths: HashMap<String, String>,
     pub security_settings: SecuritySettings,
+    /// Overrides for the built-in token price table
+    #[serde(default)]
+    pub token_prices: Vec<providers::ModelPrice>,
+    /// Capabilities of custom models, or corrections to the built-in table
+    #[serde(default)]
+    pub model_capabilities: Vec<providers::ModelCapabilityOverride>,
+    /// Lowest level written to the log file: one of `logs::LOG_LEVELS`
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    /// In offline mode, still reach providers running on this machine,
+    /// such as Ollama on localhost
+    #[serde(default)]
+    pub allow_local_providers: bool,
+}
+
+fn default_routing_mode() -> String {
+    "priority".to_string()
+}
+
+fn default_log_level() -> String {
+    logs::DEFAULT_LOG_LEVEL.to_string()
+}
+
+fn default_rate_limit_per_minute() -> u32 {
+    security::DEFAULT_RATE_LIMIT_PER_MINUTE
+}
+
+fn default_session_timeout_minutes() -> u32 {
+    security::DEFAULT_SESSION_TIMEOUT_MINUTES
+}
+
+fn default_idempotency_window_minutes() -> u32 {
+    security::DEFAULT_IDEMPOTENCY_WINDOW_MINUTES
 }
 
 /// Security settings
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct SecuritySettings {
     pub require_approval_for_risk: u8,
     pub isolation_policy: String,
     pub audit_enabled: bool,
     pub trusted_users: Vec<String>,
+    /// State-changing commands allowed per user per minute; trusted users are exempt
+    #[serde(default = "default_rate_limit_per_minute")]
+    pub rate_limit_per_minute: u32,
+    /// Idle minutes before a session must sign in again
+    #[serde(default = "default_session_timeout_minutes")]
+    pub session_timeout_minutes: u32,
+    /// Minutes a response can be replayed by resending its idempotency key
+    #[serde(default = "default_idempotency_window_minutes")]
+    pub idempotency_window_minutes: u32,
+    /// Runs of any one skill each user may start per hour; unlimited when unset
+    #[serde(default)]
+    pub skill_runs_per_hour: Option<u32>,
+    /// Per-skill overrides of `skill_runs_per_hour`, keyed by skill id
+    #[serde(default)]
+    pub skill_run_quotas: HashMap<String, u32>,
+    /// Role per user id ("viewer", "operator" or "admin"); unlisted users are viewers
+    #[serde(default)]
+    pub user_roles: HashMap<String, String>,
+    /// Hex HMAC key signing capability tokens; generated on first issue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_signing_key: Option<String>,
+    /// Hex Ed25519 public keys whose skill signatures mark a skill verified
+    #[serde(default)]
+    pub trusted_signing_keys: Vec<String>,
+    /// URLs notified when a skill is approved, rejected or archived
+    #[serde(default)]
+    pub webhook_urls: Vec<String>,
+    /// Record failed webhook deliveries in the audit log, not just the log
+    #[serde(default)]
+    pub audit_webhook_failures: bool,
+    /// Audit entries older than this many days are rotated into the archive
+    #[serde(default)]
+    pub audit_retention_days: Option<u32>,
+    /// Rotate the oldest audit entries out once the active log exceeds this size
+    #[serde(default)]
+    pub audit_max_file_bytes: Option<u64>,
+    /// Audit entries written per minute before bursts of non-critical
+    /// actions are coalesced; `audit::DEFAULT_MAX_ENTRIES_PER_MINUTE` when unset
+    #[serde(default)]
+    pub audit_max_entries_per_minute: Option<u32>,
+}
+
+impl SecuritySettings {
+    pub fn audit_retention(&self) -> audit::RetentionPolicy {
+        audit::RetentionPolicy {
+            max_age_days: self.audit_retention_days,
+            max_file_bytes: self.audit_max_file_bytes,
+        }
+    }
+
+    pub fn audit_rate_limit(&self) -> u32 {
+        self.audit_max_entries_per_minute.unwrap_or(audit::DEFAULT_MAX_ENTRIES_PER_MINUTE)
+    }
 }
 
-/// Get current configuration
-#[tauri::command]
-pub async fn get_config() -> Result<ApiResponse, String> {
-    // In production, this would load from config file
-    let config = SynapseConfig {
-        language: "en".to_string(),
-        mode: "supervised".to_string(),
-        llm_providers: vec![
-            LLMProviderConfig {
-                name: "OpenAI GPT-4".to_string(),
-                provider_type: "openai".to_string(),
-                api_key: None,
-                base_url: Some("https://api.openai.com/v1".to_string()),
-                model: "gpt-4o".to_string(),
-                priority: 1,
-                is_active: true,
+/// Coalesce audit bursts at the rate `settings` configure
+pub fn apply_audit_rate_limit(state: &AppState, settings: &SecuritySettings) {
+    state.audit_log().lock().unwrap().set_max_entries_per_minute(settings.audit_rate_limit());
+}
+
+impl Default for SynapseConfig {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            mode: "supervised".to_string(),
+            llm_providers: vec![
+                LLMProviderConfig {
+                    name: "OpenAI GPT-4".to_string(),
+                    provider_type: "openai".to_string(),
+                    api_key: None,
+                    base_url: Some("https://api.openai.com/v1".to_string()),
+                    model: "gpt-4o".to_string(),
+                    priority: 1,
+                    is_active: true,
+                    weight: None,
+                },
+            ],
+            routing_mode: default_routing_mode(),
+            data_paths: {
+                let mut paths = HashMap::new();
+                paths.insert("config".to_string(), "~/.synapse/config".to_string());
+                paths.insert("skills".to_string(), "~/.synapse/skills".to_string());
+                paths.insert("memory".to_string(), "~/.synapse/memory".to_string());
+                paths
             },
-        ],
-        data_paths: {
-            let mut paths = HashMap::new();
-            paths.insert("config".to_string(), "~/.synapse/config".to_string());
-            paths.insert("skills".to_string(), "~/.synapse/skills".to_string());
-            paths.insert("memory".to_string(), "~/.synapse/memory".to_string());
-            paths
-        },
-        security_settings: SecuritySettings {
-            require_approval_for_risk: 3,
-            isolation_policy: "container".to_string(),
-            audit_enabled: true,
-            trusted_users: vec![],
-        },
-    };
-    
-    Ok(ApiResponse::success(serde_json::to_value(config).unwrap()))
+            security_settings: SecuritySettings {
+                require_approval_for_risk: 3,
+                isolation_policy: "container".to_string(),
+                audit_enabled: true,
+                trusted_users: vec![],
+                rate_limit_per_minute: default_rate_limit_per_minute(),
+                session_timeout_minutes: default_session_timeout_minutes(),
+                idempotency_window_minutes: default_idempotency_window_minutes(),
+                skill_runs_per_hour: None,
+                skill_run_quotas: HashMap::new(),
+                user_roles: HashMap::new(),
+                token_signing_key: None,
+                trusted_signing_keys: vec![],
+                webhook_urls: vec![],
+                audit_webhook_failures: false,
+                audit_retention_days: None,
+                audit_max_file_bytes: None,
+                audit_max_entries_per_minute: None,
+            },
+            token_prices: vec![],
+            model_capabilities: vec![],
+            log_level: default_log_level(),
+            allow_local_providers: false,
+        }
+    }
 }
 
-/// Save configuration
-#[tauri::command]
-pub async fn save_config(config: SynapseConfig) -> Result<ApiResponse, String> {
-    // In production, this would save to config file
-    // Validate protocol version
+/// Environment variable holding the user's home directory
+#[cfg(windows)]
+const HOME_ENV: &str = "USERPROFILE";
+#[cfg(not(windows))]
+const HOME_ENV: &str = "HOME";
+
+/// Expand a leading `~` and any `$VAR`/`${VAR}` in a path template.
+///
+/// Variables are looked up through `env` so callers can supply something
+/// other than the process environment. Unset variables, including the home
+/// variable behind `~`, are reported as errors.
+pub fn expand_path_template(raw: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let unset = |name: &str| format!("Environment variable '{}' is not set (needed to expand '{}')", name, raw);
+    let mut expanded = String::new();
+    let mut rest = raw;
     
-    Ok(ApiResponse::success(serde_json::json!({
-        "saved": true,
-        "message": "Configuration saved successfully"
-    })))
+    if let Some(after) = raw.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            expanded.push_str(&env(HOME_ENV).ok_or_else(|| unset(HOME_ENV))?);
+            rest = after;
+        }
+    }
+    
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced.find('}').ok_or_else(|| format!("Unclosed '${{' in '{}'", raw))?;
+                (&braced[..end], end + 2)
+            }
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        if name.is_empty() {
+            expanded.push('$');
+        } else {
+            expanded.push_str(&env(name).ok_or_else(|| unset(name))?);
+        }
+        rest = &after[consumed..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
 }
 
-/// Test LLM connection
-#[tauri::command]
-pub async fn test_llm_connection(
-    provider_type: String,
-    api_key: String,
-    base_url: Option<String>,
-    model: String,
-) -> Result<ApiResponse, String> {
-    // In production, this would make a test API call
-    let success = !api_key.is_empty();
+fn process_env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+impl SynapseConfig {
+    /// Resolve a `data_paths` entry, expanding `~` and environment variables
+    pub fn data_path(&self, key: &str) -> Result<PathBuf, String> {
+        let raw = self
+            .data_paths
+            .get(key)
+            .ok_or_else(|| format!("No data path configured for '{}'", key))?;
+        expand_path_template(raw, &process_env).map(PathBuf::from)
+    }
     
-    Ok(ApiResponse::success(serde_json::json!({
-        "connected": success,
-        "provider": provider_type,
-        "model": model,
-        "message": if success { "Connection successful" } else { "Invalid API key" }
-    })))
+    /// Every `data_paths` entry expanded; the stored templates are left
+    /// untouched so they round-trip through save unchanged
+    pub fn expanded_data_paths(&self) -> Result<HashMap<String, String>, String> {
+        self.data_paths
+            .iter()
+            .map(|(key, raw)| Ok((key.clone(), expand_path_template(raw, &process_env)?)))
+            .collect()
+    }
+    
+    /// Expanded `data_paths` as (key, path), sorted by key
+    pub fn data_dirs(&self) -> Result<Vec<(String, PathBuf)>, String> {
+        let mut dirs: Vec<(String, PathBuf)> =
+            self.expanded_data_paths()?.into_iter().map(|(key, path)| (key, PathBuf::from(path))).collect();
+        dirs.sort();
+        Ok(dirs)
+    }
+    
+    /// Whether the app runs air-gapped (`mode == "offline"`)
+    pub fn is_offline(&self) -> bool {
+        self.mode == "offline"
+    }
+    
+    /// Refuse `feature`, which needs the network, in offline mode
+    pub fn require_network(&self, feature: &str) -> Result<(), SynapseError> {
+        if self.is_offline() {
+            Err(SynapseError::Offline(format!("{} is disabled in offline mode", feature)))
+        } else {
+            Ok(())
+        }
+    }
+    
+    /// Like `require_network`, but a provider on this machine may still be
+    /// reached when `allow_local_providers` is set
+    pub fn require_provider_network(&self, feature: &str, provider: &LLMProviderConfig) -> Result<(), SynapseError> {
+        if self.allow_local_providers && providers::is_local_provider(provider) {
+            return Ok(());
+        }
+        self.require_network(feature)
+    }
 }
 
-// ============================================================================
-// Skill Management Commands
-// ============================================================================
+/// Single-file configuration written before profiles existed
+pub const CONFIG_FILE: &str = "config.json";
 
-/// Skill status
-#[derive(Serialize, Deserialize, Clone)]
-pub struct SkillInfo {
-    pub id: String,
-    pub name: String,
-    pub version: String,
-    pub status: String,
-    pub trust_level: String,
-    pub risk_level: u8,
-    pub isolation_type: String,
-    pub required_capabilities: Vec<String>,
-    pub created_at: DateTime<Utc>,
-    pub last_used: Option<DateTime<Utc>>,
+/// Overrides the directory the saved configuration is loaded from
+pub const CONFIG_DIR_ENV: &str = "SYNAPSE_CONFIG_DIR";
+
+/// Profile used when none is named
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile directory inside the config directory
+const PROFILES_DIR: &str = "profiles";
+
+/// Pointer to the profile loaded at startup, inside the config directory
+const ACTIVE_PROFILE_FILE: &str = "active.json";
+
+/// Directory the saved configuration is loaded from at startup
+pub fn config_dir() -> Result<PathBuf, String> {
+    match std::env::var_os(CONFIG_DIR_ENV) {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => SynapseConfig::default().data_path("config"),
+    }
 }
 
-/// Get all skills
-#[tauri::command]
-pub async fn get_skills() -> Result<ApiResponse, String> {
-    let skills: Vec<SkillInfo> = vec![
-        SkillInfo {
-            id: "skill-001".to_string(),
-            name: "read_file".to_string(),
-            version: "1.0.0".to_string(),
-            status: "active".to_string(),
-            trust_level: "trusted".to_string(),
-            risk_level: 1,
-            isolation_type: "subprocess".to_string(),
-            required_capabilities: vec!["fs:read".to_string()],
-            created_at: Utc::now(),
-            last_used: Some(Utc::now()),
-        },
-        SkillInfo {
-            id: "skill-002".to_string(),
-            name: "write_file".to_string(),
-            version: "1.0.0".to_string(),
-            status: "active".to_string(),
-            trust_level: "verified".to_string(),
-            risk_level: 2,
-            isolation_type: "container".to_string(),
-            required_capabilities: vec!["fs:write".to_string()],
-            created_at: Utc::now(),
-            last_used: Some(Utc::now()),
-        },
-        SkillInfo {
-            id: "skill-003".to_string(),
-            name: "web_search".to_string(),
-            version: "1.0.0".to_string(),
-            status: "pending".to_string(),
-            trust_level: "unverified".to_string(),
-            risk_level: 3,
-            isolation_type: "container".to_string(),
-            required_capabilities: vec!["network:http".to_string()],
-            created_at: Utc::now(),
-            last_used: None,
-        },
-    ];
-    
-    Ok(ApiResponse::success(serde_json::to_value(skills).unwrap()))
+/// Reject profile names that could escape the profiles directory
+fn check_profile_name(name: &str) -> Result<(), String> {
+    if !name.is_empty() && name.len() <= 64 && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(())
+    } else {
+        Err(format!("Invalid profile name '{}'; use letters, digits, '-' or '_'", name))
+    }
 }
 
-/// Get skill details
-#[tauri::command]
-pub async fn get_skill_details(skill_id: String) -> Result<ApiResponse, String> {
-    // In production, this would load from skill registry
-    Ok(ApiResponse::success(serde_json::json!({
-        "id": skill_id,
-        "name": "example_skill",
-        "version": "1.0.0",
-        "description": "Example skill for demonstration",
-        "author": "synapse_core",
-        "inputs": {
-            "query": {"type": "string", "required": true}
-        },
-        "outputs": {
-            "result": {"type": "string"}
-        },
-        "required_capabilities": ["fs:read"],
-        "risk_level": 2,
-        "trust_level": "verified",
-        "isolation_type": "container"
-    })))
+/// File holding a named profile under `dir`
+pub fn profile_path(dir: &std::path::Path, name: &str) -> Result<PathBuf, String> {
+    check_profile_name(name)?;
+    Ok(dir.join(PROFILES_DIR).join(format!("{}.json", name)))
 }
 
-/// Approve a skill
-#[tauri::command]
-pub async fn approve_skill(skill_id: String, approved_by: String) -> Result<ApiResponse, String> {
-    Ok(ApiResponse::success(serde_json::json!({
-        "skill_id": skill_id,
-        "approved": true,
-        "approved_by": approved_by,
-        "approved_at": Utc::now().to_rfc3339()
-    })))
+/// Profile named by `active.json`, or the default profile
+pub fn active_profile(dir: &std::path::Path) -> String {
+    std::fs::read_to_string(dir.join(ACTIVE_PROFILE_FILE))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|pointer| pointer["profile"].as_str().map(str::to_string))
+        .filter(|name| check_profile_name(name).is_ok())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
 }
 
-/// Reject a skill
-#[tauri::command]
-pub async fn reject_skill(skill_id: String, reason: String) -> Result<ApiResponse, String> {
-    Ok(ApiResponse::success(serde_json::json!({
-        "skill_id": skill_id,
-        "rejected": true,
-        "reason": reason,
-        "rejected_at": Utc::now().to_rfc3339()
-    })))
+/// Point `active.json` at an existing profile
+pub fn set_active_profile(dir: &std::path::Path, name: &str) -> Result<(), String> {
+    if !profile_path(dir, name)?.is_file() {
+        return Err(format!("Profile '{}' does not exist", name));
+    }
+    let raw = serde_json::json!({ "profile": name }).to_string();
+    std::fs::write(dir.join(ACTIVE_PROFILE_FILE), raw).map_err(|e| format!("Failed to set active profile: {}", e))
 }
 
-/// Archive a skill
-#[tauri::command]
-pub async fn archive_skill(skill_id: String) -> Result<ApiResponse, String> {
-    Ok(ApiResponse::success(serde_json::json!({
-        "skill_id": skill_id,
-        "archived": true,
-        "archived_at": Utc::now().to_rfc3339()
-    })))
+/// Names of all saved profiles, sorted
+pub fn saved_profiles(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir.join(PROFILES_DIR))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| e.file_name().to_string_lossy().strip_suffix(".json").map(str::to_string))
+                .filter(|name| check_profile_name(name).is_ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
 }
 
-// ============================================================================
-// Metrics Commands
-// ============================================================================
+/// Load a profile from `dir`. The default profile falls back to the legacy
+/// single-file config so existing installs keep their settings.
+pub fn load_profile(dir: &std::path::Path, name: &str) -> Result<Option<SynapseConfig>, String> {
+    match read_config_file(&profile_path(dir, name)?)? {
+        Some(config) => Ok(Some(config)),
+        None if name == DEFAULT_PROFILE => read_config_file(&dir.join(CONFIG_FILE)),
+        None => Ok(None),
+    }
+}
 
-/// System metrics
-#[derive(Serialize, Deserialize)]
-pub struct SystemMetrics {
-    pub cpu_percent: f64,
-    pub memory_percent: f64,
-    pub memory_used_mb: u64,
-    pub memory_total_mb: u64,
-    pub disk_percent: f64,
-    pub uptime_seconds: u64,
+/// Apply `transform` to every non-null provider `api_key` and the token
+/// signing key
+fn map_secrets(config: &mut SynapseConfig, transform: fn(&str) -> Result<String, String>) -> Result<(), String> {
+    for provider in config.llm_providers.iter_mut() {
+        if let Some(key) = &provider.api_key {
+            provider.api_key = Some(transform(key).map_err(|e| format!("Provider '{}': {}", provider.name, e))?);
+        }
+    }
+    let security = &mut config.security_settings;
+    if let Some(key) = &security.token_signing_key {
+        security.token_signing_key = Some(transform(key).map_err(|e| format!("Token signing key: {}", e))?);
+    }
+    Ok(())
 }
 
-/// LLM usage metrics
-#[derive(Serialize, Deserialize)]
-pub struct LLMUsageMetrics {
-    pub total_tokens: u64,
-    pub prompt_tokens: u64,
-    pub completion_tokens: u64,
-    pub estimated_cost_usd: f64,
-    pub provider_distribution: HashMap<String, u64>,
+/// Read a saved configuration; `Ok(None)` when no file exists yet.
+///
+/// Encrypted secrets are decrypted; legacy plaintext keys load unchanged.
+pub fn read_config_file(path: &std::path::Path) -> Result<Option<SynapseConfig>, String> {
+    let mut config: SynapseConfig = match std::fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    map_secrets(&mut config, security::decrypt_secret)?;
+    Ok(Some(config))
 }
 
-/// Skill execution metrics
-#[derive(Serialize, Deserialize)]
-pub struct SkillMetrics {
-    pub skill_name: String,
-    pub total_executions: u64,
-    pub successful_executions: u64,
-    pub failed_executions: u64,
-    pub avg_latency_ms: f64,
-    pub success_rate: f64,
+/// Write a configuration atomically: a temp file in the same directory is
+/// renamed over the target, so a crash mid-write leaves the old file intact.
+///
+/// API keys and the token signing key are encrypted first; if the keychain
+/// is unavailable nothing is written rather than falling back to plaintext.
+pub fn write_config_file(path: &std::path::Path, config: &SynapseConfig) -> Result<(), String> {
+    use std::io::Write;
+    
+    let mut stored = config.clone();
+    map_secrets(&mut stored, security::encrypt_secret)?;
+    let dir = path.parent().ok_or_else(|| format!("Invalid config path {}", path.display()))?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let raw = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
+    
+    let mut tmp = tempfile::NamedTempFile::new_in(dir).map_err(|e| format!("Failed to create temp file: {}", e))?;
+    tmp.write_all(raw.as_bytes())
+        .and_then(|_| tmp.as_file().sync_all())
+        .map_err(|e| format!("Failed to write config: {}", e))?;
+    tmp.persist(path).map_err(|e| format!("Failed to write {}: {}", path.display(), e.error))?;
+    state::app_state().invalidate_config();
+    Ok(())
 }
 
-/// Get system metrics
+/// Load the active profile, falling back to defaults when none is saved.
+/// Served from the shared state until the file changes. A profile that
+/// fails to load also yields defaults so read-only callers keep working;
+/// anything that saves the profile back goes through `update_active_profile`.
+pub fn load_config() -> SynapseConfig {
+    state::app_state().config().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "active profile could not be loaded; using defaults");
+        SynapseConfig::default()
+    })
+}
+
+/// Read-modify-write the active profile in `dir`, returning `update`'s
+/// result and the saved config. A profile that exists but fails to load is
+/// left alone, so a parse or decryption error never replaces the user's
+/// providers and keys with defaults.
+pub fn update_active_profile<T>(
+    dir: &std::path::Path,
+    update: impl FnOnce(&mut SynapseConfig) -> Result<T, SynapseError>,
+) -> Result<(T, SynapseConfig), SynapseError> {
+    let profile = active_profile(dir);
+    let loaded = load_profile(dir, &profile).map_err(|e| {
+        SynapseError::Internal(format!("Profile '{}' could not be loaded, so it was not changed: {}", profile, e))
+    })?;
+    let mut config = loaded.unwrap_or_default();
+    let result = update(&mut config)?;
+    let path = profile_path(dir, &profile).map_err(SynapseError::Internal)?;
+    write_config_file(&path, &config).map_err(SynapseError::Internal)?;
+    Ok((result, config))
+}
+
+/// Get a profile's configuration (default: the active profile)
 #[tauri::command]
-pub async fn get_system_metrics() -> Result<ApiResponse, String> {
-    let metrics = SystemMetrics {
-        cpu_percent: 25.5,
-        memory_percent: 45.2,
-        memory_used_mb: 1843,
-        memory_total_mb: 4096,
-        disk_percent: 35.0,
-        uptime_seconds: 86400,
+pub async fn get_config(profile: Option<String>) -> Result<ApiResponse, String> {
+    let loaded = config_dir().and_then(|dir| {
+        let name = profile.unwrap_or_else(|| active_profile(&dir));
+        load_profile(&dir, &name)
+    });
+    let config = match loaded {
+        Ok(saved) => saved.unwrap_or_default(),
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Internal(e))),
     };
     
-    Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap()))
+    Ok(ApiResponse::success(redacted_config(&config)))
 }
 
-/// Get LLM usage
+/// List saved configuration profiles
 #[tauri::command]
-pub async fn get_llm_usage() -> Result<ApiResponse, String> {
-    let mut provider_dist = HashMap::new();
-    provider_dist.insert("openai".to_string(), 75000u64);
-    provider_dist.insert("anthropic".to_string(), 25000u64);
-    
-    let metrics = LLMUsageMetrics {
-        total_tokens: 100000,
-        prompt_tokens: 60000,
-        completion_tokens: 40000,
-        estimated_cost_usd: 1.25,
-        provider_distribution: provider_dist,
+pub async fn list_profiles() -> Result<ApiResponse, String> {
+    Ok(ApiResponse::success(serde_json::to_value(saved_profiles(&try_internal!(config_dir()))).unwrap()))
+}
+
+/// Make a saved profile the one loaded at startup
+#[tauri::command]
+pub async fn switch_profile(name: String, auth_token: Option<String>, session_id: Option<String>) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
     };
-    
-    Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap()))
+    match set_active_profile(&try_internal!(config_dir()), &name) {
+        Ok(()) => {
+            audit::record("config_update", &caller, "success", Some(&name), Some("Profile activated".to_string()));
+            Ok(ApiResponse::success(serde_json::json!({ "active": name })))
+        }
+        Err(e) => Ok(ApiResponse::error(SynapseError::NotFound(e))),
+    }
 }
 
-/// Get skill metrics
+/// Delete a saved profile; the active profile can't be deleted
 #[tauri::command]
-pub async fn get_skill_metrics(skill_name: Option<String>) -> Result<ApiResponse, String> {
-    let metrics = vec![
-        SkillMetrics {
-            skill_name: "read_file".to_string(),
-            total_executions: 150,
-            successful_executions: 148,
-            failed_executions: 2,
-            avg_latency_ms: 45.5,
-            success_rate: 98.67,
-        },
-        SkillMetrics {
-            skill_name: "write_file".to_string(),
-            total_executions: 75,
-            successful_executions: 73,
-            failed_executions: 2,
-            avg_latency_ms: 62.3,
-            success_rate: 97.33,
-        },
-    ];
-    
-    if let Some(name) = skill_name {
-        let filtered: Vec<_> = metrics.into_iter().filter(|m| m.skill_name == name).collect();
-        Ok(ApiResponse::success(serde_json::to_value(filtered).unwrap()))
-    } else {
-        Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap()))
+pub async fn delete_profile(name: String, auth_token: Option<String>, session_id: Option<String>) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let dir = try_internal!(config_dir());
+    if active_profile(&dir) == name {
+        return Ok(ApiResponse::error(SynapseError::Validation(format!("Profile '{}' is active; switch to another profile first", name))));
+    }
+    let path = match profile_path(&dir, &name) {
+        Ok(path) => path,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    match std::fs::remove_file(&path) {
+        Ok(()) => {
+            audit::record("config_update", &caller, "success", Some(&name), Some("Profile deleted".to_string()));
+            Ok(ApiResponse::success(serde_json::json!({ "deleted": name })))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ApiResponse::error(SynapseError::NotFound(format!("Profile '{}' does not exist", name)))),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Internal(format!("Failed to delete profile '{}': {}", name, e)))),
     }
 }
 
-// ============================================================================
-// Security Commands
-// ============================================================================
+/// Backup directory inside the config directory
+const BACKUPS_DIR: &str = "backups";
 
-/// Capability token info
-#[derive(Serialize, Deserialize)]
-pub struct CapabilityInfo {
-    pub token_id: String,
-    pub user_id: String,
-    pub capabilities: Vec<String>,
-    pub issued_at: DateTime<Utc>,
-    pub expires_at: Option<DateTime<Utc>>,
+/// Saved file backing the active profile, if any
+fn active_config_file(dir: &std::path::Path) -> Result<Option<PathBuf>, String> {
+    let name = active_profile(dir);
+    let path = profile_path(dir, &name)?;
+    if path.is_file() {
+        return Ok(Some(path));
+    }
+    let legacy = dir.join(CONFIG_FILE);
+    Ok((name == DEFAULT_PROFILE && legacy.is_file()).then_some(legacy))
+}
+
+/// Copy the active profile's file to `backups/config-{timestamp}.json`.
+///
+/// The file is copied as stored, so encrypted keys stay encrypted.
+/// Returns `Ok(None)` when there is nothing saved to back up.
+pub fn backup_config(dir: &std::path::Path) -> Result<Option<PathBuf>, String> {
+    let source = match active_config_file(dir)? {
+        Some(source) => source,
+        None => return Ok(None),
+    };
+    let backups = dir.join(BACKUPS_DIR);
+    std::fs::create_dir_all(&backups).map_err(|e| format!("Failed to create {}: {}", backups.display(), e))?;
+    let target = backups.join(format!("config-{}.json", Utc::now().format("%Y%m%dT%H%M%S%.3fZ")));
+    std::fs::copy(&source, &target).map_err(|e| format!("Failed to back up {}: {}", source.display(), e))?;
+    Ok(Some(target))
+}
+
+/// Back up the active profile, then overwrite it with the built-in defaults
+pub fn reset_config(dir: &std::path::Path) -> Result<Option<PathBuf>, String> {
+    let backup = backup_config(dir)?;
+    write_config_file(&profile_path(dir, &active_profile(dir))?, &SynapseConfig::default())?;
+    Ok(backup)
+}
+
+/// Config backups under `dir`, newest first
+pub fn config_backups(dir: &std::path::Path) -> Vec<PathBuf> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir.join(BACKUPS_DIR))
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+/// Replace the active profile with a backup, backing up the current file first.
+///
+/// Only files inside the backup directory are accepted, and the backup must
+/// parse as a `SynapseConfig` before anything is overwritten.
+pub fn restore_backup(dir: &std::path::Path, backup: &std::path::Path) -> Result<Option<PathBuf>, String> {
+    let backups = std::fs::canonicalize(dir.join(BACKUPS_DIR)).map_err(|_| "No config backups exist".to_string())?;
+    let backup = std::fs::canonicalize(backup).map_err(|e| format!("Backup {} not found: {}", backup.display(), e))?;
+    if backup.parent() != Some(backups.as_path()) {
+        return Err(format!("{} is not a config backup", backup.display()));
+    }
+    let config = read_config_file(&backup)?.ok_or_else(|| format!("Backup {} not found", backup.display()))?;
+    
+    let previous = backup_config(dir)?;
+    write_config_file(&profile_path(dir, &active_profile(dir))?, &config)?;
+    Ok(previous)
+}
+
+/// Reset the active profile to defaults, keeping a backup of the old file
+#[tauri::command]
+pub async fn reset_config_to_defaults(auth_token: Option<String>, session_id: Option<String>) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    match reset_config(&try_internal!(config_dir())) {
+        Ok(backup) => {
+            audit::record("config_update", &caller, "success", None, Some("Active profile reset to defaults".to_string()));
+            Ok(ApiResponse::success(serde_json::json!({
+                "reset": true,
+                "backup_path": backup.map(|path| path.to_string_lossy().to_string())
+            })))
+        }
+        Err(e) => Ok(ApiResponse::error(SynapseError::Internal(e))),
+    }
+}
+
+/// List saved config backups, newest first
+#[tauri::command]
+pub async fn list_config_backups() -> Result<ApiResponse, String> {
+    let backups: Vec<String> = config_backups(&try_internal!(config_dir()))
+        .into_iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    Ok(ApiResponse::success(serde_json::to_value(backups).unwrap()))
+}
+
+/// Restore the active profile from a backup
+#[tauri::command]
+pub async fn restore_config_backup(
+    path: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    match restore_backup(&try_internal!(config_dir()), std::path::Path::new(&path)) {
+        Ok(previous) => {
+            audit::record("config_update", &caller, "success", Some(&path), Some("Active profile restored from backup".to_string()));
+            Ok(ApiResponse::success(serde_json::json!({
+                "restored": path,
+                "backup_path": previous.map(|path| path.to_string_lossy().to_string())
+            })))
+        }
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Top-level folders of a data backup archive
+pub const DATA_BACKUP_FOLDERS: &[&str] = &["config", "skills", "audit"];
+
+/// Result of archiving the data directories
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DataBackupReport {
+    pub archive_path: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+    pub protocol_version: String,
+}
+
+/// Result of restoring a data backup
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DataRestoreReport {
+    pub restored_folders: Vec<String>,
+    /// Where the archived audit log was placed, beside the live one
+    pub archived_audit_log: Option<String>,
+    pub protocol_version: String,
+}
+
+type DataArchive = tar::Builder<flate2::write::GzEncoder<std::fs::File>>;
+
+/// Add the top-level entries of `dir` that `keep` accepts to `archive` as
+/// the folder `name`. A missing `dir` is archived as an empty folder.
+fn append_data_folder(
+    archive: &mut DataArchive,
+    name: &str,
+    dir: &std::path::Path,
+    keep: impl Fn(&str) -> bool,
+) -> Result<(), String> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Directory);
+    header.set_size(0);
+    header.set_mode(0o755);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, format!("{}/", name), std::io::empty())
+        .map_err(|e| format!("Failed to archive {}: {}", name, e))?;
+    
+    for entry in std::fs::read_dir(dir).into_iter().flatten().filter_map(|e| e.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !keep(&file_name) {
+            continue;
+        }
+        let archived = format!("{}/{}", name, file_name);
+        let appended = if entry.path().is_dir() {
+            archive.append_dir_all(&archived, entry.path())
+        } else {
+            archive.append_path_with_name(entry.path(), &archived)
+        };
+        appended.map_err(|e| format!("Failed to archive {}: {}", entry.path().display(), e))?;
+    }
+    Ok(())
+}
+
+/// Archive the config and skills directories and the audit log into
+/// `{config_dir}/backups/data-{timestamp}.tar.gz`. Earlier backups and the
+/// audit log are left out of the `config` folder; the log goes in `audit`.
+pub fn backup_data(config_dir: &std::path::Path, skills_dir: &std::path::Path, now: DateTime<Utc>) -> Result<DataBackupReport, String> {
+    let backups = config_dir.join(BACKUPS_DIR);
+    std::fs::create_dir_all(&backups).map_err(|e| format!("Failed to create {}: {}", backups.display(), e))?;
+    let archive_path = backups.join(format!("data-{}.tar.gz", now.format("%Y%m%dT%H%M%S%.3fZ")));
+    let file = std::fs::File::create(&archive_path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(file, flate2::Compression::default()));
+    
+    append_data_folder(&mut archive, "config", config_dir, |f| f != BACKUPS_DIR && f != audit::AUDIT_LOG_FILE)?;
+    append_data_folder(&mut archive, "skills", skills_dir, |_| true)?;
+    append_data_folder(&mut archive, "audit", config_dir, |f| f == audit::AUDIT_LOG_FILE)?;
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| format!("Failed to finish archive: {}", e))?;
+    
+    Ok(DataBackupReport {
+        size_bytes: std::fs::metadata(&archive_path).map(|m| m.len()).unwrap_or(0),
+        archive_path: archive_path.to_string_lossy().to_string(),
+        created_at: now,
+        protocol_version: PROTOCOL_VERSION.to_string(),
+    })
+}
+
+/// Put the archived skills folder `staged` over `skills_dir`. Each skill
+/// goes through `skills::install_restored_skill`, so it comes back
+/// `pending`/`unverified` whatever state the archive recorded.
+fn restore_skill_folder(staged: &std::path::Path, skills_dir: &std::path::Path) -> Result<(), String> {
+    std::fs::create_dir_all(skills_dir).map_err(|e| format!("Failed to create {}: {}", skills_dir.display(), e))?;
+    let entries = std::fs::read_dir(staged).map_err(|e| format!("Failed to read archived skills: {}", e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() {
+            skills::install_restored_skill(&entry.path(), skills_dir, &name)
+                .map_err(|e| format!("Failed to restore skill '{}': {}", name, e))?;
+        } else {
+            std::fs::copy(entry.path(), skills_dir.join(&name)).map_err(|e| format!("Failed to restore {}: {}", name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Restore a `backup_data` archive over the live config and skills
+/// directories. The archive is unpacked and its folders checked before
+/// anything live is touched. The live audit log is append-only, so the
+/// archived one is placed beside it as `audit-restored-{timestamp}.jsonl`.
+pub fn restore_data(
+    archive_path: &std::path::Path,
+    config_dir: &std::path::Path,
+    skills_dir: &std::path::Path,
+    now: DateTime<Utc>,
+) -> Result<DataRestoreReport, String> {
+    let staging = tempfile::tempdir().map_err(|e| format!("Failed to create staging directory: {}", e))?;
+    let file = std::fs::File::open(archive_path).map_err(|e| format!("Failed to open {}: {}", archive_path.display(), e))?;
+    tar::Archive::new(flate2::read::GzDecoder::new(file))
+        .unpack(staging.path())
+        .map_err(|e| format!("Failed to extract archive: {}", e))?;
+    
+    let missing: Vec<&str> = DATA_BACKUP_FOLDERS
+        .iter()
+        .copied()
+        .filter(|folder| !staging.path().join(folder).is_dir())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Archive is missing top-level folder(s): {}", missing.join(", ")));
+    }
+    
+    skills::copy_dir(&staging.path().join("config"), config_dir)?;
+    restore_skill_folder(&staging.path().join("skills"), skills_dir)?;
+    let archived_log = staging.path().join("audit").join(audit::AUDIT_LOG_FILE);
+    let archived_audit_log = if archived_log.is_file() {
+        let target = config_dir.join(format!("audit-restored-{}.jsonl", now.format("%Y%m%dT%H%M%SZ")));
+        std::fs::copy(&archived_log, &target).map_err(|e| format!("Failed to restore audit log: {}", e))?;
+        Some(target.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    
+    Ok(DataRestoreReport {
+        restored_folders: DATA_BACKUP_FOLDERS.iter().map(|f| f.to_string()).collect(),
+        archived_audit_log,
+        protocol_version: PROTOCOL_VERSION.to_string(),
+    })
+}
+
+/// Archive config, skills and the audit log into one timestamped backup
+#[tauri::command]
+pub async fn create_data_backup() -> Result<ApiResponse, String> {
+    let config_dir = try_internal!(config_dir());
+    let skills_dir = try_internal!(load_config().data_path("skills"));
+    let result = try_internal!(
+        tokio::task::spawn_blocking(move || backup_data(&config_dir, &skills_dir, Utc::now()))
+            .await
+    );
+    
+    match result {
+        Ok(report) => {
+            audit::record("data_backup", "system", "success", Some(&report.archive_path), None);
+            Ok(ApiResponse::success(serde_json::to_value(report).unwrap()))
+        }
+        Err(e) => {
+            audit::record("data_backup", "system", "failure", None, Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Internal(e)))
+        }
+    }
+}
+
+/// Restore a data backup over the live config and skills directories
+#[tauri::command]
+pub async fn restore_data_backup(
+    path: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let config_dir = try_internal!(config_dir());
+    let skills_dir = try_internal!(load_config().data_path("skills"));
+    let archive = PathBuf::from(&path);
+    let result = try_internal!(
+        tokio::task::spawn_blocking(move || restore_data(&archive, &config_dir, &skills_dir, Utc::now()))
+            .await
+    );
+    
+    match result {
+        Ok(report) => {
+            audit::record("data_restore", &caller, "success", Some(&path), None);
+            Ok(ApiResponse::success(serde_json::to_value(report).unwrap()))
+        }
+        Err(e) => {
+            audit::record("data_restore", &caller, "failure", Some(&path), Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Validation(e)))
+        }
+    }
+}
+
+/// On-disk state of one `data_paths` entry
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DataPathStatus {
+    pub key: String,
+    pub path: String,
+    /// Where the path points when it is a symlink
+    pub symlink_target: Option<String>,
+    pub exists: bool,
+    pub is_dir: bool,
+    pub writable: bool,
+    /// "ok", "missing" (repair can create it) or "error"
+    pub status: String,
+    pub message: Option<String>,
+}
+
+/// Inspect a data directory, following symlinks
+pub fn inspect_data_path(key: &str, path: &std::path::Path) -> DataPathStatus {
+    let symlink_target = std::fs::symlink_metadata(path)
+        .ok()
+        .filter(|meta| meta.file_type().is_symlink())
+        .map(|_| std::fs::canonicalize(path).or_else(|_| std::fs::read_link(path)))
+        .and_then(Result::ok)
+        .map(|target| target.to_string_lossy().to_string());
+    let exists = path.exists();
+    let is_dir = path.is_dir();
+    let writable = is_dir && tempfile::tempfile_in(path).is_ok();
+    
+    let (status, message) = if is_dir && writable {
+        ("ok", None)
+    } else if is_dir {
+        ("error", Some(format!("{} is not writable", path.display())))
+    } else if exists {
+        ("error", Some(format!("{} is not a directory", path.display())))
+    } else if let Some(target) = &symlink_target {
+        ("error", Some(format!("{} is a symlink to missing {}", path.display(), target)))
+    } else if wizard::is_writable(path) {
+        ("missing", Some(format!("{} does not exist", path.display())))
+    } else {
+        ("error", Some(format!("{} does not exist and cannot be created here", path.display())))
+    };
+    
+    DataPathStatus {
+        key: key.to_string(),
+        path: path.to_string_lossy().to_string(),
+        symlink_target,
+        exists,
+        is_dir,
+        writable,
+        status: status.to_string(),
+        message,
+    }
+}
+
+/// Create every missing data directory and re-inspect all of them. Paths in
+/// error are left alone: they need the user's attention, not a new folder.
+pub fn repair_data_dirs(dirs: &[(String, PathBuf)]) -> (Vec<String>, Vec<DataPathStatus>) {
+    let mut created = Vec::new();
+    for (key, path) in dirs {
+        if inspect_data_path(key, path).status == "missing" && std::fs::create_dir_all(path).is_ok() {
+            created.push(key.clone());
+        }
+    }
+    let statuses = dirs.iter().map(|(key, path)| inspect_data_path(key, path)).collect();
+    (created, statuses)
+}
+
+/// Report whether each configured data path exists, is a directory and is writable
+#[tauri::command]
+pub async fn check_data_paths() -> Result<ApiResponse, String> {
+    let dirs = match load_config().data_dirs() {
+        Ok(dirs) => dirs,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    let statuses: Vec<DataPathStatus> = dirs.iter().map(|(key, path)| inspect_data_path(key, path)).collect();
+    Ok(ApiResponse::success(serde_json::json!({
+        "healthy": statuses.iter().all(|s| s.status == "ok"),
+        "paths": statuses
+    })))
+}
+
+/// Create missing data directories. Without `confirm` nothing is created
+/// and the response lists what would be.
+#[tauri::command]
+pub async fn repair_data_paths(confirm: Option<bool>) -> Result<ApiResponse, String> {
+    let dirs = match load_config().data_dirs() {
+        Ok(dirs) => dirs,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    if !confirm.unwrap_or(false) {
+        let missing: Vec<String> = dirs
+            .iter()
+            .map(|(key, path)| inspect_data_path(key, path))
+            .filter(|status| status.status == "missing")
+            .map(|status| status.path)
+            .collect();
+        let mut response = ApiResponse::error(SynapseError::Validation(format!(
+            "Repair would create {} director{}. Resubmit with confirm to proceed.",
+            missing.len(),
+            if missing.len() == 1 { "y" } else { "ies" }
+        )));
+        response.data = Some(serde_json::json!({ "would_create": missing }));
+        return Ok(response);
+    }
+    
+    let (created, statuses) = try_internal!(tokio::task::spawn_blocking(move || repair_data_dirs(&dirs)).await);
+    let remaining: Vec<&str> = statuses.iter().filter(|s| s.status != "ok").map(|s| s.key.as_str()).collect();
+    audit::record(
+        "data_paths_repair",
+        "system",
+        if remaining.is_empty() { "success" } else { "partial" },
+        None,
+        Some(format!("Created: [{}]; still failing: [{}]", created.join(", "), remaining.join(", "))),
+    );
+    Ok(ApiResponse::success(serde_json::json!({
+        "created": created,
+        "paths": statuses
+    })))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ValidationIssue {
+    pub field: String,
+    pub severity: String,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl ValidationIssue {
+    pub fn warning(field: &str, message: &str, suggestion: Option<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            severity: "warning".to_string(),
+            message: message.to_string(),
+            suggestion,
+        }
+    }
+    
+    pub fn error(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            severity: "error".to_string(),
+            message: message.to_string(),
+            suggestion: None,
+        }
+    }
+}
+
+/// Cross-check a provider's model against its live catalog.
+///
+/// Returns a warning (catalogs change, so never an error) with the closest
+/// match when the model isn't listed. Skipped offline or when the catalog
+/// can't be fetched.
+pub async fn check_model_against_catalog(
+    provider: &LLMProviderConfig,
+    field: &str,
+    offline: bool,
+) -> Option<ValidationIssue> {
+    if offline || provider.model.is_empty() {
+        return None;
+    }
+    let catalog = providers::list_provider_models(provider).await.ok()?;
+    if catalog.is_empty() || catalog.contains(&provider.model) {
+        return None;
+    }
+    
+    let suggestion = providers::closest_model(&provider.model, &catalog);
+    let message = match &suggestion {
+        Some(closest) => format!("Model '{}' is not offered by {}; did you mean '{}'?", provider.model, provider.name, closest),
+        None => format!("Model '{}' is not offered by {}", provider.model, provider.name),
+    };
+    Some(ValidationIssue::warning(field, &message, suggestion))
+}
+
+/// Every field-level problem that must be fixed before a config can be saved
+pub fn validate_config(config: &SynapseConfig) -> Vec<ValidationIssue> {
+    let mut errors = Vec::new();
+    
+    if config.llm_providers.is_empty() && !config.is_offline() {
+        errors.push(ValidationIssue::error("llm_providers", "At least one LLM provider is required unless mode is \"offline\""));
+    }
+    if !logs::LOG_LEVELS.contains(&config.log_level.as_str()) {
+        errors.push(ValidationIssue::error(
+            "log_level",
+            &format!("Unknown log level '{}'; expected one of {}", config.log_level, logs::LOG_LEVELS.join(", ")),
+        ));
+    }
+    let mut seen_priorities: HashMap<u8, &str> = HashMap::new();
+    for (i, provider) in config.llm_providers.iter().enumerate() {
+        if let Some(other) = seen_priorities.insert(provider.priority, &provider.name) {
+            errors.push(ValidationIssue::error(
+                &format!("llm_providers[{}].priority", i),
+                &format!("Priority {} is already used by provider '{}'", provider.priority, other),
+            ));
+        }
+        if provider.provider_type == "custom_openai" && provider.base_url.as_deref().is_none_or(|url| url.trim().is_empty()) {
+            errors.push(ValidationIssue::error(
+                &format!("llm_providers[{}].base_url", i),
+                "A base URL is required for OpenAI-compatible providers",
+            ));
+        }
+    }
+    
+    let security = &config.security_settings;
+    if security.require_approval_for_risk > 5 {
+        errors.push(ValidationIssue::error(
+            "security_settings.require_approval_for_risk",
+            &format!("Risk threshold {} is outside 0-5", security.require_approval_for_risk),
+        ));
+    }
+    let mut role_users: Vec<_> = security.user_roles.iter().collect();
+    role_users.sort();
+    for (user_id, role) in role_users {
+        if !security::ROLES.contains(&role.as_str()) {
+            errors.push(ValidationIssue::error(
+                &format!("security_settings.user_roles.{}", user_id),
+                &format!("Unknown role '{}'; expected one of {}", role, security::ROLES.join(", ")),
+            ));
+        }
+    }
+    if security.session_timeout_minutes == 0 {
+        errors.push(ValidationIssue::error(
+            "security_settings.session_timeout_minutes",
+            "Session timeout must be greater than zero",
+        ));
+    }
+    if security.idempotency_window_minutes == 0 {
+        errors.push(ValidationIssue::error(
+            "security_settings.idempotency_window_minutes",
+            "Idempotency window must be greater than zero",
+        ));
+    }
+    if security.skill_runs_per_hour == Some(0) {
+        errors.push(ValidationIssue::error(
+            "security_settings.skill_runs_per_hour",
+            "Skill run quota must be greater than zero",
+        ));
+    }
+    let mut quota_skills: Vec<_> =
+        security.skill_run_quotas.iter().filter(|(_, runs)| **runs == 0).map(|(id, _)| id).collect();
+    quota_skills.sort();
+    for skill_id in quota_skills {
+        errors.push(ValidationIssue::error(
+            &format!("security_settings.skill_run_quotas.{}", skill_id),
+            "Skill run quota must be greater than zero",
+        ));
+    }
+    if security.rate_limit_per_minute == 0 {
+        errors.push(ValidationIssue::error(
+            "security_settings.rate_limit_per_minute",
+            "Rate limit must be greater than zero",
+        ));
+    }
+    if security.audit_retention_days == Some(0) {
+        errors.push(ValidationIssue::error(
+            "security_settings.audit_retention_days",
+            "Audit retention must be at least one day",
+        ));
+    }
+    if security.audit_max_file_bytes == Some(0) {
+        errors.push(ValidationIssue::error(
+            "security_settings.audit_max_file_bytes",
+            "Audit log size limit must be greater than zero",
+        ));
+    }
+    if security.audit_max_entries_per_minute == Some(0) {
+        errors.push(ValidationIssue::error(
+            "security_settings.audit_max_entries_per_minute",
+            "Audit rate limit must be greater than zero",
+        ));
+    }
+    for (i, key) in security.trusted_signing_keys.iter().enumerate() {
+        if let Err(e) = skills::parse_public_key(key) {
+            errors.push(ValidationIssue::error(&format!("security_settings.trusted_signing_keys[{}]", i), &e));
+        }
+    }
+    for (i, url) in security.webhook_urls.iter().enumerate() {
+        if let Err(e) = webhooks::validate_webhook_url(url) {
+            errors.push(ValidationIssue::error(&format!("security_settings.webhook_urls[{}]", i), &e));
+        }
+    }
+    if !isolation::ISOLATION_POLICIES.contains(&security.isolation_policy.as_str()) {
+        errors.push(ValidationIssue::error(
+            "security_settings.isolation_policy",
+            &format!(
+                "Unknown isolation policy '{}'; expected one of {}",
+                security.isolation_policy,
+                isolation::ISOLATION_POLICIES.join(", ")
+            ),
+        ));
+    }
+    
+    let languages = wizard::supported_language_codes();
+    if !languages.contains(&config.language.as_str()) {
+        errors.push(ValidationIssue::error(
+            "language",
+            &format!("Unsupported language '{}'; expected one of {}", config.language, languages.join(", ")),
+        ));
+    }
+    
+    for (i, price) in config.token_prices.iter().enumerate() {
+        if !(price.input_per_1k >= 0.0 && price.output_per_1k >= 0.0) {
+            errors.push(ValidationIssue::error(
+                &format!("token_prices[{}]", i),
+                &format!("Prices for '{}' must be non-negative", price.model),
+            ));
+        }
+    }
+    
+    for (i, entry) in config.model_capabilities.iter().enumerate() {
+        if entry.model.trim().is_empty() || entry.capabilities.context_window == 0 {
+            errors.push(ValidationIssue::error(
+                &format!("model_capabilities[{}]", i),
+                "A model capability entry needs a model name and a context window greater than zero",
+            ));
+        }
+    }
+    
+    errors.extend(providers::validate_routing(config));
+    errors
+}
+
+/// Save configuration to a profile (default: "default") in the directory
+/// `get_config` loads from. Changing the security settings needs an admin,
+/// and weakening isolation also needs `confirm_downgrade`.
+#[tauri::command]
+pub async fn save_config(
+    config: SynapseConfig,
+    profile: Option<String>,
+    confirm_downgrade: Option<bool>,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let confirm_downgrade = confirm_downgrade.unwrap_or(false);
+    match config_dir() {
+        Ok(dir) => {
+            save_config_in(&dir, config, profile, confirm_downgrade, auth_token.as_deref(), session_id.as_deref()).await
+        }
+        Err(e) => Ok(ApiResponse::error(SynapseError::Internal(e))),
+    }
+}
+
+/// Whether saving `incoming` changes the security settings in force. A
+/// missing signing key keeps the current one, since the UI never edits it.
+pub fn security_settings_changed(current: &SecuritySettings, incoming: &SecuritySettings) -> bool {
+    let mut incoming = incoming.clone();
+    if incoming.token_signing_key.is_none() {
+        incoming.token_signing_key = current.token_signing_key.clone();
+    }
+    incoming != *current
+}
+
+/// `save_config` into the config directory `dir`. The directory is always
+/// the caller's; the config's own `data_paths["config"]` never moves where
+/// profiles are written, or the saved file would not be read back.
+pub async fn save_config_in(
+    dir: &std::path::Path,
+    mut config: SynapseConfig,
+    profile: Option<String>,
+    confirm_downgrade: bool,
+    auth_token: Option<&str>,
+    session_id: Option<&str>,
+) -> Result<ApiResponse, String> {
+    let errors = validate_config(&config);
+    if !errors.is_empty() {
+        let mut response = ApiResponse::error(SynapseError::Validation(i18n::localize("config_invalid", &config.language)));
+        response.data = Some(serde_json::json!({ "errors": errors }));
+        return Ok(response);
+    }
+    
+    let offline = config.is_offline();
+    let mut warnings = Vec::new();
+    for (i, provider) in config.llm_providers.iter().enumerate() {
+        let field = format!("llm_providers[{}].model", i);
+        if let Some(issue) = check_model_against_catalog(provider, &field, offline).await {
+            warnings.push(issue);
+        }
+    }
+    
+    let profile = profile.unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    let path = match profile_path(dir, &profile) {
+        Ok(path) => path,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    // Roles, trusted users and signing keys decide who may do what, so
+    // changing the settings in force takes an admin, as in
+    // `update_security_settings`. Only the first save of a fresh install,
+    // with nothing in force yet, needs none.
+    let enforced = load_profile(dir, &active_profile(dir));
+    let needs_admin = match &enforced {
+        Ok(Some(current)) => security_settings_changed(&current.security_settings, &config.security_settings),
+        Ok(None) => false,
+        Err(_) => true,
+    };
+    let actor = if needs_admin {
+        match authorize(auth_token, session_id, "admin") {
+            Ok(caller) => caller,
+            Err(denied) => {
+                let reason = denied.error.as_ref().map(ToString::to_string);
+                audit::record("config_update", "unknown", "failure", Some(&profile), reason);
+                return Ok(denied);
+            }
+        }
+    } else {
+        "system".to_string()
+    };
+    if let Ok(Some(current)) = &enforced {
+        let (current, requested) = (&current.security_settings.isolation_policy, &config.security_settings.isolation_policy);
+        if let Err(refused) = check_isolation_change(current, requested, confirm_downgrade, &actor) {
+            return Ok(ApiResponse::error(SynapseError::Validation(refused)));
+        }
+    }
+    // The UI never sees the signing key or API keys; keep the saved ones so
+    // issued tokens stay valid and providers keep working
+    let saved = read_config_file(&path).ok().flatten();
+    if config.security_settings.token_signing_key.is_none() {
+        config.security_settings.token_signing_key = saved.as_ref().and_then(|saved| saved.security_settings.token_signing_key.clone());
+    }
+    restore_masked_api_keys(&mut config, saved.as_ref());
+    if let Err(e) = write_config_file(&path, &config) {
+        audit::record("config_update", &actor, "failure", Some(&profile), Some(e.clone()));
+        return Ok(ApiResponse::error(SynapseError::Internal(e)));
+    }
+    audit::record("config_update", &actor, "success", Some(&profile), None);
+    if profile == active_profile(dir) {
+        apply_audit_rate_limit(state::app_state(), &config.security_settings);
+    }
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "saved": true,
+        "profile": profile,
+        "path": path.to_string_lossy(),
+        "message": i18n::localize("config_saved", &config.language),
+        "warnings": warnings
+    })))
+}
+
+/// Copy the provider `name` into `config` as `new_name`: inactive, with the
+/// next free priority and no API key, so the key is re-entered deliberately
+pub fn clone_provider_config(config: &mut SynapseConfig, name: &str, new_name: &str) -> Result<LLMProviderConfig, SynapseError> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(SynapseError::Validation("A name for the new provider is required".to_string()));
+    }
+    let source = config
+        .llm_providers
+        .iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| SynapseError::NotFound(format!("Provider '{}' not found", name)))?;
+    if config.llm_providers.iter().any(|p| p.name == new_name) {
+        return Err(SynapseError::Conflict(format!("A provider named '{}' already exists", new_name)));
+    }
+    let priority = config
+        .llm_providers
+        .iter()
+        .map(|p| p.priority)
+        .max()
+        .map_or(Some(1), |highest| highest.checked_add(1))
+        .ok_or_else(|| SynapseError::Validation("No priority is left for another provider".to_string()))?;
+
+    let clone = LLMProviderConfig {
+        name: new_name.to_string(),
+        api_key: None,
+        priority,
+        is_active: false,
+        ..source.clone()
+    };
+    config.llm_providers.push(clone.clone());
+    Ok(clone)
+}
+
+/// Duplicate a provider under a new name in the active profile
+#[tauri::command]
+pub async fn clone_provider(
+    name: String,
+    new_name: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let cloned = config_dir()
+        .map_err(SynapseError::Internal)
+        .and_then(|dir| update_active_profile(&dir, |config| clone_provider_config(config, &name, &new_name)));
+    let clone = match cloned {
+        Ok((clone, _)) => clone,
+        Err(e @ SynapseError::Internal(_)) => {
+            audit::record("config_update", &caller, "failure", Some(new_name.trim()), Some(e.to_string()));
+            return Ok(ApiResponse::error(e));
+        }
+        Err(e) => return Ok(ApiResponse::error(e)),
+    };
+    audit::record("config_update", &caller, "success", Some(&clone.name), Some(format!("Provider cloned from '{}'", name)));
+    
+    Ok(ApiResponse::success(serde_json::to_value(clone).unwrap()))
+}
+
+/// Placeholder shown instead of secrets in config diffs
+const REDACTED: &str = "***";
+
+/// One difference between the saved config and a pending one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfigChange {
+    /// "settings", "llm_providers", "security_settings" or "data_paths"
+    pub section: String,
+    pub key: String,
+    /// "added", "removed" or "modified"
+    pub change: String,
+    pub old: Option<serde_json::Value>,
+    pub new: Option<serde_json::Value>,
+}
+
+impl ConfigChange {
+    fn new(section: &str, key: &str, old: Option<serde_json::Value>, new: Option<serde_json::Value>) -> Self {
+        let change = match (&old, &new) {
+            (None, Some(_)) => "added",
+            (Some(_), None) => "removed",
+            _ => "modified",
+        };
+        Self {
+            section: section.to_string(),
+            key: key.to_string(),
+            change: change.to_string(),
+            old,
+            new,
+        }
+    }
+}
+
+/// Provider as JSON with its API key masked
+fn redacted_provider(provider: &LLMProviderConfig) -> serde_json::Value {
+    let mut value = serde_json::to_value(provider).unwrap();
+    if provider.api_key.is_some() {
+        value["api_key"] = serde_json::json!(REDACTED);
+    }
+    value
+}
+
+/// Config as JSON for the UI: API keys are masked and the token signing key
+/// is left out entirely. Saving it back keeps both secrets as stored.
+pub fn redacted_config(config: &SynapseConfig) -> serde_json::Value {
+    let mut value = serde_json::to_value(config).unwrap();
+    value["llm_providers"] = config.llm_providers.iter().map(redacted_provider).collect();
+    value["security_settings"] = redacted_security_settings(&config.security_settings);
+    value
+}
+
+/// Security settings as JSON without the token signing key
+fn redacted_security_settings(settings: &SecuritySettings) -> serde_json::Value {
+    let mut value = serde_json::to_value(settings).unwrap();
+    if let Some(fields) = value.as_object_mut() {
+        fields.remove("token_signing_key");
+    }
+    value
+}
+
+/// Put back the saved API key of every provider sent with its key masked
+pub fn restore_masked_api_keys(config: &mut SynapseConfig, saved: Option<&SynapseConfig>) {
+    for provider in config.llm_providers.iter_mut().filter(|p| p.api_key.as_deref() == Some(REDACTED)) {
+        provider.api_key = saved
+            .and_then(|saved| saved.llm_providers.iter().find(|p| p.name == provider.name))
+            .and_then(|p| p.api_key.clone());
+    }
+}
+
+/// Field-by-field differences between two JSON objects
+fn diff_fields(section: &str, prefix: &str, old: &serde_json::Value, new: &serde_json::Value, changes: &mut Vec<ConfigChange>) {
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    
+    for key in keys {
+        let (before, after) = (old.get(key), new.get(key));
+        if before != after {
+            changes.push(ConfigChange::new(section, &format!("{}{}", prefix, key), before.cloned(), after.cloned()));
+        }
+    }
+}
+
+/// Structured differences from `old` to `new`; API keys are always redacted
+pub fn diff_configs(old: &SynapseConfig, new: &SynapseConfig) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    
+    let settings = |c: &SynapseConfig| serde_json::json!({ "language": c.language, "mode": c.mode, "routing_mode": c.routing_mode });
+    diff_fields("settings", "", &settings(old), &settings(new), &mut changes);
+    
+    let mut names: Vec<&String> = old.llm_providers.iter().chain(new.llm_providers.iter()).map(|p| &p.name).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let before = old.llm_providers.iter().find(|p| &p.name == name);
+        let after = new.llm_providers.iter().find(|p| &p.name == name);
+        match (before, after) {
+            (Some(before), Some(after)) => {
+                let start = changes.len();
+                diff_fields(
+                    "llm_providers",
+                    &format!("{}.", name),
+                    &serde_json::to_value(before).unwrap(),
+                    &serde_json::to_value(after).unwrap(),
+                    &mut changes,
+                );
+                let secret_key = format!("{}.api_key", name);
+                for change in changes[start..].iter_mut().filter(|c| c.key == secret_key) {
+                    for value in [&mut change.old, &mut change.new].into_iter().flatten() {
+                        if !value.is_null() {
+                            *value = serde_json::json!(REDACTED);
+                        }
+                    }
+                }
+            }
+            (before, after) => changes.push(ConfigChange::new(
+                "llm_providers",
+                name,
+                before.map(redacted_provider),
+                after.map(redacted_provider),
+            )),
+        }
+    }
+    
+    let redacted_security = |settings: &SecuritySettings| {
+        let mut value = serde_json::to_value(settings).unwrap();
+        if settings.token_signing_key.is_some() {
+            value["token_signing_key"] = serde_json::json!(REDACTED);
+        }
+        value
+    };
+    diff_fields(
+        "security_settings",
+        "",
+        &redacted_security(&old.security_settings),
+        &redacted_security(&new.security_settings),
+        &mut changes,
+    );
+    diff_fields(
+        "data_paths",
+        "",
+        &serde_json::to_value(&old.data_paths).unwrap(),
+        &serde_json::to_value(&new.data_paths).unwrap(),
+        &mut changes,
+    );
+    changes
+}
+
+/// Show what saving `new` would change relative to the saved config
+#[tauri::command]
+pub async fn diff_config(new: SynapseConfig) -> Result<ApiResponse, String> {
+    let changes = diff_configs(&load_config(), &new);
+    Ok(ApiResponse::success(serde_json::json!({
+        "changed": !changes.is_empty(),
+        "changes": changes
+    })))
+}
+
+/// List the models a provider currently offers
+#[tauri::command]
+pub async fn list_provider_models(provider: LLMProviderConfig) -> Result<ApiResponse, String> {
+    if let Err(e) = load_config().require_provider_network("Listing provider models", &provider) {
+        return Ok(ApiResponse::error(e));
+    }
+    match providers::list_provider_models(&provider).await {
+        Ok(models) => Ok(ApiResponse::success(serde_json::json!({
+            "provider": provider.name,
+            "models": models
+        }))),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Upstream(e))),
+    }
+}
+
+/// Check a provider's model name against its live catalog (wizard LLM step)
+#[tauri::command]
+pub async fn validate_provider_model(provider: LLMProviderConfig) -> Result<ApiResponse, String> {
+    if let Err(e) = load_config().require_provider_network("Validating a provider model", &provider) {
+        return Ok(ApiResponse::error(e));
+    }
+    let issue = check_model_against_catalog(&provider, "model", false).await;
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "valid": issue.is_none(),
+        "issue": issue
+    })))
+}
+
+/// Check a provider config field by field before it's saved; unlike
+/// `test_llm_connection` nothing is persisted and nothing goes over the network
+#[tauri::command]
+pub async fn validate_provider_config(provider: LLMProviderConfig) -> Result<ApiResponse, String> {
+    let fields = providers::check_provider_fields(&provider);
+    Ok(ApiResponse::success(serde_json::json!({
+        "valid": fields.iter().all(|f| f.valid),
+        "fields": fields
+    })))
+}
+
+/// A probe's outcome in `language`; failure details stay in English since
+/// they quote the provider or the network error
+fn probe_message(probe: &providers::ConnectionProbe, language: &str) -> String {
+    match probe.status {
+        Some(200..=299) if probe.connected => i18n::localize("connection_successful", language),
+        Some(429) => i18n::localize("connection_rate_limited", language),
+        Some(404) if probe.connected => i18n::localize("connection_models_not_listed", language),
+        _ => probe.message.clone(),
+    }
+}
+
+/// Test LLM connection with a live request to the provider
+#[tauri::command]
+pub async fn test_llm_connection(
+    provider_type: String,
+    api_key: String,
+    base_url: Option<String>,
+    model: String,
+    timeout_secs: Option<u64>,
+) -> Result<ApiResponse, String> {
+    let provider = LLMProviderConfig {
+        name: provider_type.clone(),
+        provider_type: provider_type.clone(),
+        api_key: Some(api_key).filter(|k| !k.is_empty()),
+        base_url,
+        model: model.clone(),
+        priority: 0,
+        is_active: true,
+        weight: None,
+    };
+    if !providers::SUPPORTED_PROVIDER_TYPES.contains(&provider_type.as_str()) {
+        return Ok(ApiResponse::error(SynapseError::Validation(providers::unsupported_provider(&provider_type))));
+    }
+    let config = load_config();
+    if let Err(e) = config.require_provider_network("Testing an LLM connection", &provider) {
+        return Ok(ApiResponse::error(e));
+    }
+    let timeout = std::time::Duration::from_secs(timeout_secs.unwrap_or(providers::DEFAULT_PROBE_TIMEOUT_SECS));
+    
+    let probe = match providers::probe_connection(&provider, timeout).await {
+        Ok(probe) => probe,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Upstream(e))),
+    };
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "connected": probe.connected,
+        "provider": provider_type,
+        "model": model,
+        "status": probe.status,
+        "auth_ok": probe.auth_ok,
+        "latency_ms": probe.latency_ms,
+        "message": probe_message(&probe, &config.language),
+        "available_models": probe.available_models,
+        "models_listable": probe.models_listable
+    })))
+}
+
+/// Context window and tool/vision support for a provider's model, from the
+/// config's overrides or the built-in table; `known` is false for neither
+#[tauri::command]
+pub async fn get_model_capabilities(provider: String, model: String) -> Result<ApiResponse, String> {
+    let hints = providers::model_capability_hints(&load_config(), &provider, &model);
+    Ok(ApiResponse::success(serde_json::to_value(hints).unwrap()))
+}
+
+/// Root of the Synapse data directory (`~/.synapse`)
+fn default_data_root() -> Result<PathBuf, String> {
+    tauri::api::path::home_dir()
+        .map(|home| home.join(".synapse"))
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+/// Migrate a legacy data directory to the current layout.
+///
+/// Emits a `migration-progress` event after every step. Completed steps are
+/// journaled, so re-running after a failure resumes at the failed step.
+#[tauri::command]
+pub async fn migrate_config(
+    window: tauri::Window,
+    data_dir: Option<String>,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let root = match data_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => try_internal!(default_data_root()),
+    };
+    
+    let result = try_internal!(
+        tokio::task::spawn_blocking(move || {
+            let steps = migration::plan_legacy_migration(&root)?;
+            migration::run_migration(&root, &steps, &mut |progress| {
+                let _ = window.emit("migration-progress", progress);
+            })
+        })
+        .await
+    );
+    
+    match result {
+        Ok(summary) if summary.completed => {
+            audit::record("config_update", &caller, "success", None, Some("Legacy data directory migrated".to_string()));
+            Ok(ApiResponse::success(serde_json::to_value(summary).unwrap()))
+        }
+        Ok(summary) => {
+            let mut response = ApiResponse::error(SynapseError::Internal(format!(
+                "Migration stopped at step '{}': {}",
+                summary.stopped_at.clone().unwrap_or_default(),
+                summary.error.clone().unwrap_or_default()
+            )));
+            response.data = Some(serde_json::to_value(summary).unwrap());
+            Ok(response)
+        }
+        Err(e) => Ok(ApiResponse::error(SynapseError::Internal(e))),
+    }
+}
+
+/// Pick the provider the next LLM request should use
+#[tauri::command]
+pub async fn route_llm_request() -> Result<ApiResponse, String> {
+    let config = load_config();
+    match providers::route_and_record(&config) {
+        Some(provider) => Ok(ApiResponse::success(serde_json::json!({
+            "provider": provider.name,
+            "provider_type": provider.provider_type,
+            "model": provider.model,
+            "routing_mode": config.routing_mode
+        }))),
+        None => Ok(ApiResponse::error(SynapseError::Upstream("No active, reachable LLM provider".to_string()))),
+    }
+}
+
+/// Show which provider requests will use and the order they fail over in
+#[tauri::command]
+pub async fn get_effective_provider() -> Result<ApiResponse, String> {
+    let config = load_config();
+    let Some(provider) = providers::resolve_active_provider(&config) else {
+        return Ok(ApiResponse::error(SynapseError::NotFound("No active LLM provider is configured".to_string())));
+    };
+    let failover_order: Vec<&str> = std::iter::successors(Some(provider), |p| providers::next_provider_after(&config, &p.name))
+        .map(|p| p.name.as_str())
+        .collect();
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "provider": redacted_provider(provider),
+        "failover_order": failover_order,
+        // May differ from `provider` while it is unreachable or rate limited,
+        // and is split by weight in weighted mode
+        "routing": providers::current_routing(&config)
+            .into_iter()
+            .map(|(p, share)| serde_json::json!({ "provider": p.name, "share_percent": share }))
+            .collect::<Vec<_>>()
+    })))
+}
+
+/// List providers the router is skipping because they were rate limited
+#[tauri::command]
+pub async fn get_provider_cooldowns() -> Result<ApiResponse, String> {
+    Ok(ApiResponse::success(serde_json::to_value(providers::provider_cooldowns()).unwrap()))
+}
+
+// ============================================================================
+// Skill Management Commands
+// ============================================================================
+
+/// Skill status
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SkillInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub status: String,
+    pub trust_level: String,
+    pub risk_level: u8,
+    pub isolation_type: String,
+    pub required_capabilities: Vec<String>,
+    /// Disabled skills keep their approval but are never executed
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// What the skill does, shown in details and matched by search
+    #[serde(default)]
+    pub description: String,
+    /// Lowercase labels such as "filesystem" for grouping and filtering
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Minimum capabilities the routed model must have
+    #[serde(default)]
+    pub min_model_capabilities: skills::MinModelCapabilities,
+    /// Keep pre-warmed isolation sandboxes ready for this skill
+    #[serde(default)]
+    pub keep_warm: bool,
+    /// Ids of skills that must run before this one
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Typical LLM tokens per run; `None` for skills that don't call an LLM
+    #[serde(default)]
+    pub estimated_llm_tokens: Option<u64>,
+    /// Input schema from the manifest, shorthand or JSON Schema; null when
+    /// the skill declares none
+    #[serde(default)]
+    pub inputs: serde_json::Value,
+    /// Output schema, in the same forms as `inputs`
+    #[serde(default)]
+    pub outputs: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Skills known to the registry, at their active versions
+pub fn skill_registry() -> Vec<SkillInfo> {
+    skill_registry_in(state::app_state())
+}
+
+/// Skills known to the registry, with everything `state` layers over them.
+/// The skill store is copied out before the config loads, so no lock is
+/// held across the two.
+pub fn skill_registry_in(state: &AppState) -> Vec<SkillInfo> {
+    let store = state.skills().lock().unwrap().clone();
+    registered_skills()
+        .into_iter()
+        .map(|skill| with_enabled_flag(with_active_version(skill, &store.versions), &store.toggles))
+        .map(|mut skill| {
+            if let Some(risk_override) = store.risk_overrides.get(&skill.id) {
+                skill.risk_level = risk_override.risk_level;
+            }
+            if let Some(review) = state.skill_review(&skill.id) {
+                skill.status = review.status;
+            }
+            if let Some(edited) = store.tags.get(&skill.id) {
+                skill.tags = edited.clone();
+            }
+            if let Some(rename) = store.renames.get(&skill.id) {
+                skill.name = rename.name.clone();
+            }
+            if store.archive.get(&skill.id).is_some() {
+                skill.status = "archived".to_string();
+            }
+            skill
+        })
+        .collect()
+}
+
+/// Stored versus capability-derived risk for a skill
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RiskAssessment {
+    pub skill_id: String,
+    pub stored_risk_level: u8,
+    pub computed_risk_level: u8,
+    pub risk_override: Option<skills::RiskOverride>,
+    pub warning: Option<String>,
+}
+
+/// Compare a skill's stored risk with the one its capabilities imply. An
+/// override means a person chose the value, so it never warns.
+pub fn assess_skill_risk(skill: &SkillInfo, risk_override: Option<&skills::RiskOverride>) -> RiskAssessment {
+    let computed = skills::compute_risk_level(&skill.required_capabilities);
+    let warning = (risk_override.is_none() && skill.risk_level < computed).then(|| {
+        format!(
+            "Risk level {} is lower than {} computed from its capabilities ({})",
+            skill.risk_level,
+            computed,
+            skill.required_capabilities.join(", ")
+        )
+    });
+    RiskAssessment {
+        skill_id: skill.id.clone(),
+        stored_risk_level: skill.risk_level,
+        computed_risk_level: computed,
+        risk_override: risk_override.cloned(),
+        warning,
+    }
+}
+
+/// Overlay whether a skill has been disabled
+pub fn with_enabled_flag(mut skill: SkillInfo, toggles: &skills::SkillToggles) -> SkillInfo {
+    skill.enabled = toggles.is_enabled(&skill.id);
+    skill
+}
+
+/// Skills that may run: approved and not disabled
+pub fn execution_eligible(skills: Vec<SkillInfo>) -> Vec<SkillInfo> {
+    skills.into_iter().filter(|s| s.status == "active" && s.enabled).collect()
+}
+
+/// Overlay the version a skill was rolled back to
+pub fn with_active_version(mut skill: SkillInfo, history: &skills::SkillVersionHistory) -> SkillInfo {
+    if let Some(active) = history.active_version(&skill.id) {
+        skill.version = active.version.clone();
+        skill.risk_level = active.risk_level;
+        skill.trust_level = active.trust_level.clone();
+    }
+    skill
+}
+
+/// Known versions of a skill, oldest first; a skill without recorded
+/// history reports just its current version
+pub fn skill_versions(skill: &SkillInfo, history: &skills::SkillVersionHistory) -> Vec<skills::SkillVersion> {
+    let versions = history.versions(&skill.id);
+    if !versions.is_empty() {
+        return versions;
+    }
+    vec![skills::SkillVersion {
+        version: skill.version.clone(),
+        created_at: skill.created_at,
+        risk_level: skill.risk_level,
+        trust_level: skill.trust_level.clone(),
+    }]
+}
+
+/// Bundled and imported skills, before any rollback
+fn registered_skills() -> Vec<SkillInfo> {
+    let mut skills = bundled_skills();
+    if let Ok(skills_dir) = load_config().data_path("skills") {
+        skills.extend(imported_skills(&skills_dir));
+    }
+    skills
+}
+
+/// Skills imported into `skills_dir`, as registry entries
+pub fn imported_skills(skills_dir: &std::path::Path) -> Vec<SkillInfo> {
+    skills::installed_skills(skills_dir)
+        .into_iter()
+        .filter_map(|installed| {
+            let mut entry = installed.manifest;
+            let state = &installed.state;
+            entry["id"] = serde_json::json!(installed.id);
+            entry["status"] = state.get("status").cloned().unwrap_or_else(|| serde_json::json!("pending"));
+            entry["trust_level"] = state.get("trust_level").cloned().unwrap_or_else(|| serde_json::json!("unverified"));
+            entry["created_at"] = state.get("created_at").cloned().unwrap_or_else(|| serde_json::json!(Utc::now()));
+            entry["last_used"] = serde_json::Value::Null;
+            if entry.get("isolation_type").is_none() {
+                entry["isolation_type"] = serde_json::json!("container");
+            }
+            let mut skill: SkillInfo = serde_json::from_value(entry).ok()?;
+            skill.tags = skills::normalize_tags(&skill.tags);
+            Some(skill)
+        })
+        .collect()
+}
+
+/// Skills that ship with Synapse
+fn bundled_skills() -> Vec<SkillInfo> {
+    vec![
+        SkillInfo {
+            id: "skill-001".to_string(),
+            name: "read_file".to_string(),
+            version: "1.0.0".to_string(),
+            status: "active".to_string(),
+            trust_level: "trusted".to_string(),
+            risk_level: 1,
+            isolation_type: "subprocess".to_string(),
+            required_capabilities: vec!["fs:read".to_string()],
+            enabled: true,
+            description: "Read the contents of a file from the workspace".to_string(),
+            tags: vec!["filesystem".to_string()],
+            min_model_capabilities: skills::MinModelCapabilities::default(),
+            keep_warm: false,
+            dependencies: vec![],
+            estimated_llm_tokens: None,
+            inputs: serde_json::json!({
+                "path": {"type": "string", "required": true}
+            }),
+            outputs: serde_json::json!({
+                "content": {"type": "string"}
+            }),
+            created_at: Utc::now(),
+            last_used: Some(Utc::now()),
+        },
+        SkillInfo {
+            id: "skill-002".to_string(),
+            name: "write_file".to_string(),
+            version: "1.0.0".to_string(),
+            status: "active".to_string(),
+            trust_level: "verified".to_string(),
+            risk_level: 2,
+            isolation_type: "container".to_string(),
+            required_capabilities: vec!["fs:write".to_string()],
+            enabled: true,
+            description: "Write or overwrite a file in the workspace".to_string(),
+            tags: vec!["filesystem".to_string()],
+            min_model_capabilities: skills::MinModelCapabilities::default(),
+            keep_warm: true,
+            dependencies: vec!["skill-001".to_string()],
+            estimated_llm_tokens: None,
+            inputs: serde_json::json!({
+                "path": {"type": "string", "required": true},
+                "content": {"type": "string", "required": true},
+                "append": {"type": "boolean"}
+            }),
+            outputs: serde_json::json!({
+                "bytes_written": {"type": "integer"}
+            }),
+            created_at: Utc::now(),
+            last_used: Some(Utc::now()),
+        },
+        SkillInfo {
+            id: "skill-003".to_string(),
+            name: "web_search".to_string(),
+            version: "1.0.0".to_string(),
+            status: "pending".to_string(),
+            trust_level: "unverified".to_string(),
+            risk_level: 3,
+            isolation_type: "container".to_string(),
+            required_capabilities: vec!["network:http".to_string()],
+            enabled: true,
+            description: "Search the web and return matching page summaries".to_string(),
+            tags: vec!["experimental".to_string(), "network".to_string()],
+            min_model_capabilities: skills::MinModelCapabilities {
+                requires_tools: true,
+                ..Default::default()
+            },
+            keep_warm: false,
+            dependencies: vec![],
+            estimated_llm_tokens: Some(1500),
+            inputs: serde_json::json!({
+                "query": {"type": "string", "required": true},
+                "max_results": {"type": "integer"}
+            }),
+            outputs: serde_json::json!({
+                "results": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"title": {"type": "string"}, "url": {"type": "string"}, "summary": {"type": "string"}}
+                    }
+                }
+            }),
+            created_at: Utc::now(),
+            last_used: None,
+        },
+    ]
+}
+
+/// Check a skill's `min_model_capabilities` against every provider the
+/// router could currently send it to
+pub fn check_skill_model_requirements(skill: &SkillInfo, config: &SynapseConfig) -> Result<(), String> {
+    if skill.min_model_capabilities.is_empty() {
+        return Ok(());
+    }
+    let routing = providers::current_routing(config);
+    if routing.is_empty() {
+        return Err("No active, reachable LLM provider to check model requirements against".to_string());
+    }
+    for (provider, _) in routing {
+        let capabilities = providers::resolve_model_capabilities(config, &provider.provider_type, &provider.model);
+        skills::check_model_requirements(&skill.min_model_capabilities, &provider.model, capabilities.as_ref())
+            .map_err(|e| format!("Provider '{}' may be routed this skill: {}", provider.name, e))?;
+    }
+    Ok(())
+}
+
+/// One step of a skill execution plan
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlanStep {
+    pub order: usize,
+    pub skill_id: String,
+    pub skill_name: String,
+    pub isolation_type: String,
+    pub capabilities: security::EffectiveCapabilities,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub estimated_tokens: u64,
+    pub estimated_cost_usd: f64,
+    /// Why this step would fail if run now
+    pub blockers: Vec<String>,
+}
+
+/// Ordered, side-effect-free preview of a skill run
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExecutionPlan {
+    pub skill_id: String,
+    pub user_id: String,
+    pub steps: Vec<PlanStep>,
+    pub estimated_cost_usd: f64,
+    pub budget_usd: Option<f64>,
+    pub will_fail: bool,
+    pub protocol_version: String,
+}
+
+/// Rough token count of skill inputs (about four characters per token)
+fn estimate_input_tokens(inputs: &serde_json::Value) -> u64 {
+    if inputs.is_null() {
+        0
+    } else {
+        (inputs.to_string().len() as u64).div_ceil(4)
+    }
+}
+
+/// Build the plan `execute_skill` would follow for `user_id` holding
+/// `granted`, using the same dependency, capability, and routing logic
+/// without executing anything
+pub fn plan_execution(
+    skill_id: &str,
+    inputs: &serde_json::Value,
+    user_id: &str,
+    granted: &[String],
+    budget_usd: Option<f64>,
+    registry: &[SkillInfo],
+    config: &SynapseConfig,
+) -> Result<ExecutionPlan, String> {
+    let dependencies: HashMap<String, Vec<String>> =
+        registry.iter().map(|s| (s.id.clone(), s.dependencies.clone())).collect();
+    let order = skills::resolve_dependency_order(skill_id, &dependencies)?;
+    let provider = providers::current_provider(config);
+    let prices = providers::PriceTable::from_config(config);
+
+    let mut steps = Vec::new();
+    let mut total_cost = 0.0;
+    for (index, id) in order.iter().enumerate() {
+        let skill = registry.iter().find(|s| &s.id == id).ok_or_else(|| format!("Skill '{}' not found", id))?;
+        let capabilities = security::effective_capabilities(&skill.required_capabilities, granted);
+        let mut blockers: Vec<String> = capabilities.missing.iter().map(|c| format!("Missing capability '{}'", c)).collect();
+        if !skill.enabled {
+            blockers.push("Skill is disabled".to_string());
+        }
+
+        let (mut provider_name, mut model, mut tokens, mut cost) = (None, None, 0, 0.0);
+        if let Some(base_tokens) = skill.estimated_llm_tokens {
+            tokens = base_tokens + if id == skill_id { estimate_input_tokens(inputs) } else { 0 };
+            match provider {
+                Some(p) => {
+                    provider_name = Some(p.name.clone());
+                    model = Some(p.model.clone());
+                    match providers::model_price_per_1k_tokens(&prices, &p.provider_type, &p.model) {
+                        Some(price) => cost = tokens as f64 / 1000.0 * price,
+                        None => blockers.push(format!("No price data for model '{}'; cost not estimated", p.model)),
+                    }
+                    if let Err(e) = check_skill_model_requirements(skill, config) {
+                        blockers.push(e);
+                    }
+                }
+                None => blockers.push("No active, reachable LLM provider".to_string()),
+            }
+        }
+
+        total_cost += cost;
+        if let Some(budget) = budget_usd {
+            if total_cost > budget {
+                blockers.push(format!("Cumulative cost ${:.4} exceeds budget ${:.4}", total_cost, budget));
+            }
+        }
+
+        steps.push(PlanStep {
+            order: index + 1,
+            skill_id: skill.id.clone(),
+            skill_name: skill.name.clone(),
+            isolation_type: skill.isolation_type.clone(),
+            capabilities,
+            provider: provider_name,
+            model,
+            estimated_tokens: tokens,
+            estimated_cost_usd: cost,
+            blockers,
+        });
+    }
+
+    Ok(ExecutionPlan {
+        skill_id: skill_id.to_string(),
+        user_id: user_id.to_string(),
+        will_fail: steps.iter().any(|s| !s.blockers.is_empty()),
+        steps,
+        estimated_cost_usd: total_cost,
+        budget_usd,
+        protocol_version: PROTOCOL_VERSION.to_string(),
+    })
+}
+
+/// Explain what running a skill would do, dependencies first, without running it
+#[tauri::command]
+pub async fn plan_skill_execution(
+    skill_id: String,
+    inputs: serde_json::Value,
+    user_id: String,
+    budget_usd: Option<f64>,
+) -> Result<ApiResponse, String> {
+    match plan_execution(&skill_id, &inputs, &user_id, &granted_capabilities(&user_id), budget_usd, &skill_registry(), &load_config()) {
+        Ok(plan) => Ok(ApiResponse::success(serde_json::to_value(plan).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Whether a skill could run with the given inputs, checked without running it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DryRunReport {
+    pub skill_id: String,
+    pub ready: bool,
+    pub approved: bool,
+    pub enabled: bool,
+    pub inputs: skills::InputCheck,
+    pub capabilities: security::EffectiveCapabilities,
+    /// Every reason the skill isn't ready, one per problem
+    pub blockers: Vec<String>,
+    pub protocol_version: String,
+}
+
+/// Check a skill's inputs against its schema, its approval and enabled
+/// state, and whether `granted` covers its capabilities
+pub fn dry_run(skill: &SkillInfo, inputs: &serde_json::Value, granted: &[String]) -> DryRunReport {
+    let input_check = skills::check_inputs(&skill.inputs, inputs);
+    let capabilities = security::effective_capabilities(&skill.required_capabilities, granted);
+    let approved = skill.status == "active";
+
+    let mut blockers: Vec<String> = input_check
+        .missing
+        .iter()
+        .map(|field| format!("Missing required input '{}'", field))
+        .collect();
+    blockers.extend(
+        input_check
+            .type_mismatches
+            .iter()
+            .map(|m| format!("Input '{}' should be {} but is {}", m.field, m.expected, m.actual)),
+    );
+    if !approved {
+        blockers.push(format!("Skill is not approved (status '{}')", skill.status));
+    }
+    if !skill.enabled {
+        blockers.push("Skill is disabled".to_string());
+    }
+    blockers.extend(capabilities.missing.iter().map(|c| format!("Missing capability '{}'", c)));
+
+    DryRunReport {
+        skill_id: skill.id.clone(),
+        ready: input_check.is_ok() && approved && skill.enabled && capabilities.missing.is_empty(),
+        approved,
+        enabled: skill.enabled,
+        inputs: input_check,
+        capabilities,
+        blockers,
+        protocol_version: PROTOCOL_VERSION.to_string(),
+    }
+}
+
+/// Report whether a skill is ready to run with `inputs`, without running it.
+/// Capabilities are checked against `user_id`'s grants ("system" if unset).
+#[tauri::command]
+pub async fn dry_run_skill(skill_id: String, inputs: serde_json::Value, user_id: Option<String>) -> Result<ApiResponse, String> {
+    let Some(skill) = skill_registry().into_iter().find(|s| s.id == skill_id) else {
+        return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id))));
+    };
+    let user_id = user_id.unwrap_or_else(|| "system".to_string());
+    let report = dry_run(&skill, &inputs, &granted_capabilities(&user_id));
+    
+    Ok(ApiResponse::success(serde_json::to_value(report).unwrap()))
+}
+
+/// Sort keys accepted by `SkillQuery::sort_by`
+pub const SKILL_SORT_KEYS: &[&str] = &["name", "risk_level", "created_at", "last_used"];
+
+/// Filters, ordering and paging for `get_skills`; every field is optional
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SkillQuery {
+    pub status: Option<String>,
+    pub trust_level: Option<String>,
+    pub min_risk: Option<u8>,
+    pub max_risk: Option<u8>,
+    /// Case-insensitive substring of the skill name
+    pub name_contains: Option<String>,
+    /// Only skills carrying these tags, combined per `tag_mode`
+    pub tags: Vec<String>,
+    /// One of `skills::TAG_MODES`; "any" when unset
+    pub tag_mode: Option<String>,
+    /// One of `SKILL_SORT_KEYS`; registry order when unset
+    pub sort_by: Option<String>,
+    pub descending: bool,
+    /// Page size; `DEFAULT_PAGE_LIMIT` when unset
+    pub limit: Option<u32>,
+    pub offset: u32,
+}
+
+/// Apply a `SkillQuery` to `skills`. Skills never used sort after used ones
+/// whichever direction is requested.
+pub fn query_skills(skills: Vec<SkillInfo>, query: &SkillQuery) -> Result<Page<SkillInfo>, String> {
+    let needle = query.name_contains.as_ref().map(|n| n.to_lowercase());
+    let wanted_tags = skills::normalize_tags(&query.tags);
+    let match_all_tags = match query.tag_mode.as_deref().unwrap_or("any") {
+        "any" => false,
+        "all" => true,
+        other => return Err(format!("Unknown tag mode '{}'; expected one of {}", other, skills::TAG_MODES.join(", "))),
+    };
+    let has_tag = |skill: &SkillInfo, tag: &String| skill.tags.contains(tag);
+    let mut matching: Vec<SkillInfo> = skills
+        .into_iter()
+        .filter(|s| query.status.as_ref().map_or(true, |status| &s.status == status))
+        .filter(|s| query.trust_level.as_ref().map_or(true, |trust| &s.trust_level == trust))
+        .filter(|s| query.min_risk.map_or(true, |min| s.risk_level >= min))
+        .filter(|s| query.max_risk.map_or(true, |max| s.risk_level <= max))
+        .filter(|s| needle.as_ref().map_or(true, |n| s.name.to_lowercase().contains(n.as_str())))
+        .filter(|s| {
+            wanted_tags.is_empty()
+                || if match_all_tags {
+                    wanted_tags.iter().all(|t| has_tag(s, t))
+                } else {
+                    wanted_tags.iter().any(|t| has_tag(s, t))
+                }
+        })
+        .collect();
+    
+    if let Some(sort_by) = &query.sort_by {
+        let ordered = |ordering: std::cmp::Ordering| if query.descending { ordering.reverse() } else { ordering };
+        match sort_by.as_str() {
+            "name" => matching.sort_by(|a, b| ordered(a.name.cmp(&b.name))),
+            "risk_level" => matching.sort_by(|a, b| ordered(a.risk_level.cmp(&b.risk_level))),
+            "created_at" => matching.sort_by(|a, b| ordered(a.created_at.cmp(&b.created_at))),
+            "last_used" => matching.sort_by(|a, b| match (a.last_used, b.last_used) {
+                (Some(a), Some(b)) => ordered(a.cmp(&b)),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }),
+            other => {
+                return Err(format!("Unknown sort key '{}'; expected one of {}", other, SKILL_SORT_KEYS.join(", ")));
+            }
+        }
+    }
+    
+    Ok(Page::from_items(matching, query.limit, Some(query.offset)))
+}
+
+/// Get skills, optionally filtered, sorted and paged
+#[tauri::command]
+pub async fn get_skills(query: Option<SkillQuery>) -> Result<ApiResponse, String> {
+    match query_skills(skill_registry(), &query.unwrap_or_default()) {
+        Ok(page) => Ok(ApiResponse::success(serde_json::to_value(page).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Skills whose name or description contain every word of `query`,
+/// case-insensitively. Skills matching more words by name rank first.
+pub fn search_skill_list(skills: Vec<SkillInfo>, query: &str) -> Vec<SkillInfo> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    
+    let mut hits: Vec<(usize, SkillInfo)> = skills
+        .into_iter()
+        .filter_map(|skill| {
+            let name = skill.name.to_lowercase();
+            let description = skill.description.to_lowercase();
+            if !words.iter().all(|w| name.contains(w.as_str()) || description.contains(w.as_str())) {
+                return None;
+            }
+            let name_hits = words.iter().filter(|w| name.contains(w.as_str())).count();
+            Some((name_hits, skill))
+        })
+        .collect();
+    hits.sort_by(|(a_hits, a), (b_hits, b)| b_hits.cmp(a_hits).then_with(|| a.name.cmp(&b.name)));
+    hits.into_iter().map(|(_, skill)| skill).collect()
+}
+
+/// Search skills by name and description
+#[tauri::command]
+pub async fn search_skills(query: String) -> Result<ApiResponse, String> {
+    let matches = search_skill_list(skill_registry(), &query);
+    Ok(ApiResponse::success(serde_json::to_value(matches).unwrap()))
+}
+
+/// Read a manifest from a local path or an `https://` URL
+async fn read_manifest_source(source: &str) -> Result<serde_json::Value, String> {
+    let raw = if source.starts_with("https://") {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let response = client.get(source).send().await.map_err(|e| format!("Failed to fetch {}: {}", source, e))?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to fetch {}: HTTP {}", source, response.status()));
+        }
+        response.text().await.map_err(|e| format!("Failed to read {}: {}", source, e))?
+    } else if source.contains("://") {
+        return Err("Only local paths and https:// URLs are supported".to_string());
+    } else {
+        std::fs::read_to_string(source).map_err(|e| format!("Failed to read {}: {}", source, e))?
+    };
+    serde_json::from_str(&raw).map_err(|e| format!("Manifest is not valid JSON: {}", e))
+}
+
+/// Import a skill manifest; the new skill awaits review as pending/unverified
+#[tauri::command]
+pub async fn import_skill(source: String, auth_token: Option<String>, session_id: Option<String>) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let config = load_config();
+    if source.starts_with("https://") {
+        if let Err(e) = config.require_network("Importing a skill from a URL") {
+            audit::record("skill_import", &caller, "failure", None, Some(e.to_string()));
+            return Ok(ApiResponse::error(e));
+        }
+    }
+    let skills_dir = try_internal!(config.data_path("skills"));
+    let result = match read_manifest_source(&source).await {
+        Ok(manifest) => skills::install_skill(&skills_dir, &manifest),
+        Err(e) => Err(e),
+    };
+    
+    match result {
+        Ok(skill_id) => {
+            audit::record("skill_import", &caller, "success", Some(&skill_id), Some(format!("Imported from {}", source)));
+            Ok(ApiResponse::success(serde_json::json!({
+                "skill_id": skill_id,
+                "status": "pending",
+                "trust_level": "unverified"
+            })))
+        }
+        Err(e) => {
+            audit::record("skill_import", &caller, "failure", None, Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Validation(e)))
+        }
+    }
+}
+
+/// Fields describing a skill's state on this machine rather than the skill
+/// itself; left out of exports and ignored on import
+pub const SKILL_RUNTIME_FIELDS: &[&str] = &["id", "status", "trust_level", "enabled", "created_at", "last_used"];
+
+/// A skill's portable definition: metadata, schemas, capabilities and tags
+pub fn skill_definition(skill: &SkillInfo) -> serde_json::Value {
+    let mut definition = serde_json::to_value(skill).unwrap();
+    if let Some(fields) = definition.as_object_mut() {
+        fields.retain(|key, _| !SKILL_RUNTIME_FIELDS.contains(&key.as_str()));
+    }
+    definition
+}
+
+/// Install a definition from `export_skill` under a new id as a pending,
+/// unverified skill. One whose name and version match a skill in
+/// `existing` is refused as a conflict.
+pub fn import_skill_definition(skills_dir: &std::path::Path, existing: &[SkillInfo], definition: &serde_json::Value) -> Result<String, SynapseError> {
+    let mut manifest = definition.clone();
+    let Some(fields) = manifest.as_object_mut() else {
+        return Err(SynapseError::Validation("Skill definition must be a JSON object".to_string()));
+    };
+    fields.retain(|key, _| !SKILL_RUNTIME_FIELDS.contains(&key.as_str()));
+    let field = |name: &str| manifest.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+    if let Some(duplicate) = existing.iter().find(|s| s.name == field("name") && s.version == field("version")) {
+        return Err(SynapseError::Conflict(format!(
+            "Skill '{}' {} is already installed as '{}'",
+            duplicate.name, duplicate.version, duplicate.id
+        )));
+    }
+    skills::install_skill(skills_dir, &manifest).map_err(SynapseError::Validation)
+}
+
+/// Export one skill's definition as JSON to share with another install
+#[tauri::command]
+pub async fn export_skill(skill_id: String) -> Result<ApiResponse, String> {
+    match skill_registry().into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => Ok(ApiResponse::success(skill_definition(&skill))),
+        None => Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    }
+}
+
+/// Import a definition produced by `export_skill` as a new pending skill
+#[tauri::command]
+pub async fn import_skill_json(json: String) -> Result<ApiResponse, String> {
+    let skills_dir = try_internal!(load_config().data_path("skills"));
+    let result = serde_json::from_str::<serde_json::Value>(&json)
+        .map_err(|e| SynapseError::Validation(format!("Invalid skill JSON: {}", e)))
+        .and_then(|definition| import_skill_definition(&skills_dir, &skill_registry(), &definition));
+    match result {
+        Ok(skill_id) => {
+            audit::record("skill_import", "system", "success", Some(&skill_id), Some("Imported from JSON".to_string()));
+            Ok(ApiResponse::success(serde_json::json!({
+                "skill_id": skill_id,
+                "status": "pending",
+                "trust_level": "unverified"
+            })))
+        }
+        Err(e) => {
+            audit::record("skill_import", "system", "failure", None, Some(e.message().to_string()));
+            Ok(ApiResponse::error(e))
+        }
+    }
+}
+
+/// Check an imported skill's manifest signature. A valid signature from a
+/// trusted key marks the skill verified; a broken one marks it unverified.
+#[tauri::command]
+pub async fn verify_skill_signature(skill_id: String) -> Result<ApiResponse, String> {
+    let config = load_config();
+    let skills_dir = try_internal!(config.data_path("skills"));
+    let Some(installed) = skills::installed_skills(&skills_dir).into_iter().find(|s| s.id == skill_id) else {
+        let message = if skill_registry().iter().any(|s| s.id == skill_id) {
+            format!("Skill '{}' ships with Synapse and has no manifest to verify", skill_id)
+        } else {
+            format!("Skill '{}' not found", skill_id)
+        };
+        return Ok(ApiResponse::error(SynapseError::NotFound(message)));
+    };
+    let check = skills::verify_manifest_signature(&installed.manifest, &config.security_settings.trusted_signing_keys);
+    let trust_level = check.trust_level();
+    if let Err(e) = skills::set_installed_trust_level(&skills_dir, &skill_id, trust_level) {
+        return Ok(ApiResponse::error(SynapseError::Internal(e)));
+    }
+    let outcome = if check.status == "invalid" { "failure" } else { "success" };
+    audit::record("skill_signature_verify", "system", outcome, Some(&skill_id), Some(check.message.clone()));
+    
+    let mut data = serde_json::to_value(&check).unwrap();
+    data["skill_id"] = serde_json::json!(skill_id);
+    data["trust_level"] = serde_json::json!(trust_level);
+    Ok(ApiResponse::success(data))
+}
+
+/// Lint a skill manifest, including its input/output schemas
+#[tauri::command]
+pub async fn lint_skill_manifest(manifest: serde_json::Value) -> Result<ApiResponse, String> {
+    let lint = skills::lint_skill_manifest(&manifest);
+    Ok(ApiResponse::success(serde_json::to_value(lint).unwrap()))
+}
+
+/// Archive just the skills registry into `destination`
+#[tauri::command]
+pub async fn backup_skills(
+    destination: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let skills_dir = try_internal!(load_config().data_path("skills"));
+    let history = state.skills().lock().unwrap().versions.clone();
+    let result = try_internal!(
+        tokio::task::spawn_blocking(move || {
+            skills::backup_skills(&skills_dir, &PathBuf::from(destination), &history)
+        })
+        .await
+    );
+    
+    match result {
+        Ok(report) => {
+            audit::record("skills_backup", &caller, "success", Some(&report.archive_path), None);
+            Ok(ApiResponse::success(serde_json::to_value(report).unwrap()))
+        }
+        Err(e) => {
+            audit::record("skills_backup", &caller, "failure", None, Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Internal(e)))
+        }
+    }
+}
+
+/// Restore a skills-only backup; restored skills always come back as pending
+#[tauri::command]
+pub async fn restore_skills(
+    archive_path: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let skills_dir = try_internal!(load_config().data_path("skills"));
+    let archive = PathBuf::from(&archive_path);
+    let state: &'static AppState = *state;
+    let result = try_internal!(
+        tokio::task::spawn_blocking(move || {
+            skills::restore_skills(&archive, &skills_dir, &mut state.skills().lock().unwrap().versions)
+        })
+        .await
+    );
+    
+    match result {
+        Ok(report) => {
+            audit::record(
+                "skills_restore",
+                &caller,
+                if report.failed.is_empty() { "success" } else { "partial" },
+                None,
+                Some(format!(
+                    "Restored {} skill(s) and {} version(s) from {}, {} failed validation",
+                    report.restored.len(),
+                    report.versions_restored,
+                    archive_path,
+                    report.failed.len()
+                )),
+            );
+            Ok(ApiResponse::success(serde_json::to_value(report).unwrap()))
+        }
+        Err(e) => {
+            audit::record("skills_restore", &caller, "failure", None, Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Validation(e)))
+        }
+    }
+}
+
+/// Take a pre-warmed sandbox for a `keep_warm` skill, or `None` to cold start
+pub fn acquire_warm_sandbox(skill: &SkillInfo) -> Option<String> {
+    if !skill.keep_warm {
+        return None;
+    }
+    isolation::warm_pool().lock().unwrap().acquire(&skill.isolation_type)
+}
+
+/// Time a no-op startup of a skill's isolation backend, reported apart from
+/// its typical execution time
+#[tauri::command]
+pub async fn measure_skill_coldstart(skill_id: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let skill = match skill_registry_in(&state).into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => skill,
+        None => return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    };
+    
+    let cold_start = match isolation::measure_cold_start(&skill.isolation_type).await {
+        Ok(elapsed) => elapsed,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Internal(e))),
+    };
+    let window = try_internal!(metrics::parse_time_window("last_24h", Utc::now()));
+    let recent = state.execution_history().lock().unwrap().window_stats(&skill.id, window);
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "skill_id": skill.id,
+        "isolation_type": skill.isolation_type,
+        "cold_start_ms": cold_start.as_secs_f64() * 1000.0,
+        "execution_p50_ms": if recent.execution_count > 0 { Some(recent.p50_latency_ms) } else { None },
+        "keep_warm": skill.keep_warm
+    })))
+}
+
+/// Pre-warm `count` container sandboxes for a `keep_warm` skill
+#[tauri::command]
+pub async fn warm_skill_sandboxes(skill_id: String, count: u32) -> Result<ApiResponse, String> {
+    let skill = match skill_registry().into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => skill,
+        None => return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    };
+    if !skill.keep_warm {
+        return Ok(ApiResponse::error(SynapseError::Validation(format!("Skill '{}' is not marked keep_warm", skill_id))));
+    }
+    if skill.isolation_type != "container" {
+        return Ok(ApiResponse::error(SynapseError::Validation("Only container isolation can be pre-warmed".to_string())));
+    }
+    
+    let mut started = 0;
+    for _ in 0..count {
+        match isolation::start_warm_container().await {
+            Ok(id) => {
+                isolation::warm_pool().lock().unwrap().add(&skill.isolation_type, id);
+                started += 1;
+            }
+            Err(e) if started == 0 => return Ok(ApiResponse::error(SynapseError::Internal(e))),
+            Err(_) => break,
+        }
+    }
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "skill_id": skill_id,
+        "warmed": started
+    })))
+}
+
+/// Warm pool availability and hit/miss counts per isolation type
+#[tauri::command]
+pub async fn get_warm_pool_stats() -> Result<ApiResponse, String> {
+    let stats = isolation::warm_pool().lock().unwrap().stats();
+    Ok(ApiResponse::success(serde_json::to_value(stats).unwrap()))
+}
+
+/// Get skill details
+#[tauri::command]
+pub async fn get_skill_details(skill_id: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    get_skill_details_in(&state, skill_id).await
+}
+
+/// `get_skill_details` against an explicit `state`
+pub async fn get_skill_details_in(state: &AppState, skill_id: String) -> Result<ApiResponse, String> {
+    let Some(skill) = skill_registry_in(state).into_iter().find(|s| s.id == skill_id) else {
+        return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id))));
+    };
+    let risk = assess_skill_risk(&skill, state.skills().lock().unwrap().risk_overrides.get(&skill.id));
+    let model_check = check_skill_model_requirements(&skill, &load_config());
+
+    Ok(ApiResponse::success(serde_json::json!({
+        "id": skill.id,
+        "name": skill.name,
+        "version": skill.version,
+        "status": skill.status,
+        "enabled": skill.enabled,
+        "description": skill.description,
+        "tags": skill.tags,
+        "inputs": skill.inputs,
+        "outputs": skill.outputs,
+        "required_capabilities": skill.required_capabilities,
+        "dependencies": skill.dependencies,
+        "risk_level": risk.stored_risk_level,
+        "trust_level": skill.trust_level,
+        "isolation_type": skill.isolation_type,
+        "keep_warm": skill.keep_warm,
+        "model_requirements": {
+            "requirements": skill.min_model_capabilities,
+            "satisfied": model_check.is_ok(),
+            "error": model_check.err()
+        },
+        "risk_warning": risk.warning,
+        "risk_override": risk.risk_override
+    })))
+}
+
+/// Check a skill's declared input and output schemas
+#[tauri::command]
+pub async fn validate_skill_schema(skill_id: String) -> Result<ApiResponse, String> {
+    match skill_registry().into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => {
+            let report = skills::validate_skill_schemas(&skill.id, &skill.inputs, &skill.outputs);
+            Ok(ApiResponse::success(serde_json::to_value(report).unwrap()))
+        }
+        None => Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    }
+}
+
+/// Recompute a skill's risk level from its required capabilities
+#[tauri::command]
+pub async fn recompute_skill_risk(skill_id: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    match skill_registry_in(&state).into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => {
+            let assessment = assess_skill_risk(&skill, state.skills().lock().unwrap().risk_overrides.get(&skill.id));
+            Ok(ApiResponse::success(serde_json::to_value(assessment).unwrap()))
+        }
+        None => Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    }
+}
+
+/// Record a manually chosen risk level for a skill. Requires an admin, who
+/// is recorded as the one who set it.
+#[tauri::command]
+pub async fn override_skill_risk(
+    skill_id: String,
+    risk_level: u8,
+    reason: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let set_by = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => {
+            audit::record("skill_risk_override", "unknown", "failure", Some(&skill_id), denied.error.as_ref().map(ToString::to_string));
+            return Ok(denied);
+        }
+    };
+    if risk_level > 5 {
+        return Ok(ApiResponse::error(SynapseError::Validation(format!("Risk level {} is outside 0-5", risk_level))));
+    }
+    if !registered_skills().iter().any(|s| s.id == skill_id) {
+        return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id))));
+    }
+    let risk_override = skills::RiskOverride {
+        risk_level,
+        set_by: set_by.clone(),
+        reason: reason.clone(),
+        set_at: Utc::now(),
+    };
+    state.skills().lock().unwrap().risk_overrides.set(&skill_id, risk_override.clone());
+    audit::record("skill_risk_override", &set_by, "success", Some(&skill_id), Some(format!("Risk set to {}: {}", risk_level, reason)));
+    
+    Ok(ApiResponse::success(serde_json::to_value(risk_override).unwrap()))
+}
+
+/// Resolve the caller behind `auth_token` and check they hold `required`.
+/// Returns the caller's user id.
+pub fn authorize_caller(
+    tokens: &[security::IssuedCapabilityToken],
+    key: Option<&[u8]>,
+    settings: &SecuritySettings,
+    auth_token: Option<&str>,
+    required: &str,
+    now: DateTime<Utc>,
+) -> Result<String, String> {
+    let user_id = security::resolve_caller(tokens, key, auth_token, now)
+        .map_err(|e| format!("Forbidden: requires role '{}'; {}", required, e))?;
+    security::require_role(&settings.user_roles, &user_id, required)?;
+    Ok(user_id)
+}
+
+/// Refresh the caller's session. An expired session is audited and
+/// answered with `session_expired: true` so the UI can ask to sign in again.
+fn check_session(session_id: Option<&str>, settings: &SecuritySettings, now: DateTime<Utc>) -> Result<security::Session, ApiResponse> {
+    let session_id = session_id.ok_or_else(|| ApiResponse::error(SynapseError::Unauthorized("No session; sign in first".to_string())))?;
+    let touched = state::app_state().sessions().lock().unwrap().touch(session_id, settings.session_timeout_minutes, now);
+    touched.map_err(|e| {
+        let mut response = ApiResponse::error(SynapseError::Unauthorized(e.to_string()));
+        if let security::SessionError::Expired(session) = &e {
+            audit_session_timeout(session);
+            response.data = Some(serde_json::json!({ "session_expired": true }));
+        }
+        response
+    })
+}
+
+fn audit_session_timeout(session: &security::Session) {
+    audit::record(
+        "session_timeout",
+        &session.user_id,
+        "success",
+        Some(&session.session_id),
+        Some(format!("Idle since {}", session.last_active_at.to_rfc3339())),
+    );
+}
+
+/// Check the session, then `authorize_caller` against the saved tokens,
+/// then the caller's rate limit
+fn authorize(auth_token: Option<&str>, session_id: Option<&str>, required: &str) -> Result<String, ApiResponse> {
+    let (tokens, key) = issued_capability_tokens();
+    let settings = load_config().security_settings;
+    let now = Utc::now();
+    let session = check_session(session_id, &settings, now)?;
+    let user_id = authorize_caller(&tokens, key.as_deref(), &settings, auth_token, required, now)
+        .map_err(|e| ApiResponse::error(SynapseError::Unauthorized(e)))?;
+    if user_id != session.user_id {
+        return Err(ApiResponse::error(SynapseError::Unauthorized(format!(
+            "Forbidden: session belongs to '{}' but the capability token belongs to '{}'",
+            session.user_id, user_id
+        ))));
+    }
+    match enforce_rate_limit(&user_id, &settings, now) {
+        Some(throttled) => Err(throttled),
+        None => Ok(user_id),
+    }
+}
+
+/// Count a state-changing request against the user's per-minute limit.
+/// Returns the error response to send back when the user is throttled.
+fn enforce_rate_limit(user_id: &str, settings: &SecuritySettings, now: DateTime<Utc>) -> Option<ApiResponse> {
+    if settings.trusted_users.iter().any(|u| u == user_id) {
+        return None;
+    }
+    let retry_after_seconds = state::app_state().rate_limiter()
+        .lock()
+        .unwrap()
+        .check(user_id, settings.rate_limit_per_minute, now)
+        .err()?;
+    let mut response = ApiResponse::error(SynapseError::RateLimited(format!(
+        "Rate limit of {} requests per minute exceeded; retry in {}s",
+        settings.rate_limit_per_minute, retry_after_seconds
+    )));
+    response.data = Some(serde_json::json!({ "retry_after_seconds": retry_after_seconds }));
+    Some(response)
+}
+
+/// Runs of `skill_id` each user may start per hour: the skill's own quota,
+/// else the global one; `None` when neither is set
+pub fn skill_quota_limit(settings: &SecuritySettings, skill_id: &str) -> Option<u32> {
+    settings.skill_run_quotas.get(skill_id).copied().or(settings.skill_runs_per_hour)
+}
+
+fn quota_exceeded(quota: &security::SkillQuota, now: DateTime<Utc>) -> ApiResponse {
+    let retry_after_seconds = quota.retry_after_seconds(now);
+    let mut response = ApiResponse::error(SynapseError::QuotaExceeded(format!(
+        "Quota of {} runs per hour for skill '{}' is used up; retry in {}s",
+        quota.limit.unwrap_or_default(),
+        quota.skill_id,
+        retry_after_seconds
+    )));
+    response.data = Some(serde_json::json!({ "retry_after_seconds": retry_after_seconds, "quota": quota }));
+    response
+}
+
+/// A user's remaining runs of a skill and when the next one frees up; an
+/// exhausted quota is a `QuotaExceeded` error. Checking never uses up a
+/// run: `start_skill_execution` takes one when a run is admitted.
+#[tauri::command]
+pub async fn check_skill_quota(user_id: String, skill_id: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    if !skill_registry_in(&state).iter().any(|s| s.id == skill_id) {
+        return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id))));
+    }
+    let limit = skill_quota_limit(&load_config().security_settings, &skill_id);
+    let now = Utc::now();
+    let quota = state.skill_quota(&user_id, &skill_id, limit, now);
+    if quota.remaining == Some(0) {
+        return Ok(quota_exceeded(&quota, now));
+    }
+    Ok(ApiResponse::success(serde_json::to_value(quota).unwrap()))
+}
+
+/// Admit a run of `skill_id` for the caller: the dry run must find it
+/// ready, every provider it could be routed to must meet its model
+/// requirements, and a run is taken from the caller's quota. `keep_warm` skills
+/// get a sandbox from the warm pool when one is idle. The executor reports
+/// the outcome with `finish_skill_execution`.
+#[tauri::command]
+pub async fn start_skill_execution(
+    skill_id: String,
+    inputs: serde_json::Value,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let Some(skill) = skill_registry_in(&state).into_iter().find(|s| s.id == skill_id) else {
+        return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id))));
+    };
+    let config = load_config();
+    let mut report = dry_run(&skill, &inputs, &granted_capabilities(&caller));
+    if let Err(e) = check_skill_model_requirements(&skill, &config) {
+        report.ready = false;
+        report.blockers.push(e);
+    }
+    if !report.ready {
+        let e = format!("Skill '{}' can't run: {}", skill_id, report.blockers.join("; "));
+        audit::record(audit::ACTION_SKILL_EXECUTE, &caller, "denied", Some(&skill_id), Some(e.clone()));
+        let mut response = ApiResponse::error(SynapseError::Validation(e));
+        response.data = Some(serde_json::to_value(report).unwrap());
+        return Ok(response);
+    }
+
+    let now = Utc::now();
+    let limit = skill_quota_limit(&config.security_settings, &skill_id);
+    if let Err(quota) = state.consume_skill_run(&caller, &skill_id, limit, now) {
+        audit::record(audit::ACTION_SKILL_EXECUTE, &caller, "denied", Some(&skill_id), Some("Run quota used up".to_string()));
+        return Ok(quota_exceeded(&quota, now));
+    }
+
+    let execution = metrics::RunningExecution {
+        execution_id: uuid::Uuid::new_v4().to_string(),
+        skill_id: skill_id.clone(),
+        user_id: caller,
+        sandbox_id: acquire_warm_sandbox(&skill),
+        started_at: now,
+    };
+    state.running_executions().lock().unwrap().start(execution.clone());
+    Ok(ApiResponse::success(serde_json::to_value(execution).unwrap()))
+}
+
+/// Record how an execution started with `start_skill_execution` ended, in
+/// the execution history and the audit log
+#[tauri::command]
+pub async fn finish_skill_execution(
+    execution_id: String,
+    success: bool,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let execution = {
+        let mut running = state.running_executions().lock().unwrap();
+        match running.get(&execution_id) {
+            Some(execution) if execution.user_id != caller => {
+                let e = format!("Execution '{}' was started by another user", execution_id);
+                return Ok(ApiResponse::error(SynapseError::Unauthorized(e)));
+            }
+            Some(_) => running.finish(&execution_id).unwrap(),
+            None => return Ok(ApiResponse::error(SynapseError::NotFound(format!("No running execution '{}'", execution_id)))),
+        }
+    };
+    let latency_ms = (Utc::now() - execution.started_at).num_milliseconds().max(0) as f64;
+    metrics::record_skill_execution(&execution.skill_id, success, latency_ms);
+    audit::record(
+        audit::ACTION_SKILL_EXECUTE,
+        &caller,
+        if success { "success" } else { "failure" },
+        Some(&execution.skill_id),
+        Some(format!("Execution {} took {:.0}ms", execution_id, latency_ms)),
+    );
+    Ok(ApiResponse::success(serde_json::json!({
+        "execution_id": execution_id,
+        "skill_id": execution.skill_id,
+        "success": success,
+        "latency_ms": latency_ms
+    })))
+}
+
+/// Tell the configured webhooks about a skill state change without
+/// waiting for them to answer
+fn notify_skill_event(skill_id: &str, action: &str, actor: &str) {
+    let config = load_config();
+    if let Err(e) = config.require_network("Webhook delivery") {
+        if !config.security_settings.webhook_urls.is_empty() {
+            tracing::debug!(skill_id, action, "{}", e);
+        }
+        return;
+    }
+    let settings = config.security_settings;
+    webhooks::notify(&settings.webhook_urls, settings.audit_webhook_failures, webhooks::SkillEvent::new(skill_id, action, actor, Utc::now()));
+}
+
+/// Response already given for `idempotency_key` by the same caller, if
+/// any. A key first sent with another action, skill, or caller, or whose
+/// first request is still running, is answered with a conflict. Otherwise
+/// the key is reserved until `remember_idempotent` records the outcome.
+fn replay_idempotent(
+    state: &AppState,
+    idempotency_key: Option<&str>,
+    action: &str,
+    skill_id: &str,
+    caller: &str,
+) -> Option<ApiResponse> {
+    let key = idempotency_key?;
+    let window = load_config().security_settings.idempotency_window_minutes;
+    let fingerprint = format!("{}:{}:{}", action, skill_id, caller);
+    match state.idempotency_cache().lock().unwrap().begin(key, &fingerprint, window, Utc::now()) {
+        Ok(cached) => cached.and_then(|value| serde_json::from_value(value).ok()),
+        Err(e) => Some(ApiResponse::error(SynapseError::Conflict(e))),
+    }
+}
+
+/// Cache a successful response under `idempotency_key` so a retry replays
+/// it; failures aren't cached and release the key so it can be retried
+fn remember_idempotent(
+    state: &AppState,
+    idempotency_key: Option<&str>,
+    action: &str,
+    skill_id: &str,
+    caller: &str,
+    response: ApiResponse,
+) -> ApiResponse {
+    if let Some(key) = idempotency_key {
+        let fingerprint = format!("{}:{}:{}", action, skill_id, caller);
+        let mut cache = state.idempotency_cache().lock().unwrap();
+        match serde_json::to_value(&response) {
+            Ok(value) if response.success => cache.store(key, &fingerprint, value, Utc::now()),
+            _ => cache.release(key, &fingerprint),
+        }
+    }
+    response
+}
+
+/// Approve a skill. Resending the same `idempotency_key` returns the first
+/// response without approving again.
+#[tauri::command]
+pub async fn approve_skill(
+    skill_id: String,
+    approved_by: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    idempotency_key: Option<String>,
+    confirm_high_risk: Option<bool>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    approve_skill_in(&state, skill_id, approved_by, auth_token, session_id, idempotency_key, confirm_high_risk).await
+}
+
+/// `approve_skill` against an explicit `state`
+pub async fn approve_skill_in(
+    state: &AppState,
+    skill_id: String,
+    approved_by: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    idempotency_key: Option<String>,
+    confirm_high_risk: Option<bool>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    if let Some(replayed) = replay_idempotent(state, idempotency_key.as_deref(), "skill_approve", &skill_id, &caller) {
+        return Ok(replayed);
+    }
+    let response = apply_approval(state, &skill_id, &approved_by, &caller, confirm_high_risk.unwrap_or(false));
+    Ok(remember_idempotent(state, idempotency_key.as_deref(), "skill_approve", &skill_id, &caller, response))
+}
+
+/// Approve `skill_id` on behalf of the authenticated `caller`
+fn apply_approval(state: &AppState, skill_id: &str, approved_by: &str, caller: &str, confirm_high_risk: bool) -> ApiResponse {
+    let mut warnings = Vec::new();
+    let mut approval_gate = None;
+    if let Some(skill) = skill_registry_in(state).into_iter().find(|s| s.id == skill_id) {
+        let schema = skills::validate_skill_schemas(&skill.id, &skill.inputs, &skill.outputs);
+        if !schema.valid {
+            let e = format!("Skill schema is malformed: {}", schema.errors.join("; "));
+            audit::record("skill_approve", approved_by, "failure", Some(skill_id), Some(e.clone()));
+            return ApiResponse::error(SynapseError::Validation(e));
+        }
+        let config = load_config();
+        if let Err(e) = check_skill_model_requirements(&skill, &config) {
+            audit::record("skill_approve", approved_by, "failure", Some(skill_id), Some(e.clone()));
+            return ApiResponse::error(SynapseError::Validation(e));
+        }
+        let settings = &config.security_settings;
+        match check_approval_gate(&skill, settings, caller, confirm_high_risk) {
+            Ok(passed) => approval_gate = Some(passed),
+            Err(e) => {
+                audit::record("skill_approve", caller, "failure", Some(skill_id), Some(e.clone()));
+                let mut response = ApiResponse::error(SynapseError::Unauthorized(e));
+                response.data = Some(serde_json::json!({
+                    "elevated_approval_required": true,
+                    "risk_level": skill.risk_level.max(skills::compute_risk_level(&skill.required_capabilities)),
+                    "threshold": settings.require_approval_for_risk
+                }));
+                return response;
+            }
+        }
+        // Approval is still allowed; the skill just can't run for this user yet
+        let check = skill_capability_check(&skill, approved_by, &granted_capabilities(approved_by));
+        if !check.is_covered() {
+            warnings.push(format!("{} lacks required capabilities: {}", approved_by, check.missing.join(", ")));
+        }
+    }
+    audit::record("skill_approve", approved_by, "success", Some(skill_id), None);
+    state.record_review(skill_id, "active", approved_by, Utc::now());
+    notify_skill_event(skill_id, "approved", approved_by);
+    
+    ApiResponse::success(serde_json::json!({
+        "skill_id": skill_id,
+        "approved": true,
+        "approved_by": approved_by,
+        "approved_at": Utc::now().to_rfc3339(),
+        "approval_gate": approval_gate,
+        "warnings": warnings
+    }))
+}
+
+/// Reject a skill. Resending the same `idempotency_key` returns the first
+/// response without rejecting again.
+#[tauri::command]
+pub async fn reject_skill(
+    skill_id: String,
+    reason: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    idempotency_key: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    reject_skill_in(&state, skill_id, reason, auth_token, session_id, idempotency_key).await
+}
+
+/// `reject_skill` against an explicit `state`
+pub async fn reject_skill_in(
+    state: &AppState,
+    skill_id: String,
+    reason: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    idempotency_key: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    if let Some(replayed) = replay_idempotent(state, idempotency_key.as_deref(), "skill_reject", &skill_id, &caller) {
+        return Ok(replayed);
+    }
+    audit::record("skill_reject", &caller, "success", Some(&skill_id), Some(reason.clone()));
+    state.record_review(&skill_id, "rejected", &caller, Utc::now());
+    notify_skill_event(&skill_id, "rejected", &caller);
+    
+    let response = ApiResponse::success(serde_json::json!({
+        "skill_id": skill_id,
+        "rejected": true,
+        "reason": reason,
+        "rejected_at": Utc::now().to_rfc3339()
+    }));
+    Ok(remember_idempotent(state, idempotency_key.as_deref(), "skill_reject", &skill_id, &caller, response))
+}
+
+/// Check an approval against `require_approval_for_risk`. Skills below the
+/// threshold pass; at or above it the approver must be an admin or confirm
+/// explicitly. Returns how the gate was passed, and audits the decision.
+///
+/// The gate uses the higher of the stored and the capability-derived risk,
+/// so overriding a skill's risk downward can't skip elevated approval.
+pub fn check_approval_gate(skill: &SkillInfo, settings: &SecuritySettings, approver: &str, confirmed: bool) -> Result<&'static str, String> {
+    let threshold = settings.require_approval_for_risk;
+    let risk_level = skill.risk_level.max(skills::compute_risk_level(&skill.required_capabilities));
+    let decision = if risk_level < threshold {
+        Ok("below_threshold")
+    } else if security::require_role(&settings.user_roles, approver, "admin").is_ok() {
+        Ok("elevated_role")
+    } else if confirmed {
+        Ok("confirmed")
+    } else {
+        Err(format!(
+            "Elevated approval required: skill '{}' has risk level {}, at or above the threshold of {}; confirm the approval or have an admin approve it",
+            skill.id, risk_level, threshold
+        ))
+    };
+    let details = match &decision {
+        Ok(passed) => format!("Risk level {}, threshold {}: {}", risk_level, threshold, passed),
+        Err(_) => format!("Risk level {}, threshold {}: elevated approval required", risk_level, threshold),
+    };
+    let result = if decision.is_ok() { "success" } else { "failure" };
+    audit::record("skill_approval_gate", approver, result, Some(&skill.id), Some(details));
+    decision
+}
+
+/// Outcome for one id of a bulk approve or reject
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BulkSkillResult {
+    pub skill_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Find a skill that is still awaiting review
+fn pending_skill<'a>(registry: &'a [SkillInfo], skill_id: &str) -> Result<&'a SkillInfo, String> {
+    let skill = registry
+        .iter()
+        .find(|s| s.id == skill_id)
+        .ok_or_else(|| format!("Skill '{}' not found", skill_id))?;
+    match skill.status.as_str() {
+        "pending" => Ok(skill),
+        "active" => Err(format!("Skill '{}' is already approved", skill_id)),
+        other => Err(format!("Skill '{}' is {}, not pending", skill_id, other)),
+    }
+}
+
+/// Run `review` for each id, best-effort. Repeated ids are reported rather
+/// than reviewed twice.
+fn review_skills(skill_ids: &[String], mut review: impl FnMut(&str) -> Result<(), String>) -> Vec<BulkSkillResult> {
+    let mut seen = std::collections::HashSet::new();
+    skill_ids
+        .iter()
+        .map(|skill_id| {
+            let outcome = if seen.insert(skill_id.as_str()) {
+                review(skill_id)
+            } else {
+                Err(format!("Skill '{}' appears more than once in the request", skill_id))
+            };
+            BulkSkillResult {
+                skill_id: skill_id.clone(),
+                success: outcome.is_ok(),
+                error: outcome.err(),
+            }
+        })
+        .collect()
+}
+
+/// Approve each pending skill as the authenticated `approved_by`, auditing
+/// every approval separately
+pub fn bulk_approve(skill_ids: &[String], approved_by: &str, registry: &[SkillInfo], config: &SynapseConfig) -> Vec<BulkSkillResult> {
+    review_skills(skill_ids, |skill_id| {
+        // Bulk approvals carry no per-skill confirmation, so high-risk skills need an admin
+        let result = pending_skill(registry, skill_id).and_then(|skill| {
+            check_skill_model_requirements(skill, config)?;
+            check_approval_gate(skill, &config.security_settings, approved_by, false).map(|_| ())
+        });
+        let outcome = if result.is_ok() { "success" } else { "failure" };
+        audit::record("skill_approve", approved_by, outcome, Some(skill_id), result.clone().err());
+        if result.is_ok() {
+            state::app_state().record_review(skill_id, "active", approved_by, Utc::now());
+        }
+        result
+    })
+}
+
+/// Reject each pending skill, auditing every rejection separately
+pub fn bulk_reject(skill_ids: &[String], reason: &str, registry: &[SkillInfo]) -> Vec<BulkSkillResult> {
+    review_skills(skill_ids, |skill_id| {
+        let result = pending_skill(registry, skill_id).map(|_| ());
+        let details = result.clone().err().unwrap_or_else(|| reason.to_string());
+        audit::record("skill_reject", "system", if result.is_ok() { "success" } else { "failure" }, Some(skill_id), Some(details));
+        if result.is_ok() {
+            state::app_state().record_review(skill_id, "rejected", "system", Utc::now());
+        }
+        result
+    })
+}
+
+/// Summary wrapper shared by the bulk review commands
+fn bulk_response(results: Vec<BulkSkillResult>) -> ApiResponse {
+    let succeeded = results.iter().filter(|r| r.success).count();
+    ApiResponse::success(serde_json::json!({
+        "succeeded": succeeded,
+        "failed": results.len() - succeeded,
+        "results": results
+    }))
+}
+
+/// Approve several pending skills as the authenticated caller; one bad id
+/// doesn't stop the rest
+#[tauri::command]
+pub async fn bulk_approve_skills(
+    skill_ids: Vec<String>,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let results = bulk_approve(&skill_ids, &caller, &skill_registry(), &load_config());
+    Ok(bulk_response(results))
+}
+
+/// Reject several pending skills; one bad id doesn't stop the rest
+#[tauri::command]
+pub async fn bulk_reject_skills(
+    skill_ids: Vec<String>,
+    reason: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    if let Err(denied) = authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        return Ok(denied);
+    }
+    let results = bulk_reject(&skill_ids, &reason, &skill_registry());
+    Ok(bulk_response(results))
+}
+
+/// List skills that are approved and enabled, i.e. eligible to execute
+#[tauri::command]
+pub async fn get_executable_skills() -> Result<ApiResponse, String> {
+    Ok(ApiResponse::success(serde_json::to_value(execution_eligible(skill_registry())).unwrap()))
+}
+
+/// Enable or disable a skill without touching its approval status
+#[tauri::command]
+pub async fn set_skill_enabled(
+    skill_id: String,
+    enabled: bool,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    if !registered_skills().iter().any(|s| s.id == skill_id) {
+        return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id))));
+    }
+    let changed = state.skills().lock().unwrap().toggles.set_enabled(&skill_id, enabled);
+    audit::record(
+        if enabled { "skill_enable" } else { "skill_disable" },
+        &caller,
+        "success",
+        Some(&skill_id),
+        if changed { None } else { Some(format!("Already {}", if enabled { "enabled" } else { "disabled" })) },
+    );
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "skill_id": skill_id,
+        "enabled": enabled,
+        "changed": changed
+    })))
+}
+
+/// Add or remove one tag of a skill for an already authorized `caller`,
+/// auditing the change
+pub fn edit_skill_tag(state: &AppState, caller: &str, skill_id: &str, raw_tag: &str, add: bool) -> ApiResponse {
+    let tag = match skills::validate_tag(raw_tag) {
+        Ok(tag) => tag,
+        Err(e) => return ApiResponse::error(SynapseError::Validation(e)),
+    };
+    let Some(skill) = skill_registry_in(state).into_iter().find(|s| s.id == skill_id) else {
+        return ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)));
+    };
+    let edited = {
+        let tags = &mut state.skills().lock().unwrap().tags;
+        if add { tags.add(skill_id, &skill.tags, &tag) } else { tags.remove(skill_id, &skill.tags, &tag) }
+    };
+    let action = if add { "skill_tag_add" } else { "skill_tag_remove" };
+    let changed = edited.is_some();
+    audit::record(action, caller, "success", Some(skill_id), Some(if changed { tag.clone() } else { format!("'{}' unchanged", tag) }));
+    ApiResponse::success(serde_json::json!({
+        "skill_id": skill_id,
+        "tags": edited.unwrap_or(skill.tags),
+        "changed": changed
+    }))
+}
+
+/// Tag a skill; tags are stored lowercase and only once
+#[tauri::command]
+pub async fn add_skill_tag(
+    skill_id: String,
+    tag: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    add_skill_tag_in(&state, skill_id, tag, auth_token, session_id).await
+}
+
+/// `add_skill_tag` against an explicit `state`
+pub async fn add_skill_tag_in(
+    state: &AppState,
+    skill_id: String,
+    tag: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    Ok(edit_skill_tag(state, &caller, &skill_id, &tag, true))
+}
+
+/// Remove a tag from a skill
+#[tauri::command]
+pub async fn remove_skill_tag(
+    skill_id: String,
+    tag: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    remove_skill_tag_in(&state, skill_id, tag, auth_token, session_id).await
+}
+
+/// `remove_skill_tag` against an explicit `state`
+pub async fn remove_skill_tag_in(
+    state: &AppState,
+    skill_id: String,
+    tag: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    Ok(edit_skill_tag(state, &caller, &skill_id, &tag, false))
+}
+
+/// Check that `skill_id` can be renamed to `new_name`: names compare
+/// case-insensitively, and neither another skill's name nor one it used to
+/// have may be taken, so metrics kept under old names stay unambiguous.
+/// Returns the trimmed new name and the skill's current name.
+pub fn check_skill_rename(
+    registry: &[SkillInfo],
+    renames: &skills::SkillRenames,
+    skill_id: &str,
+    new_name: &str,
+) -> Result<(String, String), SynapseError> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() {
+        return Err(SynapseError::Validation("A skill name is required".to_string()));
+    }
+    let skill = registry
+        .iter()
+        .find(|s| s.id == skill_id)
+        .ok_or_else(|| SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))?;
+    if skill.name == new_name {
+        return Err(SynapseError::Validation(format!("Skill '{}' is already named '{}'", skill_id, new_name)));
+    }
+    let same = |name: &str| name.to_lowercase() == new_name.to_lowercase();
+    if let Some(other) = registry.iter().find(|s| s.id != skill_id && same(&s.name)) {
+        return Err(SynapseError::Conflict(format!("Skill '{}' is already named '{}'", other.id, other.name)));
+    }
+    if let Some((other_id, _)) = renames.by_previous_name(new_name).filter(|(id, _)| *id != skill_id) {
+        return Err(SynapseError::Conflict(format!("'{}' is a former name of skill '{}'", new_name, other_id)));
+    }
+    Ok((new_name.to_string(), skill.name.clone()))
+}
+
+/// Give a skill a new display name; its id, and everything keyed by it, stays
+#[tauri::command]
+pub async fn rename_skill(
+    skill_id: String,
+    new_name: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    rename_skill_in(&state, skill_id, new_name, auth_token, session_id).await
+}
+
+/// `rename_skill` against an explicit `state`
+pub async fn rename_skill_in(
+    state: &AppState,
+    skill_id: String,
+    new_name: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    Ok(rename_skill_as(state, &caller, &skill_id, &new_name))
+}
+
+/// Rename `skill_id` for an already authorized `caller`
+pub fn rename_skill_as(state: &AppState, caller: &str, skill_id: &str, new_name: &str) -> ApiResponse {
+    let registry = skill_registry_in(state);
+    let renamed = {
+        let renames = &mut state.skills().lock().unwrap().renames;
+        check_skill_rename(&registry, renames, skill_id, new_name).map(|(new_name, old_name)| {
+            let rename = renames.rename(skill_id, &old_name, &new_name, caller, Utc::now());
+            (rename, old_name, new_name)
+        })
+    };
+    let (rename, old_name, new_name) = match renamed {
+        Ok(renamed) => renamed,
+        Err(e) => {
+            audit::record("skill_rename", caller, "failure", Some(skill_id), Some(e.to_string()));
+            return ApiResponse::error(e);
+        }
+    };
+    let details = format!("Renamed '{}' to '{}'", old_name, new_name);
+    audit::record("skill_rename", caller, "success", Some(skill_id), Some(details));
+    
+    ApiResponse::success(serde_json::json!({
+        "skill_id": skill_id,
+        "name": rename.name,
+        "previous_names": rename.previous_names,
+        "renamed_at": rename.renamed_at
+    }))
+}
+
+/// Metrics per skill from execution `totals`, named as in `registry`.
+/// History is kept by skill id, so a renamed skill reports under its
+/// current name; ids missing from the registry report under the id.
+pub fn skill_metrics_from(totals: &[metrics::ExecutionTotals], registry: &[SkillInfo]) -> Vec<SkillMetrics> {
+    totals
+        .iter()
+        .map(|t| {
+            let total = t.success_count + t.failure_count;
+            SkillMetrics {
+                skill_name: registry.iter().find(|s| s.id == t.skill_id).map_or_else(|| t.skill_id.clone(), |s| s.name.clone()),
+                total_executions: total,
+                successful_executions: t.success_count,
+                failed_executions: t.failure_count,
+                avg_latency_ms: t.avg_latency_ms,
+                success_rate: if total == 0 { 0.0 } else { t.success_count as f64 / total as f64 * 100.0 },
+            }
+        })
+        .collect()
+}
+
+/// A tag and how many skills carry it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Every tag in use, most used first, then alphabetically
+pub fn count_tags(skills: &[SkillInfo]) -> Vec<TagCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for tag in skills.iter().flat_map(|s| &s.tags) {
+        *counts.entry(tag).or_default() += 1;
+    }
+    let mut tags: Vec<TagCount> = counts.into_iter().map(|(tag, count)| TagCount { tag: tag.to_string(), count }).collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    tags
+}
+
+/// List every tag with the number of skills carrying it, for filter sidebars
+#[tauri::command]
+pub async fn list_all_tags() -> Result<ApiResponse, String> {
+    Ok(ApiResponse::success(serde_json::to_value(count_tags(&skill_registry())).unwrap()))
+}
+
+/// List every known version of a skill
+#[tauri::command]
+pub async fn get_skill_versions(skill_id: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let skill = match registered_skills().into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => skill,
+        None => return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    };
+    let versions = skill_versions(&skill, &state.skills().lock().unwrap().versions);
+    Ok(ApiResponse::success(serde_json::to_value(versions).unwrap()))
+}
+
+/// Make an earlier version of a skill the active one
+#[tauri::command]
+pub async fn rollback_skill(
+    skill_id: String,
+    target_version: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    if !registered_skills().iter().any(|s| s.id == skill_id) {
+        return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id))));
+    }
+    let result = state.skills().lock().unwrap().versions.rollback(&skill_id, &target_version);
+    match result {
+        Ok(version) => {
+            audit::record("skill_rollback", &caller, "success", Some(&skill_id), Some(format!("Rolled back to {}", version.version)));
+            Ok(ApiResponse::success(serde_json::json!({
+                "skill_id": skill_id,
+                "active_version": version
+            })))
+        }
+        Err(e) => {
+            audit::record("skill_rollback", &caller, "failure", Some(&skill_id), Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Validation(e)))
+        }
+    }
+}
+
+/// Archive a skill, remembering its status so it can be unarchived.
+/// Resending the same `idempotency_key` returns the first response.
+#[tauri::command]
+pub async fn archive_skill(
+    skill_id: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    idempotency_key: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    archive_skill_in(&state, skill_id, auth_token, session_id, idempotency_key).await
+}
+
+/// `archive_skill` against an explicit `state`
+pub async fn archive_skill_in(
+    state: &AppState,
+    skill_id: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    idempotency_key: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    Ok(archive_skill_as(state, &caller, &skill_id, idempotency_key.as_deref()))
+}
+
+/// Archive `skill_id` for an already authorized `caller`
+pub fn archive_skill_as(state: &AppState, caller: &str, skill_id: &str, idempotency_key: Option<&str>) -> ApiResponse {
+    if let Some(replayed) = replay_idempotent(state, idempotency_key, "skill_archive", skill_id, caller) {
+        return replayed;
+    }
+    let skill = skill_registry_in(state).into_iter().find(|s| s.id == skill_id);
+    let result = match skill {
+        Some(skill) => state.skills().lock().unwrap().archive.archive(skill_id, &skill.status, caller),
+        None => {
+            let response = ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)));
+            return remember_idempotent(state, idempotency_key, "skill_archive", skill_id, caller, response);
+        }
+    };
+    let response = match result {
+        Ok(record) => {
+            audit::record("skill_archive", caller, "success", Some(skill_id), Some(format!("Was {}", record.previous_status)));
+            notify_skill_event(skill_id, "archived", caller);
+            ApiResponse::success(serde_json::json!({
+                "skill_id": skill_id,
+                "archived": true,
+                "archived_by": record.archived_by,
+                "archived_at": record.archived_at.to_rfc3339()
+            }))
+        }
+        Err(e) => {
+            audit::record("skill_archive", caller, "failure", Some(skill_id), Some(e.clone()));
+            ApiResponse::error(SynapseError::Validation(e))
+        }
+    };
+    remember_idempotent(state, idempotency_key, "skill_archive", skill_id, caller, response)
+}
+
+/// Move an archived skill back to the status it had before archiving
+#[tauri::command]
+pub async fn unarchive_skill(
+    skill_id: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    unarchive_skill_in(&state, skill_id, auth_token, session_id).await
+}
+
+/// `unarchive_skill` against an explicit `state`
+pub async fn unarchive_skill_in(
+    state: &AppState,
+    skill_id: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "operator") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    Ok(unarchive_skill_as(state, &caller, &skill_id))
+}
+
+/// Unarchive `skill_id` for an already authorized `caller`
+pub fn unarchive_skill_as(state: &AppState, caller: &str, skill_id: &str) -> ApiResponse {
+    let result = state.skills().lock().unwrap().archive.unarchive(skill_id);
+    match result {
+        Ok(record) => {
+            audit::record("skill_unarchive", caller, "success", Some(skill_id), Some(format!("Restored to {}", record.previous_status)));
+            ApiResponse::success(serde_json::json!({
+                "skill_id": skill_id,
+                "archived": false,
+                "status": record.previous_status
+            }))
+        }
+        Err(e) => {
+            audit::record("skill_unarchive", caller, "failure", Some(skill_id), Some(e.clone()));
+            ApiResponse::error(SynapseError::Validation(e))
+        }
+    }
+}
+
+// ============================================================================
+// Metrics Commands
+// ============================================================================
+
+/// LLM usage metrics
+#[derive(Serialize, Deserialize)]
+pub struct LLMUsageMetrics {
+    pub total_tokens: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub estimated_cost_usd: f64,
+    pub provider_distribution: HashMap<String, u64>,
+    /// Tokens and estimated cost per model
+    pub model_distribution: HashMap<String, metrics::ModelUsage>,
+    /// Share of routed requests per provider, in percent
+    pub routed_distribution: HashMap<String, f64>,
+    /// Highest share of any monthly budget spent this month; `None` without budgets
+    pub budget_used_percent: Option<f64>,
+    pub budget_exceeded: bool,
+    pub budgets: Vec<metrics::BudgetStatus>,
+    /// When usage counters were last cleared by `reset_llm_usage`
+    pub last_reset_at: Option<DateTime<Utc>>,
+}
+
+/// Skill execution metrics
+#[derive(Serialize, Deserialize)]
+pub struct SkillMetrics {
+    pub skill_name: String,
+    pub total_executions: u64,
+    pub successful_executions: u64,
+    pub failed_executions: u64,
+    pub avg_latency_ms: f64,
+    pub success_rate: f64,
+}
+
+/// Get system metrics
+#[tauri::command]
+pub async fn get_system_metrics() -> Result<ApiResponse, String> {
+    let data_dir = try_internal!(load_config().data_path("config"));
+    let metrics = try_internal!(tokio::task::spawn_blocking(move || metrics::get_system_metrics(&data_dir)).await);
+    
+    Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap()))
+}
+
+/// Timeout for each provider probe made by `health_check`
+const HEALTH_PROBE_TIMEOUT_SECS: u64 = 3;
+
+/// Check config, data directories, providers, the audit log and disk space.
+/// A failed config or audit log makes the app unhealthy; anything else
+/// that isn't ok degrades it.
+#[tauri::command]
+pub async fn health_check(state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let mut subsystems = Vec::new();
+    
+    let loaded = config_dir().and_then(|dir| load_profile(&dir, &active_profile(&dir)));
+    let config = match loaded {
+        Ok(saved) => {
+            subsystems.push(metrics::SubsystemHealth::ok("config", if saved.is_some() { "Profile loaded" } else { "Using defaults" }).critical());
+            saved.unwrap_or_default()
+        }
+        Err(e) => {
+            subsystems.push(metrics::SubsystemHealth::failed("config", e).critical());
+            SynapseConfig::default()
+        }
+    };
+    
+    match config.data_dirs() {
+        Ok(dirs) => subsystems.push(metrics::check_data_dirs(&dirs)),
+        Err(e) => subsystems.push(metrics::SubsystemHealth::failed("data_dirs", e)),
+    }
+    
+    if config.is_offline() {
+        subsystems.push(metrics::SubsystemHealth::ok("llm_providers", "Offline mode; remote providers not probed"));
+    } else if !config.llm_providers.iter().any(|p| p.is_active) {
+        subsystems.push(metrics::SubsystemHealth::degraded("llm_providers", "No active LLM provider"));
+    }
+    let timeout = std::time::Duration::from_secs(HEALTH_PROBE_TIMEOUT_SECS);
+    let probed = |p: &&LLMProviderConfig| p.is_active && config.require_provider_network("Probing a provider", p).is_ok();
+    for provider in config.llm_providers.iter().filter(probed) {
+        let name = format!("llm_provider:{}", provider.name);
+        subsystems.push(match providers::probe_connection(provider, timeout).await {
+            Ok(probe) if probe.connected => metrics::SubsystemHealth::ok(&name, format!("Reachable in {} ms", probe.latency_ms)),
+            Ok(probe) => metrics::SubsystemHealth::failed(&name, probe.message),
+            Err(e) => metrics::SubsystemHealth::failed(&name, e),
+        });
+    }
+    
+    let audit_path = state.audit_log().lock().unwrap().path().map(PathBuf::from);
+    subsystems.push(metrics::check_appendable("audit_log", audit_path.as_deref()).critical());
+    
+    let data_dir = try_internal!(config.data_path("config"));
+    let system = try_internal!(tokio::task::spawn_blocking(move || metrics::get_system_metrics(&data_dir)).await);
+    subsystems.push(metrics::check_disk_headroom(system.disk.as_ref(), metrics::MIN_DISK_HEADROOM_PERCENT));
+    
+    Ok(ApiResponse::success(serde_json::to_value(metrics::summarize_health(subsystems, Utc::now())).unwrap()))
+}
+
+/// Emit `metrics://system` events with fresh system metrics every
+/// `interval_ms`; calling again while streaming changes the interval
+#[tauri::command]
+pub async fn start_metrics_stream(window: tauri::Window, interval_ms: u64) -> Result<ApiResponse, String> {
+    let data_dir = try_internal!(load_config().data_path("config"));
+    let emit = move |metrics: metrics::SystemMetrics| {
+        let _ = window.emit(metrics::METRICS_STREAM_EVENT, metrics);
+    };
+    match metrics::start_metrics_stream(data_dir, std::time::Duration::from_millis(interval_ms), emit) {
+        Ok(started) => Ok(ApiResponse::success(serde_json::json!({
+            "streaming": true,
+            "started": started,
+            "interval_ms": metrics::metrics_stream_interval().map(|interval| interval.as_millis() as u64),
+        }))),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Stop the live metrics stream
+#[tauri::command]
+pub async fn stop_metrics_stream() -> Result<ApiResponse, String> {
+    let stopped = try_internal!(tokio::task::spawn_blocking(metrics::stop_metrics_stream).await);
+    Ok(ApiResponse::success(serde_json::json!({ "streaming": false, "stopped": stopped })))
+}
+
+/// Render system, LLM and skill metrics for a Prometheus scrape
+#[tauri::command]
+pub async fn get_metrics_prometheus() -> Result<String, String> {
+    let data_dir = load_config().data_path("config")?;
+    let system = tokio::task::spawn_blocking(move || metrics::get_system_metrics(&data_dir))
+        .await
+        .map_err(|e| e.to_string())?;
+    let registry = skill_registry();
+    let skill_name = |id: &str| registry.iter().find(|s| s.id == id).map(|s| s.name.clone()).unwrap_or_else(|| id.to_string());
+    
+    Ok(metrics::render_prometheus(
+        &system,
+        &metrics::get_llm_usage_stats(&providers::PriceTable::from_config(&load_config())),
+        &metrics::get_skill_execution_metrics(None),
+        &skill_name,
+    ))
+}
+
+/// Bundle system, LLM, per-skill and sampled metrics into one JSON document,
+/// written to `path` when given, with the incident snapshots named in
+/// `incident_ids` attached. The hostname is only included on request.
+#[tauri::command]
+pub async fn export_metrics_snapshot(
+    path: Option<String>,
+    include_hostname: Option<bool>,
+    incident_ids: Option<Vec<String>>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    export_metrics_snapshot_in(&state, path, include_hostname, incident_ids).await
+}
+
+/// `export_metrics_snapshot` against an explicit `state`
+pub async fn export_metrics_snapshot_in(
+    state: &AppState,
+    path: Option<String>,
+    include_hostname: Option<bool>,
+    incident_ids: Option<Vec<String>>,
+) -> Result<ApiResponse, String> {
+    let incident_dir = try_internal!(incident_dir());
+    let incidents = match incident_ids
+        .unwrap_or_default()
+        .iter()
+        .map(|id| metrics::read_incident_snapshot(&incident_dir, id))
+        .collect::<Result<Vec<_>, String>>()
+    {
+        Ok(incidents) => incidents,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::NotFound(e))),
+    };
+    let data_dir = try_internal!(load_config().data_path("config"));
+    let system = try_internal!(tokio::task::spawn_blocking(move || metrics::get_system_metrics(&data_dir)).await);
+    let snapshot = metrics::MetricsSnapshot {
+        captured_at: Utc::now(),
+        hostname: if include_hostname.unwrap_or(false) { metrics::hostname() } else { None },
+        system,
+        llm_usage: metrics::get_llm_usage_stats(&providers::PriceTable::from_config(&load_config())),
+        skills: metrics::get_skill_execution_metrics(None),
+        history: state.metrics_history().lock().unwrap().since(None),
+        incidents,
+        protocol_version: PROTOCOL_VERSION.to_string(),
+    };
+    
+    let written = match path.map(PathBuf::from) {
+        Some(path) => match metrics::write_snapshot(&path, &snapshot) {
+            Ok(()) => Some(std::fs::canonicalize(&path).unwrap_or(path)),
+            Err(e) => return Ok(ApiResponse::error(SynapseError::Internal(e))),
+        },
+        None => None,
+    };
+    Ok(ApiResponse::success(serde_json::json!({
+        "path": written,
+        "snapshot": snapshot
+    })))
+}
+
+/// Get sampled system metrics, oldest first, optionally only since a time
+#[tauri::command]
+pub async fn get_metrics_history(since: Option<DateTime<Utc>>, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let history = state.metrics_history().lock().unwrap();
+    Ok(ApiResponse::success(serde_json::json!({
+        "capacity": history.capacity(),
+        "samples": history.since(since)
+    })))
+}
+
+/// Get cumulative network traffic; loopback interfaces are skipped by default
+#[tauri::command]
+pub async fn get_network_metrics(include_loopback: Option<bool>) -> Result<ApiResponse, String> {
+    let metrics = metrics::get_network_metrics(include_loopback.unwrap_or(false));
+    Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap()))
+}
+
+/// Get CPU, memory and run time of a process (default: this one)
+#[tauri::command]
+pub async fn get_process_metrics(pid: Option<u32>) -> Result<ApiResponse, String> {
+    let result = try_internal!(tokio::task::spawn_blocking(move || metrics::get_process_metrics(pid)).await);
+    match result {
+        Ok(metrics) => Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::NotFound(e))),
+    }
+}
+
+/// Get LLM tokens and estimated cost for each UTC day from `start` to `end`
+/// inclusive, both `YYYY-MM-DD`
+#[tauri::command]
+pub async fn get_llm_usage_by_day(start: String, end: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    get_llm_usage_by_day_in(&state, start, end).await
+}
+
+/// `get_llm_usage_by_day` against an explicit `state`
+pub async fn get_llm_usage_by_day_in(
+    state: &AppState,
+    start: String,
+    end: String,
+) -> Result<ApiResponse, String> {
+    let parse = |raw: &str, which: &str| {
+        chrono::NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid {} date '{}'; expected YYYY-MM-DD", which, raw))
+    };
+    let (start, end) = match parse(&start, "start").and_then(|start| Ok((start, parse(&end, "end")?))) {
+        Ok(range) => range,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    let since = start.and_hms_opt(0, 0, 0).map(|t| t.and_utc());
+    let until = end.succ_opt().and_then(|day| day.and_hms_opt(0, 0, 0)).map(|t| t.and_utc());
+    let records = state.usage_ledger().lock().unwrap().records(since, until);
+    match metrics::usage_by_day(&records, &providers::PriceTable::from_config(&load_config()), start, end) {
+        Ok(days) => Ok(ApiResponse::success(serde_json::to_value(days).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Estimate what a planned LLM call will cost before making it, plus any
+/// further calls in `batch`. Models without a price are listed under
+/// `unpriced_models` and left out of the totals.
+#[tauri::command]
+pub async fn estimate_llm_cost(
+    provider: String,
+    model: String,
+    prompt_tokens: u64,
+    expected_completion_tokens: u64,
+    batch: Option<Vec<providers::PlannedLlmCall>>,
+) -> Result<ApiResponse, String> {
+    let mut calls = vec![providers::PlannedLlmCall {
+        provider,
+        model,
+        prompt_tokens,
+        expected_completion_tokens,
+    }];
+    calls.extend(batch.unwrap_or_default());
+    let estimate = providers::estimate_call_costs(&providers::PriceTable::from_config(&load_config()), &calls);
+    Ok(ApiResponse::success(serde_json::to_value(estimate).unwrap()))
+}
+
+/// Get LLM usage, optionally limited to `[since, until)` such as a billing period
+#[tauri::command]
+pub async fn get_llm_usage(
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    get_llm_usage_in(&state, since, until).await
+}
+
+/// `get_llm_usage` against an explicit `state`
+pub async fn get_llm_usage_in(
+    state: &AppState,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> Result<ApiResponse, String> {
+    let prices = providers::PriceTable::from_config(&load_config());
+    let (records, last_reset_at) = {
+        let ledger = state.usage_ledger().lock().unwrap();
+        (ledger.records(since, until), ledger.last_reset_at())
+    };
+    let usage = metrics::usage_breakdown(&records, &prices);
+    let budgets = check_token_budgets(state, &prices);
+    
+    let metrics = LLMUsageMetrics {
+        total_tokens: usage.prompt_tokens + usage.completion_tokens,
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        estimated_cost_usd: usage.estimated_cost_usd,
+        provider_distribution: usage.providers,
+        model_distribution: usage.models,
+        routed_distribution: providers::routed_distribution(),
+        budget_used_percent: budgets.iter().map(|b| b.used_percent).reduce(f64::max),
+        budget_exceeded: budgets.iter().any(|b| b.exceeded),
+        budgets,
+        last_reset_at,
+    };
+    
+    Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap()))
+}
+
+/// Clear accumulated token and cost counters for one provider, or all
+/// providers when none is given. The pre-reset totals go to the audit log.
+#[tauri::command]
+pub async fn reset_llm_usage(provider: Option<String>, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let now = Utc::now();
+    // The ledger lock keeps records from landing mid-reset
+    let removed = state.usage_ledger().lock().unwrap().reset(provider.as_deref(), now);
+    let totals = metrics::usage_breakdown(&removed, &providers::PriceTable::from_config(&load_config()));
+    let cleared = serde_json::json!({
+        "prompt_tokens": totals.prompt_tokens,
+        "completion_tokens": totals.completion_tokens,
+        "estimated_cost_usd": totals.estimated_cost_usd,
+    });
+    audit::record(
+        "llm_usage_reset",
+        "system",
+        "success",
+        Some(provider.as_deref().unwrap_or("all")),
+        Some(format!("Cleared {} at {}", cleared, now.to_rfc3339())),
+    );
+
+    Ok(ApiResponse::success(serde_json::json!({
+        "provider": provider,
+        "reset_at": now,
+        "cleared": cleared,
+    })))
+}
+
+/// App handle for events not tied to a command's window; set once at startup
+pub fn app_handle() -> &'static std::sync::OnceLock<tauri::AppHandle> {
+    static HANDLE: std::sync::OnceLock<tauri::AppHandle> = std::sync::OnceLock::new();
+    &HANDLE
+}
+
+/// Current-month status of every token budget, emitting a
+/// `token-budget-alert` event for each newly crossed threshold
+fn check_token_budgets(state: &AppState, prices: &providers::PriceTable) -> Vec<metrics::BudgetStatus> {
+    use tauri::Manager;
+
+    let now = Utc::now();
+    let records = state.usage_ledger().lock().unwrap().records(Some(metrics::month_start(now)), None);
+    let mut tracker = state.budget_tracker().lock().unwrap();
+    let statuses = tracker.statuses(&records, prices);
+    for alert in tracker.check_alerts(&statuses, now) {
+        if let Some(app) = app_handle().get() {
+            let _ = app.emit_all("token-budget-alert", alert);
+        }
+    }
+    statuses
+}
+
+/// Set a monthly spend limit for one provider, or for all providers when
+/// `provider` is omitted
+#[tauri::command]
+pub async fn set_token_budget(
+    provider: Option<String>,
+    monthly_limit_usd: f64,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    if let Err(e) = state.budget_tracker().lock().unwrap().set_budget(provider.clone(), monthly_limit_usd) {
+        return Ok(ApiResponse::error(SynapseError::Validation(e)));
+    }
+    let budgets = check_token_budgets(&state, &providers::PriceTable::from_config(&load_config()));
+    let status = budgets.into_iter().find(|b| b.provider == provider);
+    Ok(ApiResponse::success(serde_json::json!({ "budget": status })))
+}
+
+/// Execution counts, mean latency and success rate per skill from the
+/// recorded execution history; `skill_name` may be a current or former name
+#[tauri::command]
+pub async fn get_skill_metrics(skill_name: Option<String>, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    get_skill_metrics_in(&state, skill_name).await
+}
+
+/// `get_skill_metrics` against an explicit `state`
+pub async fn get_skill_metrics_in(
+    state: &AppState,
+    skill_name: Option<String>,
+) -> Result<ApiResponse, String> {
+    let totals = state.execution_history().lock().unwrap().totals();
+    let mut metrics = skill_metrics_from(&totals, &skill_registry_in(state));
+    if let Some(name) = skill_name {
+        // A former name finds the skill under its current one
+        let renamed = state.skills().lock().unwrap().renames.by_previous_name(&name).map(|(_, r)| r.name.clone());
+        let name = renamed.unwrap_or(name);
+        metrics.retain(|m| m.skill_name == name);
+    }
+    
+    Ok(ApiResponse::success(serde_json::to_value(metrics).unwrap()))
+}
+
+/// Success rate per hour, day or week for one skill, or all skills when
+/// `skill_name` is omitted
+#[tauri::command]
+pub async fn get_skill_success_trend(
+    skill_name: Option<String>,
+    bucket: String,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let skill_ids = match &skill_name {
+        Some(name) => match skill_registry_in(&state).into_iter().find(|s| &s.name == name) {
+            Some(skill) => Some(vec![skill.id]),
+            None => return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", name)))),
+        },
+        None => None,
+    };
+    let points = state.execution_history()
+        .lock()
+        .unwrap()
+        .success_trend(skill_ids.as_deref(), &bucket, Utc::now());
+    match points {
+        Ok(points) => Ok(ApiResponse::success(serde_json::to_value(metrics::SuccessTrend {
+            skill_name,
+            bucket,
+            points,
+            protocol_version: PROTOCOL_VERSION.to_string(),
+        }).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Compare a skill's success rate, latency percentiles, and volume between
+/// two windows, e.g. `"previous_24h"` vs `"last_24h"`
+#[tauri::command]
+pub async fn compare_skill_metrics(
+    skill_id: String,
+    window_a: String,
+    window_b: String,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let now = Utc::now();
+    let (window_a, window_b) = match (metrics::parse_time_window(&window_a, now), metrics::parse_time_window(&window_b, now)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    
+    let comparison = state.execution_history().lock().unwrap().compare(&skill_id, window_a, window_b);
+    Ok(ApiResponse::success(serde_json::to_value(comparison).unwrap()))
+}
+
+/// Number of recent failed audit entries included in an incident snapshot
+const INCIDENT_RECENT_ERRORS: usize = 50;
+
+/// Directory holding incident snapshots
+fn incident_dir() -> Result<PathBuf, String> {
+    Ok(default_data_root()?.join("incidents"))
+}
+
+/// Freeze current and recent metrics, running executions, recent errors,
+/// and provider state into a timestamped snapshot file for an incident report
+#[tauri::command]
+pub async fn capture_incident_snapshot(note: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let config = load_config();
+    let secrets: Vec<&str> = config.llm_providers.iter().filter_map(|p| p.api_key.as_deref()).collect();
+    let note = security::redact_secrets(&note, &secrets);
+
+    let mut recent_errors: Vec<AuditLogEntry> = audit::entries()
+        .into_iter()
+        .rev()
+        .filter(|e| matches!(e.result.as_str(), "failure" | "error" | "denied"))
+        .take(INCIDENT_RECENT_ERRORS)
+        .collect();
+    for entry in &mut recent_errors {
+        entry.details = entry.details.as_deref().map(|d| security::redact_secrets(d, &secrets));
+    }
+
+    let data_dir = try_internal!(config.data_path("config"));
+    let (system, process) = try_internal!(
+        tokio::task::spawn_blocking(move || (metrics::get_system_metrics(&data_dir), metrics::get_process_metrics(None).ok()))
+            .await
+    );
+    let captured_at = Utc::now();
+    let snapshot = metrics::IncidentSnapshot {
+        id: metrics::incident_snapshot_id(captured_at),
+        captured_at,
+        note,
+        system,
+        process,
+        recent_errors,
+        providers: providers::provider_reachability(&config),
+        history: state.metrics_history().lock().unwrap().since(None),
+        running_executions: state.running_executions().lock().unwrap().list(),
+        protocol_version: PROTOCOL_VERSION.to_string(),
+    };
+
+    match metrics::write_incident_snapshot(&try_internal!(incident_dir()), &snapshot) {
+        Ok(path) => {
+            let path = path.to_string_lossy().to_string();
+            audit::record("incident_snapshot", "system", "success", Some(&snapshot.id), Some(format!("Captured {}", path)));
+            Ok(ApiResponse::success(serde_json::json!({
+                "id": snapshot.id,
+                "path": path,
+                "captured_at": captured_at
+            })))
+        }
+        Err(e) => {
+            audit::record("incident_snapshot", "system", "failure", Some(&snapshot.id), Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Internal(e)))
+        }
+    }
+}
+
+/// List captured incident snapshots, newest first
+#[tauri::command]
+pub async fn list_incident_snapshots() -> Result<ApiResponse, String> {
+    let snapshots = metrics::list_incident_snapshots(&try_internal!(incident_dir()));
+    Ok(ApiResponse::success(serde_json::to_value(snapshots).unwrap()))
+}
+
+// ============================================================================
+// Security Commands
+// ============================================================================
+
+/// Capability token info
+#[derive(Serialize, Deserialize)]
+pub struct CapabilityInfo {
+    pub token_id: String,
+    pub user_id: String,
+    pub capabilities: Vec<String>,
+    /// Skills the token is limited to; empty when any skill may use it
+    pub skill_scope: Vec<String>,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Computed when listed from the signature, expiry and revocation
     pub is_valid: bool,
 }
 
-/// Audit log entry
-#[derive(Serialize, Deserialize)]
-pub struct AuditLogEntry {
-    pub id: String,
-    pub timestamp: DateTime<Utc>,
-    pub action: String,
-    pub user_id: String,
-    pub result: String,
-    pub details: Option<String>,
+/// Audit log entry
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub user_id: String,
+    pub result: String,
+    /// Skill, provider, or setting the action targeted
+    #[serde(default)]
+    pub resource: Option<String>,
+    pub details: Option<String>,
+    /// `entry_hash` of the previous entry; all zeros for the first
+    #[serde(default)]
+    pub prev_hash: String,
+    /// SHA-256 chaining this entry to `prev_hash`; set when written
+    #[serde(default)]
+    pub entry_hash: String,
+}
+
+/// Issued token file inside the configured config directory
+fn capability_tokens_path() -> Result<PathBuf, String> {
+    Ok(load_config().data_path("config")?.join(security::CAPABILITY_TOKENS_FILE))
+}
+
+/// Decoded token signing key from the config, if one is set
+fn token_signing_key(config: &SynapseConfig) -> Option<Vec<u8>> {
+    config.security_settings.token_signing_key.as_deref().and_then(|key| hex::decode(key).ok())
+}
+
+/// Issued tokens with validity checked against `key` at `now`; without a
+/// key no token is valid
+pub fn capability_infos(tokens: Vec<security::IssuedCapabilityToken>, key: Option<&[u8]>, now: DateTime<Utc>) -> Vec<CapabilityInfo> {
+    tokens
+        .into_iter()
+        .map(|token| CapabilityInfo {
+            is_valid: key.map_or(false, |key| security::capability_token_is_valid(key, &token, now)),
+            token_id: token.token_id,
+            user_id: token.user_id,
+            capabilities: token.capabilities,
+            skill_scope: token.skill_scope,
+            issued_at: token.issued_at,
+            expires_at: token.expires_at,
+            revoked_at: token.revoked_at,
+        })
+        .collect()
+}
+
+/// Tokens of `user_id` (every user's when `None`), leaving out expired and
+/// revoked ones unless asked for
+pub fn select_capability_tokens(
+    tokens: Vec<security::IssuedCapabilityToken>,
+    user_id: Option<&str>,
+    include_expired: bool,
+    include_revoked: bool,
+    now: DateTime<Utc>,
+) -> Vec<security::IssuedCapabilityToken> {
+    tokens
+        .into_iter()
+        .filter(|t| user_id.is_none_or(|user| t.user_id == user))
+        .filter(|t| include_expired || t.expires_at.is_none_or(|at| at > now))
+        .filter(|t| include_revoked || t.revoked_at.is_none())
+        .collect()
+}
+
+/// Saved tokens and the key to check them with
+fn issued_capability_tokens() -> (Vec<security::IssuedCapabilityToken>, Option<Vec<u8>>) {
+    let tokens = capability_tokens_path()
+        .and_then(|path| security::load_capability_tokens(&path))
+        .unwrap_or_default();
+    (tokens, token_signing_key(&load_config()))
+}
+
+/// Capability tokens issued to users
+pub fn capability_tokens() -> Vec<CapabilityInfo> {
+    let (tokens, key) = issued_capability_tokens();
+    capability_infos(tokens, key.as_deref(), Utc::now())
+}
+
+/// Check that the saved token `token_id` is valid and grants `capability`
+/// to the skill `skill_id` is requesting for
+pub fn validate_capability(token_id: &str, capability: &str, skill_id: Option<&str>) -> Result<(), String> {
+    let (tokens, key) = issued_capability_tokens();
+    let token = tokens
+        .iter()
+        .find(|t| t.token_id == token_id)
+        .ok_or_else(|| format!("Capability token '{}' not found", token_id))?;
+    let key = key.ok_or_else(|| "No token signing key is configured".to_string())?;
+    security::validate_capability(&key, token, capability, skill_id, Utc::now())
+}
+
+/// Signing key from the active profile, generating and saving one on first use
+fn ensure_token_signing_key() -> Result<Vec<u8>, String> {
+    if let Some(key) = token_signing_key(&load_config()) {
+        return Ok(key);
+    }
+    let dir = config_dir()?;
+    let (key, _) = update_active_profile(&dir, |config| {
+        Ok(config.security_settings.token_signing_key.get_or_insert_with(security::generate_signing_key).clone())
+    })
+    .map_err(|e| e.to_string())?;
+    hex::decode(key).map_err(|e| e.to_string())
+}
+
+/// Whether issuance may skip authorization because no token was ever
+/// issued. Fails closed: an unloadable profile or an unreadable or corrupt
+/// token file keeps the admin check in place.
+fn first_token_bootstrap() -> bool {
+    state::app_state()
+        .config()
+        .and_then(|config| config.data_path("config"))
+        .is_ok_and(|dir| security::capability_tokens_missing(&dir.join(security::CAPABILITY_TOKENS_FILE)))
+}
+
+/// Mint a signed capability token for a user; without `ttl_seconds` the
+/// token never expires, and without `skill_scope` any skill may use it.
+/// Only an admin may issue tokens, except for the very first one, which
+/// bootstraps the admin's own sign-in. Capabilities the org policy forbids
+/// the user are refused either way.
+#[tauri::command]
+pub async fn issue_capability_token(
+    user_id: String,
+    capabilities: Vec<String>,
+    ttl_seconds: Option<u64>,
+    skill_scope: Option<Vec<String>>,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = if first_token_bootstrap() {
+        "system".to_string()
+    } else {
+        match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+            Ok(caller) => caller,
+            Err(denied) => {
+                audit::record("capability_grant", "unknown", "failure", None, denied.error.as_ref().map(ToString::to_string));
+                return Ok(denied);
+            }
+        }
+    };
+    let policy = match current_org_policy() {
+        Ok(policy) => policy,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Internal(e))),
+    };
+    if let Some(violation) = capabilities.iter().find_map(|c| policy.check_grant(&user_id, c).err()) {
+        audit::record("capability_grant", &caller, "denied", Some(&user_id), Some(violation.message.clone()));
+        let mut response = ApiResponse::error(SynapseError::Unauthorized(violation.message.clone()));
+        response.data = Some(serde_json::to_value(violation).unwrap());
+        return Ok(response);
+    }
+    let skill_scope = skill_scope.unwrap_or_default();
+    let issued = ensure_token_signing_key()
+        .and_then(|key| security::issue_capability_token(&key, &user_id, capabilities, skill_scope, ttl_seconds, Utc::now()))
+        .and_then(|token| {
+            security::save_capability_token(&capability_tokens_path()?, &token)?;
+            Ok(token)
+        });
+    match issued {
+        Ok(token) => {
+            audit::record(
+                "capability_grant",
+                &caller,
+                "success",
+                Some(&token.token_id),
+                Some(if token.skill_scope.is_empty() {
+                    format!("Granted {} to {}", token.capabilities.join(", "), token.user_id)
+                } else {
+                    format!(
+                        "Granted {} to {} for skills {}",
+                        token.capabilities.join(", "),
+                        token.user_id,
+                        token.skill_scope.join(", ")
+                    )
+                }),
+            );
+            let mut data = serde_json::to_value(&token).unwrap();
+            // Shown once; the holder signs in with it
+            data["bearer_token"] = serde_json::json!(security::bearer_token(&token));
+            Ok(ApiResponse::success(data))
+        }
+        Err(e) => {
+            audit::record("capability_grant", &caller, "failure", None, Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Validation(e)))
+        }
+    }
+}
+
+/// Capabilities currently granted to a user by valid, unexpired tokens
+pub fn granted_capabilities(user_id: &str) -> Vec<String> {
+    let (tokens, key) = issued_capability_tokens();
+    match key {
+        Some(key) => capabilities_granted_by(&tokens, &key, user_id, Utc::now()),
+        None => Vec::new(),
+    }
+}
+
+/// Capabilities `tokens` grant to a user at `now`, as checked by
+/// `security::validate_capability`; revoked and expired tokens grant nothing,
+/// nor do skill-scoped ones since they don't grant the user as a whole
+pub fn capabilities_granted_by(
+    tokens: &[security::IssuedCapabilityToken],
+    key: &[u8],
+    user_id: &str,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut granted: Vec<String> = tokens
+        .iter()
+        .filter(|t| t.user_id == user_id)
+        .flat_map(|t| t.capabilities.iter().filter(move |c| security::validate_capability(key, t, c, None, now).is_ok()))
+        .cloned()
+        .collect();
+    granted.sort();
+    granted.dedup();
+    granted
+}
+
+/// Check whether a token currently grants a capability, optionally to a
+/// particular skill
+#[tauri::command]
+pub async fn check_capability_token(
+    token_id: String,
+    capability: String,
+    skill_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let result = validate_capability(&token_id, &capability, skill_id.as_deref());
+    Ok(ApiResponse::success(serde_json::json!({
+        "token_id": token_id,
+        "capability": capability,
+        "skill_id": skill_id,
+        "valid": result.is_ok(),
+        "reason": result.err(),
+    })))
+}
+
+/// Revoke a capability token before it expires; revoking twice is a no-op.
+/// Requires an admin, who is recorded as the revoker.
+#[tauri::command]
+pub async fn revoke_capability_token(
+    token_id: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let revoked_by = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => {
+            audit::record("capability_revoke", "unknown", "failure", Some(&token_id), denied.error.as_ref().map(ToString::to_string));
+            return Ok(denied);
+        }
+    };
+    let revoked = capability_tokens_path()
+        .and_then(|path| security::revoke_capability_token(&path, &token_id, &revoked_by, Utc::now()));
+    match revoked {
+        Ok(token) => {
+            audit::record("capability_revoke", &revoked_by, "success", Some(&token_id), None);
+            Ok(ApiResponse::success(serde_json::json!({
+                "token_id": token.token_id,
+                "revoked_at": token.revoked_at,
+                "revoked_by": token.revoked_by,
+            })))
+        }
+        Err(e) => {
+            audit::record("capability_revoke", &revoked_by, "failure", Some(&token_id), Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::NotFound(e)))
+        }
+    }
+}
+
+/// Get issued capability tokens one page at a time, for one user or every
+/// user when `user_id` is omitted. Expired and revoked tokens are left out
+/// unless `include_expired` / `include_revoked` are set.
+#[tauri::command]
+pub async fn get_capabilities(
+    user_id: Option<String>,
+    include_expired: Option<bool>,
+    include_revoked: Option<bool>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<ApiResponse, String> {
+    let (tokens, key) = issued_capability_tokens();
+    let now = Utc::now();
+    let selected = select_capability_tokens(
+        tokens,
+        user_id.as_deref(),
+        include_expired.unwrap_or(false),
+        include_revoked.unwrap_or(false),
+        now,
+    );
+    let page = Page::from_items(capability_infos(selected, key.as_deref(), now), limit, offset);
+    
+    Ok(ApiResponse::success(serde_json::to_value(page).unwrap()))
+}
+
+/// Which of a skill's required capabilities a user holds
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SkillCapabilityCheck {
+    pub skill_id: String,
+    pub user_id: String,
+    pub granted: Vec<String>,
+    pub missing: Vec<String>,
+}
+
+impl SkillCapabilityCheck {
+    pub fn is_covered(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Cross-reference a skill's required capabilities with a user's grants
+pub fn skill_capability_check(skill: &SkillInfo, user_id: &str, granted: &[String]) -> SkillCapabilityCheck {
+    let effective = security::effective_capabilities(&skill.required_capabilities, granted);
+    SkillCapabilityCheck {
+        skill_id: skill.id.clone(),
+        user_id: user_id.to_string(),
+        granted: skill
+            .required_capabilities
+            .iter()
+            .filter(|c| !effective.missing.contains(c))
+            .cloned()
+            .collect(),
+        missing: effective.missing,
+    }
+}
+
+/// Check whether a user holds every capability a skill requires
+#[tauri::command]
+pub async fn check_skill_capabilities(skill_id: String, user_id: String) -> Result<ApiResponse, String> {
+    match skill_registry().into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => {
+            let check = skill_capability_check(&skill, &user_id, &granted_capabilities(&user_id));
+            Ok(ApiResponse::success(serde_json::to_value(check).unwrap()))
+        }
+        None => Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    }
+}
+
+/// Org policy file inside the configured config directory
+fn org_policy_path() -> Result<PathBuf, String> {
+    Ok(load_config().data_path("config")?.join(security::ORG_POLICY_FILE))
+}
+
+/// Org policy state, loading it from disk on first use
+fn current_org_policy() -> Result<security::OrgPolicyState, String> {
+    let loaded = state::app_state().org_policy().lock().unwrap().clone();
+    match loaded {
+        (Some(_), state) => Ok(state),
+        (None, _) => Ok(security::reload_org_policy(&org_policy_path()?)),
+    }
+}
+
+/// Reload the org policy file, e.g. after an administrator edited it.
+/// Requires an admin, since the policy decides which grants are allowed.
+#[tauri::command]
+pub async fn reload_org_policy(auth_token: Option<String>, session_id: Option<String>) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let path = try_internal!(org_policy_path());
+    let state = security::reload_org_policy(&path);
+    let (status, error) = match &state {
+        security::OrgPolicyState::Absent => ("absent", None),
+        security::OrgPolicyState::Loaded(_) => ("loaded", None),
+        security::OrgPolicyState::Invalid(reason) => ("invalid", Some(reason.clone())),
+    };
+    audit::record("security_update", &caller, if error.is_none() { "success" } else { "failure" }, None, Some(format!("Org policy {}", status)));
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "path": path.to_string_lossy(),
+        "status": status,
+        "error": error
+    })))
+}
+
+/// Audit every existing grant against the org policy and return the
+/// tokens it no longer permits
+#[tauri::command]
+pub async fn validate_against_org_policy() -> Result<ApiResponse, String> {
+    let policy = try_internal!(current_org_policy());
+    let violations: Vec<security::PolicyViolation> = capability_tokens()
+        .into_iter()
+        .flat_map(|token| {
+            let policy = &policy;
+            token.capabilities.into_iter().filter_map(move |capability| {
+                policy.check_grant(&token.user_id, &capability).err().map(|mut violation| {
+                    violation.token_id = Some(token.token_id.clone());
+                    violation
+                })
+            })
+        })
+        .collect();
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "compliant": violations.is_empty(),
+        "violations": violations
+    })))
 }
 
-/// Get capabilities
+/// Show the scoped capabilities the isolation runtime would receive if
+/// `user_id` ran `skill_id` right now
 #[tauri::command]
-pub async fn get_capabilities(user_id: Option<String>) -> Result<ApiResponse, String> {
-    let capabilities = vec![
-        CapabilityInfo {
-            token_id: "cap-001".to_string(),
-            user_id: "admin".to_string(),
-            capabilities: vec!["fs:read".to_string(), "fs:write".to_string(), "network:http".to_string()],
-            issued_at: Utc::now(),
-            expires_at: None,
-            is_valid: true,
-        },
-    ];
+pub async fn get_skill_effective_capabilities(skill_id: String, user_id: String) -> Result<ApiResponse, String> {
+    let skill = match skill_registry().into_iter().find(|s| s.id == skill_id) {
+        Some(skill) => skill,
+        None => return Ok(ApiResponse::error(SynapseError::NotFound(format!("Skill '{}' not found", skill_id)))),
+    };
     
-    Ok(ApiResponse::success(serde_json::to_value(capabilities).unwrap()))
+    let effective = security::effective_capabilities(&skill.required_capabilities, &granted_capabilities(&user_id));
+    Ok(ApiResponse::success(serde_json::json!({
+        "skill_id": skill_id,
+        "user_id": user_id,
+        "capabilities": effective
+    })))
 }
 
-/// Get audit log
+/// Get a page of audit log entries, newest first, filtered by time range
+/// (inclusive), action, and user
 #[tauri::command]
 pub async fn get_audit_log(
     limit: Option<u32>,
+    offset: Option<u32>,
     action_filter: Option<String>,
     user_filter: Option<String>,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
 ) -> Result<ApiResponse, String> {
-    let entries = vec![
-        AuditLogEntry {
-            id: "audit-001".to_string(),
-            timestamp: Utc::now(),
-            action: "skill_execute".to_string(),
-            user_id: "admin".to_string(),
-            result: "success".to_string(),
-            details: Some("Executed read_file skill".to_string()),
-        },
-        AuditLogEntry {
-            id: "audit-002".to_string(),
-            timestamp: Utc::now(),
-            action: "config_update".to_string(),
-            user_id: "admin".to_string(),
-            result: "success".to_string(),
-            details: Some("Updated LLM provider settings".to_string()),
-        },
-    ];
+    let query = audit::AuditQuery {
+        since: start_time,
+        until: end_time,
+        action: action_filter,
+        user_id: user_filter,
+        limit: None,
+    };
+    let page = Page::from_items(audit::query(&audit::history(), &query), limit, offset);
+    
+    Ok(ApiResponse::success(serde_json::to_value(page).unwrap()))
+}
+
+/// Push each new audit entry matching the filters to the calling window as an
+/// `audit://entry` event until `unsubscribe_audit_log` is called
+#[tauri::command]
+pub async fn subscribe_audit_log(
+    window: tauri::Window,
+    action_filter: Option<String>,
+    user_filter: Option<String>,
+) -> Result<ApiResponse, String> {
+    let filter = audit::AuditQuery {
+        action: action_filter,
+        user_id: user_filter,
+        ..Default::default()
+    };
+    let subscription_id = format!("audit-{:016x}", rand::random::<u64>());
+    let mut receiver = audit::entry_sink().subscribe();
+    let forward = tokio::spawn(async move {
+        while let Some(entry) = audit::next_matching(&mut receiver, &filter).await {
+            let _ = window.emit("audit://entry", entry);
+        }
+    });
+    audit::subscriptions().lock().unwrap().insert(subscription_id.clone(), forward);
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "subscription_id": subscription_id,
+        "event": "audit://entry"
+    })))
+}
+
+/// Stop a `subscribe_audit_log` stream
+#[tauri::command]
+pub async fn unsubscribe_audit_log(subscription_id: String) -> Result<ApiResponse, String> {
+    let forward = audit::subscriptions().lock().unwrap().remove(&subscription_id);
+    if let Some(forward) = &forward {
+        forward.abort();
+    }
+    Ok(ApiResponse::success(serde_json::json!({
+        "subscription_id": subscription_id,
+        "unsubscribed": forward.is_some()
+    })))
+}
+
+/// An optional RFC 3339 timestamp from the UI, normalized to UTC
+fn parse_timestamp(raw: Option<String>) -> Result<Option<DateTime<Utc>>, String> {
+    raw.map(|raw| {
+        DateTime::parse_from_rfc3339(&raw)
+            .map(|at| at.with_timezone(&Utc))
+            .map_err(|e| format!("Invalid timestamp '{}': {}", raw, e))
+    })
+    .transpose()
+}
+
+/// Export audit entries between two RFC 3339 timestamps as "csv" or "json"
+#[tauri::command]
+pub async fn export_audit_log(format: String, start_time: Option<String>, end_time: Option<String>) -> Result<ApiResponse, String> {
+    let query = match (parse_timestamp(start_time), parse_timestamp(end_time)) {
+        (Ok(since), Ok(until)) => audit::AuditQuery { since, until, ..Default::default() },
+        (Err(e), _) | (_, Err(e)) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    
+    // Oldest first, matching the order entries were chained in
+    let mut entries = audit::query(&audit::history(), &query);
+    entries.reverse();
+    match audit::export_entries(&entries, &format) {
+        Ok(content) => Ok(ApiResponse::success(serde_json::json!({
+            "format": format,
+            "entry_count": entries.len(),
+            "content": content
+        }))),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Walk the audit hash chain, archived segments included, and report the
+/// first entry where it breaks
+#[tauri::command]
+pub async fn verify_audit_chain() -> Result<ApiResponse, String> {
+    let verification = audit::verify_chain(&audit::history());
+    Ok(ApiResponse::success(serde_json::to_value(verification).unwrap()))
+}
+
+/// Move audit entries past the configured retention limits into the archive
+#[tauri::command]
+pub async fn rotate_audit_log(
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let policy = load_config().security_settings.audit_retention();
+    if policy.is_empty() {
+        return Ok(ApiResponse::error(SynapseError::Validation(
+            "No audit retention limits are configured".to_string(),
+        )));
+    }
+    let state: &'static AppState = *state;
+    let result = try_internal!(
+        tokio::task::spawn_blocking(move || state.audit_log().lock().unwrap().rotate(&policy, Utc::now()))
+            .await
+    );
+    
+    match result {
+        Ok(report) => {
+            audit::record(
+                "audit_rotate",
+                &caller,
+                "success",
+                report.segment.as_deref(),
+                Some(format!("Archived {} entries, kept {}", report.archived_entries, report.kept_entries)),
+            );
+            Ok(ApiResponse::success(serde_json::to_value(report).unwrap()))
+        }
+        Err(e) => {
+            audit::record("audit_rotate", &caller, "failure", None, Some(e.clone()));
+            Ok(ApiResponse::error(SynapseError::Internal(e)))
+        }
+    }
+}
+
+/// Summarize audit activity between two RFC 3339 timestamps, archived
+/// entries included. `end` defaults to now and `start` to 24 hours before it.
+#[tauri::command]
+pub async fn get_audit_summary(start: Option<String>, end: Option<String>) -> Result<ApiResponse, String> {
+    let (start, end) = match (parse_timestamp(start), parse_timestamp(end)) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(e), _) | (_, Err(e)) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    let until = end.unwrap_or_else(Utc::now);
+    let since = start.unwrap_or_else(|| until - chrono::Duration::hours(24));
+    if since > until {
+        return Ok(ApiResponse::error(SynapseError::Validation("'start' must not be after 'end'".to_string())));
+    }
+    
+    let summary = audit::summarize(&audit::history(), since, until);
+    Ok(ApiResponse::success(serde_json::to_value(summary).unwrap()))
+}
+
+/// Report whether the audit growth guard is currently coalescing entries
+#[tauri::command]
+pub async fn get_audit_coalescing_status(state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let status = state.audit_log().lock().unwrap().status();
+    Ok(ApiResponse::success(serde_json::to_value(status).unwrap()))
+}
+
+/// Set the per-minute audit entry cap above which bursts are coalesced,
+/// saving it to the active profile so it survives a restart
+#[tauri::command]
+pub async fn set_audit_rate_limit(
+    max_entries_per_minute: u32,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+    state: SharedState<'_>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    if max_entries_per_minute == 0 {
+        return Ok(ApiResponse::error(SynapseError::Validation("max_entries_per_minute must be greater than zero".to_string())));
+    }
+    let dir = try_internal!(config_dir());
+    let saved = update_active_profile(&dir, |config| {
+        config.security_settings.audit_max_entries_per_minute = Some(max_entries_per_minute);
+        Ok(())
+    });
+    let config = match saved {
+        Ok((_, config)) => config,
+        Err(e) => {
+            audit::record("security_update", &caller, "failure", None, Some(e.to_string()));
+            return Ok(ApiResponse::error(e));
+        }
+    };
+    apply_audit_rate_limit(&state, &config.security_settings);
+    audit::record("security_update", &caller, "success", None, Some(format!("Audit rate limit set to {}/min", max_entries_per_minute)));
     
-    Ok(ApiResponse::success(serde_json::to_value(entries).unwrap()))
+    Ok(ApiResponse::success(serde_json::json!({
+        "updated": true,
+        "max_entries_per_minute": max_entries_per_minute
+    })))
 }
 
 /// Get security settings
 #[tauri::command]
 pub async fn get_security_settings() -> Result<ApiResponse, String> {
+    let config = load_config();
     Ok(ApiResponse::success(serde_json::json!({
-        "require_approval_for_risk": 3,
-        "isolation_policy": "container",
-        "audit_enabled": true,
-        "trusted_users": [],
-        "rate_limit_per_minute": 60,
-        "session_timeout_minutes": 30
+        "require_approval_for_risk": config.security_settings.require_approval_for_risk,
+        "isolation_policy": config.security_settings.isolation_policy,
+        "audit_enabled": config.security_settings.audit_enabled,
+        "trusted_users": config.security_settings.trusted_users,
+        "rate_limit_per_minute": config.security_settings.rate_limit_per_minute,
+        "session_timeout_minutes": config.security_settings.session_timeout_minutes,
+        "idempotency_window_minutes": config.security_settings.idempotency_window_minutes,
+        "skill_runs_per_hour": config.security_settings.skill_runs_per_hour,
+        "skill_run_quotas": config.security_settings.skill_run_quotas,
+        "webhook_urls": config.security_settings.webhook_urls,
+        "audit_max_entries_per_minute": config.security_settings.audit_rate_limit()
     })))
 }
 
-/// Update security settings
+/// Check a change of isolation policy with `isolation::check_policy_transition`,
+/// auditing confirmed and refused downgrades
+fn check_isolation_change(current: &str, requested: &str, confirm_downgrade: bool, actor: &str) -> Result<(), String> {
+    let transition = format!("{} -> {}", current, requested);
+    match isolation::check_policy_transition(current, requested, confirm_downgrade) {
+        Ok(true) => {
+            audit::record(
+                "isolation_downgrade",
+                actor,
+                "success",
+                Some(&transition),
+                Some(format!("Isolation downgraded from '{}' to '{}' with explicit confirmation", current, requested)),
+            );
+            Ok(())
+        }
+        Ok(false) => Ok(()),
+        Err(e) => {
+            audit::record("isolation_downgrade", actor, "failure", Some(&transition), Some(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+/// Replace the active profile's security settings in `dir`, refusing
+/// unconfirmed isolation downgrades and invalid values. A missing signing
+/// key keeps the saved one, since the UI never sees it.
+pub fn apply_security_settings(
+    dir: &std::path::Path,
+    mut settings: SecuritySettings,
+    confirm_downgrade: bool,
+    actor: &str,
+) -> Result<SecuritySettings, SynapseError> {
+    let (_, config) = update_active_profile(dir, |config| {
+        let current = &config.security_settings.isolation_policy;
+        check_isolation_change(current, &settings.isolation_policy, confirm_downgrade, actor).map_err(SynapseError::Validation)?;
+        if settings.token_signing_key.is_none() {
+            settings.token_signing_key = config.security_settings.token_signing_key.take();
+        }
+        config.security_settings = settings;
+        let errors: Vec<String> = validate_config(config)
+            .into_iter()
+            .filter(|issue| issue.field.starts_with("security_settings"))
+            .map(|issue| format!("{}: {}", issue.field, issue.message))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SynapseError::Validation(errors.join("; ")))
+        }
+    })?;
+    apply_audit_rate_limit(state::app_state(), &config.security_settings);
+    Ok(config.security_settings)
+}
+
+/// Update security settings in the active profile
 #[tauri::command]
-pub async fn update_security_settings(settings: SecuritySettings) -> Result<ApiResponse, String> {
+pub async fn update_security_settings(
+    settings: SecuritySettings,
+    confirm_downgrade: Option<bool>,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => {
+            audit::record("security_update", "unknown", "failure", None, denied.error.as_ref().map(ToString::to_string));
+            return Ok(denied);
+        }
+    };
+    
+    let confirm_downgrade = confirm_downgrade.unwrap_or(false);
+    let applied = config_dir()
+        .map_err(SynapseError::Internal)
+        .and_then(|dir| apply_security_settings(&dir, settings, confirm_downgrade, &caller));
+    let settings = match applied {
+        Ok(settings) => settings,
+        Err(e) => {
+            audit::record("security_update", &caller, "failure", None, Some(e.to_string()));
+            return Ok(ApiResponse::error(e));
+        }
+    };
+    audit::record(
+        "security_update",
+        &caller,
+        "success",
+        None,
+        Some(format!(
+            "Approval threshold {}, isolation {}, audit {}",
+            settings.require_approval_for_risk,
+            settings.isolation_policy,
+            if settings.audit_enabled { "enabled" } else { "disabled" }
+        )),
+    );
     Ok(ApiResponse::success(serde_json::json!({
         "updated": true,
-        "settings": settings
+        "settings": redacted_security_settings(&settings)
+    })))
+}
+
+/// Start a session for the holder of a valid capability token, presented as
+/// the bearer token returned at issuance; commands that change state
+/// require one
+#[tauri::command]
+pub async fn create_session(auth_token: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    create_session_in(&state, auth_token).await
+}
+
+/// `create_session` against an explicit `state`
+pub async fn create_session_in(state: &AppState, auth_token: String) -> Result<ApiResponse, String> {
+    let (tokens, key) = issued_capability_tokens();
+    let user_id = match security::resolve_caller(&tokens, key.as_deref(), Some(&auth_token), Utc::now()) {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            audit::record("session_start", "unknown", "failure", None, Some(e.clone()));
+            return Ok(ApiResponse::error(SynapseError::Unauthorized(format!("Cannot start a session: {}", e))));
+        }
+    };
+    let user_id = user_id.as_str();
+    let timeout_minutes = load_config().security_settings.session_timeout_minutes;
+    let now = Utc::now();
+    let (session, expired) = {
+        let mut sessions = state.sessions().lock().unwrap();
+        (sessions.create(user_id, now), sessions.expire_idle(timeout_minutes, now))
+    };
+    for stale in &expired {
+        audit_session_timeout(stale);
+    }
+    audit::record("session_start", user_id, "success", Some(&session.session_id), None);
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "session": session,
+        "timeout_minutes": timeout_minutes
+    })))
+}
+
+/// Sign out of a session
+#[tauri::command]
+pub async fn end_session(session_id: String, state: SharedState<'_>) -> Result<ApiResponse, String> {
+    let Some(session) = state.sessions().lock().unwrap().end(&session_id) else {
+        return Ok(ApiResponse::error(SynapseError::NotFound(security::SessionError::NotFound(session_id).to_string())));
+    };
+    audit::record("session_end", &session.user_id, "success", Some(&session.session_id), None);
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "ended": true,
+        "session_id": session.session_id
+    })))
+}
+
+/// Add a user to the trusted list without replacing the whole settings block
+#[tauri::command]
+pub async fn add_trusted_user(
+    user_id: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let added = config_dir().map_err(SynapseError::Internal).and_then(|dir| {
+        update_active_profile(&dir, |config| {
+            security::add_trusted_user(&mut config.security_settings.trusted_users, &user_id)
+                .map_err(SynapseError::Validation)
+        })
+    });
+    let config = match added {
+        Ok((_, config)) => config,
+        Err(e) => return Ok(ApiResponse::error(e)),
+    };
+    audit::record("security_update", &caller, "success", Some(user_id.trim()), Some("Trusted user added".to_string()));
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "added": true,
+        "trusted_users": config.security_settings.trusted_users
+    })))
+}
+
+/// Remove a user from the trusted list; removing an absent user is a no-op
+#[tauri::command]
+pub async fn remove_trusted_user(
+    user_id: String,
+    auth_token: Option<String>,
+    session_id: Option<String>,
+) -> Result<ApiResponse, String> {
+    let caller = match authorize(auth_token.as_deref(), session_id.as_deref(), "admin") {
+        Ok(caller) => caller,
+        Err(denied) => return Ok(denied),
+    };
+    let removed = config_dir().map_err(SynapseError::Internal).and_then(|dir| {
+        update_active_profile(&dir, |config| {
+            Ok(security::remove_trusted_user(&mut config.security_settings.trusted_users, &user_id))
+        })
+    });
+    let (removed, config) = match removed {
+        Ok(removed) => removed,
+        Err(e) => return Ok(ApiResponse::error(e)),
+    };
+    if removed {
+        audit::record("security_update", &caller, "success", Some(user_id.trim()), Some("Trusted user removed".to_string()));
+    }
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "removed": removed,
+        "trusted_users": config.security_settings.trusted_users
+    })))
+}
+
+/// Check each configured provider's API key for signs of exposure.
+///
+/// The online breach check is strictly opt-in, is skipped in offline mode,
+/// and only ever sends a short hash prefix of the key.
+#[tauri::command]
+pub async fn check_key_exposure(online_check: Option<bool>, breach_check_url: Option<String>) -> Result<ApiResponse, String> {
+    let config = load_config();
+    let online = online_check.unwrap_or(false) && !config.is_offline();
+    let url = if online { breach_check_url.as_deref() } else { None };
+    
+    let mut results = Vec::new();
+    for provider in &config.llm_providers {
+        results.push(security::check_key_exposure(&provider.name, provider.api_key.as_deref(), url).await);
+    }
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "providers": results,
+        "online_check_performed": url.is_some(),
+        "rotate_recommended": results.iter().any(|r| r.rotate_now)
+    })))
+}
+
+/// Probe which isolation backends are installed and functional on this host
+#[tauri::command]
+pub async fn check_isolation_backends() -> Result<ApiResponse, String> {
+    let backends = isolation::check_isolation_backends().await;
+    let available_policies: Vec<&str> = isolation::ISOLATION_POLICIES
+        .iter()
+        .copied()
+        .filter(|policy| isolation::is_policy_available(policy, &backends))
+        .collect();
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "backends": backends,
+        "available_policies": available_policies
+    })))
+}
+
+// ============================================================================
+// Developer Log Commands
+// ============================================================================
+
+/// API keys from the active configuration, for redacting log output
+fn configured_secrets(config: &SynapseConfig) -> Vec<String> {
+    config.llm_providers.iter().filter_map(|p| p.api_key.clone()).collect()
+}
+
+/// Folder the developer log is written to
+pub fn log_dir() -> Result<PathBuf, String> {
+    Ok(default_data_root()?.join(logs::LOG_DIR))
+}
+
+/// Get the log folder and the file currently written, so the UI can reveal
+/// them for a bug report
+#[tauri::command]
+pub async fn get_log_path() -> Result<ApiResponse, String> {
+    let dir = try_internal!(log_dir());
+    Ok(ApiResponse::success(serde_json::json!({
+        "directory": dir,
+        "current_file": logs::current_log_file(&dir)
+    })))
+}
+
+/// Fetch the last `lines` lines of the developer log, secrets redacted
+#[tauri::command]
+pub async fn read_log_tail(lines: usize) -> Result<ApiResponse, String> {
+    let path = logs::current_log_file(&try_internal!(log_dir()));
+    let config = load_config();
+    let secrets = configured_secrets(&config);
+    let secrets: Vec<&str> = secrets.iter().map(String::as_str).collect();
+    
+    match logs::read_log_tail(&path, lines) {
+        Ok(tail) => {
+            let tail: Vec<logs::LogLine> = tail
+                .iter()
+                .map(|line| logs::LogLine::new(security::redact_secrets(line, &secrets)))
+                .collect();
+            Ok(ApiResponse::success(serde_json::to_value(tail).unwrap()))
+        }
+        Err(e) => Ok(ApiResponse::error(SynapseError::Internal(e))),
+    }
+}
+
+/// Follow the developer log, emitting each new line at or above
+/// `min_level` as a `log-line` event until `unsubscribe_log` is called
+#[tauri::command]
+pub async fn subscribe_log(window: tauri::Window, min_level: Option<String>) -> Result<ApiResponse, String> {
+    if let Some(level) = &min_level {
+        if logs::level_rank(level).is_none() {
+            return Ok(ApiResponse::error(SynapseError::Validation(format!("Unknown log level '{}'", level))));
+        }
+    }
+    
+    let dir = try_internal!(log_dir());
+    let secrets = configured_secrets(&load_config());
+    let subscription_id = format!("log-{:016x}", rand::random::<u64>());
+    logs::subscriptions().lock().unwrap().insert(subscription_id.clone());
+    
+    let id = subscription_id.clone();
+    tokio::spawn(async move {
+        let secrets: Vec<&str> = secrets.iter().map(String::as_str).collect();
+        let mut follower = logs::LogFollower::new(&logs::current_log_file(&dir));
+        while logs::is_subscribed(&id) {
+            let current = logs::current_log_file(&dir);
+            if current != follower.path() {
+                follower = logs::LogFollower::from_start(&current);
+            }
+            for line in follower.poll() {
+                if logs::passes_level(&line, min_level.as_deref()) {
+                    let _ = window.emit("log-line", logs::LogLine::new(security::redact_secrets(&line, &secrets)));
+                }
+            }
+            tokio::time::sleep(logs::FOLLOW_INTERVAL).await;
+        }
+    });
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "subscription_id": subscription_id,
+        "event": "log-line"
+    })))
+}
+
+/// Stop a `subscribe_log` follower
+#[tauri::command]
+pub async fn unsubscribe_log(subscription_id: String) -> Result<ApiResponse, String> {
+    let removed = logs::subscriptions().lock().unwrap().remove(&subscription_id);
+    Ok(ApiResponse::success(serde_json::json!({
+        "subscription_id": subscription_id,
+        "unsubscribed": removed
+    })))
+}
+
+// ============================================================================
+// Wizard Commands
+// ============================================================================
+
+fn wizard_state_path() -> Result<PathBuf, String> {
+    Ok(load_config().data_path("config")?.join(wizard::WIZARD_STATE_FILE))
+}
+
+/// Get wizard steps with saved progress and the step to resume at
+#[tauri::command]
+pub async fn get_wizard_steps() -> Result<ApiResponse, String> {
+    let state = match wizard_state_path().and_then(|path| wizard::load_wizard_state(&path)) {
+        Ok(state) => state,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Internal(e))),
+    };
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "steps": wizard::get_wizard_steps(&state),
+        "current_step": state.current_step,
+        "finished": state.finished
+    })))
+}
+
+/// Save one wizard step's completion; completing "review" finishes setup
+#[tauri::command]
+pub async fn save_wizard_progress(step_id: String, is_complete: bool) -> Result<ApiResponse, String> {
+    match wizard_state_path().and_then(|path| wizard::save_wizard_progress(&path, &step_id, is_complete, Utc::now())) {
+        Ok(state) => Ok(ApiResponse::success(serde_json::to_value(state).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Turn the wizard's answers into a config and save it like `save_config`.
+/// On validation errors nothing is saved and the wizard stays open.
+#[tauri::command]
+pub async fn finalize_wizard(answers: wizard::WizardAnswers) -> Result<ApiResponse, String> {
+    match config_dir() {
+        Ok(dir) => finalize_wizard_in(&dir, answers).await,
+        Err(e) => Ok(ApiResponse::error(SynapseError::Internal(e))),
+    }
+}
+
+/// `finalize_wizard` saving into the config directory `dir`
+pub async fn finalize_wizard_in(dir: &std::path::Path, answers: wizard::WizardAnswers) -> Result<ApiResponse, String> {
+    let config = match wizard::config_from_answers(&answers) {
+        Ok(config) => config,
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    let state_path = match config.data_path("config") {
+        Ok(dir) => dir.join(wizard::WIZARD_STATE_FILE),
+        Err(e) => return Ok(ApiResponse::error(SynapseError::Validation(e))),
+    };
+    let mode = config.mode.clone();
+    
+    let saved = try_internal!(save_config_in(dir, config, None, false, None, None).await);
+    if !saved.success {
+        return Ok(saved);
+    }
+    if let Err(e) = wizard::save_wizard_progress(&state_path, "review", true, Utc::now()) {
+        return Ok(ApiResponse::error(SynapseError::Internal(format!("Configuration saved but wizard progress was not: {}", e))));
+    }
+    
+    Ok(ApiResponse::success(serde_json::json!({
+        "finished": true,
+        "mode": mode,
+        "saved": saved.data
     })))
 }
+
+/// Current wizard step, percent complete, and whether it may move; `payload`
+/// is the current step's input, checked to decide `can_go_next`
+#[tauri::command]
+pub async fn get_wizard_status(payload: Option<serde_json::Value>) -> Result<ApiResponse, String> {
+    match wizard_state_path().and_then(|path| wizard::load_wizard_state(&path)) {
+        Ok(state) => {
+            let status = wizard::wizard_status(&state, &payload.unwrap_or(serde_json::Value::Null));
+            Ok(ApiResponse::success(serde_json::to_value(status).unwrap()))
+        }
+        Err(e) => Ok(ApiResponse::error(SynapseError::Internal(e))),
+    }
+}
+
+/// Load, move, and save the wizard state
+fn navigate_wizard(
+    payload: &serde_json::Value,
+    step: fn(&mut wizard::WizardState, &serde_json::Value, DateTime<Utc>) -> Result<wizard::WizardStatus, String>,
+) -> Result<wizard::WizardStatus, String> {
+    let path = wizard_state_path()?;
+    let mut state = wizard::load_wizard_state(&path)?;
+    let status = step(&mut state, payload, Utc::now())?;
+    wizard::save_wizard_state(&path, &state)?;
+    Ok(status)
+}
+
+/// Complete the current step and advance, if its input validates
+#[tauri::command]
+pub async fn wizard_next(payload: serde_json::Value) -> Result<ApiResponse, String> {
+    match navigate_wizard(&payload, wizard::wizard_next) {
+        Ok(status) => Ok(ApiResponse::success(serde_json::to_value(status).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Go back one wizard step
+#[tauri::command]
+pub async fn wizard_back(payload: Option<serde_json::Value>) -> Result<ApiResponse, String> {
+    match navigate_wizard(&payload.unwrap_or(serde_json::Value::Null), wizard::wizard_back) {
+        Ok(status) => Ok(ApiResponse::success(serde_json::to_value(status).unwrap())),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Languages the wizard's language step offers
+#[tauri::command]
+pub async fn get_supported_languages() -> Result<ApiResponse, String> {
+    Ok(ApiResponse::success(serde_json::to_value(wizard::get_supported_languages()).unwrap()))
+}
+
+/// Models for the LLM step's dropdown, listed live with the entered
+/// credentials when possible. Live lists are cached for the session unless
+/// `refresh` is set.
+#[tauri::command]
+pub async fn get_wizard_models(
+    provider_type: String,
+    api_key: String,
+    base_url: Option<String>,
+    refresh: Option<bool>,
+) -> Result<ApiResponse, String> {
+    let key = wizard::ModelListCache::key(&provider_type, base_url.as_deref(), &api_key);
+    if !refresh.unwrap_or(false) {
+        if let Some(cached) = wizard::model_list_cache().lock().unwrap().get(&key) {
+            return Ok(ApiResponse::success(serde_json::to_value(cached).unwrap()));
+        }
+    }
+    
+    let provider = LLMProviderConfig {
+        name: provider_type.clone(),
+        provider_type: provider_type.clone(),
+        api_key: Some(api_key).filter(|k| !k.is_empty()),
+        base_url,
+        model: String::new(),
+        priority: 0,
+        is_active: true,
+        weight: None,
+    };
+    // Offline, the dropdown falls back to the static list without probing
+    if let Err(e) = load_config().require_provider_network("Listing models", &provider) {
+        let list = wizard::model_list_from_probe(&provider_type, Err(e.message()));
+        return Ok(ApiResponse::success(serde_json::to_value(list).unwrap()));
+    }
+    let timeout = std::time::Duration::from_secs(providers::DEFAULT_PROBE_TIMEOUT_SECS);
+    let probe = providers::probe_connection(&provider, timeout).await;
+    let list = wizard::model_list_from_probe(&provider_type, probe.as_ref().map_err(String::as_str));
+    wizard::model_list_cache().lock().unwrap().insert(key, &list);
+    
+    Ok(ApiResponse::success(serde_json::to_value(list).unwrap()))
+}
+
+/// Check a wizard step's input before the user may advance past it
+#[tauri::command]
+pub async fn validate_wizard_step(step_id: String, payload: serde_json::Value) -> Result<ApiResponse, String> {
+    match wizard::validate_wizard_step(&step_id, &payload) {
+        Ok(errors) => Ok(ApiResponse::success(serde_json::json!({
+            "step_id": step_id,
+            "valid": errors.is_empty(),
+            "errors": errors
+        }))),
+        Err(e) => Ok(ApiResponse::error(SynapseError::Validation(e))),
+    }
+}
+
+/// Get the isolation policies the security step may offer on this host
+#[tauri::command]
+pub async fn get_wizard_security_options() -> Result<ApiResponse, String> {
+    let backends = isolation::check_isolation_backends().await;
+    let options = wizard::get_isolation_options(&backends);
+    
+    Ok(ApiResponse::success(serde_json::to_value(options).unwrap()))
+}
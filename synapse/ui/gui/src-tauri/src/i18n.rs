@@ -0,0 +1,48 @@
+//! Localization Module
+//!
+//! Message catalog for user-facing command messages.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+/// Language every message has a translation in
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// (message id, language, text)
+const MESSAGES: &[(&str, &str, &str)] = &[
+    ("config_saved", "en", "Configuration saved successfully"),
+    ("config_saved", "ru", "Конфигурация успешно сохранена"),
+    ("config_invalid", "en", "Configuration is invalid"),
+    ("config_invalid", "ru", "Конфигурация содержит ошибки"),
+    ("connection_successful", "en", "Connection successful"),
+    ("connection_successful", "ru", "Подключение установлено"),
+    ("connection_rate_limited", "en", "Connected, but the provider is rate limiting requests"),
+    ("connection_rate_limited", "ru", "Подключение установлено, но провайдер ограничивает частоту запросов"),
+    ("connection_models_not_listed", "en", "Connected; the server does not list its models"),
+    ("connection_models_not_listed", "ru", "Подключение установлено; сервер не предоставляет список моделей"),
+];
+
+fn lookup(id: &str, language: &str) -> Option<&'static str> {
+    MESSAGES
+        .iter()
+        .find(|(message_id, lang, text)| *message_id == id && *lang == language && !text.is_empty())
+        .map(|(_, _, text)| *text)
+}
+
+/// Text of message `id` in `language`, falling back to English with a
+/// logged warning. An unknown id comes back as the id itself, so the
+/// result is never empty.
+pub fn localize(id: &str, language: &str) -> String {
+    if let Some(text) = lookup(id, language) {
+        return text.to_string();
+    }
+    match lookup(id, FALLBACK_LANGUAGE) {
+        Some(text) => {
+            tracing::warn!(message_id = id, language, "missing translation; using English");
+            text.to_string()
+        }
+        None => {
+            tracing::warn!(message_id = id, "unknown message id");
+            id.to_string()
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Isolation Backend Module
+//!
+//! Probes the host for the isolation mechanisms Synapse can run skills in.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Protocol version constant for isolation responses
+const ISOLATION_PROTOCOL_VERSION: &str = "1.0";
+
+/// Image used for the container no-op probe
+const PROBE_IMAGE: &str = "busybox:latest";
+
+/// Upper bound for a single probe command
+const PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Isolation policies in increasing order of strength
+pub const ISOLATION_POLICIES: [&str; 3] = ["none", "subprocess", "container"];
+
+/// Availability of a single isolation backend
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IsolationBackendStatus {
+    pub backend: String,
+    pub isolation_type: String,
+    pub installed: bool,
+    pub functional: bool,
+    pub message: String,
+    pub protocol_version: String,
+}
+
+impl IsolationBackendStatus {
+    fn new(backend: &str, isolation_type: &str, installed: bool, functional: bool, message: String) -> Self {
+        Self {
+            backend: backend.to_string(),
+            isolation_type: isolation_type.to_string(),
+            installed,
+            functional,
+            message,
+            protocol_version: ISOLATION_PROTOCOL_VERSION.to_string(),
+        }
+    }
+}
+
+/// Outcome of running a probe command
+enum ProbeOutcome {
+    Ok,
+    Failed(String),
+    Missing,
+    TimedOut,
+}
+
+/// Run a command to completion with the probe timeout, discarding its output
+async fn run_probe(program: &str, args: &[&str]) -> ProbeOutcome {
+    let mut command = Command::new(program);
+    command.args(args).kill_on_drop(true);
+
+    match tokio::time::timeout(PROBE_TIMEOUT, command.output()).await {
+        Err(_) => ProbeOutcome::TimedOut,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => ProbeOutcome::Missing,
+        Ok(Err(e)) => ProbeOutcome::Failed(e.to_string()),
+        Ok(Ok(output)) if output.status.success() => ProbeOutcome::Ok,
+        Ok(Ok(output)) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let line = stderr.lines().next().unwrap_or("").trim().to_string();
+            ProbeOutcome::Failed(if line.is_empty() { format!("exited with {}", output.status) } else { line })
+        }
+    }
+}
+
+/// Probe a Docker-compatible container runtime (docker, podman)
+async fn probe_container_runtime(program: &str, label: &str) -> IsolationBackendStatus {
+    match run_probe(program, &["--version"]).await {
+        ProbeOutcome::Ok => {}
+        ProbeOutcome::Missing => {
+            return IsolationBackendStatus::new(program, "container", false, false, format!("{} is not installed", label));
+        }
+        ProbeOutcome::Failed(reason) => {
+            return IsolationBackendStatus::new(program, "container", false, false, format!("{} could not be started: {}", label, reason));
+        }
+        ProbeOutcome::TimedOut => {
+            return IsolationBackendStatus::new(program, "container", false, false, format!("{} did not respond", label));
+        }
+    }
+
+    if let ProbeOutcome::Failed(_) | ProbeOutcome::TimedOut = run_probe(program, &["info"]).await {
+        return IsolationBackendStatus::new(program, "container", true, false, format!("{} installed but daemon not running", label));
+    }
+
+    match run_probe(program, &["run", "--rm", "--network", "none", PROBE_IMAGE, "true"]).await {
+        ProbeOutcome::Ok => IsolationBackendStatus::new(program, "container", true, true, format!("{} is available", label)),
+        ProbeOutcome::Failed(reason) => IsolationBackendStatus::new(
+            program, "container", true, false,
+            format!("{} daemon is running but a test container failed: {}", label, reason),
+        ),
+        ProbeOutcome::TimedOut => IsolationBackendStatus::new(program, "container", true, false, format!("{} test container timed out", label)),
+        ProbeOutcome::Missing => IsolationBackendStatus::new(program, "container", false, false, format!("{} is not installed", label)),
+    }
+}
+
+/// Probe plain subprocess isolation by spawning a no-op child
+async fn probe_subprocess() -> IsolationBackendStatus {
+    #[cfg(windows)]
+    let outcome = run_probe("cmd", &["/C", "exit 0"]).await;
+    #[cfg(not(windows))]
+    let outcome = run_probe("true", &[]).await;
+
+    match outcome {
+        ProbeOutcome::Ok => IsolationBackendStatus::new("subprocess", "subprocess", true, true, "Subprocess isolation is available".to_string()),
+        ProbeOutcome::Failed(reason) => IsolationBackendStatus::new("subprocess", "subprocess", true, false, format!("Failed to spawn a child process: {}", reason)),
+        _ => IsolationBackendStatus::new("subprocess", "subprocess", true, false, "Failed to spawn a child process".to_string()),
+    }
+}
+
+/// Probe bubblewrap by running a no-op inside a fresh namespace
+async fn probe_bubblewrap() -> IsolationBackendStatus {
+    if cfg!(not(target_os = "linux")) {
+        return IsolationBackendStatus::new("bubblewrap", "sandbox", false, false, "Bubblewrap is only available on Linux".to_string());
+    }
+
+    match run_probe("bwrap", &["--ro-bind", "/", "/", "--dev", "/dev", "--unshare-all", "true"]).await {
+        ProbeOutcome::Ok => IsolationBackendStatus::new("bubblewrap", "sandbox", true, true, "Bubblewrap sandbox is available".to_string()),
+        ProbeOutcome::Missing => IsolationBackendStatus::new("bubblewrap", "sandbox", false, false, "Bubblewrap is not installed".to_string()),
+        ProbeOutcome::Failed(reason) => IsolationBackendStatus::new(
+            "bubblewrap", "sandbox", true, false,
+            format!("Bubblewrap installed but sandbox creation failed (user namespaces disabled?): {}", reason),
+        ),
+        ProbeOutcome::TimedOut => IsolationBackendStatus::new("bubblewrap", "sandbox", true, false, "Bubblewrap sandbox timed out".to_string()),
+    }
+}
+
+/// Probe every known isolation backend
+pub async fn check_isolation_backends() -> Vec<IsolationBackendStatus> {
+    let (docker, podman, subprocess, bubblewrap) = tokio::join!(
+        probe_container_runtime("docker", "Docker"),
+        probe_container_runtime("podman", "Podman"),
+        probe_subprocess(),
+        probe_bubblewrap(),
+    );
+
+    vec![docker, podman, subprocess, bubblewrap]
+}
+
+/// Whether an isolation policy can be enforced given the probed backends
+pub fn is_policy_available(policy: &str, backends: &[IsolationBackendStatus]) -> bool {
+    match policy {
+        "none" => true,
+        "subprocess" => backends.iter().any(|b| b.isolation_type == "subprocess" && b.functional),
+        "container" => backends.iter().any(|b| b.isolation_type == "container" && b.functional),
+        _ => false,
+    }
+}
+
+/// Check a change of isolation policy. Moving to a weaker policy needs
+/// `confirm_downgrade`; returns whether the change is a downgrade.
+pub fn check_policy_transition(from: &str, to: &str, confirm_downgrade: bool) -> Result<bool, String> {
+    let strength = |policy: &str| ISOLATION_POLICIES.iter().position(|p| *p == policy);
+    let target = strength(to).ok_or_else(|| {
+        format!("Unknown isolation policy '{}'; expected one of {}", to, ISOLATION_POLICIES.join(", "))
+    })?;
+    // An unrecognized current policy offers no protection to lose
+    let downgrade = strength(from).is_some_and(|current| target < current);
+    if downgrade && !confirm_downgrade {
+        return Err(format!(
+            "Changing isolation from '{}' to '{}' weakens skill sandboxing: skills would run with {}. \
+             Resubmit with confirm_downgrade to proceed.",
+            from,
+            to,
+            if to == "none" { "full access to this machine" } else { "less separation from the host" }
+        ));
+    }
+    Ok(downgrade)
+}
+
+// ============================================================================
+// Cold Start and Warm Pool
+// ============================================================================
+
+/// How long a pre-warmed container idles before exiting on its own
+const WARM_SANDBOX_TTL_SECS: &str = "3600";
+
+/// Time an isolated no-op startup of an isolation type's backend
+pub async fn measure_cold_start(isolation_type: &str) -> Result<Duration, String> {
+    let started = Instant::now();
+    let outcome = match isolation_type {
+        "container" => run_probe("docker", &["run", "--rm", "--network", "none", PROBE_IMAGE, "true"]).await,
+        "subprocess" => {
+            #[cfg(windows)]
+            let outcome = run_probe("cmd", &["/C", "exit 0"]).await;
+            #[cfg(not(windows))]
+            let outcome = run_probe("true", &[]).await;
+            outcome
+        }
+        "none" => return Ok(Duration::ZERO),
+        other => return Err(format!("Unknown isolation type '{}'", other)),
+    };
+    let elapsed = started.elapsed();
+
+    match outcome {
+        ProbeOutcome::Ok => Ok(elapsed),
+        ProbeOutcome::Failed(reason) => Err(format!("No-op {} startup failed: {}", isolation_type, reason)),
+        ProbeOutcome::Missing => Err(format!("No backend installed for {} isolation", isolation_type)),
+        ProbeOutcome::TimedOut => Err(format!("No-op {} startup timed out", isolation_type)),
+    }
+}
+
+/// Start an idle, network-less container and return its id
+pub async fn start_warm_container() -> Result<String, String> {
+    let output = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        Command::new("docker")
+            .args(["run", "-d", "--rm", "--network", "none", PROBE_IMAGE, "sleep", WARM_SANDBOX_TTL_SECS])
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| "Warm container start timed out".to_string())?
+    .map_err(|e| format!("Failed to start warm container: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("Failed to start warm container: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Hit/miss counters and idle sandboxes for one isolation type
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct WarmPoolStats {
+    pub isolation_type: String,
+    pub available: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Pre-warmed sandboxes that `keep_warm` skills draw from before paying a cold start
+#[derive(Default)]
+pub struct WarmPool {
+    sandboxes: HashMap<String, Vec<String>>,
+    stats: HashMap<String, WarmPoolStats>,
+}
+
+impl WarmPool {
+    /// Add an idle sandbox to the pool
+    pub fn add(&mut self, isolation_type: &str, sandbox_id: String) {
+        self.sandboxes.entry(isolation_type.to_string()).or_default().push(sandbox_id);
+    }
+
+    /// Take a warm sandbox, or `None` when the caller must cold start
+    pub fn acquire(&mut self, isolation_type: &str) -> Option<String> {
+        let sandbox = self.sandboxes.get_mut(isolation_type).and_then(|idle| idle.pop());
+        let stats = self.stats.entry(isolation_type.to_string()).or_insert_with(|| WarmPoolStats {
+            isolation_type: isolation_type.to_string(),
+            ..Default::default()
+        });
+        if sandbox.is_some() {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+        sandbox
+    }
+
+    pub fn stats(&self) -> Vec<WarmPoolStats> {
+        let mut types: Vec<&String> = self.sandboxes.keys().chain(self.stats.keys()).collect();
+        types.sort();
+        types.dedup();
+        types
+            .into_iter()
+            .map(|isolation_type| {
+                let mut stats = self.stats.get(isolation_type).cloned().unwrap_or_else(|| WarmPoolStats {
+                    isolation_type: isolation_type.clone(),
+                    ..Default::default()
+                });
+                stats.available = self.sandboxes.get(isolation_type).map(|idle| idle.len()).unwrap_or(0);
+                stats
+            })
+            .collect()
+    }
+}
+
+/// Process-wide warm sandbox pool
+pub fn warm_pool() -> &'static Mutex<WarmPool> {
+    static POOL: OnceLock<Mutex<WarmPool>> = OnceLock::new();
+    POOL.get_or_init(|| Mutex::new(WarmPool::default()))
+}
@@ -0,0 +1,222 @@
+//! Developer Log Module
+//!
+//! Writes the developer log with sensitive fields redacted, and reads and
+//! follows it so it can be inspected from the GUI.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Protocol version constant for log responses
+const LOGS_PROTOCOL_VERSION: &str = "1.0";
+
+/// Folder under the data root holding the developer log
+pub const LOG_DIR: &str = "logs";
+
+/// Log files rotate daily as `synapse.<YYYY-MM-DD>.log`
+pub const LOG_FILE_PREFIX: &str = "synapse";
+pub const LOG_FILE_SUFFIX: &str = "log";
+
+/// Single log file written before daily rotation
+pub const LEGACY_LOG_FILE: &str = "synapse.log";
+
+/// Log level used when the config doesn't set one
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// Fields whose values are never written to a log
+pub const SENSITIVE_FIELDS: &[&str] = &["api_key", "auth_token", "token", "password", "secret", "signing_key"];
+
+/// Upper bound on lines returned by a single tail request
+pub const MAX_TAIL_LINES: usize = 5000;
+
+/// How often a subscription checks the log for new lines
+pub const FOLLOW_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Log levels from least to most severe
+pub const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+/// A log line delivered to the frontend
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LogLine {
+    pub level: Option<String>,
+    pub line: String,
+    pub protocol_version: String,
+}
+
+impl LogLine {
+    pub fn new(line: String) -> Self {
+        Self {
+            level: line_level(&line).map(str::to_string),
+            line,
+            protocol_version: LOGS_PROTOCOL_VERSION.to_string(),
+        }
+    }
+}
+
+/// Severity rank of a level name, accepting `warning` for `warn`
+pub fn level_rank(level: &str) -> Option<usize> {
+    let level = level.trim().to_lowercase();
+    let level = if level == "warning" { "warn".to_string() } else { level };
+    LOG_LEVELS.iter().position(|l| *l == level)
+}
+
+/// Level of a log line, taken from the first word that names one
+pub fn line_level(line: &str) -> Option<&'static str> {
+    line.split(|c: char| !c.is_ascii_alphabetic())
+        .find_map(|word| level_rank(word).filter(|_| word.chars().all(|c| c.is_ascii_uppercase())))
+        .map(|rank| LOG_LEVELS[rank])
+}
+
+/// Whether a line passes a minimum-level filter; unleveled lines always pass
+pub fn passes_level(line: &str, min_level: Option<&str>) -> bool {
+    match (min_level.and_then(level_rank), line_level(line).and_then(level_rank)) {
+        (Some(min), Some(rank)) => rank >= min,
+        _ => true,
+    }
+}
+
+/// The log file being written in `dir`: the newest rotated file, or the
+/// legacy single file when nothing has rotated yet
+pub fn current_log_file(dir: &Path) -> PathBuf {
+    let (prefix, suffix) = (format!("{}.", LOG_FILE_PREFIX), format!(".{}", LOG_FILE_SUFFIX));
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name != LEGACY_LOG_FILE && name.starts_with(&prefix) && name.ends_with(&suffix))
+        .max()
+        .map_or_else(|| dir.join(LEGACY_LOG_FILE), |name| dir.join(name))
+}
+
+/// Field formatter for log output. Sensitive fields are written as
+/// `[REDACTED]` and anything shaped like a provider key is masked in the
+/// remaining values, messages included.
+pub fn redacting_fields() -> impl for<'writer> tracing_subscriber::fmt::FormatFields<'writer> + Send + Sync + 'static {
+    use tracing_subscriber::field::MakeExt;
+
+    tracing_subscriber::fmt::format::debug_fn(|writer, field, value| {
+        let name = field.name();
+        if SENSITIVE_FIELDS.contains(&name) {
+            return write!(writer, "{}=[REDACTED]", name);
+        }
+        let text = crate::security::redact_secrets(&format!("{:?}", value), &[]);
+        if name == "message" {
+            write!(writer, "{}", text)
+        } else {
+            write!(writer, "{}={}", name, text)
+        }
+    })
+    .delimited(" ")
+}
+
+/// Last `lines` lines of the log at `path`
+pub fn read_log_tail(path: &Path, lines: usize) -> Result<Vec<String>, String> {
+    let raw = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let text = String::from_utf8_lossy(&raw);
+    let all: Vec<&str> = text.lines().collect();
+    let start = all.len().saturating_sub(lines.min(MAX_TAIL_LINES));
+    Ok(all[start..].iter().map(|l| l.to_string()).collect())
+}
+
+/// Identity of a log file, so a rename-and-recreate rotation is noticed
+fn file_identity(metadata: &fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Incremental reader that keeps following a log across rotation.
+///
+/// When the file is replaced or truncated, reading restarts at the top of
+/// the new file instead of waiting on the old offset.
+pub struct LogFollower {
+    path: PathBuf,
+    offset: u64,
+    identity: Option<u64>,
+    partial: String,
+}
+
+impl LogFollower {
+    /// Start following at the current end of the file
+    pub fn new(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        Self {
+            path: path.to_path_buf(),
+            offset: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+            identity: metadata.as_ref().and_then(file_identity),
+            partial: String::new(),
+        }
+    }
+
+    /// Follow a freshly rotated file from its first line
+    pub fn from_start(path: &Path) -> Self {
+        Self {
+            offset: 0,
+            ..Self::new(path)
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Complete lines written since the last poll
+    pub fn poll(&mut self) -> Vec<String> {
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(_) => return Vec::new(),
+        };
+        let identity = file_identity(&metadata);
+        if identity != self.identity || metadata.len() < self.offset {
+            self.identity = identity;
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if metadata.len() == self.offset {
+            return Vec::new();
+        }
+
+        let mut buffer = Vec::new();
+        let read = fs::File::open(&self.path).and_then(|mut file| {
+            file.seek(SeekFrom::Start(self.offset))?;
+            file.read_to_end(&mut buffer)
+        });
+        if read.is_err() {
+            return Vec::new();
+        }
+        self.offset += buffer.len() as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&buffer));
+
+        let mut lines: Vec<String> = self.partial.split('\n').map(|l| l.trim_end_matches('\r').to_string()).collect();
+        self.partial = lines.pop().unwrap_or_default();
+        lines
+    }
+}
+
+// ============================================================================
+// Subscriptions
+// ============================================================================
+
+/// Ids of live `subscribe_log` followers
+pub fn subscriptions() -> &'static Mutex<HashSet<String>> {
+    static SUBSCRIPTIONS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    SUBSCRIPTIONS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Whether a follower should keep running
+pub fn is_subscribed(id: &str) -> bool {
+    subscriptions().lock().unwrap().contains(id)
+}
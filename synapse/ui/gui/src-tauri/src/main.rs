@@ -14,6 +14,54 @@ mod wizard;
 mod skills;
 mod metrics;
 mod security;
+mod isolation;
+mod migration;
+mod audit;
+mod providers;
+mod logs;
+mod i18n;
+mod webhooks;
+mod state;
+
+#[cfg(test)]
+#[path = "__tests__/commands_test.rs"]
+mod commands_test;
+#[cfg(test)]
+#[path = "__tests__/migration_test.rs"]
+mod migration_test;
+#[cfg(test)]
+#[path = "__tests__/audit_test.rs"]
+mod audit_test;
+#[cfg(test)]
+#[path = "__tests__/providers_test.rs"]
+mod providers_test;
+#[cfg(test)]
+#[path = "__tests__/security_test.rs"]
+mod security_test;
+#[cfg(test)]
+#[path = "__tests__/skills_test.rs"]
+mod skills_test;
+#[cfg(test)]
+#[path = "__tests__/metrics_test.rs"]
+mod metrics_test;
+#[cfg(test)]
+#[path = "__tests__/logs_test.rs"]
+mod logs_test;
+#[cfg(test)]
+#[path = "__tests__/isolation_test.rs"]
+mod isolation_test;
+#[cfg(test)]
+#[path = "__tests__/wizard_test.rs"]
+mod wizard_test;
+#[cfg(test)]
+#[path = "__tests__/i18n_test.rs"]
+mod i18n_test;
+#[cfg(test)]
+#[path = "__tests__/webhooks_test.rs"]
+mod webhooks_test;
+#[cfg(test)]
+#[path = "__tests__/state_test.rs"]
+mod state_test;
 
 use tauri::Manager;
 
@@ -22,39 +70,170 @@ pub const PROTOCOL_VERSION: &str = "1.0";
 pub const SPEC_VERSION: &str = "3.1";
 
 fn main() {
+    let config = commands::load_config();
+    let _log_guard = commands::init_tracing(commands::log_dir().ok().as_deref(), &config.log_level);
+    let handler = tauri::generate_handler![
+        // Configuration commands
+        commands::get_config,
+        commands::save_config,
+        commands::clone_provider,
+        commands::diff_config,
+        commands::list_profiles,
+        commands::switch_profile,
+        commands::delete_profile,
+        commands::reset_config_to_defaults,
+        commands::list_config_backups,
+        commands::restore_config_backup,
+        commands::create_data_backup,
+        commands::restore_data_backup,
+        commands::check_data_paths,
+        commands::repair_data_paths,
+        commands::test_llm_connection,
+        commands::list_provider_models,
+        commands::validate_provider_model,
+        commands::validate_provider_config,
+        commands::get_model_capabilities,
+        commands::route_llm_request,
+        commands::get_provider_cooldowns,
+        commands::get_effective_provider,
+        commands::migrate_config,
+        
+        // Skill management commands
+        commands::get_skills,
+        commands::search_skills,
+        commands::get_skill_details,
+        commands::validate_skill_schema,
+        commands::recompute_skill_risk,
+        commands::override_skill_risk,
+        commands::plan_skill_execution,
+        commands::dry_run_skill,
+        commands::approve_skill,
+        commands::reject_skill,
+        commands::bulk_approve_skills,
+        commands::bulk_reject_skills,
+        commands::archive_skill,
+        commands::unarchive_skill,
+        commands::get_skill_versions,
+        commands::rollback_skill,
+        commands::set_skill_enabled,
+        commands::add_skill_tag,
+        commands::remove_skill_tag,
+        commands::rename_skill,
+        commands::list_all_tags,
+        commands::get_executable_skills,
+        commands::check_skill_quota,
+        commands::start_skill_execution,
+        commands::finish_skill_execution,
+        commands::lint_skill_manifest,
+        commands::import_skill,
+        commands::export_skill,
+        commands::import_skill_json,
+        commands::verify_skill_signature,
+        commands::backup_skills,
+        commands::restore_skills,
+        commands::measure_skill_coldstart,
+        commands::warm_skill_sandboxes,
+        commands::get_warm_pool_stats,
+        
+        // Metrics commands
+        commands::get_system_metrics,
+        commands::health_check,
+        commands::get_metrics_history,
+        commands::export_metrics_snapshot,
+        commands::get_metrics_prometheus,
+        commands::start_metrics_stream,
+        commands::stop_metrics_stream,
+        commands::get_network_metrics,
+        commands::get_process_metrics,
+        commands::get_llm_usage,
+        commands::get_llm_usage_by_day,
+        commands::estimate_llm_cost,
+        commands::set_token_budget,
+        commands::reset_llm_usage,
+        commands::get_skill_metrics,
+        commands::get_skill_success_trend,
+        commands::compare_skill_metrics,
+        commands::capture_incident_snapshot,
+        commands::list_incident_snapshots,
+        
+        // Security commands
+        commands::get_capabilities,
+        commands::issue_capability_token,
+        commands::revoke_capability_token,
+        commands::check_capability_token,
+        commands::check_skill_capabilities,
+        commands::get_skill_effective_capabilities,
+        commands::reload_org_policy,
+        commands::validate_against_org_policy,
+        commands::get_audit_log,
+        commands::verify_audit_chain,
+        commands::rotate_audit_log,
+        commands::export_audit_log,
+        commands::subscribe_audit_log,
+        commands::unsubscribe_audit_log,
+        commands::get_audit_summary,
+        commands::get_audit_coalescing_status,
+        commands::set_audit_rate_limit,
+        commands::get_security_settings,
+        commands::update_security_settings,
+        commands::create_session,
+        commands::end_session,
+        commands::add_trusted_user,
+        commands::remove_trusted_user,
+        commands::check_isolation_backends,
+        commands::check_key_exposure,
+        
+        // Developer log commands
+        commands::get_log_path,
+        commands::read_log_tail,
+        commands::subscribe_log,
+        commands::unsubscribe_log,
+        
+        // Wizard commands
+        commands::get_wizard_steps,
+        commands::save_wizard_progress,
+        commands::get_wizard_status,
+        commands::wizard_next,
+        commands::wizard_back,
+        commands::get_supported_languages,
+        commands::get_wizard_models,
+        commands::validate_wizard_step,
+        commands::finalize_wizard,
+        commands::get_wizard_security_options,
+    ];
+
     tauri::Builder::default()
+        .manage(state::app_state())
         .setup(|app| {
             #[cfg(debug_assertions)]
             {
                 let window = app.get_window("main").unwrap();
                 window.open_devtools();
             }
+            let _ = commands::app_handle().set(app.handle());
+            let config = commands::load_config();
+            let data_dir = config.data_path("config").unwrap_or_default();
+            // Without a writable file the audit log stays in memory
+            let _ = audit::open_log(&data_dir.join(audit::AUDIT_LOG_FILE));
+            commands::apply_audit_rate_limit(state::app_state(), &config.security_settings);
+            audit::start_rotation_schedule(audit::ROTATION_CHECK_INTERVAL, || {
+                commands::load_config().security_settings.audit_retention()
+            });
+            metrics::start_sampler(data_dir, metrics::DEFAULT_SAMPLE_INTERVAL, metrics::DEFAULT_HISTORY_CAPACITY);
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            // Configuration commands
-            commands::get_config,
-            commands::save_config,
-            commands::test_llm_connection,
-            
-            // Skill management commands
-            commands::get_skills,
-            commands::get_skill_details,
-            commands::approve_skill,
-            commands::reject_skill,
-            commands::archive_skill,
-            
-            // Metrics commands
-            commands::get_system_metrics,
-            commands::get_llm_usage,
-            commands::get_skill_metrics,
-            
-            // Security commands
-            commands::get_capabilities,
-            commands::get_audit_log,
-            commands::get_security_settings,
-            commands::update_security_settings,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        // One span per invocation carrying the command name, caller and request id
+        .invoke_handler(move |invoke| {
+            let _span = commands::command_span(invoke.message.command(), invoke.message.payload()).entered();
+            handler(invoke)
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                metrics::stop_metrics_stream();
+                metrics::stop_sampler();
+                audit::stop_rotation_schedule();
+            }
+        });
 }
@@ -4,26 +4,67 @@
 //! Protocol Version: 1.0
 //! Spec Version: 3.1
 
+use chrono::{DateTime, Datelike, Duration, DurationRound, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sysinfo::{System, SystemExt, CpuExt, ProcessExt};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use sysinfo::{System, SystemExt, CpuExt, DiskExt, NetworkExt, NetworksExt, ProcessExt};
 
+use crate::commands::AuditLogEntry;
+use crate::providers::{PriceTable, ProviderReachability};
 use crate::{PROTOCOL_VERSION, SPEC_VERSION};
 
 /// Protocol version constant for metrics responses
 const METRICS_PROTOCOL_VERSION: &str = "1.0";
 
 /// System metrics structure
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct SystemMetrics {
+    /// Average across all cores
     pub cpu_percent: f32,
+    pub per_core_percent: Vec<f32>,
+    /// Logical cores; always `per_core_percent.len()`
+    pub core_count: usize,
+    /// `None` when the platform doesn't report physical cores
+    pub physical_core_count: Option<usize>,
     pub memory_percent: f32,
     pub memory_used_mb: u64,
     pub memory_total_mb: u64,
     pub disk_percent: f32,
+    /// Disk backing the data directory; `None` when no disk could be read
+    pub disk: Option<DiskUsage>,
     pub uptime_seconds: u64,
+    /// Empty when no GPU is detected or the `gpu` feature is off
+    #[serde(default)]
+    pub gpus: Vec<GpuMetrics>,
     pub protocol_version: String,
 }
 
+/// Load of one GPU, read through NVML on NVIDIA systems
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GpuMetrics {
+    pub name: String,
+    pub utilization_percent: f32,
+    pub memory_used_mb: u64,
+    pub memory_total_mb: u64,
+    /// Degrees Celsius; `None` when the GPU doesn't report it
+    pub temperature_celsius: Option<u32>,
+}
+
+/// Usage of the filesystem backing a path
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DiskUsage {
+    pub mount_point: String,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+    pub percent: f32,
+    /// The path's disk couldn't be determined, so the root filesystem is
+    /// reported instead
+    pub fallback: bool,
+}
+
 /// LLM usage statistics
 #[derive(Serialize, Deserialize)]
 pub struct LLMUsage {
@@ -42,45 +83,550 @@ pub struct SkillMetrics {
     pub success_count: u64,
     pub failure_count: u64,
     pub average_latency_ms: f64,
+    /// Latency percentiles over the recent sample window; `None` with fewer
+    /// than `MIN_PERCENTILE_SAMPLES` samples
+    pub p50_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub p99_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
     pub protocol_version: String,
 }
 
-/// Get system metrics
-pub fn get_system_metrics() -> SystemMetrics {
+/// Pick the disk whose mount point is the deepest ancestor of `path`,
+/// falling back to the root filesystem. `disks` holds
+/// `(mount_point, total_bytes, available_bytes)`.
+pub fn select_disk(path: &Path, disks: &[(PathBuf, u64, u64)]) -> Option<DiskUsage> {
+    // The data directory may not exist yet; resolve its nearest existing ancestor
+    let resolved = path
+        .ancestors()
+        .find_map(|ancestor| fs::canonicalize(ancestor).ok())
+        .unwrap_or_else(|| path.to_path_buf());
+    let backing = disks
+        .iter()
+        .filter(|(mount, _, _)| resolved.starts_with(mount))
+        .max_by_key(|(mount, _, _)| mount.components().count());
+    let (disk, fallback) = match backing {
+        Some(disk) => (disk, false),
+        None => (disks.iter().find(|(mount, _, _)| mount.parent().is_none())?, true),
+    };
+
+    let (mount, total, available) = disk;
+    let used = total.saturating_sub(*available);
+    Some(DiskUsage {
+        mount_point: mount.to_string_lossy().to_string(),
+        used_bytes: used,
+        total_bytes: *total,
+        percent: if *total == 0 { 0.0 } else { (used as f64 / *total as f64 * 100.0) as f32 },
+        fallback,
+    })
+}
+
+/// Usage of the disk holding `path`
+pub fn disk_usage(sys: &System, path: &Path) -> Option<DiskUsage> {
+    let disks: Vec<(PathBuf, u64, u64)> = sys
+        .disks()
+        .iter()
+        .map(|disk| (disk.mount_point().to_path_buf(), disk.total_space(), disk.available_space()))
+        .collect();
+    select_disk(path, &disks)
+}
+
+/// GPUs visible through NVML. A missing driver library or GPU yields an
+/// empty list, as does a GPU whose memory can't be read.
+#[cfg(feature = "gpu")]
+pub fn gpu_metrics() -> Vec<GpuMetrics> {
+    use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+    use nvml_wrapper::Nvml;
+
+    // Loading NVML is slow, so it is tried once and the outcome kept
+    static NVML: OnceLock<Option<Nvml>> = OnceLock::new();
+    let Some(nvml) = NVML.get_or_init(|| Nvml::init().ok()) else {
+        return Vec::new();
+    };
+    (0..nvml.device_count().unwrap_or(0))
+        .filter_map(|index| nvml.device_by_index(index).ok())
+        .filter_map(|device| {
+            let memory = device.memory_info().ok()?;
+            Some(GpuMetrics {
+                name: device.name().unwrap_or_else(|_| "Unknown GPU".to_string()),
+                utilization_percent: device.utilization_rates().map(|rates| rates.gpu as f32).unwrap_or(0.0),
+                memory_used_mb: memory.used / 1024 / 1024,
+                memory_total_mb: memory.total / 1024 / 1024,
+                temperature_celsius: device.temperature(TemperatureSensor::Gpu).ok(),
+            })
+        })
+        .collect()
+}
+
+/// GPU metrics need the `gpu` feature; without it none are reported
+#[cfg(not(feature = "gpu"))]
+pub fn gpu_metrics() -> Vec<GpuMetrics> {
+    Vec::new()
+}
+
+/// Get system metrics; disk usage is for the filesystem holding `data_dir`
+pub fn get_system_metrics(data_dir: &Path) -> SystemMetrics {
     let mut sys = System::new_all();
     sys.refresh_all();
+    // CPU usage is measured between two refreshes; a single read is all zeros
+    std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_cpu();
+    let disk = disk_usage(&sys, data_dir);
     
     let cpu_percent = sys.global_cpu_info().cpu_usage();
+    let per_core_percent: Vec<f32> = sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
     let total_memory = sys.total_memory();
     let used_memory = sys.used_memory();
     let memory_percent = (used_memory as f64 / total_memory as f64 * 100.0) as f32;
     
     SystemMetrics {
         cpu_percent,
+        core_count: per_core_percent.len(),
+        per_core_percent,
+        physical_core_count: sys.physical_core_count(),
         memory_percent,
         memory_used_mb: used_memory / 1024 / 1024,
         memory_total_mb: total_memory / 1024 / 1024,
-        disk_percent: 35.0, // Placeholder
+        disk_percent: disk.as_ref().map(|d| d.percent).unwrap_or(0.0),
+        disk,
         uptime_seconds: sys.uptime(),
+        gpus: gpu_metrics(),
         protocol_version: METRICS_PROTOCOL_VERSION.to_string(),
     }
 }
 
-/// Get LLM usage statistics
-pub fn get_llm_usage_stats() -> LLMUsage {
-    // In real implementation, query from database
+/// Get LLM usage statistics priced with `prices`
+pub fn get_llm_usage_stats(prices: &PriceTable) -> LLMUsage {
+    let records = crate::state::app_state().usage_ledger().lock().unwrap().records(None, None);
+    let breakdown = usage_breakdown(&records, prices);
     LLMUsage {
-        total_tokens: 100000,
-        prompt_tokens: 60000,
-        completion_tokens: 40000,
-        estimated_cost_usd: 1.25,
+        total_tokens: breakdown.prompt_tokens + breakdown.completion_tokens,
+        prompt_tokens: breakdown.prompt_tokens,
+        completion_tokens: breakdown.completion_tokens,
+        estimated_cost_usd: breakdown.estimated_cost_usd,
         protocol_version: METRICS_PROTOCOL_VERSION.to_string(),
     }
 }
 
+// ============================================================================
+// Snapshots
+// ============================================================================
+
+/// Every metrics section at one moment, as a single document for offline
+/// analysis. Metrics carry no secrets; the hostname is the only identifying
+/// value and is left out unless asked for.
+#[derive(Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub captured_at: DateTime<Utc>,
+    pub hostname: Option<String>,
+    pub system: SystemMetrics,
+    pub llm_usage: LLMUsage,
+    pub skills: Vec<SkillMetrics>,
+    /// Sampled system metrics, oldest first; empty when nothing was sampled
+    pub history: Vec<MetricsSample>,
+    /// Incident snapshots attached to the export
+    #[serde(default)]
+    pub incidents: Vec<IncidentSnapshot>,
+    pub protocol_version: String,
+}
+
+/// This machine's hostname, when the platform reports one
+pub fn hostname() -> Option<String> {
+    System::new().host_name()
+}
+
+/// Write `snapshot` as pretty-printed JSON, creating parent folders
+pub fn write_snapshot(path: &Path, snapshot: &MetricsSnapshot) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let raw = serde_json::to_string_pretty(snapshot).map_err(|e| e.to_string())?;
+    fs::write(path, raw).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// ============================================================================
+// Health Checks
+// ============================================================================
+
+/// Free disk share below which the disk check degrades
+pub const MIN_DISK_HEADROOM_PERCENT: f32 = 10.0;
+
+/// One subsystem's health: "ok", "degraded" or "failed"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub status: String,
+    pub reason: String,
+    /// A failure here makes the whole app unhealthy rather than degraded
+    pub critical: bool,
+}
+
+impl SubsystemHealth {
+    fn new(name: &str, status: &str, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: status.to_string(),
+            reason: reason.into(),
+            critical: false,
+        }
+    }
+
+    pub fn ok(name: &str, reason: impl Into<String>) -> Self {
+        Self::new(name, "ok", reason)
+    }
+
+    pub fn degraded(name: &str, reason: impl Into<String>) -> Self {
+        Self::new(name, "degraded", reason)
+    }
+
+    pub fn failed(name: &str, reason: impl Into<String>) -> Self {
+        Self::new(name, "failed", reason)
+    }
+
+    pub fn critical(mut self) -> Self {
+        self.critical = true;
+        self
+    }
+}
+
+/// Overall health: "healthy", "degraded", or "unhealthy" when a critical
+/// subsystem failed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HealthReport {
+    pub status: String,
+    pub subsystems: Vec<SubsystemHealth>,
+    pub checked_at: DateTime<Utc>,
+    pub protocol_version: String,
+}
+
+pub fn summarize_health(subsystems: Vec<SubsystemHealth>, now: DateTime<Utc>) -> HealthReport {
+    let status = if subsystems.iter().any(|s| s.critical && s.status == "failed") {
+        "unhealthy"
+    } else if subsystems.iter().any(|s| s.status != "ok") {
+        "degraded"
+    } else {
+        "healthy"
+    };
+    HealthReport {
+        status: status.to_string(),
+        subsystems,
+        checked_at: now,
+        protocol_version: METRICS_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+/// Check every data directory (key, expanded path) can be written to
+pub fn check_data_dirs(dirs: &[(String, PathBuf)]) -> SubsystemHealth {
+    let unwritable: Vec<String> = dirs
+        .iter()
+        .filter(|(_, path)| !crate::wizard::is_writable(path))
+        .map(|(key, path)| format!("{} ({})", key, path.display()))
+        .collect();
+    if unwritable.is_empty() {
+        SubsystemHealth::ok("data_dirs", format!("{} directories writable", dirs.len()))
+    } else {
+        SubsystemHealth::failed("data_dirs", format!("Not writable: {}", unwritable.join(", ")))
+    }
+}
+
+/// Check a log file can be appended to; an in-memory log is degraded
+pub fn check_appendable(name: &str, path: Option<&Path>) -> SubsystemHealth {
+    let Some(path) = path else {
+        return SubsystemHealth::degraded(name, "Not backed by a file; entries are lost on exit");
+    };
+    match fs::OpenOptions::new().create(true).append(true).open(path) {
+        Ok(_) => SubsystemHealth::ok(name, format!("{} is writable", path.display())),
+        Err(e) => SubsystemHealth::failed(name, format!("{} is not writable: {}", path.display(), e)),
+    }
+}
+
+/// Degrade when less than `min_free_percent` of the data disk is free
+pub fn check_disk_headroom(disk: Option<&DiskUsage>, min_free_percent: f32) -> SubsystemHealth {
+    let Some(disk) = disk else {
+        return SubsystemHealth::degraded("disk", "Disk usage could not be read");
+    };
+    let free_percent = 100.0 - disk.percent;
+    let reason = format!("{:.1}% free on {}", free_percent, disk.mount_point);
+    if disk.used_bytes >= disk.total_bytes {
+        SubsystemHealth::failed("disk", reason)
+    } else if free_percent < min_free_percent {
+        SubsystemHealth::degraded("disk", format!("{} (below {}%)", reason, min_free_percent))
+    } else {
+        SubsystemHealth::ok("disk", reason)
+    }
+}
+
+// ============================================================================
+// LLM Usage Ledger
+// ============================================================================
+
+/// Tokens used by one LLM request
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UsageRecord {
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// Usage and estimated cost of one model
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ModelUsage {
+    pub provider: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub estimated_cost_usd: f64,
+    /// False when the model has no known price and its cost counts as 0
+    pub has_price: bool,
+}
+
+/// Usage totals with per-provider and per-model breakdowns
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UsageBreakdown {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub estimated_cost_usd: f64,
+    /// Total tokens per provider
+    pub providers: HashMap<String, u64>,
+    pub models: HashMap<String, ModelUsage>,
+}
+
+/// Sum usage per model, pricing each model's input and output tokens
+/// separately. Models without a known price cost 0.
+pub fn usage_breakdown(records: &[UsageRecord], prices: &PriceTable) -> UsageBreakdown {
+    let mut breakdown = UsageBreakdown::default();
+    for record in records {
+        let model = breakdown.models.entry(record.model.clone()).or_insert_with(|| ModelUsage {
+            provider: record.provider.clone(),
+            ..Default::default()
+        });
+        model.prompt_tokens += record.prompt_tokens;
+        model.completion_tokens += record.completion_tokens;
+        model.total_tokens += record.prompt_tokens + record.completion_tokens;
+        *breakdown.providers.entry(record.provider.clone()).or_default() += record.prompt_tokens + record.completion_tokens;
+        breakdown.prompt_tokens += record.prompt_tokens;
+        breakdown.completion_tokens += record.completion_tokens;
+    }
+    for (name, model) in breakdown.models.iter_mut() {
+        let cost = prices.cost(&model.provider, name, model.prompt_tokens, model.completion_tokens);
+        model.estimated_cost_usd = cost.cost_usd;
+        model.has_price = cost.has_price;
+        breakdown.estimated_cost_usd += model.estimated_cost_usd;
+    }
+    breakdown
+}
+
+/// Longest date range `usage_by_day` answers, in days
+pub const MAX_USAGE_RANGE_DAYS: i64 = 366;
+
+/// Token and cost totals for one UTC day
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub total_tokens: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub estimated_cost_usd: f64,
+}
+
+/// Usage per UTC day from `start` to `end` inclusive. Days without usage
+/// are listed with zeros so the result has one entry per day.
+pub fn usage_by_day(records: &[UsageRecord], prices: &PriceTable, start: NaiveDate, end: NaiveDate) -> Result<Vec<DailyUsage>, String> {
+    if end < start {
+        return Err(format!("End date {} is before start date {}", end, start));
+    }
+    let days = (end - start).num_days() + 1;
+    if days > MAX_USAGE_RANGE_DAYS {
+        return Err(format!("Range covers {} days; at most {} are allowed", days, MAX_USAGE_RANGE_DAYS));
+    }
+    let mut by_day: HashMap<NaiveDate, Vec<UsageRecord>> = HashMap::new();
+    for record in records {
+        by_day.entry(record.timestamp.date_naive()).or_default().push(record.clone());
+    }
+    Ok(start
+        .iter_days()
+        .take(days as usize)
+        .map(|date| {
+            let usage = usage_breakdown(by_day.get(&date).map(Vec::as_slice).unwrap_or_default(), prices);
+            DailyUsage {
+                date,
+                total_tokens: usage.prompt_tokens + usage.completion_tokens,
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                estimated_cost_usd: usage.estimated_cost_usd,
+            }
+        })
+        .collect())
+}
+
+/// Per-request LLM usage
+#[derive(Default)]
+pub struct UsageLedger {
+    records: Vec<UsageRecord>,
+    last_reset_at: Option<DateTime<Utc>>,
+}
+
+impl UsageLedger {
+    pub fn record(&mut self, record: UsageRecord) {
+        self.records.push(record);
+    }
+
+    /// Clear the records of one provider, or all when `provider` is `None`,
+    /// returning what was removed
+    pub fn reset(&mut self, provider: Option<&str>, now: DateTime<Utc>) -> Vec<UsageRecord> {
+        let (removed, kept) = std::mem::take(&mut self.records)
+            .into_iter()
+            .partition(|r| provider.map_or(true, |p| r.provider == p));
+        self.records = kept;
+        self.last_reset_at = Some(now);
+        removed
+    }
+
+    pub fn last_reset_at(&self) -> Option<DateTime<Utc>> {
+        self.last_reset_at
+    }
+
+    /// Records within `[since, until)`; either bound may be open
+    pub fn records(&self, since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>) -> Vec<UsageRecord> {
+        self.records
+            .iter()
+            .filter(|r| since.map_or(true, |since| r.timestamp >= since) && until.map_or(true, |until| r.timestamp < until))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Usage recorded before persistent storage exists
+pub fn sample_usage() -> UsageLedger {
+    let now = Utc::now();
+    let mut ledger = UsageLedger::default();
+    for (provider, model, prompt_tokens, completion_tokens) in [
+        ("openai", "gpt-4o", 30000, 20000),
+        ("openai", "gpt-3.5-turbo", 15000, 10000),
+        ("anthropic", "claude-3.5-sonnet", 15000, 10000),
+    ] {
+        ledger.record(UsageRecord {
+            timestamp: now - Duration::hours(1),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_tokens,
+            completion_tokens,
+        });
+    }
+    ledger
+}
+
+// ============================================================================
+// Token Budgets
+// ============================================================================
+
+/// Percent-of-budget levels that raise a `token-budget-alert` event
+pub const BUDGET_ALERT_THRESHOLDS: [u8; 2] = [80, 100];
+
+/// Spend against one monthly budget
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BudgetStatus {
+    /// `None` for the budget covering all providers
+    pub provider: Option<String>,
+    pub monthly_limit_usd: f64,
+    pub spent_usd: f64,
+    pub used_percent: f64,
+    pub exceeded: bool,
+}
+
+/// Payload of the `token-budget-alert` event
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BudgetAlert {
+    pub provider: Option<String>,
+    pub threshold_percent: u8,
+    pub status: BudgetStatus,
+}
+
+/// Midnight UTC on the first day of `now`'s month
+pub fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive()
+        .with_day(1)
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|start| start.and_utc())
+        .unwrap_or(now)
+}
+
+/// Monthly spend limits, overall or per provider, and the alert thresholds
+/// already crossed this month
+#[derive(Default)]
+pub struct BudgetTracker {
+    limits: BTreeMap<Option<String>, f64>,
+    alerted: HashMap<Option<String>, (DateTime<Utc>, u8)>,
+}
+
+impl BudgetTracker {
+    pub fn set_budget(&mut self, provider: Option<String>, monthly_limit_usd: f64) -> Result<(), String> {
+        if monthly_limit_usd.is_nan() || monthly_limit_usd <= 0.0 {
+            return Err(format!("Monthly limit must be positive, got {}", monthly_limit_usd));
+        }
+        self.alerted.remove(&provider);
+        self.limits.insert(provider, monthly_limit_usd);
+        Ok(())
+    }
+
+    /// Status of every budget; `records` should cover the current month only
+    pub fn statuses(&self, records: &[UsageRecord], prices: &PriceTable) -> Vec<BudgetStatus> {
+        self.limits
+            .iter()
+            .map(|(provider, &limit)| {
+                let relevant: Vec<UsageRecord> = records
+                    .iter()
+                    .filter(|r| provider.as_ref().map_or(true, |p| &r.provider == p))
+                    .cloned()
+                    .collect();
+                let spent_usd = usage_breakdown(&relevant, prices).estimated_cost_usd;
+                let used_percent = spent_usd / limit * 100.0;
+                BudgetStatus {
+                    provider: provider.clone(),
+                    monthly_limit_usd: limit,
+                    spent_usd,
+                    used_percent,
+                    exceeded: spent_usd >= limit,
+                }
+            })
+            .collect()
+    }
+
+    /// Alerts for thresholds crossed since the last check. Each threshold
+    /// fires once per budget per calendar month.
+    pub fn check_alerts(&mut self, statuses: &[BudgetStatus], now: DateTime<Utc>) -> Vec<BudgetAlert> {
+        let month = month_start(now);
+        let mut alerts = Vec::new();
+        for status in statuses {
+            let highest = match self.alerted.get(&status.provider) {
+                Some(&(alerted_month, threshold)) if alerted_month == month => threshold,
+                _ => 0,
+            };
+            let crossed = BUDGET_ALERT_THRESHOLDS
+                .iter()
+                .copied()
+                .filter(|&t| t > highest && status.used_percent >= t as f64)
+                .max();
+            if let Some(threshold) = crossed {
+                self.alerted.insert(status.provider.clone(), (month, threshold));
+                alerts.push(BudgetAlert {
+                    provider: status.provider.clone(),
+                    threshold_percent: threshold,
+                    status: status.clone(),
+                });
+            }
+        }
+        alerts
+    }
+}
+
 /// Get skill execution metrics
 pub fn get_skill_execution_metrics(skill_id: Option<&str>) -> Vec<SkillMetrics> {
     // In real implementation, query from database
+    let history = crate::state::app_state().execution_history().lock().unwrap();
+    let latency = history.latency_summary("skill-001");
     vec![
         SkillMetrics {
             skill_id: "skill-001".to_string(),
@@ -88,7 +634,849 @@ pub fn get_skill_execution_metrics(skill_id: Option<&str>) -> Vec<SkillMetrics>
             success_count: 95,
             failure_count: 5,
             average_latency_ms: 45.5,
+            p50_latency_ms: latency.p50_latency_ms,
+            p95_latency_ms: latency.p95_latency_ms,
+            p99_latency_ms: latency.p99_latency_ms,
+            max_latency_ms: latency.max_latency_ms,
             protocol_version: METRICS_PROTOCOL_VERSION.to_string(),
         },
     ]
 }
+
+/// Traffic through one network interface
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InterfaceMetrics {
+    pub name: String,
+    pub bytes_received: u64,
+    pub bytes_transmitted: u64,
+}
+
+/// Network traffic. Counts are cumulative totals reported by the OS
+/// (typically since boot), not rates; diff two readings for throughput.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NetworkMetrics {
+    pub bytes_received: u64,
+    pub bytes_transmitted: u64,
+    pub interfaces: Vec<InterfaceMetrics>,
+    pub protocol_version: String,
+}
+
+/// Whether an interface name is a loopback device on any supported OS
+pub fn is_loopback_interface(name: &str) -> bool {
+    name == "lo" || name.starts_with("lo0") || name.to_lowercase().contains("loopback")
+}
+
+/// Sum per-interface traffic, dropping loopback unless asked to keep it
+pub fn summarize_network(mut interfaces: Vec<InterfaceMetrics>, include_loopback: bool) -> NetworkMetrics {
+    interfaces.retain(|i| include_loopback || !is_loopback_interface(&i.name));
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    NetworkMetrics {
+        bytes_received: interfaces.iter().map(|i| i.bytes_received).sum(),
+        bytes_transmitted: interfaces.iter().map(|i| i.bytes_transmitted).sum(),
+        interfaces,
+        protocol_version: METRICS_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+/// Get cumulative network traffic per interface
+pub fn get_network_metrics(include_loopback: bool) -> NetworkMetrics {
+    let mut sys = System::new();
+    sys.refresh_networks_list();
+    let interfaces = sys
+        .networks()
+        .iter()
+        .map(|(name, data)| InterfaceMetrics {
+            name: name.clone(),
+            bytes_received: data.total_received(),
+            bytes_transmitted: data.total_transmitted(),
+        })
+        .collect();
+    summarize_network(interfaces, include_loopback)
+}
+
+/// Resource usage of a process, by default the Synapse process itself
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessMetrics {
+    pub pid: u32,
+    pub cpu_percent: f32,
+    pub memory_mb: u64,
+    pub resident_memory_bytes: u64,
+    pub virtual_memory_bytes: u64,
+    /// Open file descriptors; `None` where the platform doesn't expose them
+    pub open_files: Option<usize>,
+    pub run_time_seconds: u64,
+    pub protocol_version: String,
+}
+
+/// Open descriptors of a process, read from `/proc` on Linux
+fn open_file_count(pid: u32) -> Option<usize> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    fs::read_dir(format!("/proc/{}/fd", pid)).ok().map(|entries| entries.count())
+}
+
+/// Get metrics for process `pid`, or the running Synapse process
+pub fn get_process_metrics(pid: Option<u32>) -> Result<ProcessMetrics, String> {
+    let raw_pid = pid.unwrap_or_else(std::process::id);
+    let pid = sysinfo::Pid::from(raw_pid as usize);
+    let mut sys = System::new();
+    if !sys.refresh_process(pid) {
+        return Err(format!("No process with pid {}", raw_pid));
+    }
+    // CPU usage is measured between two refreshes
+    std::thread::sleep(System::MINIMUM_CPU_UPDATE_INTERVAL);
+    sys.refresh_process(pid);
+    let process = sys.process(pid).ok_or_else(|| format!("No process with pid {}", raw_pid))?;
+
+    Ok(ProcessMetrics {
+        pid: raw_pid,
+        cpu_percent: process.cpu_usage(),
+        memory_mb: process.memory() / 1024 / 1024,
+        resident_memory_bytes: process.memory(),
+        virtual_memory_bytes: process.virtual_memory(),
+        open_files: open_file_count(raw_pid),
+        run_time_seconds: process.run_time(),
+        protocol_version: METRICS_PROTOCOL_VERSION.to_string(),
+    })
+}
+
+// ============================================================================
+// Prometheus Exposition
+// ============================================================================
+
+/// Escape a label value for the Prometheus text format
+fn prometheus_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Appends metric families in Prometheus text exposition format
+#[derive(Default)]
+struct PrometheusWriter {
+    out: String,
+}
+
+impl PrometheusWriter {
+    /// Start a metric family; `kind` is "gauge" or "counter"
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        self.out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, prometheus_label(value)))
+                .collect();
+            self.out.push_str(&format!("{{{}}}", labels.join(",")));
+        }
+        self.out.push_str(&format!(" {}\n", value));
+    }
+}
+
+/// Render metrics in Prometheus text exposition format. Token, cost and
+/// execution totals are counters; point-in-time readings are gauges.
+pub fn render_prometheus(
+    system: &SystemMetrics,
+    usage: &LLMUsage,
+    skills: &[SkillMetrics],
+    skill_name: &dyn Fn(&str) -> String,
+) -> String {
+    let mut w = PrometheusWriter::default();
+
+    w.family("synapse_cpu_usage_percent", "gauge", "Average CPU usage across all cores.");
+    w.sample("synapse_cpu_usage_percent", &[], system.cpu_percent as f64);
+    w.family("synapse_cpu_core_usage_percent", "gauge", "CPU usage per logical core.");
+    for (core, percent) in system.per_core_percent.iter().enumerate() {
+        w.sample("synapse_cpu_core_usage_percent", &[("core", &core.to_string())], *percent as f64);
+    }
+    w.family("synapse_memory_used_bytes", "gauge", "Memory in use.");
+    w.sample("synapse_memory_used_bytes", &[], (system.memory_used_mb * 1024 * 1024) as f64);
+    w.family("synapse_memory_total_bytes", "gauge", "Total memory.");
+    w.sample("synapse_memory_total_bytes", &[], (system.memory_total_mb * 1024 * 1024) as f64);
+    w.family("synapse_disk_usage_percent", "gauge", "Usage of the disk holding the data directory.");
+    w.sample("synapse_disk_usage_percent", &[], system.disk_percent as f64);
+    w.family("synapse_uptime_seconds", "gauge", "System uptime.");
+    w.sample("synapse_uptime_seconds", &[], system.uptime_seconds as f64);
+
+    w.family("synapse_llm_tokens_total", "counter", "LLM tokens used.");
+    w.sample("synapse_llm_tokens_total", &[("type", "prompt")], usage.prompt_tokens as f64);
+    w.sample("synapse_llm_tokens_total", &[("type", "completion")], usage.completion_tokens as f64);
+    w.family("synapse_llm_cost_usd_total", "counter", "Estimated LLM spend in US dollars.");
+    w.sample("synapse_llm_cost_usd_total", &[], usage.estimated_cost_usd);
+
+    let labelled: Vec<(String, &SkillMetrics)> = skills.iter().map(|m| (skill_name(&m.skill_id), m)).collect();
+    let families: [(&str, &str, &str, fn(&SkillMetrics) -> f64); 4] = [
+        ("synapse_skill_executions_total", "counter", "Skill executions.", |m| m.execution_count as f64),
+        ("synapse_skill_successes_total", "counter", "Successful skill executions.", |m| m.success_count as f64),
+        ("synapse_skill_failures_total", "counter", "Failed skill executions.", |m| m.failure_count as f64),
+        ("synapse_skill_average_latency_milliseconds", "gauge", "Average skill execution latency.", |m| m.average_latency_ms),
+    ];
+    for (name, kind, help, value) in families {
+        w.family(name, kind, help);
+        for (skill, metrics) in &labelled {
+            w.sample(name, &[("skill", skill), ("skill_id", &metrics.skill_id)], value(metrics));
+        }
+    }
+    w.out
+}
+
+// ============================================================================
+// Metrics History
+// ============================================================================
+
+/// Samples kept by default: 25 minutes at the default interval
+pub const DEFAULT_HISTORY_CAPACITY: usize = 300;
+
+/// Time between background samples by default
+pub const DEFAULT_SAMPLE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// System metrics at one point in time
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MetricsSample {
+    pub timestamp: DateTime<Utc>,
+    pub metrics: SystemMetrics,
+}
+
+/// Ring buffer of recent samples; the oldest is dropped once full
+pub struct MetricsHistory {
+    capacity: usize,
+    samples: VecDeque<MetricsSample>,
+}
+
+impl MetricsHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, dropping the oldest samples if it shrinks
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn push(&mut self, sample: MetricsSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Samples taken at or after `since`, oldest first
+    pub fn since(&self, since: Option<DateTime<Utc>>) -> Vec<MetricsSample> {
+        self.samples
+            .iter()
+            .filter(|s| since.map_or(true, |since| s.timestamp >= since))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Running background sampler; dropping the sender stops it
+struct Sampler {
+    stop: std::sync::mpsc::Sender<()>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+fn sampler() -> &'static Mutex<Option<Sampler>> {
+    static SAMPLER: OnceLock<Mutex<Option<Sampler>>> = OnceLock::new();
+    SAMPLER.get_or_init(|| Mutex::new(None))
+}
+
+/// Start sampling system metrics into `AppState::metrics_history` every `interval`,
+/// replacing any sampler already running
+pub fn start_sampler(data_dir: PathBuf, interval: std::time::Duration, capacity: usize) {
+    stop_sampler();
+    crate::state::app_state().metrics_history().lock().unwrap().set_capacity(capacity);
+
+    let (stop, stopped) = std::sync::mpsc::channel::<()>();
+    let thread = std::thread::spawn(move || loop {
+        let sample = MetricsSample {
+            timestamp: Utc::now(),
+            metrics: get_system_metrics(&data_dir),
+        };
+        crate::state::app_state().metrics_history().lock().unwrap().push(sample);
+        match stopped.recv_timeout(interval) {
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            _ => break,
+        }
+    });
+    *sampler().lock().unwrap() = Some(Sampler { stop, thread });
+}
+
+/// Stop the background sampler and wait for it to finish
+pub fn stop_sampler() {
+    if let Some(Sampler { stop, thread }) = sampler().lock().unwrap().take() {
+        let _ = stop.send(());
+        let _ = thread.join();
+    }
+}
+
+// ============================================================================
+// Live Metrics Stream
+// ============================================================================
+
+/// Event carrying a fresh `SystemMetrics` payload
+pub const METRICS_STREAM_EVENT: &str = "metrics://system";
+
+enum StreamControl {
+    SetInterval(std::time::Duration),
+    Stop,
+}
+
+/// Running metrics stream
+struct MetricsStream {
+    control: std::sync::mpsc::Sender<StreamControl>,
+    thread: std::thread::JoinHandle<()>,
+    interval: std::time::Duration,
+}
+
+fn metrics_stream() -> &'static Mutex<Option<MetricsStream>> {
+    static STREAM: OnceLock<Mutex<Option<MetricsStream>>> = OnceLock::new();
+    STREAM.get_or_init(|| Mutex::new(None))
+}
+
+/// Pass fresh system metrics to `emit` every `interval`. If a stream is
+/// already running only its interval changes; returns whether a new stream
+/// was started.
+pub fn start_metrics_stream<F>(data_dir: PathBuf, interval: std::time::Duration, emit: F) -> Result<bool, String>
+where
+    F: Fn(SystemMetrics) + Send + 'static,
+{
+    if interval.is_zero() {
+        return Err("Stream interval must be greater than zero".to_string());
+    }
+    let mut stream = metrics_stream().lock().unwrap();
+    if let Some(running) = stream.as_mut() {
+        if running.control.send(StreamControl::SetInterval(interval)).is_ok() {
+            running.interval = interval;
+            return Ok(false);
+        }
+        // The thread has exited; replace it below
+        if let Some(finished) = stream.take() {
+            let _ = finished.thread.join();
+        }
+    }
+
+    let (control, commands) = std::sync::mpsc::channel();
+    let thread = std::thread::spawn(move || {
+        let mut interval = interval;
+        loop {
+            emit(get_system_metrics(&data_dir));
+            match commands.recv_timeout(interval) {
+                Ok(StreamControl::SetInterval(next)) => interval = next,
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+        }
+    });
+    *stream = Some(MetricsStream { control, thread, interval });
+    Ok(true)
+}
+
+/// Interval of the running stream, if any
+pub fn metrics_stream_interval() -> Option<std::time::Duration> {
+    metrics_stream().lock().unwrap().as_ref().map(|stream| stream.interval)
+}
+
+/// Stop the metrics stream and wait for it to finish; returns whether one
+/// was running
+pub fn stop_metrics_stream() -> bool {
+    match metrics_stream().lock().unwrap().take() {
+        Some(MetricsStream { control, thread, .. }) => {
+            let _ = control.send(StreamControl::Stop);
+            let _ = thread.join();
+            true
+        }
+        None => false,
+    }
+}
+
+// ============================================================================
+// Incident Snapshots
+// ============================================================================
+
+/// Point-in-time capture taken by an operator while investigating an incident
+#[derive(Serialize, Deserialize)]
+pub struct IncidentSnapshot {
+    pub id: String,
+    pub captured_at: DateTime<Utc>,
+    pub note: String,
+    pub system: SystemMetrics,
+    pub process: Option<ProcessMetrics>,
+    /// Recent failed audit entries, details redacted
+    pub recent_errors: Vec<AuditLogEntry>,
+    pub providers: Vec<ProviderReachability>,
+    /// Sampled system metrics leading up to the capture, oldest first
+    #[serde(default)]
+    pub history: Vec<MetricsSample>,
+    /// Skill executions still running at the capture
+    #[serde(default)]
+    pub running_executions: Vec<RunningExecution>,
+    pub protocol_version: String,
+}
+
+/// Listing entry for a captured snapshot
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IncidentSnapshotInfo {
+    pub id: String,
+    pub path: String,
+    pub captured_at: DateTime<Utc>,
+    pub note: String,
+}
+
+/// File name prefix for snapshot files
+const SNAPSHOT_PREFIX: &str = "incident-";
+
+/// Write a snapshot as `incident-<timestamp>.json` under `dir`
+pub fn write_incident_snapshot(dir: &Path, snapshot: &IncidentSnapshot) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.json", snapshot.id));
+    let raw = serde_json::to_string_pretty(snapshot).map_err(|e| e.to_string())?;
+    fs::write(&path, raw).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Read back the snapshot with `id` from `dir`. Only ids of the form
+/// `list_incident_snapshots` reports are accepted.
+pub fn read_incident_snapshot(dir: &Path, id: &str) -> Result<IncidentSnapshot, String> {
+    let is_plain_id = id.starts_with(SNAPSHOT_PREFIX) && id.chars().all(|c| c.is_ascii_alphanumeric() || "-.".contains(c));
+    if !is_plain_id || id.contains("..") {
+        return Err(format!("'{}' is not an incident snapshot id", id));
+    }
+    let path = dir.join(format!("{}.json", id));
+    let raw = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Invalid snapshot {}: {}", path.display(), e))
+}
+
+/// Snapshot id for a capture time, unique to the millisecond
+pub fn incident_snapshot_id(captured_at: DateTime<Utc>) -> String {
+    format!("{}{}", SNAPSHOT_PREFIX, captured_at.format("%Y%m%dT%H%M%S%.3fZ"))
+}
+
+/// Snapshots under `dir`, newest first. Unreadable files are skipped.
+pub fn list_incident_snapshots(dir: &Path) -> Vec<IncidentSnapshotInfo> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut snapshots: Vec<IncidentSnapshotInfo> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().map(|ext| ext == "json").unwrap_or(false)
+                && p.file_name().map(|n| n.to_string_lossy().starts_with(SNAPSHOT_PREFIX)).unwrap_or(false)
+        })
+        .filter_map(|path| {
+            let raw: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+            Some(IncidentSnapshotInfo {
+                id: raw["id"].as_str()?.to_string(),
+                path: path.to_string_lossy().to_string(),
+                captured_at: serde_json::from_value(raw["captured_at"].clone()).ok()?,
+                note: raw["note"].as_str().unwrap_or_default().to_string(),
+            })
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.captured_at.cmp(&a.captured_at));
+    snapshots
+}
+
+// ============================================================================
+// Skill Execution History
+// ============================================================================
+
+/// How long execution buckets are kept
+pub const EXECUTION_RETENTION_DAYS: i64 = 30;
+
+/// Executions in a window below which comparisons are flagged as unreliable
+pub const MIN_WINDOW_EXECUTIONS: u64 = 5;
+
+/// Executions of one skill that started within the same hour
+#[derive(Default, Clone)]
+struct ExecutionBucket {
+    success_count: u64,
+    failure_count: u64,
+    latencies_ms: Vec<f64>,
+}
+
+/// Latencies kept per skill for percentile reporting
+pub const LATENCY_SAMPLE_WINDOW: usize = 1000;
+
+/// Samples below which latency percentiles are reported as `None`
+pub const MIN_PERCENTILE_SAMPLES: usize = 5;
+
+/// Hour-bucketed execution history per skill, plus the most recent
+/// latencies for tail-latency reporting
+#[derive(Default)]
+pub struct ExecutionHistory {
+    buckets: HashMap<String, BTreeMap<DateTime<Utc>, ExecutionBucket>>,
+    recent_latencies: HashMap<String, VecDeque<f64>>,
+}
+
+/// Tail latency of one skill over its recent sample window
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct LatencySummary {
+    pub sample_count: usize,
+    pub p50_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub p99_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<f64>,
+}
+
+/// Half-open time range `[start, end)`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct TimeWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Execution statistics for one skill over one window
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WindowStats {
+    pub window: TimeWindow,
+    pub execution_count: u64,
+    pub success_rate: f64,
+    pub p50_latency_ms: f64,
+    pub p95_latency_ms: f64,
+    pub p99_latency_ms: f64,
+    pub insufficient_data: bool,
+}
+
+/// Execution totals of one skill over the retained history
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExecutionTotals {
+    pub skill_id: String,
+    pub success_count: u64,
+    pub failure_count: u64,
+    /// Mean latency; 0 when nothing was recorded
+    pub avg_latency_ms: f64,
+}
+
+/// Change from window A to window B; percentages are `None` when A is zero
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MetricsDelta {
+    pub execution_count: i64,
+    pub success_rate: f64,
+    pub p50_latency_ms: f64,
+    pub p95_latency_ms: f64,
+    pub p99_latency_ms: f64,
+    pub p95_latency_change_percent: Option<f64>,
+}
+
+/// Side-by-side comparison of a skill across two windows
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SkillMetricsComparison {
+    pub skill_id: String,
+    pub window_a: WindowStats,
+    pub window_b: WindowStats,
+    pub delta: MetricsDelta,
+    pub insufficient_data: bool,
+    pub protocol_version: String,
+}
+
+fn hour_of(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.duration_trunc(Duration::hours(1)).unwrap_or(at)
+}
+
+/// Trend bucket sizes with how many buckets a trend covers by default
+pub const TREND_BUCKETS: &[(&str, u32)] = &[("hour", 24), ("day", 7), ("week", 4)];
+
+/// Executions and successes of one trend bucket
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TrendPoint {
+    pub start: DateTime<Utc>,
+    pub execution_count: u64,
+    pub success_count: u64,
+    /// Percentage of executions that succeeded; null when there were none
+    pub success_rate: Option<f64>,
+}
+
+/// Success rate over consecutive buckets, oldest first
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SuccessTrend {
+    pub skill_name: Option<String>,
+    pub bucket: String,
+    pub points: Vec<TrendPoint>,
+    pub protocol_version: String,
+}
+
+/// Start of the `bucket` containing `at`; weeks start on Monday (UTC)
+fn bucket_start(bucket: &str, at: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let day = at.duration_trunc(Duration::days(1)).unwrap_or(at);
+    match bucket {
+        "hour" => Ok(hour_of(at)),
+        "day" => Ok(day),
+        "week" => Ok(day - Duration::days(day.weekday().num_days_from_monday() as i64)),
+        other => {
+            let names: Vec<&str> = TREND_BUCKETS.iter().map(|(name, _)| *name).collect();
+            Err(format!("Unknown bucket '{}'; expected one of {}", other, names.join(", ")))
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice; 0 when empty
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+impl ExecutionHistory {
+    /// Record one execution and drop buckets past retention
+    pub fn record(&mut self, skill_id: &str, at: DateTime<Utc>, success: bool, latency_ms: f64) {
+        let buckets = self.buckets.entry(skill_id.to_string()).or_default();
+        let bucket = buckets.entry(hour_of(at)).or_default();
+        if success {
+            bucket.success_count += 1;
+        } else {
+            bucket.failure_count += 1;
+        }
+        bucket.latencies_ms.push(latency_ms);
+
+        let recent = self.recent_latencies.entry(skill_id.to_string()).or_default();
+        if recent.len() == LATENCY_SAMPLE_WINDOW {
+            recent.pop_front();
+        }
+        recent.push_back(latency_ms);
+
+        let cutoff = hour_of(Utc::now() - Duration::days(EXECUTION_RETENTION_DAYS));
+        for skill in self.buckets.values_mut() {
+            *skill = skill.split_off(&cutoff);
+        }
+    }
+
+    /// Totals per skill over every retained bucket, ordered by skill id
+    pub fn totals(&self) -> Vec<ExecutionTotals> {
+        let mut totals: Vec<ExecutionTotals> = self
+            .buckets
+            .iter()
+            .map(|(skill_id, buckets)| {
+                let (mut success_count, mut failure_count, mut latency_sum, mut samples) = (0, 0, 0.0, 0);
+                for bucket in buckets.values() {
+                    success_count += bucket.success_count;
+                    failure_count += bucket.failure_count;
+                    latency_sum += bucket.latencies_ms.iter().sum::<f64>();
+                    samples += bucket.latencies_ms.len();
+                }
+                ExecutionTotals {
+                    skill_id: skill_id.clone(),
+                    success_count,
+                    failure_count,
+                    avg_latency_ms: if samples == 0 { 0.0 } else { latency_sum / samples as f64 },
+                }
+            })
+            .collect();
+        totals.sort_by(|a, b| a.skill_id.cmp(&b.skill_id));
+        totals
+    }
+
+    /// Percentiles and maximum of the skill's recent latencies
+    pub fn latency_summary(&self, skill_id: &str) -> LatencySummary {
+        let mut latencies: Vec<f64> = self
+            .recent_latencies
+            .get(skill_id)
+            .map(|recent| recent.iter().copied().collect())
+            .unwrap_or_default();
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        let enough = latencies.len() >= MIN_PERCENTILE_SAMPLES;
+        let at = |p: f64| if enough { Some(percentile(&latencies, p)) } else { None };
+        LatencySummary {
+            sample_count: latencies.len(),
+            p50_latency_ms: at(50.0),
+            p95_latency_ms: at(95.0),
+            p99_latency_ms: at(99.0),
+            max_latency_ms: latencies.last().copied(),
+        }
+    }
+
+    /// Statistics for executions whose hour bucket starts inside `window`
+    pub fn window_stats(&self, skill_id: &str, window: TimeWindow) -> WindowStats {
+        let mut successes = 0;
+        let mut total = 0;
+        let mut latencies = Vec::new();
+        if let Some(buckets) = self.buckets.get(skill_id) {
+            for bucket in buckets.range(window.start..window.end).map(|(_, b)| b) {
+                successes += bucket.success_count;
+                total += bucket.success_count + bucket.failure_count;
+                latencies.extend_from_slice(&bucket.latencies_ms);
+            }
+        }
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        WindowStats {
+            window,
+            execution_count: total,
+            success_rate: if total == 0 { 0.0 } else { successes as f64 / total as f64 * 100.0 },
+            p50_latency_ms: percentile(&latencies, 50.0),
+            p95_latency_ms: percentile(&latencies, 95.0),
+            p99_latency_ms: percentile(&latencies, 99.0),
+            insufficient_data: total < MIN_WINDOW_EXECUTIONS,
+        }
+    }
+
+    /// Executions per `bucket` for the skills in `skill_ids` (every skill
+    /// when `None`), over the default number of buckets ending with the one
+    /// containing `now`. Buckets without executions are included.
+    pub fn success_trend(&self, skill_ids: Option<&[String]>, bucket: &str, now: DateTime<Utc>) -> Result<Vec<TrendPoint>, String> {
+        let current = bucket_start(bucket, now)?;
+        let count = TREND_BUCKETS.iter().find(|(name, _)| *name == bucket).map_or(0, |(_, count)| *count);
+        let mut points: Vec<TrendPoint> = (0..count)
+            .rev()
+            .map(|back| {
+                // Step back from inside the current bucket so each start is aligned
+                let start = match bucket {
+                    "hour" => current - Duration::hours(back as i64),
+                    "day" => current - Duration::days(back as i64),
+                    _ => current - Duration::weeks(back as i64),
+                };
+                TrendPoint { start, execution_count: 0, success_count: 0, success_rate: None }
+            })
+            .collect();
+        
+        let skills = self
+            .buckets
+            .iter()
+            .filter(|(skill_id, _)| skill_ids.is_none_or(|ids| ids.contains(skill_id)));
+        for (_, hours) in skills {
+            for (hour, executions) in hours.range(points[0].start..) {
+                let Some(point) = points.iter_mut().rev().find(|p| p.start <= *hour) else {
+                    continue;
+                };
+                point.execution_count += executions.success_count + executions.failure_count;
+                point.success_count += executions.success_count;
+            }
+        }
+        for point in &mut points {
+            if point.execution_count > 0 {
+                point.success_rate = Some(point.success_count as f64 / point.execution_count as f64 * 100.0);
+            }
+        }
+        Ok(points)
+    }
+
+    /// Compare a skill between two windows
+    pub fn compare(&self, skill_id: &str, window_a: TimeWindow, window_b: TimeWindow) -> SkillMetricsComparison {
+        let a = self.window_stats(skill_id, window_a);
+        let b = self.window_stats(skill_id, window_b);
+        let delta = MetricsDelta {
+            execution_count: b.execution_count as i64 - a.execution_count as i64,
+            success_rate: b.success_rate - a.success_rate,
+            p50_latency_ms: b.p50_latency_ms - a.p50_latency_ms,
+            p95_latency_ms: b.p95_latency_ms - a.p95_latency_ms,
+            p99_latency_ms: b.p99_latency_ms - a.p99_latency_ms,
+            p95_latency_change_percent: if a.p95_latency_ms > 0.0 {
+                Some((b.p95_latency_ms - a.p95_latency_ms) / a.p95_latency_ms * 100.0)
+            } else {
+                None
+            },
+        };
+
+        SkillMetricsComparison {
+            skill_id: skill_id.to_string(),
+            insufficient_data: a.insufficient_data || b.insufficient_data,
+            window_a: a,
+            window_b: b,
+            delta,
+            protocol_version: METRICS_PROTOCOL_VERSION.to_string(),
+        }
+    }
+}
+
+/// Record a finished skill execution in the shared history
+pub fn record_skill_execution(skill_id: &str, success: bool, latency_ms: f64) {
+    crate::state::app_state().execution_history().lock().unwrap().record(skill_id, Utc::now(), success, latency_ms);
+}
+
+/// A skill execution that has started and not finished yet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunningExecution {
+    pub execution_id: String,
+    pub skill_id: String,
+    pub user_id: String,
+    /// Warm sandbox drawn from the pool; `None` means a cold start
+    pub sandbox_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Executions in flight, by execution id
+#[derive(Default)]
+pub struct RunningExecutions {
+    running: HashMap<String, RunningExecution>,
+}
+
+impl RunningExecutions {
+    pub fn start(&mut self, execution: RunningExecution) {
+        self.running.insert(execution.execution_id.clone(), execution);
+    }
+
+    pub fn get(&self, execution_id: &str) -> Option<&RunningExecution> {
+        self.running.get(execution_id)
+    }
+
+    /// Stop tracking an execution, returning it if it was running
+    pub fn finish(&mut self, execution_id: &str) -> Option<RunningExecution> {
+        self.running.remove(execution_id)
+    }
+
+    /// Running executions, longest-running first
+    pub fn list(&self) -> Vec<RunningExecution> {
+        let mut running: Vec<RunningExecution> = self.running.values().cloned().collect();
+        running.sort_by_key(|e| e.started_at);
+        running
+    }
+}
+
+fn parse_span(raw: &str) -> Option<Duration> {
+    let (split, _) = raw.char_indices().last()?;
+    let (amount, unit) = raw.split_at(split);
+    let amount: i64 = amount.parse().ok().filter(|n| *n > 0)?;
+    match unit {
+        "m" => Some(Duration::minutes(amount)),
+        "h" => Some(Duration::hours(amount)),
+        "d" => Some(Duration::days(amount)),
+        _ => None,
+    }
+}
+
+/// Parse a window specifier relative to `now`.
+///
+/// Accepts `last_<n><m|h|d>` (e.g. `last_24h`), `previous_<n><m|h|d>` for
+/// the span immediately before that, or `<rfc3339>..<rfc3339>`.
+pub fn parse_time_window(spec: &str, now: DateTime<Utc>) -> Result<TimeWindow, String> {
+    let spec = spec.trim();
+    if let Some((start, end)) = spec.split_once("..") {
+        let parse = |raw: &str| {
+            DateTime::parse_from_rfc3339(raw.trim())
+                .map(|at| at.with_timezone(&Utc))
+                .map_err(|e| format!("Invalid timestamp '{}': {}", raw.trim(), e))
+        };
+        let window = TimeWindow { start: parse(start)?, end: parse(end)? };
+        if window.start >= window.end {
+            return Err(format!("Window '{}' must start before it ends", spec));
+        }
+        return Ok(window);
+    }
+
+    let invalid = || format!("Unknown window '{}'; expected e.g. \"last_24h\", \"previous_24h\" or \"<start>..<end>\"", spec);
+    let (kind, span) = spec.split_once('_').ok_or_else(invalid)?;
+    let span = parse_span(span).ok_or_else(invalid)?;
+    match kind {
+        "last" => Ok(TimeWindow { start: now - span, end: now }),
+        "previous" | "prev" => Ok(TimeWindow { start: now - span - span, end: now - span }),
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,326 @@
+//! Configuration Migration Module
+//!
+//! Moves a legacy Synapse data directory to the current layout in small,
+//! resumable steps. Completed steps are journaled so an interrupted
+//! migration continues where it stopped instead of starting over.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Protocol version constant for migration responses
+const MIGRATION_PROTOCOL_VERSION: &str = "1.0";
+
+/// Journal file recording completed steps, relative to the data root
+pub const MIGRATION_JOURNAL: &str = ".migration_state.json";
+
+/// Legacy single-file configuration, relative to the data root
+const LEGACY_CONFIG: &str = "config.json";
+
+/// Legacy data directory holding skills/memory, relative to the data root
+const LEGACY_DATA_DIR: &str = "data";
+
+/// Current configuration file, relative to the data root
+const CURRENT_CONFIG: &str = "config/config.json";
+
+/// A single idempotent migration step
+pub trait MigrationStep {
+    /// Stable name recorded in the journal
+    fn name(&self) -> String;
+
+    /// Apply the step. Must be safe to re-run after a partial failure.
+    fn apply(&self, root: &Path) -> Result<(), String>;
+
+    /// Undo whatever a failed `apply` may have left behind
+    fn rollback(&self, root: &Path);
+}
+
+/// Progress event emitted after each step
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MigrationProgress {
+    pub step: String,
+    pub index: usize,
+    pub total: usize,
+    pub percent: f64,
+    pub status: String,
+    pub protocol_version: String,
+}
+
+/// Persisted record of migration progress
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct MigrationJournal {
+    pub completed_steps: Vec<String>,
+    pub failed_step: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Final result of a migration run
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MigrationSummary {
+    pub completed: bool,
+    pub total_steps: usize,
+    pub applied_steps: Vec<String>,
+    pub skipped_steps: Vec<String>,
+    pub stopped_at: Option<String>,
+    pub error: Option<String>,
+    pub protocol_version: String,
+}
+
+/// Load the journal for a data root, or an empty one if none exists
+pub fn load_journal(root: &Path) -> MigrationJournal {
+    fs::read_to_string(root.join(MIGRATION_JOURNAL))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_journal(root: &Path, journal: &MigrationJournal) -> Result<(), String> {
+    let raw = serde_json::to_string_pretty(journal).map_err(|e| e.to_string())?;
+    fs::write(root.join(MIGRATION_JOURNAL), raw).map_err(|e| format!("Failed to write migration journal: {}", e))
+}
+
+/// Run `steps` against `root`, skipping steps already recorded as completed.
+///
+/// A failing step is rolled back and the run stops there; calling again
+/// resumes from that step.
+pub fn run_migration(
+    root: &Path,
+    steps: &[Box<dyn MigrationStep + Send + Sync>],
+    on_progress: &mut dyn FnMut(&MigrationProgress),
+) -> Result<MigrationSummary, String> {
+    let mut journal = load_journal(root);
+    journal.failed_step = None;
+    journal.error = None;
+
+    let total = steps.len();
+    let mut applied_steps = Vec::new();
+    let mut skipped_steps = Vec::new();
+
+    for (index, step) in steps.iter().enumerate() {
+        let name = step.name();
+        let mut progress = MigrationProgress {
+            step: name.clone(),
+            index,
+            total,
+            percent: (index + 1) as f64 / total as f64 * 100.0,
+            status: "completed".to_string(),
+            protocol_version: MIGRATION_PROTOCOL_VERSION.to_string(),
+        };
+
+        if journal.completed_steps.contains(&name) {
+            skipped_steps.push(name);
+            progress.status = "skipped".to_string();
+            on_progress(&progress);
+            continue;
+        }
+
+        if let Err(e) = step.apply(root) {
+            step.rollback(root);
+            journal.failed_step = Some(name.clone());
+            journal.error = Some(e.clone());
+            save_journal(root, &journal)?;
+
+            progress.percent = index as f64 / total as f64 * 100.0;
+            progress.status = "failed".to_string();
+            on_progress(&progress);
+
+            return Ok(MigrationSummary {
+                completed: false,
+                total_steps: total,
+                applied_steps,
+                skipped_steps,
+                stopped_at: Some(name),
+                error: Some(e),
+                protocol_version: MIGRATION_PROTOCOL_VERSION.to_string(),
+            });
+        }
+
+        journal.completed_steps.push(name.clone());
+        save_journal(root, &journal)?;
+        applied_steps.push(name);
+        on_progress(&progress);
+    }
+
+    Ok(MigrationSummary {
+        completed: true,
+        total_steps: total,
+        applied_steps,
+        skipped_steps,
+        stopped_at: None,
+        error: None,
+        protocol_version: MIGRATION_PROTOCOL_VERSION.to_string(),
+    })
+}
+
+// ============================================================================
+// Legacy Layout Steps
+// ============================================================================
+
+fn read_json(path: &Path) -> Result<serde_json::Value, String> {
+    let raw = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let raw = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, raw).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn current_config(root: &Path) -> serde_json::Value {
+    read_json(&root.join(CURRENT_CONFIG)).unwrap_or_else(|_| serde_json::json!({ "llm_providers": [] }))
+}
+
+/// Copies top-level settings (language, mode, security) into the new config
+struct SettingsStep;
+
+impl MigrationStep for SettingsStep {
+    fn name(&self) -> String {
+        "settings".to_string()
+    }
+
+    fn apply(&self, root: &Path) -> Result<(), String> {
+        let legacy = read_json(&root.join(LEGACY_CONFIG))?;
+        let mut config = current_config(root);
+        for key in ["language", "mode", "data_paths", "security_settings"] {
+            if let Some(value) = legacy.get(key) {
+                config[key] = value.clone();
+            }
+        }
+        write_json(&root.join(CURRENT_CONFIG), &config)
+    }
+
+    fn rollback(&self, root: &Path) {
+        let _ = fs::remove_file(root.join(CURRENT_CONFIG).with_extension("json.tmp"));
+    }
+}
+
+/// Copies one legacy provider entry into the new config, replacing by name
+struct ProviderStep {
+    name: String,
+}
+
+impl MigrationStep for ProviderStep {
+    fn name(&self) -> String {
+        format!("provider:{}", self.name)
+    }
+
+    fn apply(&self, root: &Path) -> Result<(), String> {
+        let legacy = read_json(&root.join(LEGACY_CONFIG))?;
+        let provider = legacy["llm_providers"]
+            .as_array()
+            .and_then(|providers| providers.iter().find(|p| p["name"] == self.name.as_str()))
+            .cloned()
+            .ok_or_else(|| format!("Provider '{}' not found in legacy config", self.name))?;
+
+        let mut config = current_config(root);
+        let mut providers: Vec<serde_json::Value> = config["llm_providers"].as_array().cloned().unwrap_or_default();
+        providers.retain(|p| p["name"] != self.name.as_str());
+        providers.push(provider);
+        config["llm_providers"] = serde_json::Value::Array(providers);
+        write_json(&root.join(CURRENT_CONFIG), &config)
+    }
+
+    fn rollback(&self, root: &Path) {
+        let _ = fs::remove_file(root.join(CURRENT_CONFIG).with_extension("json.tmp"));
+    }
+}
+
+/// Moves `data/<dir>` up to `<dir>` at the data root
+struct MoveDataStep {
+    dir: String,
+}
+
+impl MoveDataStep {
+    fn paths(&self, root: &Path) -> (PathBuf, PathBuf) {
+        (root.join(LEGACY_DATA_DIR).join(&self.dir), root.join(&self.dir))
+    }
+}
+
+impl MigrationStep for MoveDataStep {
+    fn name(&self) -> String {
+        format!("move:{}", self.dir)
+    }
+
+    fn apply(&self, root: &Path) -> Result<(), String> {
+        let (from, to) = self.paths(root);
+        if !from.exists() && to.exists() {
+            return Ok(());
+        }
+        if to.exists() {
+            return Err(format!("Destination {} already exists", to.display()));
+        }
+        fs::rename(&from, &to).map_err(|e| format!("Failed to move {}: {}", from.display(), e))
+    }
+
+    fn rollback(&self, root: &Path) {
+        let (from, to) = self.paths(root);
+        if !from.exists() && to.exists() {
+            let _ = fs::rename(&to, &from);
+        }
+    }
+}
+
+/// Retires the legacy config once everything else has been moved
+struct RetireLegacyStep;
+
+impl MigrationStep for RetireLegacyStep {
+    fn name(&self) -> String {
+        "retire_legacy_config".to_string()
+    }
+
+    fn apply(&self, root: &Path) -> Result<(), String> {
+        let legacy = root.join(LEGACY_CONFIG);
+        if !legacy.exists() {
+            return Ok(());
+        }
+        fs::rename(&legacy, root.join("config.json.migrated")).map_err(|e| format!("Failed to retire legacy config: {}", e))
+    }
+
+    fn rollback(&self, root: &Path) {
+        let retired = root.join("config.json.migrated");
+        if retired.exists() {
+            let _ = fs::rename(retired, root.join(LEGACY_CONFIG));
+        }
+    }
+}
+
+/// Build the step list for migrating the legacy layout under `root`
+pub fn plan_legacy_migration(root: &Path) -> Result<Vec<Box<dyn MigrationStep + Send + Sync>>, String> {
+    let mut steps: Vec<Box<dyn MigrationStep + Send + Sync>> = Vec::new();
+    let legacy_path = root.join(LEGACY_CONFIG);
+
+    if legacy_path.exists() {
+        let legacy = read_json(&legacy_path)?;
+        steps.push(Box::new(SettingsStep));
+        for provider in legacy["llm_providers"].as_array().cloned().unwrap_or_default() {
+            if let Some(name) = provider["name"].as_str() {
+                steps.push(Box::new(ProviderStep { name: name.to_string() }));
+            }
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(root.join(LEGACY_DATA_DIR)) {
+        let mut dirs: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            steps.push(Box::new(MoveDataStep { dir }));
+        }
+    }
+
+    if legacy_path.exists() {
+        steps.push(Box::new(RetireLegacyStep));
+    }
+
+    Ok(steps)
+}
@@ -0,0 +1,907 @@
+//! LLM Providers Module
+//!
+//! Talks to LLM provider APIs on behalf of the configurator.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::commands::{LLMProviderConfig, SynapseConfig, ValidationIssue};
+use crate::i18n;
+
+/// Default timeout for provider catalog requests
+const CATALOG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Anthropic API version header value
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Cooldown applied to a rate-limited provider that sent no `retry-after`
+const DEFAULT_COOLDOWN_SECS: i64 = 60;
+
+/// Provider types the configurator can talk to
+pub const SUPPORTED_PROVIDER_TYPES: &[&str] = &["openai", "anthropic", "ollama", "custom_openai"];
+
+/// Error for a `provider_type` outside `SUPPORTED_PROVIDER_TYPES`
+pub fn unsupported_provider(provider_type: &str) -> String {
+    format!(
+        "Unsupported provider '{}'; expected one of {}",
+        provider_type,
+        SUPPORTED_PROVIDER_TYPES.join(", ")
+    )
+}
+
+/// Base URL used when a provider doesn't override it
+pub fn default_base_url(provider_type: &str) -> Option<&'static str> {
+    match provider_type {
+        "openai" => Some("https://api.openai.com/v1"),
+        "anthropic" => Some("https://api.anthropic.com/v1"),
+        "ollama" => Some("http://localhost:11434"),
+        _ => None,
+    }
+}
+
+/// Priorities a provider may take; lower numbers are tried first
+pub const PROVIDER_PRIORITY_RANGE: std::ops::RangeInclusive<u8> = 1..=100;
+
+/// Provider types that can't authenticate without an API key
+const API_KEY_REQUIRED: &[&str] = &["openai", "anthropic"];
+
+/// Provider types with no default endpoint, so `base_url` must be set
+const BASE_URL_REQUIRED: &[&str] = &["custom_openai"];
+
+/// Outcome of checking one field of a provider config
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProviderFieldCheck {
+    pub field: String,
+    pub valid: bool,
+    /// Why the field is invalid; `None` when it's valid
+    pub message: Option<String>,
+}
+
+impl ProviderFieldCheck {
+    fn new(field: &str, problem: Option<String>) -> Self {
+        Self { field: field.to_string(), valid: problem.is_none(), message: problem }
+    }
+}
+
+fn is_blank(value: Option<&str>) -> bool {
+    value.is_none_or(|v| v.trim().is_empty())
+}
+
+/// Check a provider config's shape field by field, without saving it or
+/// touching the network
+pub fn check_provider_fields(provider: &LLMProviderConfig) -> Vec<ProviderFieldCheck> {
+    let provider_type = provider.provider_type.as_str();
+    let supported = SUPPORTED_PROVIDER_TYPES.contains(&provider_type);
+
+    let name = is_blank(Some(&provider.name)).then(|| "A provider name is required".to_string());
+    let provider_type_problem = (!supported).then(|| unsupported_provider(provider_type));
+    let api_key = (API_KEY_REQUIRED.contains(&provider_type) && is_blank(provider.api_key.as_deref()))
+        .then(|| format!("An API key is required for {} providers", provider_type));
+    let base_url = match provider.base_url.as_deref() {
+        Some(url) if !url.trim().is_empty() => crate::webhooks::validate_webhook_url(url).err(),
+        _ if BASE_URL_REQUIRED.contains(&provider_type) => Some(format!("A base URL is required for {} providers", provider_type)),
+        _ => None,
+    };
+    let model = is_blank(Some(&provider.model)).then(|| "A model is required".to_string());
+    let priority = (!PROVIDER_PRIORITY_RANGE.contains(&provider.priority)).then(|| {
+        format!(
+            "Priority {} is outside {}-{}",
+            provider.priority,
+            PROVIDER_PRIORITY_RANGE.start(),
+            PROVIDER_PRIORITY_RANGE.end()
+        )
+    });
+
+    vec![
+        ProviderFieldCheck::new("name", name),
+        ProviderFieldCheck::new("provider_type", provider_type_problem),
+        ProviderFieldCheck::new("api_key", api_key),
+        ProviderFieldCheck::new("base_url", base_url),
+        ProviderFieldCheck::new("model", model),
+        ProviderFieldCheck::new("priority", priority),
+    ]
+}
+
+fn base_url(provider: &LLMProviderConfig) -> Result<String, String> {
+    provider
+        .base_url
+        .clone()
+        .or_else(|| default_base_url(&provider.provider_type).map(str::to_string))
+        .map(|url| url.trim_end_matches('/').to_string())
+        .ok_or_else(|| format!("No base_url configured for provider '{}'", provider.name))
+}
+
+/// Whether a provider's base URL points at this machine
+pub fn is_local_provider(provider: &LLMProviderConfig) -> bool {
+    let Some(url) = base_url(provider).ok().and_then(|url| reqwest::Url::parse(&url).ok()) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+    match host.parse::<std::net::IpAddr>() {
+        Ok(ip) => ip.is_loopback(),
+        Err(_) => host.eq_ignore_ascii_case("localhost"),
+    }
+}
+
+/// Fetch the model names a provider currently serves
+pub async fn list_provider_models(provider: &LLMProviderConfig) -> Result<Vec<String>, String> {
+    match provider.provider_type.as_str() {
+        "openai" => OpenAiProvider(provider).list_models().await,
+        "anthropic" => AnthropicProvider(provider).list_models().await,
+        "ollama" => OllamaProvider(provider).list_models().await,
+        "custom_openai" => CustomOpenAiProvider(provider).list_models().await,
+        other => Err(unsupported_provider(other)),
+    }
+}
+
+/// Model names from a catalog response body
+pub fn parse_model_list(provider_type: &str, body: &serde_json::Value) -> Result<Vec<String>, String> {
+    // OpenAI/Anthropic: {"data": [{"id": ...}]}, Ollama: {"models": [{"name": ...}]}
+    let models = match provider_type {
+        "ollama" => body["models"].as_array().map(|m| m.iter().filter_map(|m| m["name"].as_str()).collect::<Vec<_>>()),
+        _ => body["data"].as_array().map(|m| m.iter().filter_map(|m| m["id"].as_str()).collect::<Vec<_>>()),
+    };
+
+    models
+        .map(|names| names.into_iter().map(str::to_string).collect())
+        .ok_or_else(|| "Provider catalog response had no model list".to_string())
+}
+
+/// Whether a provider type is known to expose a model-listing endpoint;
+/// OpenAI-compatible servers may or may not
+pub fn models_listable(provider_type: &str) -> bool {
+    matches!(provider_type, "openai" | "anthropic" | "ollama")
+}
+
+/// What a model can do, as far as skill requirements are concerned
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ModelCapabilities {
+    /// Tokens of prompt and completion the model accepts together
+    pub context_window: u64,
+    pub supports_tools: bool,
+    pub supports_vision: bool,
+}
+
+/// Capabilities declared in the config for a model missing from the
+/// built-in table, or to correct it
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ModelCapabilityOverride {
+    pub provider: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub capabilities: ModelCapabilities,
+}
+
+/// Known capabilities of a provider's model; `None` for models not in the table
+pub fn get_model_capabilities(provider_type: &str, model: &str) -> Option<ModelCapabilities> {
+    let (context_window, supports_tools, supports_vision) = match (provider_type, model) {
+        ("openai", "gpt-4o") | ("openai", "gpt-4o-mini") | ("openai", "gpt-4-turbo") => (128_000, true, true),
+        ("openai", "gpt-3.5-turbo") => (16_385, true, false),
+        ("anthropic", m) if m.starts_with("claude-3") => (200_000, true, true),
+        ("ollama", "llama3") => (8_192, false, false),
+        ("ollama", "mistral") => (32_768, false, false),
+        ("ollama", "codellama") => (16_384, false, false),
+        _ => return None,
+    };
+    Some(ModelCapabilities {
+        context_window,
+        supports_tools,
+        supports_vision,
+    })
+}
+
+/// Capabilities of a provider's model, preferring the config's overrides
+/// to the built-in table
+pub fn resolve_model_capabilities(config: &SynapseConfig, provider_type: &str, model: &str) -> Option<ModelCapabilities> {
+    config
+        .model_capabilities
+        .iter()
+        .find(|o| o.provider == provider_type && o.model == model)
+        .map(|o| o.capabilities.clone())
+        .or_else(|| get_model_capabilities(provider_type, model))
+}
+
+/// Capability hints the wizard shows next to a model; every flag is null
+/// for a model neither the table nor the config knows
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ModelCapabilityHints {
+    pub provider: String,
+    pub model: String,
+    pub known: bool,
+    pub context_window: Option<u64>,
+    pub supports_vision: Option<bool>,
+    pub supports_tools: Option<bool>,
+}
+
+pub fn model_capability_hints(config: &SynapseConfig, provider_type: &str, model: &str) -> ModelCapabilityHints {
+    let capabilities = resolve_model_capabilities(config, provider_type, model);
+    ModelCapabilityHints {
+        provider: provider_type.to_string(),
+        model: model.to_string(),
+        known: capabilities.is_some(),
+        context_window: capabilities.as_ref().map(|c| c.context_window),
+        supports_vision: capabilities.as_ref().map(|c| c.supports_vision),
+        supports_tools: capabilities.as_ref().map(|c| c.supports_tools),
+    }
+}
+
+/// USD prices per 1,000 tokens for one provider/model pair
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ModelPrice {
+    pub provider: String,
+    pub model: String,
+    pub input_per_1k: f64,
+    pub output_per_1k: f64,
+}
+
+/// Estimated cost of a number of tokens on one model
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CostEstimate {
+    pub cost_usd: f64,
+    /// False when the model has no known price and `cost_usd` is 0
+    pub has_price: bool,
+}
+
+/// Token prices by `(provider, model)`. Local providers (ollama) are free.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceTable {
+    prices: HashMap<(String, String), (f64, f64)>,
+}
+
+impl Default for PriceTable {
+    /// List prices for the known OpenAI and Anthropic models
+    fn default() -> Self {
+        let mut table = Self { prices: HashMap::new() };
+        for (provider, model, input, output) in [
+            ("openai", "gpt-4o", 0.0025, 0.01),
+            ("openai", "gpt-4o-mini", 0.00015, 0.0006),
+            ("openai", "gpt-4-turbo", 0.01, 0.03),
+            ("openai", "gpt-3.5-turbo", 0.0005, 0.0015),
+            ("anthropic", "claude-3.5-sonnet", 0.003, 0.015),
+            ("anthropic", "claude-3-opus", 0.015, 0.075),
+        ] {
+            table.prices.insert((provider.to_string(), model.to_string()), (input, output));
+        }
+        table
+    }
+}
+
+impl PriceTable {
+    /// Defaults with the `token_prices` configured by the user applied on top
+    pub fn from_config(config: &SynapseConfig) -> Self {
+        let mut table = Self::default();
+        for price in &config.token_prices {
+            table.set(price);
+        }
+        table
+    }
+
+    pub fn set(&mut self, price: &ModelPrice) {
+        self.prices.insert((price.provider.clone(), price.model.clone()), (price.input_per_1k, price.output_per_1k));
+    }
+
+    /// `(input, output)` USD per 1,000 tokens
+    pub fn price(&self, provider_type: &str, model: &str) -> Option<(f64, f64)> {
+        if provider_type == "ollama" {
+            return Some((0.0, 0.0));
+        }
+        self.prices.get(&(provider_type.to_string(), model.to_string())).copied()
+    }
+
+    /// `prompt/1000 * input + completion/1000 * output`; unknown models cost 0
+    pub fn cost(&self, provider_type: &str, model: &str, prompt_tokens: u64, completion_tokens: u64) -> CostEstimate {
+        match self.price(provider_type, model) {
+            Some((input, output)) => CostEstimate {
+                cost_usd: prompt_tokens as f64 / 1000.0 * input + completion_tokens as f64 / 1000.0 * output,
+                has_price: true,
+            },
+            None => CostEstimate { cost_usd: 0.0, has_price: false },
+        }
+    }
+}
+
+/// One LLM call whose cost should be estimated before it is made
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlannedLlmCall {
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: u64,
+    pub expected_completion_tokens: u64,
+}
+
+/// Projected cost of one planned call. The USD fields are `None` when the
+/// model has no price entry, so an unknown price never reads as free.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CallCostEstimate {
+    #[serde(flatten)]
+    pub call: PlannedLlmCall,
+    pub has_price: bool,
+    pub prompt_cost_usd: Option<f64>,
+    pub completion_cost_usd: Option<f64>,
+    pub total_cost_usd: Option<f64>,
+}
+
+/// Summed estimate over a batch of planned calls. Totals cover only the
+/// priced calls; `unpriced_models` lists the `provider/model` pairs left out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BatchCostEstimate {
+    pub calls: Vec<CallCostEstimate>,
+    pub prompt_cost_usd: f64,
+    pub completion_cost_usd: f64,
+    pub total_cost_usd: f64,
+    pub unpriced_models: Vec<String>,
+}
+
+/// Split the projected cost of each call into prompt and completion parts
+/// and sum them
+pub fn estimate_call_costs(table: &PriceTable, calls: &[PlannedLlmCall]) -> BatchCostEstimate {
+    let mut batch = BatchCostEstimate {
+        calls: Vec::new(),
+        prompt_cost_usd: 0.0,
+        completion_cost_usd: 0.0,
+        total_cost_usd: 0.0,
+        unpriced_models: Vec::new(),
+    };
+    for call in calls {
+        let price = table.price(&call.provider, &call.model);
+        let prompt = price.map(|(input, _)| call.prompt_tokens as f64 / 1000.0 * input);
+        let completion = price.map(|(_, output)| call.expected_completion_tokens as f64 / 1000.0 * output);
+        match (prompt, completion) {
+            (Some(prompt), Some(completion)) => {
+                batch.prompt_cost_usd += prompt;
+                batch.completion_cost_usd += completion;
+            }
+            _ => {
+                let name = format!("{}/{}", call.provider, call.model);
+                if !batch.unpriced_models.contains(&name) {
+                    batch.unpriced_models.push(name);
+                }
+            }
+        }
+        batch.calls.push(CallCostEstimate {
+            call: call.clone(),
+            has_price: price.is_some(),
+            prompt_cost_usd: prompt,
+            completion_cost_usd: completion,
+            total_cost_usd: prompt.zip(completion).map(|(p, c)| p + c),
+        });
+    }
+    batch.total_cost_usd = batch.prompt_cost_usd + batch.completion_cost_usd;
+    batch
+}
+
+/// Blended USD price per 1,000 tokens (mean of input and output) for
+/// estimates that can't split tokens; `None` for unknown models
+pub fn model_price_per_1k_tokens(table: &PriceTable, provider_type: &str, model: &str) -> Option<f64> {
+    table.price(provider_type, model).map(|(input, output)| (input + output) / 2.0)
+}
+
+/// Default timeout for connection tests
+pub const DEFAULT_PROBE_TIMEOUT_SECS: u64 = 10;
+
+/// Outcome of a live connection test against a provider
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConnectionProbe {
+    pub connected: bool,
+    pub status: Option<u16>,
+    pub auth_ok: bool,
+    pub latency_ms: u64,
+    pub message: String,
+    /// Models the provider reports, empty when it can't list them
+    pub available_models: Vec<String>,
+    pub models_listable: bool,
+}
+
+/// Outcome of `LlmProvider::probe`; `Err` only when no request could be made
+pub type ProbeResult = Result<ConnectionProbe, String>;
+
+/// Endpoints and auth headers for one provider's API. The probe and
+/// catalog logic is shared; implementations only format requests.
+pub trait LlmProvider {
+    fn config(&self) -> &LLMProviderConfig;
+
+    /// Authenticated request for the provider's model catalog
+    fn models_request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder;
+
+    /// Lightest authenticated request the provider supports
+    fn probe_request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder {
+        self.models_request(client, base)
+    }
+
+    /// Whether a successful probe response body is the model catalog
+    fn probe_lists_models(&self) -> bool {
+        true
+    }
+
+    /// Whether a 404 from the catalog just means the server can't list
+    /// models, rather than that it isn't the expected API
+    fn catalog_optional(&self) -> bool {
+        false
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let provider = self.config();
+        let client = reqwest::Client::builder()
+            .timeout(CATALOG_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let base = base_url(provider)?;
+
+        let response = self
+            .models_request(&client, &base)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach provider: {}", e))?;
+        observe_response(&provider.name, &response);
+        if !response.status().is_success() {
+            return Err(format!("Provider returned HTTP {}", response.status().as_u16()));
+        }
+        let body: serde_json::Value = response.json().await.map_err(|e| format!("Invalid catalog response: {}", e))?;
+        parse_model_list(&provider.provider_type, &body)
+    }
+
+    /// Send the probe request and classify the result as success, auth
+    /// failure, or network failure
+    async fn probe(&self, timeout: Duration) -> ProbeResult {
+        let provider = self.config();
+        let client = reqwest::Client::builder().timeout(timeout).build().map_err(|e| e.to_string())?;
+        let base = base_url(provider)?;
+
+        let started = std::time::Instant::now();
+        let response = self.probe_request(&client, &base).send().await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                let reason = if e.is_timeout() {
+                    format!("timed out after {}s", timeout.as_secs())
+                } else {
+                    e.to_string()
+                };
+                return Ok(ConnectionProbe {
+                    connected: false,
+                    status: None,
+                    auth_ok: false,
+                    latency_ms,
+                    message: format!("Network error: could not reach {} ({})", base, reason),
+                    available_models: Vec::new(),
+                    models_listable: models_listable(&provider.provider_type),
+                });
+            }
+        };
+        observe_response(&provider.name, &response);
+
+        let status = response.status().as_u16();
+        let (connected, auth_ok, message) = match status {
+            200..=299 => (true, true, i18n::localize("connection_successful", i18n::FALLBACK_LANGUAGE)),
+            401 | 403 => (false, false, format!("Authentication failed: provider returned HTTP {}", status)),
+            429 => (true, true, i18n::localize("connection_rate_limited", i18n::FALLBACK_LANGUAGE)),
+            404 if self.catalog_optional() => (true, true, i18n::localize("connection_models_not_listed", i18n::FALLBACK_LANGUAGE)),
+            _ => (false, false, format!("Provider returned HTTP {}", status)),
+        };
+
+        let available_models = if !connected || status == 404 {
+            Vec::new()
+        } else if self.probe_lists_models() {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            parse_model_list(&provider.provider_type, &body).unwrap_or_default()
+        } else {
+            self.list_models().await.unwrap_or_default()
+        };
+
+        Ok(ConnectionProbe {
+            connected,
+            status: Some(status),
+            auth_ok,
+            latency_ms,
+            message,
+            models_listable: models_listable(&provider.provider_type) || !available_models.is_empty(),
+            available_models,
+        })
+    }
+}
+
+/// OpenAI: `Authorization: Bearer`, probed through the model catalog
+pub struct OpenAiProvider<'a>(pub &'a LLMProviderConfig);
+
+impl LlmProvider for OpenAiProvider<'_> {
+    fn config(&self) -> &LLMProviderConfig {
+        self.0
+    }
+
+    fn models_request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder {
+        client
+            .get(format!("{}/models", base))
+            .bearer_auth(self.0.api_key.as_deref().unwrap_or_default())
+    }
+}
+
+/// Anthropic: `x-api-key` plus `anthropic-version`, probed with a
+/// one-token messages call since listing models doesn't exercise the model
+pub struct AnthropicProvider<'a>(pub &'a LLMProviderConfig);
+
+impl AnthropicProvider<'_> {
+    fn authenticated(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        request
+            .header("x-api-key", self.0.api_key.as_deref().unwrap_or_default())
+            .header("anthropic-version", ANTHROPIC_VERSION)
+    }
+}
+
+impl LlmProvider for AnthropicProvider<'_> {
+    fn config(&self) -> &LLMProviderConfig {
+        self.0
+    }
+
+    fn models_request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder {
+        self.authenticated(client.get(format!("{}/models", base)))
+    }
+
+    fn probe_request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder {
+        self.authenticated(client.post(format!("{}/messages", base))).json(&serde_json::json!({
+            "model": self.0.model,
+            "max_tokens": 1,
+            "messages": [{"role": "user", "content": "ping"}]
+        }))
+    }
+
+    fn probe_lists_models(&self) -> bool {
+        false
+    }
+}
+
+/// Ollama: a local server without auth, probed through its tag list
+pub struct OllamaProvider<'a>(pub &'a LLMProviderConfig);
+
+impl LlmProvider for OllamaProvider<'_> {
+    fn config(&self) -> &LLMProviderConfig {
+        self.0
+    }
+
+    fn models_request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder {
+        client.get(format!("{}/api/tags", base))
+    }
+}
+
+/// Any server speaking the OpenAI API, e.g. vLLM. Requires an explicit
+/// `base_url`; the key is optional and the catalog endpoint may be missing.
+pub struct CustomOpenAiProvider<'a>(pub &'a LLMProviderConfig);
+
+impl LlmProvider for CustomOpenAiProvider<'_> {
+    fn config(&self) -> &LLMProviderConfig {
+        self.0
+    }
+
+    fn models_request(&self, client: &reqwest::Client, base: &str) -> reqwest::RequestBuilder {
+        let request = client.get(format!("{}/models", base));
+        match self.0.api_key.as_deref().filter(|key| !key.is_empty()) {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    fn catalog_optional(&self) -> bool {
+        true
+    }
+}
+
+/// Probe a provider with the request format its `provider_type` expects,
+/// and record whether it answered for the router
+pub async fn probe_connection(provider: &LLMProviderConfig, timeout: Duration) -> ProbeResult {
+    let result = match provider.provider_type.as_str() {
+        "openai" => OpenAiProvider(provider).probe(timeout).await,
+        "anthropic" => AnthropicProvider(provider).probe(timeout).await,
+        "ollama" => OllamaProvider(provider).probe(timeout).await,
+        "custom_openai" => CustomOpenAiProvider(provider).probe(timeout).await,
+        other => Err(unsupported_provider(other)),
+    };
+    // An error here is a bad config, not an answer from the provider
+    if let Ok(probe) = &result {
+        set_reachable(&provider.name, probe.connected);
+    }
+    result
+}
+
+/// Levenshtein distance between two strings
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Catalog entry closest to `model`. Entries extending `model` (e.g. a
+/// dated or suffixed variant) win over the rest; within each group the
+/// smallest edit distance wins, ties broken alphabetically.
+pub fn closest_model(model: &str, catalog: &[String]) -> Option<String> {
+    catalog
+        .iter()
+        .min_by_key(|entry| (!entry.starts_with(model), edit_distance(model, entry), entry.as_str()))
+        .cloned()
+}
+
+// ============================================================================
+// Routing
+// ============================================================================
+
+/// Shared routing state: reachability, rate-limit cooldowns, and observed traffic split
+#[derive(Default)]
+pub struct RouterState {
+    unreachable: HashSet<String>,
+    cooldowns: HashMap<String, DateTime<Utc>>,
+    routed: HashMap<String, u64>,
+}
+
+/// A provider skipped by the router until `until`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProviderCooldown {
+    pub provider: String,
+    pub until: DateTime<Utc>,
+    pub remaining_seconds: i64,
+}
+
+impl RouterState {
+    /// Skip `name` until `until`
+    pub fn mark_rate_limited(&mut self, name: &str, until: DateTime<Utc>) {
+        self.cooldowns.insert(name.to_string(), until);
+    }
+
+    /// Whether the router may send traffic to `name` at `now`
+    pub fn is_available(&self, name: &str, now: DateTime<Utc>) -> bool {
+        !self.unreachable.contains(name) && self.cooldowns.get(name).map(|until| *until <= now).unwrap_or(true)
+    }
+
+    /// Providers still cooling down at `now`
+    pub fn cooldowns(&self, now: DateTime<Utc>) -> Vec<ProviderCooldown> {
+        let mut active: Vec<ProviderCooldown> = self
+            .cooldowns
+            .iter()
+            .filter(|(_, until)| **until > now)
+            .map(|(provider, until)| ProviderCooldown {
+                provider: provider.clone(),
+                until: *until,
+                remaining_seconds: (*until - now).num_seconds(),
+            })
+            .collect();
+        active.sort_by(|a, b| a.until.cmp(&b.until));
+        active
+    }
+}
+
+fn router() -> &'static Mutex<RouterState> {
+    static STATE: OnceLock<Mutex<RouterState>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(RouterState::default()))
+}
+
+/// Record whether a provider answered its last probe
+pub fn set_reachable(name: &str, reachable: bool) {
+    let mut state = router().lock().unwrap();
+    if reachable {
+        state.unreachable.remove(name);
+    } else {
+        state.unreachable.insert(name.to_string());
+    }
+}
+
+/// Active providers ordered by priority, ties broken by name
+fn by_priority(config: &SynapseConfig) -> Vec<&LLMProviderConfig> {
+    let mut active: Vec<&LLMProviderConfig> = config.llm_providers.iter().filter(|p| p.is_active).collect();
+    active.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.name.cmp(&b.name)));
+    active
+}
+
+/// Active provider with the lowest priority number, ignoring reachability;
+/// ties go to the name that sorts first
+pub fn resolve_active_provider(config: &SynapseConfig) -> Option<&LLMProviderConfig> {
+    by_priority(config).first().copied()
+}
+
+/// Provider to fail over to when `name` fails: the next active one in
+/// priority order, or the first active one when `name` isn't active.
+/// `None` once the order is exhausted.
+pub fn next_provider_after<'a>(config: &'a SynapseConfig, name: &str) -> Option<&'a LLMProviderConfig> {
+    let ordered = by_priority(config);
+    match ordered.iter().position(|p| p.name == name) {
+        Some(index) => ordered.get(index + 1).copied(),
+        None => ordered.first().copied(),
+    }
+}
+
+/// Choose a provider for the next request.
+///
+/// In `"weighted"` mode a provider is drawn among the reachable ones in
+/// proportion to its weight, using `roll` in `[0, 1)`. When no weighted
+/// provider is reachable, or in `"priority"` mode, the reachable provider
+/// with the lowest priority number wins.
+pub fn route_llm_request<'a>(
+    config: &'a SynapseConfig,
+    is_available: impl Fn(&LLMProviderConfig) -> bool,
+    roll: f64,
+) -> Option<&'a LLMProviderConfig> {
+    let candidates: Vec<&LLMProviderConfig> = by_priority(config).into_iter().filter(|p| is_available(*p)).collect();
+
+    if config.routing_mode == "weighted" {
+        let total: u64 = candidates.iter().map(|p| p.weight.unwrap_or(0) as u64).sum();
+        if total > 0 {
+            let target = roll.clamp(0.0, 1.0) * total as f64;
+            let mut cumulative = 0.0;
+            for provider in candidates.iter().filter(|p| p.weight.unwrap_or(0) > 0) {
+                cumulative += provider.weight.unwrap_or(0) as f64;
+                if target < cumulative {
+                    return Some(*provider);
+                }
+            }
+            return candidates.iter().rev().find(|p| p.weight.unwrap_or(0) > 0).copied();
+        }
+    }
+
+    candidates.first().copied()
+}
+
+/// Percentage of requests each provider would get from `route_llm_request`:
+/// split by weight among reachable weighted providers in `"weighted"` mode,
+/// otherwise all to the reachable provider with the lowest priority number
+pub fn routing_shares(
+    config: &SynapseConfig,
+    is_available: impl Fn(&LLMProviderConfig) -> bool,
+) -> Vec<(&LLMProviderConfig, f64)> {
+    let candidates: Vec<&LLMProviderConfig> = by_priority(config).into_iter().filter(|p| is_available(p)).collect();
+
+    if config.routing_mode == "weighted" {
+        let total: u64 = candidates.iter().map(|p| p.weight.unwrap_or(0) as u64).sum();
+        if total > 0 {
+            return candidates
+                .into_iter()
+                .filter(|p| p.weight.unwrap_or(0) > 0)
+                .map(|p| (p, p.weight.unwrap_or(0) as f64 / total as f64 * 100.0))
+                .collect();
+        }
+    }
+
+    candidates.first().map(|p| vec![(*p, 100.0)]).unwrap_or_default()
+}
+
+/// Routing shares given the shared reachability and cooldown state
+pub fn current_routing(config: &SynapseConfig) -> Vec<(&LLMProviderConfig, f64)> {
+    let now = Utc::now();
+    let state = router().lock().unwrap();
+    routing_shares(config, |p| state.is_available(&p.name, now))
+}
+
+/// Provider expected to take the largest share of requests, without
+/// recording traffic; ties go to the higher priority
+pub fn current_provider(config: &SynapseConfig) -> Option<&LLMProviderConfig> {
+    current_routing(config)
+        .into_iter()
+        .fold(None, |best: Option<(&LLMProviderConfig, f64)>, (p, share)| match best {
+            Some((_, best_share)) if best_share >= share => best,
+            _ => Some((p, share)),
+        })
+        .map(|(p, _)| p)
+}
+
+/// Route using shared reachability and cooldown state and record the choice
+pub fn route_and_record(config: &SynapseConfig) -> Option<&LLMProviderConfig> {
+    let now = Utc::now();
+    let mut state = router().lock().unwrap();
+    let chosen = route_llm_request(config, |p| state.is_available(&p.name, now), rand::random::<f64>())?;
+    *state.routed.entry(chosen.name.clone()).or_default() += 1;
+    Some(chosen)
+}
+
+/// How long to cool a provider down after a response, if at all.
+///
+/// A 429, or a parsed rate-limit remaining of zero, triggers a cooldown for
+/// the `retry-after` duration (seconds or HTTP date), defaulting to a minute.
+pub fn rate_limit_cooldown(
+    status: u16,
+    retry_after: Option<&str>,
+    remaining: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<chrono::Duration> {
+    let exhausted = remaining.and_then(|r| r.trim().parse::<u64>().ok()) == Some(0);
+    if status != 429 && !exhausted {
+        return None;
+    }
+
+    let retry_after = retry_after.map(str::trim).and_then(|value| {
+        value
+            .parse::<i64>()
+            .ok()
+            .map(chrono::Duration::seconds)
+            .or_else(|| DateTime::parse_from_rfc2822(value).ok().map(|at| at.with_timezone(&Utc) - now))
+    });
+
+    Some(retry_after.filter(|d| *d > chrono::Duration::zero()).unwrap_or(chrono::Duration::seconds(DEFAULT_COOLDOWN_SECS)))
+}
+
+/// Update shared reachability and rate-limit state from a provider
+/// response; a server error counts as unreachable
+pub fn observe_response(name: &str, response: &reqwest::Response) {
+    let header = |key: &str| response.headers().get(key).and_then(|v| v.to_str().ok());
+    let remaining = header("x-ratelimit-remaining-requests").or_else(|| header("x-ratelimit-remaining"));
+    let now = Utc::now();
+    set_reachable(name, !response.status().is_server_error());
+
+    if let Some(cooldown) = rate_limit_cooldown(response.status().as_u16(), header("retry-after"), remaining, now) {
+        router().lock().unwrap().mark_rate_limited(name, now + cooldown);
+    }
+}
+
+/// Whether the router can currently use a provider
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProviderReachability {
+    pub provider: String,
+    pub is_active: bool,
+    pub reachable: bool,
+    pub cooldown_until: Option<DateTime<Utc>>,
+}
+
+/// Reachability and cooldown state for every configured provider
+pub fn provider_reachability(config: &SynapseConfig) -> Vec<ProviderReachability> {
+    let now = Utc::now();
+    let state = router().lock().unwrap();
+    config
+        .llm_providers
+        .iter()
+        .map(|p| ProviderReachability {
+            provider: p.name.clone(),
+            is_active: p.is_active,
+            reachable: !state.unreachable.contains(&p.name),
+            cooldown_until: state.cooldowns.get(&p.name).copied().filter(|until| *until > now),
+        })
+        .collect()
+}
+
+/// Providers currently skipped because they were rate limited
+pub fn provider_cooldowns() -> Vec<ProviderCooldown> {
+    router().lock().unwrap().cooldowns(Utc::now())
+}
+
+/// Percentage of routed requests that went to each provider
+pub fn routed_distribution() -> HashMap<String, f64> {
+    let state = router().lock().unwrap();
+    let total: u64 = state.routed.values().sum();
+    state
+        .routed
+        .iter()
+        .map(|(name, count)| (name.clone(), *count as f64 / total as f64 * 100.0))
+        .collect()
+}
+
+/// Validate routing settings; weighted mode needs at least one positive weight
+pub fn validate_routing(config: &SynapseConfig) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    match config.routing_mode.as_str() {
+        "priority" => {}
+        "weighted" => {
+            let active: Vec<&LLMProviderConfig> = config.llm_providers.iter().filter(|p| p.is_active).collect();
+            if !active.is_empty() && active.iter().all(|p| p.weight.unwrap_or(0) == 0) {
+                issues.push(ValidationIssue::error(
+                    "llm_providers.weight",
+                    "Weighted routing requires at least one active provider with a weight above zero",
+                ));
+            }
+        }
+        other => issues.push(ValidationIssue::error(
+            "routing_mode",
+            &format!("Unknown routing mode '{}'; expected \"priority\" or \"weighted\"", other),
+        )),
+    }
+    issues
+}
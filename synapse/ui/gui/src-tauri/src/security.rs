@@ -4,8 +4,12 @@
 //! Protocol Version: 1.0
 //! Spec Version: 3.1
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
 use crate::{PROTOCOL_VERSION, SPEC_VERSION};
 
@@ -76,24 +80,1159 @@ pub fn get_capability_tokens(user_id: Option<&str>) -> Vec<CapabilityToken> {
     ]
 }
 
-/// Get audit log
+/// Get audit log entries between two RFC 3339 timestamps (inclusive)
 pub fn get_audit_log(
     start_time: Option<&str>,
     end_time: Option<&str>,
     user_id: Option<&str>,
 ) -> Vec<AuditLogEntry> {
-    // In real implementation, query from database
-    vec![
-        AuditLogEntry {
-            id: "audit-001".to_string(),
-            timestamp: "2026-02-20T12:00:00Z".to_string(),
-            action: "skill_execution".to_string(),
-            user_id: "user-001".to_string(),
-            details: HashMap::from([
-                ("skill_id".to_string(), "skill-001".to_string()),
-                ("status".to_string(), "success".to_string()),
-            ]),
-            protocol_version: SECURITY_PROTOCOL_VERSION.to_string(),
+    let parse = |raw: Option<&str>| {
+        raw.and_then(|raw| DateTime::parse_from_rfc3339(raw).ok()).map(|at| at.with_timezone(&Utc))
+    };
+    let query = crate::audit::AuditQuery {
+        since: parse(start_time),
+        until: parse(end_time),
+        user_id: user_id.map(str::to_string),
+        ..Default::default()
+    };
+    crate::audit::query(&crate::audit::entries(), &query)
+        .into_iter()
+        .map(|entry| {
+            let mut details = HashMap::from([("result".to_string(), entry.result)]);
+            if let Some(resource) = entry.resource {
+                details.insert("resource".to_string(), resource);
+            }
+            if let Some(message) = entry.details {
+                details.insert("details".to_string(), message);
+            }
+            AuditLogEntry {
+                id: entry.id,
+                timestamp: entry.timestamp.to_rfc3339(),
+                action: entry.action,
+                user_id: entry.user_id,
+                details,
+                protocol_version: SECURITY_PROTOCOL_VERSION.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Length of the hash prefix sent to a breach-check service
+const BREACH_PREFIX_LEN: usize = 5;
+
+/// Placeholder or documentation keys that are public by definition
+const KNOWN_EXPOSED_KEY_PATTERNS: &[&str] = &[
+    "your-api-key",
+    "your_api_key",
+    "changeme",
+    "sk-xxxx",
+    "sk-test",
+    "sk-1234567890",
+    "test-key",
+    "example",
+    "placeholder",
+];
+
+/// Key exposure status for one provider
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KeyExposureStatus {
+    pub provider: String,
+    /// Last four characters only, never the full key
+    pub key_hint: Option<String>,
+    pub status: String,
+    pub reasons: Vec<String>,
+    pub online_checked: bool,
+    pub rotate_now: bool,
+    pub protocol_version: String,
+}
+
+/// Uppercase hex SHA-256 of a key, used for k-anonymity lookups
+pub fn key_fingerprint(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:X}", Sha256::digest(key.as_bytes()))
+}
+
+/// Ask a breach-check service whether the key's hash appears in its corpus.
+///
+/// Only the first few hex characters of the hash leave the machine; the
+/// service answers with every known suffix for that prefix and the match
+/// is done locally.
+async fn breach_check(url: &str, key: &str) -> Result<bool, String> {
+    let fingerprint = key_fingerprint(key);
+    let (prefix, suffix) = fingerprint.split_at(BREACH_PREFIX_LEN);
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let body = client
+        .get(format!("{}/range/{}", url.trim_end_matches('/'), prefix))
+        .send()
+        .await
+        .map_err(|e| format!("Breach check unavailable: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Breach check unavailable: {}", e))?;
+
+    Ok(body
+        .lines()
+        .any(|line| line.split(':').next().map(|s| s.trim().eq_ignore_ascii_case(suffix)).unwrap_or(false)))
+}
+
+/// Check a provider key against known-exposed patterns and, when a
+/// breach-check URL is given, the remote corpus
+pub async fn check_key_exposure(provider: &str, api_key: Option<&str>, breach_check_url: Option<&str>) -> KeyExposureStatus {
+    let mut status = KeyExposureStatus {
+        provider: provider.to_string(),
+        key_hint: None,
+        status: "ok".to_string(),
+        reasons: Vec::new(),
+        online_checked: false,
+        rotate_now: false,
+        protocol_version: SECURITY_PROTOCOL_VERSION.to_string(),
+    };
+
+    let key = match api_key.map(str::trim).filter(|k| !k.is_empty()) {
+        Some(key) => key,
+        None => {
+            status.status = "no_key".to_string();
+            return status;
+        }
+    };
+    let chars: Vec<char> = key.chars().collect();
+    status.key_hint = Some(format!("...{}", chars[chars.len().saturating_sub(4)..].iter().collect::<String>()));
+
+    let mut exposed = false;
+    let lowered = key.to_lowercase();
+    if let Some(pattern) = KNOWN_EXPOSED_KEY_PATTERNS.iter().find(|p| lowered.contains(*p)) {
+        exposed = true;
+        status.reasons.push(format!("Key matches publicly known pattern '{}'", pattern));
+    }
+
+    if let Some(url) = breach_check_url {
+        match breach_check(url, key).await {
+            Ok(found) => {
+                status.online_checked = true;
+                if found {
+                    exposed = true;
+                    status.reasons.push("Key hash found in breach corpus".to_string());
+                }
+            }
+            Err(e) => status.reasons.push(e),
+        }
+    }
+
+    if exposed {
+        status.status = "exposed".to_string();
+        status.rotate_now = true;
+    } else if !status.reasons.is_empty() {
+        status.status = "unchecked".to_string();
+    }
+
+    status
+}
+
+/// Prefixes of well-known API key formats
+const SECRET_PREFIXES: &[&str] = &["sk-", "sk_", "gsk_", "xai-", "ghp_", "github_pat_", "AKIA"];
+
+/// Shortest token treated as a key when it carries a known prefix
+const MIN_SECRET_LEN: usize = 16;
+
+/// Replace API keys in free text with `[REDACTED]`.
+///
+/// Redacts every occurrence of the `known` secrets plus any run of key
+/// characters (letters, digits, `-`, `_`) that looks like a provider key by
+/// prefix, wherever it sits: quoted, after `=`, or inside JSON.
+pub fn redact_secrets(text: &str, known: &[&str]) -> String {
+    let mut redacted = text.to_string();
+    for secret in known.iter().filter(|s| !s.is_empty()) {
+        redacted = redacted.replace(secret, "[REDACTED]");
+    }
+
+    let is_key_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let mut output = String::with_capacity(redacted.len());
+    let mut rest = redacted.as_str();
+    while let Some(start) = rest.find(is_key_char) {
+        output.push_str(&rest[..start]);
+        let run = &rest[start..];
+        let end = run.find(|c: char| !is_key_char(c)).unwrap_or(run.len());
+        let word = &run[..end];
+        if word.len() >= MIN_SECRET_LEN && SECRET_PREFIXES.iter().any(|p| word.starts_with(p)) {
+            output.push_str("[REDACTED]");
+        } else {
+            output.push_str(word);
+        }
+        rest = &run[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+// ============================================================================
+// API Key Encryption
+// ============================================================================
+
+/// Prefix marking an encrypted value in the saved config
+pub const ENCRYPTED_PREFIX: &str = "enc:";
+
+/// Keychain service and account holding the config encryption key
+const KEYCHAIN_SERVICE: &str = "synapse-configurator";
+const KEYCHAIN_ACCOUNT: &str = "config-encryption-key";
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+fn unconfigured(reason: impl std::fmt::Display) -> String {
+    format!("API key encryption is unconfigured: {}", reason)
+}
+
+/// The config encryption key, created in the OS keychain on first use and
+/// cached for the life of the process
+fn master_key() -> Result<[u8; 32], String> {
+    static KEY: OnceLock<Mutex<Option<[u8; 32]>>> = OnceLock::new();
+    let mut cached = KEY.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    if let Some(key) = *cached {
+        return Ok(key);
+    }
+
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT).map_err(unconfigured)?;
+    let key = match entry.get_password() {
+        Ok(stored) => {
+            let bytes = hex::decode(stored.trim()).map_err(|_| unconfigured("keychain entry is corrupt"))?;
+            <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_| unconfigured("keychain entry is corrupt"))?
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key: [u8; 32] = rand::random();
+            entry.set_password(&hex::encode(key)).map_err(unconfigured)?;
+            key
+        }
+        Err(e) => return Err(unconfigured(e)),
+    };
+    *cached = Some(key);
+    Ok(key)
+}
+
+/// Whether a stored value was written by `encrypt_secret`
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Encrypt with AES-256-GCM as `enc:<hex nonce||ciphertext>`
+pub fn encrypt_secret_with(key: &[u8; 32], plaintext: &str) -> Result<String, String> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|_| "Failed to encrypt secret".to_string())?;
+    Ok(format!("{}{}{}", ENCRYPTED_PREFIX, hex::encode(nonce), hex::encode(ciphertext)))
+}
+
+/// Decrypt an `enc:` value; anything else is legacy plaintext and returned as-is
+pub fn decrypt_secret_with(key: &[u8; 32], stored: &str) -> Result<String, String> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+    let encoded = match stored.strip_prefix(ENCRYPTED_PREFIX) {
+        Some(encoded) => encoded,
+        None => return Ok(stored.to_string()),
+    };
+    let bytes = hex::decode(encoded).map_err(|_| "Encrypted secret is not valid hex".to_string())?;
+    if bytes.len() <= NONCE_LEN {
+        return Err("Encrypted secret is truncated".to_string());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt secret; the keychain key may have changed".to_string())?;
+    String::from_utf8(plaintext).map_err(|_| "Decrypted secret is not valid UTF-8".to_string())
+}
+
+/// Encrypt a secret with the keychain-held key
+pub fn encrypt_secret(plaintext: &str) -> Result<String, String> {
+    encrypt_secret_with(&master_key()?, plaintext)
+}
+
+/// Decrypt a stored secret with the keychain-held key
+pub fn decrypt_secret(stored: &str) -> Result<String, String> {
+    if !is_encrypted(stored) {
+        return Ok(stored.to_string());
+    }
+    decrypt_secret_with(&master_key()?, stored)
+}
+
+// ============================================================================
+// Capability Implication and Scoping
+// ============================================================================
+
+/// Parsed capability of the form `domain:action[:scope]`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Capability {
+    pub domain: String,
+    pub action: String,
+    /// Path prefix for `fs`, host for `network`; `None` means unrestricted
+    pub scope: Option<String>,
+}
+
+impl Capability {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut parts = raw.trim().splitn(3, ':');
+        let domain = parts.next().filter(|d| !d.is_empty())?;
+        let action = parts.next().filter(|a| !a.is_empty())?;
+        let scope = parts.next().filter(|s| !s.is_empty()).map(str::to_string);
+        Some(Self {
+            domain: domain.to_string(),
+            action: action.to_string(),
+            scope,
+        })
+    }
+
+    /// Canonical `domain:action[:scope]` form
+    pub fn to_string_repr(&self) -> String {
+        match &self.scope {
+            Some(scope) => format!("{}:{}:{}", self.domain, self.action, scope),
+            None => format!("{}:{}", self.domain, self.action),
+        }
+    }
+
+    /// Whether this grant's action covers `needed` (wildcard, or write ⊇ read)
+    fn action_implies(&self, needed: &str) -> bool {
+        self.action == "*" || self.action == needed || (self.domain == "fs" && self.action == "write" && needed == "read")
+    }
+}
+
+/// Capabilities skills may declare, as `domain:action`; a scope may follow
+pub const KNOWN_CAPABILITIES: &[&str] = &["fs:read", "fs:write", "network:http", "process:spawn"];
+
+/// Whether a declared capability names a known `domain:action`
+pub fn is_known_capability(raw: &str) -> bool {
+    Capability::parse(raw)
+        .map(|c| KNOWN_CAPABILITIES.contains(&format!("{}:{}", c.domain, c.action).as_str()))
+        .unwrap_or(false)
+}
+
+/// `path` with `.` and `..` resolved lexically; `None` when `..` climbs
+/// above where the path starts, which no scope can contain
+fn normalize_scope_path(path: &str) -> Option<PathBuf> {
+    let mut normal = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normal.pop() {
+                    return None;
+                }
+            }
+            other => normal.push(other),
+        }
+    }
+    Some(normal)
+}
+
+/// Whether `inner` lies within `outer` for a capability domain. Paths are
+/// compared after resolving `..`, so `/data/../etc` is not within `/data`.
+fn scope_within(domain: &str, inner: &str, outer: &str) -> bool {
+    match domain {
+        "fs" => match (normalize_scope_path(inner), normalize_scope_path(outer)) {
+            (Some(inner), Some(outer)) => inner.starts_with(outer),
+            _ => false,
         },
-    ]
+        "network" => {
+            let outer = outer.trim_start_matches("*.");
+            inner == outer || inner.ends_with(&format!(".{}", outer))
+        }
+        _ => inner == outer,
+    }
+}
+
+/// Narrow a needed capability by a grant, returning the capability the
+/// runtime would receive, or `None` when the grant doesn't cover the need
+pub fn narrow_capability(grant: &Capability, needed: &Capability) -> Option<Capability> {
+    if grant.domain != needed.domain || !grant.action_implies(&needed.action) {
+        return None;
+    }
+    let scope = match (&grant.scope, &needed.scope) {
+        (None, None) => None,
+        (Some(g), None) => Some(g.clone()),
+        (None, Some(n)) => Some(n.clone()),
+        (Some(g), Some(n)) if scope_within(&grant.domain, n, g) => Some(n.clone()),
+        (Some(g), Some(n)) if scope_within(&grant.domain, g, n) => Some(g.clone()),
+        _ => return None,
+    };
+    Some(Capability {
+        domain: needed.domain.clone(),
+        action: needed.action.clone(),
+        scope,
+    })
+}
+
+/// Declared, available, and resulting capability sets for a skill run
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EffectiveCapabilities {
+    pub declared: Vec<String>,
+    pub available: Vec<String>,
+    pub effective: Vec<String>,
+    pub missing: Vec<String>,
+    pub protocol_version: String,
+}
+
+/// Intersect a skill's declared needs with a user's grants after scope narrowing
+pub fn effective_capabilities(declared: &[String], granted: &[String]) -> EffectiveCapabilities {
+    let grants: Vec<Capability> = granted.iter().filter_map(|g| Capability::parse(g)).collect();
+    let mut effective = Vec::new();
+    let mut missing = Vec::new();
+
+    for need in declared {
+        let narrowed = Capability::parse(need)
+            .and_then(|needed| grants.iter().find_map(|grant| narrow_capability(grant, &needed)));
+        match narrowed {
+            Some(capability) => effective.push(capability.to_string_repr()),
+            None => missing.push(need.clone()),
+        }
+    }
+
+    EffectiveCapabilities {
+        declared: declared.to_vec(),
+        available: granted.to_vec(),
+        effective,
+        missing,
+        protocol_version: SECURITY_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+// ============================================================================
+// Capability Token Signing
+// ============================================================================
+
+/// Issued capability tokens, relative to the config directory
+pub const CAPABILITY_TOKENS_FILE: &str = "capability_tokens.json";
+
+/// A capability grant signed with the configured HMAC key
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IssuedCapabilityToken {
+    pub token_id: String,
+    pub user_id: String,
+    pub capabilities: Vec<String>,
+    /// Skill ids the token may be used by; empty for a token any skill can use
+    #[serde(default)]
+    pub skill_scope: Vec<String>,
+    pub issued_at: DateTime<Utc>,
+    /// `None` for tokens that never expire
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Hex HMAC-SHA256 over the claims above
+    pub signature: String,
+    #[serde(default)]
+    pub revoked_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub revoked_by: Option<String>,
+}
+
+/// Fresh random signing key, hex encoded
+pub fn generate_signing_key() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+fn token_mac(key: &[u8], token: &IssuedCapabilityToken) -> hmac::Hmac<sha2::Sha256> {
+    use hmac::Mac;
+
+    // serde_json objects are key-sorted, so the encoding is canonical
+    let mut claims = serde_json::json!({
+        "token_id": token.token_id,
+        "user_id": token.user_id,
+        "capabilities": token.capabilities,
+        "issued_at": token.issued_at.to_rfc3339(),
+        "expires_at": token.expires_at.map(|at| at.to_rfc3339()),
+    });
+    // Only scoped tokens sign a scope, so tokens issued before scoping existed still verify
+    if !token.skill_scope.is_empty() {
+        claims["skill_scope"] = serde_json::json!(token.skill_scope);
+    }
+    let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(claims.to_string().as_bytes());
+    mac
+}
+
+/// Hex HMAC-SHA256 of a token's claims
+pub fn sign_capability_token(key: &[u8], token: &IssuedCapabilityToken) -> String {
+    use hmac::Mac;
+    hex::encode(token_mac(key, token).finalize().into_bytes())
+}
+
+/// Whether a token's signature matches its claims, compared in constant time
+pub fn verify_capability_token(key: &[u8], token: &IssuedCapabilityToken) -> bool {
+    use hmac::Mac;
+    match hex::decode(&token.signature) {
+        Ok(signature) => token_mac(key, token).verify_slice(&signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Why a token can't be used at `now`, or `None` when it's valid
+pub fn token_invalid_reason(key: &[u8], token: &IssuedCapabilityToken, now: DateTime<Utc>) -> Option<String> {
+    if !verify_capability_token(key, token) {
+        return Some("has an invalid signature".to_string());
+    }
+    if let Some(revoked_at) = token.revoked_at {
+        let by = token.revoked_by.as_deref().unwrap_or("unknown");
+        return Some(format!("was revoked by {} at {}", by, revoked_at.to_rfc3339()));
+    }
+    match token.expires_at {
+        Some(expires_at) if expires_at <= now => Some(format!("expired at {}", expires_at.to_rfc3339())),
+        _ => None,
+    }
+}
+
+/// Whether a token is correctly signed, unrevoked and unexpired at `now`
+pub fn capability_token_is_valid(key: &[u8], token: &IssuedCapabilityToken, now: DateTime<Utc>) -> bool {
+    token_invalid_reason(key, token, now).is_none()
+}
+
+/// Check that a token is valid and grants `capability`, allowing scope
+/// narrowing. A skill-scoped token only works for `skill_id` when the skill
+/// is in its scope. Every capability check goes through here.
+pub fn validate_capability(
+    key: &[u8],
+    token: &IssuedCapabilityToken,
+    capability: &str,
+    skill_id: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    if let Some(reason) = token_invalid_reason(key, token, now) {
+        return Err(format!("Token '{}' {}", token.token_id, reason));
+    }
+    if !token.skill_scope.is_empty() && !skill_id.is_some_and(|id| token.skill_scope.iter().any(|s| s == id)) {
+        return Err(match skill_id {
+            Some(id) => format!("Token '{}' is not scoped to skill '{}'", token.token_id, id),
+            None => format!("Token '{}' is scoped to skills {}", token.token_id, token.skill_scope.join(", ")),
+        });
+    }
+    let requested = Capability::parse(capability).ok_or_else(|| format!("'{}' is not a valid capability", capability))?;
+    let granted = token
+        .capabilities
+        .iter()
+        .filter_map(|c| Capability::parse(c))
+        .any(|grant| covers(&grant, &requested));
+    if granted {
+        Ok(())
+    } else {
+        Err(format!("Token '{}' does not grant '{}'", token.token_id, capability))
+    }
+}
+
+/// Mint a signed token; every capability must be a known one. An empty
+/// `skill_scope` leaves the token usable by any skill.
+pub fn issue_capability_token(
+    key: &[u8],
+    user_id: &str,
+    capabilities: Vec<String>,
+    skill_scope: Vec<String>,
+    ttl_seconds: Option<u64>,
+    now: DateTime<Utc>,
+) -> Result<IssuedCapabilityToken, String> {
+    if user_id.trim().is_empty() {
+        return Err("User id is required".to_string());
+    }
+    if capabilities.is_empty() {
+        return Err("At least one capability is required".to_string());
+    }
+    if let Some(unknown) = capabilities.iter().find(|c| !is_known_capability(c)) {
+        return Err(format!("Unknown capability '{}'", unknown));
+    }
+    if skill_scope.iter().any(|id| id.trim().is_empty()) {
+        return Err("Skill ids in a token scope can't be blank".to_string());
+    }
+    let mut skill_scope: Vec<String> = skill_scope.iter().map(|id| id.trim().to_string()).collect();
+    skill_scope.sort();
+    skill_scope.dedup();
+    let expires_at = match ttl_seconds {
+        Some(0) => return Err("Token lifetime must be greater than zero".to_string()),
+        Some(ttl) => {
+            let expires_at = i64::try_from(ttl)
+                .ok()
+                .and_then(chrono::Duration::try_seconds)
+                .and_then(|ttl| now.checked_add_signed(ttl));
+            Some(expires_at.ok_or_else(|| "Token lifetime is too long".to_string())?)
+        }
+        None => None,
+    };
+
+    let mut token = IssuedCapabilityToken {
+        token_id: format!("cap-{}", hex::encode(rand::random::<[u8; 8]>())),
+        user_id: user_id.to_string(),
+        capabilities,
+        skill_scope,
+        issued_at: now,
+        expires_at,
+        signature: String::new(),
+        revoked_at: None,
+        revoked_by: None,
+    };
+    token.signature = sign_capability_token(key, &token);
+    Ok(token)
+}
+
+/// Tokens saved at `path`; none when the file doesn't exist yet
+pub fn load_capability_tokens(path: &Path) -> Result<Vec<IssuedCapabilityToken>, String> {
+    match fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Whether the token file at `path` is confirmed absent, i.e. no token was
+/// ever issued. A file that exists but can't be read or parsed is not.
+pub fn capability_tokens_missing(path: &Path) -> bool {
+    matches!(fs::symlink_metadata(path), Err(e) if e.kind() == std::io::ErrorKind::NotFound)
+}
+
+fn write_capability_tokens(path: &Path, tokens: &[IssuedCapabilityToken]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let raw = serde_json::to_string_pretty(tokens).map_err(|e| e.to_string())?;
+    fs::write(path, raw).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Append a token to the file at `path`
+pub fn save_capability_token(path: &Path, token: &IssuedCapabilityToken) -> Result<(), String> {
+    let mut tokens = load_capability_tokens(path)?;
+    tokens.push(token.clone());
+    write_capability_tokens(path, &tokens)
+}
+
+/// Mark a saved token revoked. Revoking it again keeps the original
+/// revocation and succeeds.
+pub fn revoke_capability_token(
+    path: &Path,
+    token_id: &str,
+    revoked_by: &str,
+    now: DateTime<Utc>,
+) -> Result<IssuedCapabilityToken, String> {
+    let mut tokens = load_capability_tokens(path)?;
+    let token = tokens
+        .iter_mut()
+        .find(|t| t.token_id == token_id)
+        .ok_or_else(|| format!("Capability token '{}' not found", token_id))?;
+    if token.revoked_at.is_some() {
+        return Ok(token.clone());
+    }
+    token.revoked_at = Some(now);
+    token.revoked_by = Some(revoked_by.to_string());
+    let revoked = token.clone();
+    write_capability_tokens(path, &tokens)?;
+    Ok(revoked)
+}
+
+/// Append a user to the trusted list, rejecting blanks and duplicates
+pub fn add_trusted_user(trusted_users: &mut Vec<String>, user_id: &str) -> Result<(), String> {
+    let user_id = user_id.trim();
+    if user_id.is_empty() {
+        return Err("User ID must not be empty".to_string());
+    }
+    if trusted_users.iter().any(|u| u == user_id) {
+        return Err(format!("User '{}' is already trusted", user_id));
+    }
+    trusted_users.push(user_id.to_string());
+    Ok(())
+}
+
+/// Drop a user from the trusted list; returns whether it was present
+pub fn remove_trusted_user(trusted_users: &mut Vec<String>, user_id: &str) -> bool {
+    let before = trusted_users.len();
+    trusted_users.retain(|u| u != user_id.trim());
+    trusted_users.len() != before
+}
+
+// ============================================================================
+// Roles
+// ============================================================================
+
+/// Roles from least to most privileged; each role can do what the ones
+/// before it can
+pub const ROLES: &[&str] = &["viewer", "operator", "admin"];
+
+/// Role of users with no entry in `user_roles`
+pub const DEFAULT_ROLE: &str = "viewer";
+
+fn role_rank(role: &str) -> Option<usize> {
+    ROLES.iter().position(|r| *r == role)
+}
+
+/// A user's configured role, falling back to `DEFAULT_ROLE`
+pub fn role_of<'a>(user_roles: &'a HashMap<String, String>, user_id: &str) -> &'a str {
+    user_roles.get(user_id).map_or(DEFAULT_ROLE, String::as_str)
+}
+
+/// Check that `user_id` holds `required` or a more privileged role
+pub fn require_role(user_roles: &HashMap<String, String>, user_id: &str, required: &str) -> Result<(), String> {
+    let role = role_of(user_roles, user_id);
+    match (role_rank(role), role_rank(required)) {
+        (Some(held), Some(needed)) if held >= needed => Ok(()),
+        _ => Err(format!("Forbidden: requires role '{}'; user '{}' has role '{}'", required, user_id, role)),
+    }
+}
+
+/// Credential a token's holder signs in with: the token id and its
+/// signature. Only the issuance response carries it; listings and the
+/// audit log show the id alone, which proves nothing.
+pub fn bearer_token(token: &IssuedCapabilityToken) -> String {
+    format!("{}.{}", token.token_id, token.signature)
+}
+
+/// Identify the caller presenting `bearer`, as made by `bearer_token`. The
+/// signature must match the saved token's claims and the token must be valid.
+pub fn resolve_caller(
+    tokens: &[IssuedCapabilityToken],
+    key: Option<&[u8]>,
+    bearer: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<String, String> {
+    let bearer = bearer.ok_or_else(|| "no capability token was presented".to_string())?;
+    let (token_id, signature) = bearer
+        .split_once('.')
+        .ok_or_else(|| "the capability token is malformed; present the token given at issuance".to_string())?;
+    let token = tokens
+        .iter()
+        .find(|t| t.token_id == token_id)
+        .ok_or_else(|| format!("capability token '{}' not found", token_id))?;
+    let key = key.ok_or_else(|| "no token signing key is configured".to_string())?;
+    let presented = IssuedCapabilityToken { signature: signature.to_string(), ..token.clone() };
+    if !verify_capability_token(key, &presented) {
+        return Err(format!("capability token '{}' was presented with an invalid signature", token_id));
+    }
+    match token_invalid_reason(key, token, now) {
+        Some(reason) => Err(format!("capability token '{}' {}", token_id, reason)),
+        None => Ok(token.user_id.clone()),
+    }
+}
+
+// ============================================================================
+// Sessions
+// ============================================================================
+
+/// Idle minutes before a session expires when settings don't say otherwise
+pub const DEFAULT_SESSION_TIMEOUT_MINUTES: u32 = 30;
+
+/// A signed-in user's session; activity pushes back its idle expiry
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Session {
+    pub session_id: String,
+    pub user_id: String,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: DateTime<Utc>,
+}
+
+impl Session {
+    fn is_idle(&self, timeout_minutes: u32, now: DateTime<Utc>) -> bool {
+        now - self.last_active_at >= chrono::Duration::minutes(timeout_minutes as i64)
+    }
+}
+
+/// Why a session can't be used
+#[derive(Clone, Debug, PartialEq)]
+pub enum SessionError {
+    NotFound(String),
+    /// The session sat idle past the timeout and has been dropped
+    Expired(Session),
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::NotFound(id) => write!(f, "Session '{}' not found", id),
+            SessionError::Expired(session) => write!(
+                f,
+                "Session '{}' expired after inactivity since {}; sign in again",
+                session.session_id,
+                session.last_active_at.to_rfc3339()
+            ),
+        }
+    }
+}
+
+/// Open sessions by id
+#[derive(Default)]
+pub struct SessionRegistry {
+    sessions: HashMap<String, Session>,
+}
+
+impl SessionRegistry {
+    pub fn create(&mut self, user_id: &str, now: DateTime<Utc>) -> Session {
+        let session = Session {
+            session_id: format!("sess-{}", hex::encode(rand::random::<[u8; 16]>())),
+            user_id: user_id.to_string(),
+            created_at: now,
+            last_active_at: now,
+        };
+        self.sessions.insert(session.session_id.clone(), session.clone());
+        session
+    }
+
+    /// Record activity on a session, dropping it instead if it went idle
+    pub fn touch(&mut self, session_id: &str, timeout_minutes: u32, now: DateTime<Utc>) -> Result<Session, SessionError> {
+        let session = self
+            .sessions
+            .get_mut(session_id)
+            .ok_or_else(|| SessionError::NotFound(session_id.to_string()))?;
+        if session.is_idle(timeout_minutes, now) {
+            let expired = self.sessions.remove(session_id).unwrap();
+            return Err(SessionError::Expired(expired));
+        }
+        session.last_active_at = now;
+        Ok(session.clone())
+    }
+
+    pub fn end(&mut self, session_id: &str) -> Option<Session> {
+        self.sessions.remove(session_id)
+    }
+
+    /// Drop every idle session and return them
+    pub fn expire_idle(&mut self, timeout_minutes: u32, now: DateTime<Utc>) -> Vec<Session> {
+        let idle: Vec<String> = self
+            .sessions
+            .values()
+            .filter(|s| s.is_idle(timeout_minutes, now))
+            .map(|s| s.session_id.clone())
+            .collect();
+        let mut expired: Vec<Session> = idle.iter().filter_map(|id| self.sessions.remove(id)).collect();
+        expired.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        expired
+    }
+}
+
+// ============================================================================
+// Rate Limiting
+// ============================================================================
+
+/// Requests allowed per user per minute when settings don't say otherwise
+pub const DEFAULT_RATE_LIMIT_PER_MINUTE: u32 = 60;
+
+/// Length of the sliding window the rate limit is counted over
+pub const RATE_LIMIT_WINDOW_SECONDS: i64 = 60;
+
+/// Per-user sliding-window limiter for state-changing commands
+#[derive(Default)]
+pub struct RateLimiter {
+    requests: HashMap<String, VecDeque<DateTime<Utc>>>,
+}
+
+impl RateLimiter {
+    /// Count a request from `user_id`. When `limit` requests already fall
+    /// inside the window, nothing is counted and the error carries the
+    /// seconds until the oldest one expires.
+    pub fn check(&mut self, user_id: &str, limit: u32, now: DateTime<Utc>) -> Result<(), u64> {
+        let window = chrono::Duration::seconds(RATE_LIMIT_WINDOW_SECONDS);
+        let requests = self.requests.entry(user_id.to_string()).or_default();
+        while requests.front().is_some_and(|at| now - *at >= window) {
+            requests.pop_front();
+        }
+        if requests.len() as u32 >= limit {
+            let retry_after = requests.front().map_or(window, |oldest| *oldest + window - now);
+            return Err((retry_after.num_milliseconds().max(0) as u64).div_ceil(1000));
+        }
+        requests.push_back(now);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Skill Run Quotas
+// ============================================================================
+
+/// Length of the sliding window skill run quotas are counted over
+pub const SKILL_QUOTA_WINDOW_SECONDS: i64 = 3600;
+
+/// Where a user stands against one skill's run quota
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkillQuota {
+    pub user_id: String,
+    pub skill_id: String,
+    /// Runs allowed per window; `None` when the skill has no quota
+    pub limit: Option<u32>,
+    /// Runs counted inside the current window
+    pub used: u32,
+    pub remaining: Option<u32>,
+    /// When the oldest counted run leaves the window, freeing a slot
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+impl SkillQuota {
+    /// Whole seconds until another run is allowed; zero when one is allowed now
+    pub fn retry_after_seconds(&self, now: DateTime<Utc>) -> u64 {
+        match (self.remaining, self.resets_at) {
+            (Some(0), Some(resets_at)) => ((resets_at - now).num_milliseconds().max(0) as u64).div_ceil(1000),
+            _ => 0,
+        }
+    }
+}
+
+/// Per-user, per-skill sliding-window run counts
+#[derive(Default)]
+pub struct SkillQuotaLedger {
+    runs: HashMap<(String, String), VecDeque<DateTime<Utc>>>,
+}
+
+impl SkillQuotaLedger {
+    fn runs_in_window(&mut self, user_id: &str, skill_id: &str, now: DateTime<Utc>) -> &mut VecDeque<DateTime<Utc>> {
+        let window = chrono::Duration::seconds(SKILL_QUOTA_WINDOW_SECONDS);
+        let runs = self.runs.entry((user_id.to_string(), skill_id.to_string())).or_default();
+        while runs.front().is_some_and(|at| now - *at >= window) {
+            runs.pop_front();
+        }
+        runs
+    }
+
+    /// `user_id`'s standing against `limit` runs of `skill_id`, counting nothing
+    pub fn status(&mut self, user_id: &str, skill_id: &str, limit: Option<u32>, now: DateTime<Utc>) -> SkillQuota {
+        let runs = self.runs_in_window(user_id, skill_id, now);
+        let used = runs.len() as u32;
+        SkillQuota {
+            user_id: user_id.to_string(),
+            skill_id: skill_id.to_string(),
+            limit,
+            used,
+            remaining: limit.map(|limit| limit.saturating_sub(used)),
+            resets_at: runs.front().map(|oldest| *oldest + chrono::Duration::seconds(SKILL_QUOTA_WINDOW_SECONDS)),
+        }
+    }
+
+    /// Count a run of `skill_id` by `user_id`. When the quota is used up
+    /// nothing is counted and the error carries the current standing.
+    pub fn consume(
+        &mut self,
+        user_id: &str,
+        skill_id: &str,
+        limit: Option<u32>,
+        now: DateTime<Utc>,
+    ) -> Result<SkillQuota, SkillQuota> {
+        let before = self.status(user_id, skill_id, limit, now);
+        if before.remaining == Some(0) {
+            return Err(before);
+        }
+        self.runs_in_window(user_id, skill_id, now).push_back(now);
+        Ok(self.status(user_id, skill_id, limit, now))
+    }
+}
+
+// ============================================================================
+// Idempotency Keys
+// ============================================================================
+
+/// Minutes a response stays replayable under its idempotency key
+pub const DEFAULT_IDEMPOTENCY_WINDOW_MINUTES: u32 = 60;
+
+/// Response stored under one idempotency key
+struct IdempotentResponse {
+    /// Action, target, and caller the key was first used for, e.g. `skill_approve:skill-001:alice`
+    fingerprint: String,
+    /// `None` while the first request is still being applied
+    response: Option<serde_json::Value>,
+    stored_at: DateTime<Utc>,
+}
+
+/// Responses of state-changing commands by caller-supplied key, so a retried
+/// request replays the first answer instead of being applied twice
+#[derive(Default)]
+pub struct IdempotencyCache {
+    entries: HashMap<String, IdempotentResponse>,
+}
+
+impl IdempotencyCache {
+    /// Start a request under `key`. Returns the response stored within the
+    /// last `window_minutes`, or `None` after reserving the key for this
+    /// request, which must then be finished with `store` or `release`. A key
+    /// first used for a different fingerprint, or still in flight, is an error.
+    pub fn begin(&mut self, key: &str, fingerprint: &str, window_minutes: u32, now: DateTime<Utc>) -> Result<Option<serde_json::Value>, String> {
+        let window = chrono::Duration::minutes(window_minutes as i64);
+        self.entries.retain(|_, entry| now - entry.stored_at < window);
+        match self.entries.get(key) {
+            Some(entry) if entry.fingerprint != fingerprint => Err(format!(
+                "Idempotency key '{}' was already used for {}",
+                key, entry.fingerprint
+            )),
+            Some(IdempotentResponse { response: Some(response), .. }) => Ok(Some(response.clone())),
+            Some(_) => Err(format!("A request with idempotency key '{}' is still in progress", key)),
+            None => {
+                self.entries.insert(key.to_string(), IdempotentResponse {
+                    fingerprint: fingerprint.to_string(),
+                    response: None,
+                    stored_at: now,
+                });
+                Ok(None)
+            }
+        }
+    }
+
+    /// Remember the response for `key`
+    pub fn store(&mut self, key: &str, fingerprint: &str, response: serde_json::Value, now: DateTime<Utc>) {
+        self.entries.insert(key.to_string(), IdempotentResponse {
+            fingerprint: fingerprint.to_string(),
+            response: Some(response),
+            stored_at: now,
+        });
+    }
+
+    /// Drop the reservation for `key` after a failed request so it can be
+    /// retried. A stored response is left alone.
+    pub fn release(&mut self, key: &str, fingerprint: &str) {
+        if self.entries.get(key).is_some_and(|entry| entry.fingerprint == fingerprint && entry.response.is_none()) {
+            self.entries.remove(key);
+        }
+    }
+}
+
+// ============================================================================
+// Organization Policy
+// ============================================================================
+
+/// Org policy file, relative to the config directory
+pub const ORG_POLICY_FILE: &str = "org_policy.json";
+
+/// One rule of an org policy; `subjects` are `user:<id>`, `role:<name>` or `*`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrgPolicyRule {
+    pub id: String,
+    pub subjects: Vec<String>,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Centrally managed limits on which capabilities may be granted to whom
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OrgPolicy {
+    #[serde(default)]
+    pub user_roles: HashMap<String, Vec<String>>,
+    pub rules: Vec<OrgPolicyRule>,
+}
+
+/// Loaded policy, or why loading failed
+#[derive(Clone, Debug)]
+pub enum OrgPolicyState {
+    /// No policy file: only discretionary grants apply
+    Absent,
+    Loaded(OrgPolicy),
+    /// The file exists but couldn't be used; every grant is denied
+    Invalid(String),
+}
+
+/// A grant the org policy forbids
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolicyViolation {
+    pub token_id: Option<String>,
+    pub user_id: String,
+    pub capability: String,
+    /// Id of the deny rule that matched; `None` when no rule allows the grant
+    pub rule: Option<String>,
+    pub message: String,
+}
+
+fn covers(allowed: &Capability, requested: &Capability) -> bool {
+    narrow_capability(allowed, requested).as_ref() == Some(requested)
+}
+
+/// Whether granting `requested` would confer any part of `denied`: an
+/// unscoped `fs:write` overlaps a deny on `fs:write:/etc`, since it
+/// includes /etc. A wildcard deny action overlaps every action.
+fn overlaps(denied: &Capability, requested: &Capability) -> bool {
+    narrow_capability(requested, denied).is_some() || (denied.action == "*" && narrow_capability(denied, requested).is_some())
+}
+
+fn matches_any(patterns: &[String], requested: &Capability, matches: fn(&Capability, &Capability) -> bool) -> bool {
+    patterns.iter().filter_map(|p| Capability::parse(p)).any(|p| matches(&p, requested))
+}
+
+impl OrgPolicy {
+    fn applies_to(&self, rule: &OrgPolicyRule, user_id: &str) -> bool {
+        let roles = self.user_roles.get(user_id);
+        rule.subjects.iter().any(|subject| {
+            subject == "*"
+                || subject.strip_prefix("user:") == Some(user_id)
+                || subject
+                    .strip_prefix("role:")
+                    .map(|role| roles.map(|r| r.iter().any(|have| have == role)).unwrap_or(false))
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Check one grant: a deny rule overlapping the grant at all wins over
+    /// allow rules, and anything not fully allowed is forbidden
+    pub fn check_grant(&self, user_id: &str, capability: &str) -> Result<(), PolicyViolation> {
+        let violation = |rule: Option<&str>, message: String| PolicyViolation {
+            token_id: None,
+            user_id: user_id.to_string(),
+            capability: capability.to_string(),
+            rule: rule.map(str::to_string),
+            message,
+        };
+        let requested = Capability::parse(capability)
+            .ok_or_else(|| violation(None, format!("'{}' is not a valid capability", capability)))?;
+        let rules: Vec<&OrgPolicyRule> = self.rules.iter().filter(|r| self.applies_to(r, user_id)).collect();
+
+        if let Some(rule) = rules.iter().find(|r| matches_any(&r.deny, &requested, overlaps)) {
+            return Err(violation(
+                Some(&rule.id),
+                format!("Org policy rule '{}' forbids granting '{}' to '{}'", rule.id, capability, user_id),
+            ));
+        }
+        if rules.iter().any(|r| matches_any(&r.allow, &requested, covers)) {
+            return Ok(());
+        }
+        Err(violation(None, format!("No org policy rule permits granting '{}' to '{}'", capability, user_id)))
+    }
+}
+
+impl OrgPolicyState {
+    /// Check a grant under this state, failing closed when the policy is unusable
+    pub fn check_grant(&self, user_id: &str, capability: &str) -> Result<(), PolicyViolation> {
+        match self {
+            OrgPolicyState::Absent => Ok(()),
+            OrgPolicyState::Loaded(policy) => policy.check_grant(user_id, capability),
+            OrgPolicyState::Invalid(reason) => Err(PolicyViolation {
+                token_id: None,
+                user_id: user_id.to_string(),
+                capability: capability.to_string(),
+                rule: None,
+                message: format!("Org policy could not be loaded, denying all grants: {}", reason),
+            }),
+        }
+    }
+}
+
+/// Read the policy at `path`; a missing file means no policy, anything
+/// unreadable or malformed is `Invalid`
+pub fn load_org_policy(path: &Path) -> OrgPolicyState {
+    let raw = match fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return OrgPolicyState::Absent,
+        Err(e) => return OrgPolicyState::Invalid(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let policy: OrgPolicy = match serde_json::from_str(&raw) {
+        Ok(policy) => policy,
+        Err(e) => return OrgPolicyState::Invalid(format!("Failed to parse {}: {}", path.display(), e)),
+    };
+
+    let malformed: Vec<String> = policy
+        .rules
+        .iter()
+        .flat_map(|r| r.allow.iter().chain(r.deny.iter()))
+        .filter(|c| Capability::parse(c).is_none())
+        .cloned()
+        .collect();
+    if !malformed.is_empty() {
+        return OrgPolicyState::Invalid(format!("Invalid capabilities in policy: {}", malformed.join(", ")));
+    }
+    OrgPolicyState::Loaded(policy)
+}
+
+/// (Re)load the org policy from `path` into the shared state
+pub fn reload_org_policy(path: &Path) -> OrgPolicyState {
+    let state = load_org_policy(path);
+    *crate::state::app_state().org_policy().lock().unwrap() = (Some(path.to_path_buf()), state.clone());
+    state
 }
@@ -4,9 +4,16 @@
 //! Protocol Version: 1.0
 //! Spec Version: 3.1
 
+use chrono::{DateTime, Duration, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
 
+use crate::providers::ModelCapabilities;
 use crate::{PROTOCOL_VERSION, SPEC_VERSION};
 
 /// Protocol version constant for skills responses
@@ -82,3 +89,1193 @@ pub fn archive_skill(id: &str) -> bool {
     // In real implementation, update database
     true
 }
+
+// ============================================================================
+// Dependency Resolution
+// ============================================================================
+
+/// Order `root` and its transitive dependencies so every skill follows the
+/// skills it depends on. Siblings are visited by id, so the order is
+/// deterministic. Fails on unknown skills and dependency cycles.
+pub fn resolve_dependency_order(root: &str, dependencies: &HashMap<String, Vec<String>>) -> Result<Vec<String>, String> {
+    fn visit(
+        id: &str,
+        dependencies: &HashMap<String, Vec<String>>,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if order.iter().any(|done| done == id) {
+            return Ok(());
+        }
+        if visiting.iter().any(|v| v == id) {
+            visiting.push(id.to_string());
+            return Err(format!("Dependency cycle: {}", visiting.join(" -> ")));
+        }
+        let mut deps = dependencies.get(id).ok_or_else(|| format!("Skill '{}' not found", id))?.clone();
+        deps.sort();
+
+        visiting.push(id.to_string());
+        for dep in &deps {
+            visit(dep, dependencies, visiting, order)?;
+        }
+        visiting.pop();
+        order.push(id.to_string());
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(root, dependencies, &mut Vec::new(), &mut order)?;
+    Ok(order)
+}
+
+// ============================================================================
+// Model Requirements
+// ============================================================================
+
+/// Minimum model capabilities a skill declares via `min_model_capabilities`
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug)]
+pub struct MinModelCapabilities {
+    #[serde(default)]
+    pub requires_tools: bool,
+    #[serde(default)]
+    pub requires_vision: bool,
+    #[serde(default)]
+    pub min_context_tokens: Option<u64>,
+}
+
+impl MinModelCapabilities {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Check a model against a skill's requirements, naming the first unmet one
+pub fn check_model_requirements(
+    required: &MinModelCapabilities,
+    model: &str,
+    capabilities: Option<&ModelCapabilities>,
+) -> Result<(), String> {
+    if required.is_empty() {
+        return Ok(());
+    }
+    let capabilities = capabilities
+        .ok_or_else(|| format!("Capabilities of model '{}' are unknown, so its requirements can't be verified", model))?;
+
+    if required.requires_tools && !capabilities.supports_tools {
+        return Err(format!("Model '{}' does not support tool use, which this skill requires", model));
+    }
+    if required.requires_vision && !capabilities.supports_vision {
+        return Err(format!("Model '{}' does not support vision input, which this skill requires", model));
+    }
+    if let Some(min) = required.min_context_tokens {
+        if capabilities.context_window < min {
+            return Err(format!(
+                "Model '{}' has a {}-token context window; this skill requires at least {}",
+                model, capabilities.context_window, min
+            ));
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Archiving
+// ============================================================================
+
+/// Who archived a skill, when, and the status to restore on unarchive
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ArchiveRecord {
+    pub previous_status: String,
+    pub archived_by: String,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Archive records by skill id
+#[derive(Clone, Default)]
+pub struct SkillArchive {
+    records: HashMap<String, ArchiveRecord>,
+}
+
+impl SkillArchive {
+    pub fn get(&self, skill_id: &str) -> Option<&ArchiveRecord> {
+        self.records.get(skill_id)
+    }
+
+    /// Archive a skill currently in `current_status`
+    pub fn archive(&mut self, skill_id: &str, current_status: &str, archived_by: &str) -> Result<ArchiveRecord, String> {
+        if self.records.contains_key(skill_id) {
+            return Err(format!("Skill '{}' is already archived", skill_id));
+        }
+        let record = ArchiveRecord {
+            previous_status: current_status.to_string(),
+            archived_by: archived_by.to_string(),
+            archived_at: Utc::now(),
+        };
+        self.records.insert(skill_id.to_string(), record.clone());
+        Ok(record)
+    }
+
+    /// Unarchive a skill, returning the record holding its previous status
+    pub fn unarchive(&mut self, skill_id: &str) -> Result<ArchiveRecord, String> {
+        self.records
+            .remove(skill_id)
+            .ok_or_else(|| format!("Skill '{}' is not archived", skill_id))
+    }
+}
+
+// ============================================================================
+// Risk Scoring
+// ============================================================================
+
+/// Baseline risk of each known capability
+const CAPABILITY_RISK: &[(&str, u8)] = &[("fs:read", 1), ("fs:write", 2), ("network:http", 3), ("process:spawn", 4)];
+
+/// Risk assumed for capabilities missing from `CAPABILITY_RISK`
+const UNKNOWN_CAPABILITY_RISK: u8 = 5;
+
+/// Derive a risk level from declared capabilities: the riskiest one, plus
+/// one when network access combines with writing files or spawning
+/// processes. Scopes are ignored; the result is capped at 5.
+pub fn compute_risk_level(capabilities: &[String]) -> u8 {
+    let kinds: Vec<String> = capabilities
+        .iter()
+        .filter_map(|raw| crate::security::Capability::parse(raw))
+        .map(|c| format!("{}:{}", c.domain, c.action))
+        .collect();
+    let risk_of = |kind: &String| {
+        CAPABILITY_RISK
+            .iter()
+            .find(|(name, _)| name == kind)
+            .map(|(_, risk)| *risk)
+            .unwrap_or(UNKNOWN_CAPABILITY_RISK)
+    };
+    let has = |kind: &str| kinds.iter().any(|k| k == kind);
+
+    let base = kinds.iter().map(risk_of).max().unwrap_or(0);
+    let bump = has("network:http") && (has("fs:write") || has("process:spawn"));
+    (base + bump as u8).min(5)
+}
+
+/// Risk level chosen by a person instead of the computed one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RiskOverride {
+    pub risk_level: u8,
+    pub set_by: String,
+    pub reason: String,
+    pub set_at: DateTime<Utc>,
+}
+
+/// Manual risk overrides by skill id
+#[derive(Clone, Default)]
+pub struct RiskOverrides {
+    overrides: HashMap<String, RiskOverride>,
+}
+
+impl RiskOverrides {
+    pub fn get(&self, skill_id: &str) -> Option<&RiskOverride> {
+        self.overrides.get(skill_id)
+    }
+
+    pub fn set(&mut self, skill_id: &str, risk_override: RiskOverride) {
+        self.overrides.insert(skill_id.to_string(), risk_override);
+    }
+}
+
+// ============================================================================
+// Version History
+// ============================================================================
+
+/// A skill as it was at one version
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkillVersion {
+    pub version: String,
+    pub created_at: DateTime<Utc>,
+    pub risk_level: u8,
+    pub trust_level: String,
+}
+
+/// Known versions per skill and any version pinned by a rollback
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SkillVersionHistory {
+    versions: HashMap<String, Vec<SkillVersion>>,
+    active: HashMap<String, String>,
+}
+
+impl SkillVersionHistory {
+    /// Record a version, replacing an earlier record of the same version
+    pub fn record(&mut self, skill_id: &str, version: SkillVersion) {
+        let versions = self.versions.entry(skill_id.to_string()).or_default();
+        versions.retain(|v| v.version != version.version);
+        versions.push(version);
+        versions.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    }
+
+    /// Known versions of a skill, oldest first
+    pub fn versions(&self, skill_id: &str) -> Vec<SkillVersion> {
+        self.versions.get(skill_id).cloned().unwrap_or_default()
+    }
+
+    /// Version pinned by a rollback, if any
+    pub fn active_version(&self, skill_id: &str) -> Option<&SkillVersion> {
+        let pinned = self.active.get(skill_id)?;
+        self.versions.get(skill_id)?.iter().find(|v| &v.version == pinned)
+    }
+
+    /// Make `target` the active version of a skill
+    pub fn rollback(&mut self, skill_id: &str, target: &str) -> Result<SkillVersion, String> {
+        let versions = self.versions.get(skill_id).map(Vec::as_slice).unwrap_or_default();
+        let version = versions.iter().find(|v| v.version == target).cloned().ok_or_else(|| {
+            let known: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+            format!(
+                "Skill '{}' has no version '{}'; known versions: {}",
+                skill_id,
+                target,
+                if known.is_empty() { "none".to_string() } else { known.join(", ") }
+            )
+        })?;
+        self.active.insert(skill_id.to_string(), target.to_string());
+        Ok(version)
+    }
+
+    /// Record every version in `other`, taking its rollback pins for skills
+    /// with none here. Returns the number of versions recorded.
+    pub fn merge(&mut self, other: SkillVersionHistory) -> usize {
+        let mut recorded = 0;
+        for (skill_id, versions) in other.versions {
+            for version in versions {
+                self.record(&skill_id, version);
+                recorded += 1;
+            }
+        }
+        for (skill_id, pinned) in other.active {
+            self.active.entry(skill_id).or_insert(pinned);
+        }
+        recorded
+    }
+
+    /// Number of versions recorded across every skill
+    pub fn version_count(&self) -> usize {
+        self.versions.values().map(Vec::len).sum()
+    }
+}
+
+/// Earlier releases of the bundled skills
+fn sample_versions() -> SkillVersionHistory {
+    let mut history = SkillVersionHistory::default();
+    let now = Utc::now();
+    history.record("skill-002", SkillVersion {
+        version: "0.9.0".to_string(),
+        created_at: now - Duration::days(30),
+        risk_level: 3,
+        trust_level: "unverified".to_string(),
+    });
+    history.record("skill-002", SkillVersion {
+        version: "1.0.0".to_string(),
+        created_at: now - Duration::days(7),
+        risk_level: 2,
+        trust_level: "verified".to_string(),
+    });
+    history
+}
+
+// ============================================================================
+// Enable/Disable
+// ============================================================================
+
+/// Skills temporarily switched off; approval and trust are left untouched
+#[derive(Clone, Default)]
+pub struct SkillToggles {
+    disabled: HashSet<String>,
+}
+
+impl SkillToggles {
+    pub fn is_enabled(&self, skill_id: &str) -> bool {
+        !self.disabled.contains(skill_id)
+    }
+
+    /// Returns whether the flag actually changed
+    pub fn set_enabled(&mut self, skill_id: &str, enabled: bool) -> bool {
+        if enabled {
+            self.disabled.remove(skill_id)
+        } else {
+            self.disabled.insert(skill_id.to_string())
+        }
+    }
+}
+
+// ============================================================================
+// Tags
+// ============================================================================
+
+/// Longest tag accepted, in characters
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// How a tag filter combines several tags: a skill needs any or all of them
+pub const TAG_MODES: &[&str] = &["any", "all"];
+
+/// Lowercase and trim a tag, rejecting empty, overlong or oddly spelled ones
+pub fn validate_tag(raw: &str) -> Result<String, String> {
+    let tag = raw.trim().to_lowercase();
+    if tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH {
+        return Err(format!("Tag must be 1-{} characters", MAX_TAG_LENGTH));
+    }
+    if !tag.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid tag '{}'; use letters, digits, '-' or '_'", raw));
+    }
+    Ok(tag)
+}
+
+/// Lowercase, sort and deduplicate tags, dropping any that aren't valid
+pub fn normalize_tags(raw: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = raw.iter().filter_map(|tag| validate_tag(tag).ok()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Tags edited after a skill was registered, replacing its manifest tags
+#[derive(Clone, Default)]
+pub struct SkillTags {
+    tags: HashMap<String, Vec<String>>,
+}
+
+impl SkillTags {
+    /// Edited tags of a skill, or `None` while it keeps its manifest tags
+    pub fn get(&self, skill_id: &str) -> Option<&Vec<String>> {
+        self.tags.get(skill_id)
+    }
+
+    /// Add `tag` to `current`; returns the new tags, or `None` if it was there
+    pub fn add(&mut self, skill_id: &str, current: &[String], tag: &str) -> Option<Vec<String>> {
+        if current.iter().any(|t| t == tag) {
+            return None;
+        }
+        let mut tags = current.to_vec();
+        tags.push(tag.to_string());
+        let tags = normalize_tags(&tags);
+        self.tags.insert(skill_id.to_string(), tags.clone());
+        Some(tags)
+    }
+
+    /// Remove `tag` from `current`; returns the new tags, or `None` if it was absent
+    pub fn remove(&mut self, skill_id: &str, current: &[String], tag: &str) -> Option<Vec<String>> {
+        if !current.iter().any(|t| t == tag) {
+            return None;
+        }
+        let tags: Vec<String> = current.iter().filter(|t| *t != tag).cloned().collect();
+        self.tags.insert(skill_id.to_string(), tags.clone());
+        Some(tags)
+    }
+}
+
+/// A skill's name after it was renamed, with the names it had before
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkillRename {
+    pub name: String,
+    /// Earlier names, oldest first
+    pub previous_names: Vec<String>,
+    pub renamed_by: String,
+    pub renamed_at: DateTime<Utc>,
+}
+
+/// Names given to skills after they were registered; ids never change
+#[derive(Clone, Default)]
+pub struct SkillRenames {
+    renames: HashMap<String, SkillRename>,
+}
+
+impl SkillRenames {
+    /// Latest rename of a skill, or `None` while it keeps its manifest name
+    pub fn get(&self, skill_id: &str) -> Option<&SkillRename> {
+        self.renames.get(skill_id)
+    }
+
+    /// Rename `skill_id` from `current` to `new_name`
+    pub fn rename(
+        &mut self,
+        skill_id: &str,
+        current: &str,
+        new_name: &str,
+        renamed_by: &str,
+        now: DateTime<Utc>,
+    ) -> SkillRename {
+        let entry = self.renames.entry(skill_id.to_string()).or_insert_with(|| SkillRename {
+            name: current.to_string(),
+            previous_names: Vec::new(),
+            renamed_by: renamed_by.to_string(),
+            renamed_at: now,
+        });
+        entry.previous_names.push(current.to_string());
+        entry.name = new_name.to_string();
+        entry.renamed_by = renamed_by.to_string();
+        entry.renamed_at = now;
+        entry.clone()
+    }
+
+    /// Id and rename of the skill that was once called `name`, ignoring case
+    pub fn by_previous_name(&self, name: &str) -> Option<(&str, &SkillRename)> {
+        let name = name.to_lowercase();
+        self.renames
+            .iter()
+            .find(|(_, rename)| rename.previous_names.iter().any(|previous| previous.to_lowercase() == name))
+            .map(|(id, rename)| (id.as_str(), rename))
+    }
+}
+
+// ============================================================================
+// Runtime Store
+// ============================================================================
+
+/// Everything layered over the registered skills at runtime, kept together
+/// so one lock guards all of it (`state::AppState::skills`)
+#[derive(Clone)]
+pub struct SkillStore {
+    pub archive: SkillArchive,
+    pub risk_overrides: RiskOverrides,
+    pub versions: SkillVersionHistory,
+    pub toggles: SkillToggles,
+    pub tags: SkillTags,
+    pub renames: SkillRenames,
+}
+
+impl Default for SkillStore {
+    /// Nothing archived, overridden, toggled, tagged or renamed yet, with
+    /// the earlier releases of the bundled skills
+    fn default() -> Self {
+        Self {
+            archive: SkillArchive::default(),
+            risk_overrides: RiskOverrides::default(),
+            versions: sample_versions(),
+            toggles: SkillToggles::default(),
+            tags: SkillTags::default(),
+            renames: SkillRenames::default(),
+        }
+    }
+}
+
+// ============================================================================
+// Skills Registry Backup
+// ============================================================================
+
+/// Manifest file inside each skill directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Persisted lifecycle state inside each skill directory
+pub const STATE_FILE: &str = "state.json";
+
+/// Checksum index stored at the archive root
+const BACKUP_INDEX: &str = "skills_index.json";
+
+/// Top-level folder holding skills inside a backup archive
+const BACKUP_ROOT: &str = "skills";
+
+/// Skill version history stored at the archive root
+const BACKUP_VERSIONS: &str = "skills_versions.json";
+
+/// Result of a skills-only backup
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SkillBackupReport {
+    pub archive_path: String,
+    pub skills_backed_up: usize,
+    pub versions_backed_up: usize,
+    pub protocol_version: String,
+}
+
+/// Result of restoring a skills-only backup
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SkillRestoreReport {
+    pub restored: Vec<String>,
+    pub failed: HashMap<String, String>,
+    pub versions_restored: usize,
+    pub protocol_version: String,
+}
+
+/// JSON Schema types a skill I/O schema may declare
+pub const SCHEMA_TYPES: &[&str] = &["string", "number", "integer", "boolean", "object", "array", "null"];
+
+/// Keywords understood by the skill I/O schema subset
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "type", "properties", "required", "items", "description", "title", "enum", "default",
+    "format", "minimum", "maximum", "minLength", "maxLength", "pattern", "additionalProperties",
+];
+
+/// Type names other languages use, mapped to the JSON Schema type
+const TYPE_ALIASES: &[(&str, &str)] = &[
+    ("str", "string"), ("text", "string"), ("int", "integer"), ("float", "number"), ("double", "number"),
+    ("bool", "boolean"), ("dict", "object"), ("map", "object"), ("list", "array"), ("none", "null"),
+];
+
+/// Likely intended name for an unrecognized one: a known alias, else the
+/// closest of `known` within two edits
+fn suggest_name(raw: &str, known: &[&'static str], aliases: &[(&str, &'static str)]) -> Option<&'static str> {
+    let lowered = raw.to_lowercase();
+    if let Some((_, name)) = aliases.iter().find(|(alias, _)| *alias == lowered) {
+        return Some(name);
+    }
+    known
+        .iter()
+        .map(|name| (crate::providers::edit_distance(&lowered, &name.to_lowercase()), *name))
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// "; did you mean 'x'?" when there is a suggestion, else nothing
+fn did_you_mean(suggestion: Option<&str>) -> String {
+    suggestion.map(|name| format!("; did you mean '{}'?", name)).unwrap_or_default()
+}
+
+/// Validate one schema node, appending problems to `errors`.
+///
+/// Property schemas may carry a boolean `required` flag (the manifest
+/// shorthand); object schemas use a JSON Schema `required` array that must
+/// only name declared properties.
+fn validate_schema_node(schema: &serde_json::Value, path: &str, is_property: bool, errors: &mut Vec<String>) {
+    let object = match schema.as_object() {
+        Some(object) => object,
+        None => {
+            errors.push(format!("{}: schema must be an object", path));
+            return;
+        }
+    };
+
+    for key in object.keys() {
+        if !SCHEMA_KEYWORDS.contains(&key.as_str()) {
+            let suggestion = suggest_name(key, SCHEMA_KEYWORDS, &[]);
+            errors.push(format!("{}: unknown keyword '{}'{}", path, key, did_you_mean(suggestion)));
+        }
+    }
+
+    match object.get("type") {
+        None => {}
+        Some(serde_json::Value::String(t)) if SCHEMA_TYPES.contains(&t.as_str()) => {}
+        Some(serde_json::Value::String(t)) => {
+            let suggestion = suggest_name(t, SCHEMA_TYPES, TYPE_ALIASES);
+            errors.push(format!("{}: unknown type '{}'{}", path, t, did_you_mean(suggestion)));
+        }
+        Some(other) => errors.push(format!("{}: 'type' must be a string, not {}", path, other)),
+    }
+
+    if let Some(properties) = object.get("properties") {
+        match properties.as_object() {
+            Some(properties) => {
+                for (name, property) in properties {
+                    validate_schema_node(property, &format!("{}.{}", path, name), true, errors);
+                }
+            }
+            None => errors.push(format!("{}: 'properties' must be an object", path)),
+        }
+    }
+
+    match object.get("required") {
+        None => {}
+        Some(serde_json::Value::Bool(_)) if is_property => {}
+        Some(serde_json::Value::Array(names)) => {
+            let declared = object.get("properties").and_then(|p| p.as_object());
+            for name in names {
+                match name.as_str() {
+                    Some(name) if declared.map(|d| d.contains_key(name)).unwrap_or(false) => {}
+                    Some(name) => errors.push(format!("{}: required property '{}' is not declared", path, name)),
+                    None => errors.push(format!("{}: 'required' entries must be strings", path)),
+                }
+            }
+        }
+        Some(_) => errors.push(format!("{}: 'required' must be an array of property names", path)),
+    }
+
+    if let Some(items) = object.get("items") {
+        validate_schema_node(items, &format!("{}[]", path), false, errors);
+    }
+}
+
+/// Validate a manifest `inputs`/`outputs` schema.
+///
+/// Accepts either a JSON Schema object (`type`/`properties`/`required`) or
+/// the manifest shorthand mapping each field name to its property schema.
+pub fn validate_io_schema(schema: &serde_json::Value, path: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let is_full_schema = schema.get("type").map(|t| t.is_string()).unwrap_or(false) || schema.get("properties").is_some();
+
+    if is_full_schema {
+        validate_schema_node(schema, path, false, &mut errors);
+    } else if let Some(fields) = schema.as_object() {
+        for (name, property) in fields {
+            validate_schema_node(property, &format!("{}.{}", path, name), true, &mut errors);
+        }
+    } else {
+        errors.push(format!("{}: schema must be an object", path));
+    }
+    errors
+}
+
+/// Problems with a skill's declared input and output schemas
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SkillSchemaReport {
+    pub skill_id: String,
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub protocol_version: String,
+}
+
+/// Check a skill's `inputs` and `outputs` schemas. A null section declares
+/// nothing and is not an error.
+pub fn validate_skill_schemas(skill_id: &str, inputs: &serde_json::Value, outputs: &serde_json::Value) -> SkillSchemaReport {
+    let errors: Vec<String> = [("inputs", inputs), ("outputs", outputs)]
+        .into_iter()
+        .filter(|(_, schema)| !schema.is_null())
+        .flat_map(|(section, schema)| validate_io_schema(schema, section))
+        .collect();
+    SkillSchemaReport {
+        skill_id: skill_id.to_string(),
+        valid: errors.is_empty(),
+        errors,
+        protocol_version: SKILLS_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+/// An input whose JSON type differs from the one its schema declares
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InputTypeMismatch {
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Missing and mistyped inputs found by `check_inputs`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct InputCheck {
+    pub missing: Vec<String>,
+    pub type_mismatches: Vec<InputTypeMismatch>,
+}
+
+impl InputCheck {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.type_mismatches.is_empty()
+    }
+}
+
+/// JSON Schema type of a value; whole numbers are "integer"
+pub fn json_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Check `inputs` against an `inputs` schema in either form
+/// `validate_io_schema` accepts. Only top-level fields are checked and
+/// undeclared inputs are ignored; a null schema declares nothing.
+pub fn check_inputs(schema: &serde_json::Value, inputs: &serde_json::Value) -> InputCheck {
+    let mut check = InputCheck::default();
+    let empty = serde_json::Map::new();
+    let provided = match inputs {
+        serde_json::Value::Object(fields) => fields,
+        serde_json::Value::Null => &empty,
+        other => {
+            check.type_mismatches.push(InputTypeMismatch {
+                field: "inputs".to_string(),
+                expected: "object".to_string(),
+                actual: json_type(other).to_string(),
+            });
+            return check;
+        }
+    };
+
+    let is_full_schema = schema.get("type").map(|t| t.is_string()).unwrap_or(false) || schema.get("properties").is_some();
+    let (properties, required): (&serde_json::Map<String, serde_json::Value>, Vec<&str>) = if is_full_schema {
+        let properties = schema.get("properties").and_then(|p| p.as_object()).unwrap_or(&empty);
+        let required = schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|names| names.iter().filter_map(|n| n.as_str()).collect())
+            .unwrap_or_default();
+        (properties, required)
+    } else {
+        let properties = schema.as_object().unwrap_or(&empty);
+        let required = properties
+            .iter()
+            .filter(|(_, property)| property.get("required").and_then(|r| r.as_bool()).unwrap_or(false))
+            .map(|(name, _)| name.as_str())
+            .collect();
+        (properties, required)
+    };
+
+    for name in required {
+        if provided.get(name).is_none_or(|value| value.is_null()) {
+            check.missing.push(name.to_string());
+        }
+    }
+    for (name, property) in properties {
+        let (Some(value), Some(expected)) = (provided.get(name), property.get("type").and_then(|t| t.as_str())) else {
+            continue;
+        };
+        let actual = json_type(value);
+        let widened = expected == "number" && actual == "integer";
+        // A null required input is already reported as missing
+        let reported = value.is_null() && check.missing.contains(name);
+        if actual != expected && !widened && !reported {
+            check.type_mismatches.push(InputTypeMismatch {
+                field: name.clone(),
+                expected: expected.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+    }
+    check
+}
+
+/// Every problem with a skill manifest, including malformed I/O schemas
+pub fn validate_manifest(manifest: &serde_json::Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    for field in ["name", "version"] {
+        match manifest.get(field).and_then(|v| v.as_str()) {
+            Some(value) if !value.trim().is_empty() => {}
+            _ => errors.push(format!("Manifest field '{}' is missing or empty", field)),
+        }
+    }
+    if !manifest.get("required_capabilities").map(|v| v.is_array()).unwrap_or(false) {
+        errors.push("Manifest field 'required_capabilities' must be an array".to_string());
+    }
+    match manifest.get("risk_level").and_then(|v| v.as_u64()) {
+        Some(level) if level <= 5 => {}
+        _ => errors.push("Manifest field 'risk_level' must be an integer between 0 and 5".to_string()),
+    }
+    for field in [SIGNATURE_FIELD, SIGNER_KEY_FIELD] {
+        if manifest.get(field).map(|v| !v.is_string()).unwrap_or(false) {
+            errors.push(format!("Manifest field '{}' must be a hex string", field));
+        }
+    }
+    if manifest.get("keep_warm").map(|v| !v.is_boolean()).unwrap_or(false) {
+        errors.push("Manifest field 'keep_warm' must be a boolean".to_string());
+    }
+    if let Some(min) = manifest.get("min_model_capabilities") {
+        if let Err(e) = serde_json::from_value::<MinModelCapabilities>(min.clone()) {
+            errors.push(format!("Manifest field 'min_model_capabilities' is invalid: {}", e));
+        }
+    }
+    for section in ["inputs", "outputs"] {
+        if let Some(schema) = manifest.get(section) {
+            errors.extend(validate_io_schema(schema, section));
+        }
+    }
+    errors
+}
+
+/// Check a skill manifest has the fields the registry relies on
+pub fn validate_manifest_value(manifest: &serde_json::Value) -> Result<(), String> {
+    let errors = validate_manifest(manifest);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Lint findings for a manifest: blocking errors plus advisory warnings
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestLint {
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    pub protocol_version: String,
+}
+
+/// Lint a manifest before import or approval
+pub fn lint_skill_manifest(manifest: &serde_json::Value) -> ManifestLint {
+    let errors = validate_manifest(manifest);
+    let mut warnings = Vec::new();
+    if manifest.get("description").and_then(|d| d.as_str()).map(|d| d.trim().is_empty()).unwrap_or(true) {
+        warnings.push("Manifest has no description".to_string());
+    }
+    for section in ["inputs", "outputs"] {
+        if manifest.get(section).is_none() {
+            warnings.push(format!("Manifest declares no {} schema", section));
+        }
+    }
+
+    ManifestLint {
+        valid: errors.is_empty(),
+        errors,
+        warnings,
+        protocol_version: SKILLS_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+// ============================================================================
+// Skill Import
+// ============================================================================
+
+/// Validate a manifest for import: the registry checks plus declared I/O
+/// schemas and capabilities from `security::KNOWN_CAPABILITIES`
+pub fn validate_import_manifest(manifest: &serde_json::Value) -> Vec<String> {
+    let mut errors = validate_manifest(manifest);
+    for section in ["inputs", "outputs"] {
+        if manifest.get(section).is_none() {
+            errors.push(format!("Manifest field '{}' is missing", section));
+        }
+    }
+    let capabilities = manifest.get("required_capabilities").and_then(|v| v.as_array());
+    for capability in capabilities.into_iter().flatten() {
+        match capability.as_str() {
+            Some(raw) if crate::security::is_known_capability(raw) => {}
+            Some(raw) => errors.push(format!("Unknown capability '{}'", raw)),
+            None => errors.push("Manifest field 'required_capabilities' must contain strings".to_string()),
+        }
+    }
+    errors
+}
+
+/// Store a validated manifest under a new skill id in `skills_dir`.
+/// Imported skills always start `pending`/`unverified`.
+pub fn install_skill(skills_dir: &Path, manifest: &serde_json::Value) -> Result<String, String> {
+    let errors = validate_import_manifest(manifest);
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    let id = format!("skill-{}", hex::encode(rand::random::<[u8; 4]>()));
+    let target = skills_dir.join(&id);
+    fs::create_dir_all(&target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    let raw = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    fs::write(target.join(MANIFEST_FILE), raw).map_err(|e| format!("Failed to write manifest: {}", e))?;
+    let state = serde_json::json!({
+        "status": "pending",
+        "trust_level": "unverified",
+        "created_at": Utc::now().to_rfc3339()
+    });
+    fs::write(target.join(STATE_FILE), state.to_string()).map_err(|e| format!("Failed to write skill state: {}", e))?;
+    Ok(id)
+}
+
+/// Manifest and lifecycle state of a skill stored on disk
+pub struct InstalledSkill {
+    pub id: String,
+    pub manifest: serde_json::Value,
+    pub state: serde_json::Value,
+}
+
+/// Skills stored under `skills_dir`, sorted by id. Directories without a
+/// readable manifest are skipped.
+pub fn installed_skills(skills_dir: &Path) -> Vec<InstalledSkill> {
+    let read_json = |path: &Path| -> Option<serde_json::Value> { serde_json::from_slice(&fs::read(path).ok()?).ok() };
+    let mut installed: Vec<InstalledSkill> = fs::read_dir(skills_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|entry| {
+                    Some(InstalledSkill {
+                        id: entry.file_name().to_string_lossy().to_string(),
+                        manifest: read_json(&entry.path().join(MANIFEST_FILE))?,
+                        state: read_json(&entry.path().join(STATE_FILE)).unwrap_or_default(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    installed.sort_by(|a, b| a.id.cmp(&b.id));
+    installed
+}
+
+// ============================================================================
+// Manifest Signatures
+// ============================================================================
+
+/// Manifest field holding the hex Ed25519 signature
+pub const SIGNATURE_FIELD: &str = "signature";
+
+/// Manifest field holding the signer's hex Ed25519 public key
+pub const SIGNER_KEY_FIELD: &str = "signer_public_key";
+
+/// JSON with object keys sorted and no whitespace, so the same manifest
+/// always yields the same bytes whatever order its fields were written in
+pub fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            let entries: Vec<String> = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", serde_json::Value::String(key.clone()), canonical_json(&fields[key])))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        serde_json::Value::Array(items) => format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        other => other.to_string(),
+    }
+}
+
+/// Bytes a manifest's signature covers: the canonical manifest without the
+/// signature itself. The signer key is covered, so it can't be swapped.
+pub fn signed_manifest_bytes(manifest: &serde_json::Value) -> Vec<u8> {
+    let mut unsigned = manifest.clone();
+    if let Some(fields) = unsigned.as_object_mut() {
+        fields.remove(SIGNATURE_FIELD);
+    }
+    canonical_json(&unsigned).into_bytes()
+}
+
+/// Outcome of checking a manifest's signature
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SignatureCheck {
+    /// "valid", "invalid" or "missing"
+    pub status: String,
+    pub signer_public_key: Option<String>,
+    /// Valid and made by one of the configured trusted keys
+    pub trusted: bool,
+    pub message: String,
+}
+
+impl SignatureCheck {
+    fn new(status: &str, signer_public_key: Option<String>, trusted: bool, message: String) -> Self {
+        Self { status: status.to_string(), signer_public_key, trusted, message }
+    }
+
+    /// Trust level this check earns: "verified" only for a valid signature
+    /// from a trusted key, "unverified" for anything else
+    pub fn trust_level(&self) -> &'static str {
+        if self.status == "valid" && self.trusted {
+            "verified"
+        } else {
+            "unverified"
+        }
+    }
+}
+
+fn decode_hex<const N: usize>(raw: &str, what: &str) -> Result<[u8; N], String> {
+    hex::decode(raw.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("{} must be {} hex-encoded bytes", what, N))
+}
+
+/// Parse a hex Ed25519 public key
+pub fn parse_public_key(raw: &str) -> Result<ed25519_dalek::VerifyingKey, String> {
+    let bytes = decode_hex::<32>(raw, "Public key")?;
+    ed25519_dalek::VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid public key: {}", e))
+}
+
+/// Check a manifest's Ed25519 signature against its own signer key, and
+/// whether that key is among `trusted_keys` (hex, compared case-insensitively)
+pub fn verify_manifest_signature(manifest: &serde_json::Value, trusted_keys: &[String]) -> SignatureCheck {
+    let field = |name: &str| manifest.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let (Some(signature), Some(signer)) = (field(SIGNATURE_FIELD), field(SIGNER_KEY_FIELD)) else {
+        return SignatureCheck::new("missing", field(SIGNER_KEY_FIELD), false, "Manifest is not signed".to_string());
+    };
+    let verified = parse_public_key(&signer).and_then(|key| {
+        let signature = ed25519_dalek::Signature::from_bytes(&decode_hex::<64>(&signature, "Signature")?);
+        key.verify_strict(&signed_manifest_bytes(manifest), &signature)
+            .map_err(|_| "Signature does not match the manifest; it was altered or signed by another key".to_string())
+    });
+    match verified {
+        Ok(()) => {
+            let trusted = trusted_keys.iter().any(|k| k.trim().eq_ignore_ascii_case(signer.trim()));
+            let message = if trusted { "Signed by a trusted key" } else { "Valid signature from a key that isn't trusted" };
+            SignatureCheck::new("valid", Some(signer), trusted, message.to_string())
+        }
+        Err(e) => SignatureCheck::new("invalid", Some(signer), false, e),
+    }
+}
+
+/// Record an installed skill's trust level in its state file
+pub fn set_installed_trust_level(skills_dir: &Path, skill_id: &str, trust_level: &str) -> Result<(), String> {
+    let path = skills_dir.join(skill_id).join(STATE_FILE);
+    let mut state: serde_json::Value = fs::read(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_slice(&raw).ok())
+        .unwrap_or_else(|| serde_json::json!({}));
+    state["trust_level"] = serde_json::json!(trust_level);
+    fs::write(&path, state.to_string()).map_err(|e| format!("Failed to write skill state: {}", e))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Add `value` as the JSON file `path` in an archive being built
+fn append_json<W: std::io::Write>(builder: &mut tar::Builder<W>, path: &str, value: &impl Serialize) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, path, bytes.as_slice()).map_err(|e| e.to_string())
+}
+
+/// Archive every skill directory under `skills_dir` into a `.tar.gz` in
+/// `destination`, with a checksum index of the manifests and the skills'
+/// version `history`.
+pub fn backup_skills(skills_dir: &Path, destination: &Path, history: &SkillVersionHistory) -> Result<SkillBackupReport, String> {
+    fs::create_dir_all(destination).map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+    let archive_path = destination.join(format!("skills-{}.tar.gz", Utc::now().format("%Y%m%dT%H%M%SZ")));
+
+    let mut index: HashMap<String, String> = HashMap::new();
+    let file = fs::File::create(&archive_path).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let entries = fs::read_dir(skills_dir).map_err(|e| format!("Failed to read {}: {}", skills_dir.display(), e))?;
+    for entry in entries.filter_map(|e| e.ok()).filter(|e| e.path().join(MANIFEST_FILE).is_file()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let manifest = fs::read(entry.path().join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+        index.insert(name.clone(), sha256_hex(&manifest));
+        builder
+            .append_dir_all(format!("{}/{}", BACKUP_ROOT, name), entry.path())
+            .map_err(|e| format!("Failed to archive skill '{}': {}", name, e))?;
+    }
+
+    append_json(&mut builder, BACKUP_INDEX, &index)?;
+    append_json(&mut builder, BACKUP_VERSIONS, history)?;
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| format!("Failed to finish archive: {}", e))?;
+
+    Ok(SkillBackupReport {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        skills_backed_up: index.len(),
+        versions_backed_up: history.version_count(),
+        protocol_version: SKILLS_PROTOCOL_VERSION.to_string(),
+    })
+}
+
+/// Check that a skill directory name from an archive is one plain path
+/// component, so joining it to the skills directory can't escape it
+fn check_restored_name(name: &str) -> Result<(), String> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(_)), None) => Ok(()),
+        _ => Err(format!("'{}' is not a valid skill directory name", name)),
+    }
+}
+
+/// Copy the staged skill directory `staged` to `skills_dir/name`, replacing
+/// any skill there, reset to `pending`/`unverified` so nothing comes back
+/// approved or trusted without review.
+///
+/// The copy is assembled in a sibling temporary directory and renamed into
+/// place, so a failed copy leaves the existing skill untouched.
+pub fn install_restored_skill(staged: &Path, skills_dir: &Path, name: &str) -> Result<(), String> {
+    check_restored_name(name)?;
+    let target = skills_dir.join(name);
+    let work = tempfile::Builder::new()
+        .prefix(".restore-")
+        .tempdir_in(skills_dir)
+        .map_err(|e| format!("Failed to create a temporary directory: {}", e))?;
+    let incoming = work.path().join("incoming");
+    copy_dir(staged, &incoming)?;
+    let state = serde_json::json!({ "status": "pending", "trust_level": "unverified" });
+    fs::write(incoming.join(STATE_FILE), state.to_string()).map_err(|e| e.to_string())?;
+
+    // The replaced skill moves into the temporary directory, which is
+    // removed when it goes out of scope
+    let replaced = work.path().join("replaced");
+    let had_target = target.exists();
+    if had_target {
+        fs::rename(&target, &replaced).map_err(|e| format!("Failed to move the existing skill aside: {}", e))?;
+    }
+    if let Err(e) = fs::rename(&incoming, &target) {
+        if had_target {
+            let _ = fs::rename(&replaced, &target);
+        }
+        return Err(format!("Failed to install the restored skill: {}", e));
+    }
+    Ok(())
+}
+
+/// Restore skills from a `backup_skills` archive into `skills_dir`.
+///
+/// Every restored skill is reset to `pending`/`unverified` so nothing comes
+/// back approved without review. Skills whose name isn't a plain directory
+/// name, whose manifest fails validation or doesn't match the archived
+/// checksum, or whose signature doesn't verify are skipped and reported.
+/// Unsigned manifests are restored, as on install. Archived versions are
+/// merged into `history`; archives made before versions were backed up
+/// restore none.
+pub fn restore_skills(
+    archive_path: &Path,
+    skills_dir: &Path,
+    history: &mut SkillVersionHistory,
+) -> Result<SkillRestoreReport, String> {
+    let staging = tempfile::tempdir().map_err(|e| format!("Failed to create staging directory: {}", e))?;
+    let file = fs::File::open(archive_path).map_err(|e| format!("Failed to open {}: {}", archive_path.display(), e))?;
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(staging.path())
+        .map_err(|e| format!("Failed to extract archive: {}", e))?;
+
+    let index: HashMap<String, String> = fs::read(staging.path().join(BACKUP_INDEX))
+        .ok()
+        .and_then(|raw| serde_json::from_slice(&raw).ok())
+        .ok_or_else(|| "Archive is missing its skills index".to_string())?;
+
+    let mut restored = Vec::new();
+    let mut failed = HashMap::new();
+    fs::create_dir_all(skills_dir).map_err(|e| format!("Failed to create {}: {}", skills_dir.display(), e))?;
+
+    for (name, checksum) in &index {
+        let staged = staging.path().join(BACKUP_ROOT).join(name);
+        let result = check_restored_name(name)
+            .and_then(|_| fs::read(staged.join(MANIFEST_FILE)).map_err(|e| format!("Manifest unreadable: {}", e)))
+            .and_then(|raw| {
+                if sha256_hex(&raw) != *checksum {
+                    return Err("Manifest does not match archived checksum".to_string());
+                }
+                let manifest: serde_json::Value =
+                    serde_json::from_slice(&raw).map_err(|e| format!("Manifest is not valid JSON: {}", e))?;
+                validate_manifest_value(&manifest)?;
+                let signature = verify_manifest_signature(&manifest, &[]);
+                if signature.status == "invalid" {
+                    return Err(signature.message);
+                }
+                Ok(())
+            })
+            .and_then(|_| install_restored_skill(&staged, skills_dir, name));
+
+        match result {
+            Ok(()) => restored.push(name.clone()),
+            Err(e) => {
+                failed.insert(name.clone(), e);
+            }
+        }
+    }
+    restored.sort();
+    let versions_restored = match fs::read(staging.path().join(BACKUP_VERSIONS)) {
+        Ok(raw) => {
+            let archived: SkillVersionHistory =
+                serde_json::from_slice(&raw).map_err(|e| format!("Archived skill versions are not valid: {}", e))?;
+            history.merge(archived)
+        }
+        Err(_) => 0,
+    };
+
+    Ok(SkillRestoreReport {
+        restored,
+        failed,
+        versions_restored,
+        protocol_version: SKILLS_PROTOCOL_VERSION.to_string(),
+    })
+}
+
+/// Copy `from` into `to` recursively, overwriting files that exist in both.
+/// Symbolic links are refused, since following one could copy files from
+/// outside `from`.
+pub fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| e.to_string())?;
+    for entry in fs::read_dir(from).map_err(|e| e.to_string())?.filter_map(|e| e.ok()) {
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        if file_type.is_symlink() {
+            return Err(format!("{} is a symbolic link", entry.path().display()));
+        }
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! Shared Application State Module
+//!
+//! One `AppState` per process holds what commands read and change: the
+//! loaded config, everything layered over the skill registry, the audit
+//! log, sessions and request limits, and the metrics accumulators. Tauri
+//! injects it into commands as `SharedState`; code running outside a
+//! command reaches the same instance through `app_state`.
+//!
+//! Every lock here is independent. Nothing holds one while taking
+//! another: callers copy what they need out of a guard and drop it first.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+
+use crate::audit::{self, AuditLog};
+use crate::commands::{self, SynapseConfig};
+use crate::metrics::{self, BudgetTracker, ExecutionHistory, MetricsHistory, RunningExecutions, UsageLedger};
+use crate::security::{
+    IdempotencyCache, OrgPolicyState, RateLimiter, SessionRegistry, SkillQuota, SkillQuotaLedger,
+};
+use crate::skills::SkillStore;
+
+/// Outcome of approving or rejecting a skill
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkillReview {
+    /// Status the skill takes on: "active" once approved, "rejected" otherwise
+    pub status: String,
+    pub reviewed_by: String,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+/// Modification time and length of a file, `None` when it doesn't exist
+type FileStamp = Option<(SystemTime, u64)>;
+
+fn file_stamp(path: &Path) -> FileStamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Config as last read, with what it was read from
+struct CachedConfig {
+    dir: PathBuf,
+    profile: String,
+    stamps: (FileStamp, FileStamp),
+    config: SynapseConfig,
+}
+
+/// State shared by every command, safe to use from concurrent invocations
+pub struct AppState {
+    config: RwLock<Option<CachedConfig>>,
+    skill_reviews: RwLock<HashMap<String, SkillReview>>,
+    skill_runs: RwLock<SkillQuotaLedger>,
+    skills: Mutex<SkillStore>,
+    audit_log: Mutex<AuditLog>,
+    sessions: Mutex<SessionRegistry>,
+    rate_limiter: Mutex<RateLimiter>,
+    idempotency_cache: Mutex<IdempotencyCache>,
+    /// Loaded org policy and the file it came from
+    org_policy: Mutex<(Option<PathBuf>, OrgPolicyState)>,
+    usage_ledger: Mutex<UsageLedger>,
+    budget_tracker: Mutex<BudgetTracker>,
+    execution_history: Mutex<ExecutionHistory>,
+    running_executions: Mutex<RunningExecutions>,
+    metrics_history: Mutex<MetricsHistory>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            config: RwLock::default(),
+            skill_reviews: RwLock::default(),
+            skill_runs: RwLock::default(),
+            skills: Mutex::default(),
+            audit_log: Mutex::new(AuditLog::new(audit::DEFAULT_MAX_ENTRIES_PER_MINUTE)),
+            sessions: Mutex::default(),
+            rate_limiter: Mutex::default(),
+            idempotency_cache: Mutex::default(),
+            org_policy: Mutex::new((None, OrgPolicyState::Absent)),
+            usage_ledger: Mutex::new(metrics::sample_usage()),
+            budget_tracker: Mutex::default(),
+            execution_history: Mutex::default(),
+            running_executions: Mutex::default(),
+            metrics_history: Mutex::new(MetricsHistory::new(metrics::DEFAULT_HISTORY_CAPACITY)),
+        }
+    }
+}
+
+impl AppState {
+    /// Archive records, risk overrides, versions, toggles, tags and renames
+    pub fn skills(&self) -> &Mutex<SkillStore> {
+        &self.skills
+    }
+
+    pub fn audit_log(&self) -> &Mutex<AuditLog> {
+        &self.audit_log
+    }
+
+    pub fn sessions(&self) -> &Mutex<SessionRegistry> {
+        &self.sessions
+    }
+
+    /// Per-user limiter consulted by state-changing commands
+    pub fn rate_limiter(&self) -> &Mutex<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// Responses replayed for repeated idempotency keys
+    pub fn idempotency_cache(&self) -> &Mutex<IdempotencyCache> {
+        &self.idempotency_cache
+    }
+
+    pub fn org_policy(&self) -> &Mutex<(Option<PathBuf>, OrgPolicyState)> {
+        &self.org_policy
+    }
+
+    pub fn usage_ledger(&self) -> &Mutex<UsageLedger> {
+        &self.usage_ledger
+    }
+
+    pub fn budget_tracker(&self) -> &Mutex<BudgetTracker> {
+        &self.budget_tracker
+    }
+
+    pub fn execution_history(&self) -> &Mutex<ExecutionHistory> {
+        &self.execution_history
+    }
+
+    pub fn running_executions(&self) -> &Mutex<RunningExecutions> {
+        &self.running_executions
+    }
+
+    /// System metrics filled by the background sampler
+    pub fn metrics_history(&self) -> &Mutex<MetricsHistory> {
+        &self.metrics_history
+    }
+
+    /// The active profile's config, re-read only when the profile switches
+    /// or its file changes on disk; defaults when nothing is saved
+    pub fn config(&self) -> Result<SynapseConfig, String> {
+        self.config_in(&commands::config_dir()?)
+    }
+
+    /// `config` for the config directory `dir`. A profile that exists but
+    /// can't be read is an error rather than defaults, so nothing mistakes
+    /// it for a fresh install and saves over it.
+    pub fn config_in(&self, dir: &Path) -> Result<SynapseConfig, String> {
+        let profile = commands::active_profile(dir);
+        let profile_file = commands::profile_path(dir, &profile)?;
+        // The default profile falls back to the legacy single config file
+        let stamps = (file_stamp(&profile_file), file_stamp(&dir.join(commands::CONFIG_FILE)));
+        if let Some(cached) = self.config.read().unwrap().as_ref() {
+            if cached.dir == dir && cached.profile == profile && cached.stamps == stamps {
+                return Ok(cached.config.clone());
+            }
+        }
+        let config = commands::load_profile(dir, &profile)?.unwrap_or_default();
+        let cached = CachedConfig { dir: dir.to_path_buf(), profile, stamps, config: config.clone() };
+        *self.config.write().unwrap() = Some(cached);
+        Ok(config)
+    }
+
+    /// Drop the cached config so the next read goes to disk
+    pub fn invalidate_config(&self) {
+        *self.config.write().unwrap() = None;
+    }
+
+    /// Record an approval or rejection; later reads of the registry show it
+    pub fn record_review(&self, skill_id: &str, status: &str, reviewed_by: &str, now: DateTime<Utc>) {
+        self.skill_reviews.write().unwrap().insert(skill_id.to_string(), SkillReview {
+            status: status.to_string(),
+            reviewed_by: reviewed_by.to_string(),
+            reviewed_at: now,
+        });
+    }
+
+    pub fn skill_review(&self, skill_id: &str) -> Option<SkillReview> {
+        self.skill_reviews.read().unwrap().get(skill_id).cloned()
+    }
+
+    /// Where `user_id` stands against `limit` hourly runs of `skill_id`
+    pub fn skill_quota(&self, user_id: &str, skill_id: &str, limit: Option<u32>, now: DateTime<Utc>) -> SkillQuota {
+        self.skill_runs.write().unwrap().status(user_id, skill_id, limit, now)
+    }
+
+    /// Count a run of `skill_id` unless `user_id` has used up its quota
+    pub fn consume_skill_run(
+        &self,
+        user_id: &str,
+        skill_id: &str,
+        limit: Option<u32>,
+        now: DateTime<Utc>,
+    ) -> Result<SkillQuota, SkillQuota> {
+        self.skill_runs.write().unwrap().consume(user_id, skill_id, limit, now)
+    }
+}
+
+/// How Tauri injects the process-wide state into a command
+pub type SharedState<'a> = tauri::State<'a, &'static AppState>;
+
+/// The process-wide state. `main` hands the same instance to Tauri with
+/// `manage`, and code outside commands reaches it here.
+pub fn app_state() -> &'static AppState {
+    static STATE: OnceLock<AppState> = OnceLock::new();
+    STATE.get_or_init(AppState::default)
+}
@@ -0,0 +1,85 @@
+//! Webhook Notifications Module
+//!
+//! Posts skill state changes to the webhook URLs in the security settings.
+//! Protocol Version: 1.0
+//! Spec Version: 3.1
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::audit;
+use crate::PROTOCOL_VERSION;
+
+/// How long one delivery may take before it is abandoned
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Event POSTed as JSON to every webhook after a skill changes state
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SkillEvent {
+    pub skill_id: String,
+    /// "approved", "rejected" or "archived"
+    pub action: String,
+    pub actor: String,
+    pub timestamp: DateTime<Utc>,
+    pub protocol_version: String,
+}
+
+impl SkillEvent {
+    pub fn new(skill_id: &str, action: &str, actor: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            skill_id: skill_id.to_string(),
+            action: action.to_string(),
+            actor: actor.to_string(),
+            timestamp: now,
+            protocol_version: PROTOCOL_VERSION.to_string(),
+        }
+    }
+}
+
+/// Check a webhook URL is an absolute http(s) URL
+pub fn validate_webhook_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("'{}' is not a valid URL: {}", url, e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(format!("'{}' uses unsupported scheme '{}'; expected http or https", url, scheme)),
+    }
+}
+
+/// POST `event` to one webhook; anything but a 2xx answer is a failure
+pub async fn deliver(url: &str, event: &SkillEvent) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(url)
+        .json(event)
+        .send()
+        .await
+        .map_err(|e| format!("Webhook {} unreachable: {}", url, e))?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("Webhook {} answered {}", url, response.status()))
+    }
+}
+
+/// Deliver `event` to every URL in the background and return at once, so a
+/// slow webhook never holds up the command that triggered it. Failures are
+/// logged and, when `audit_failures` is set, recorded in the audit log.
+pub fn notify(urls: &[String], audit_failures: bool, event: SkillEvent) -> Vec<tokio::task::JoinHandle<()>> {
+    urls.iter()
+        .map(|url| {
+            let (url, event) = (url.clone(), event.clone());
+            tokio::spawn(async move {
+                if let Err(e) = deliver(&url, &event).await {
+                    tracing::warn!(skill_id = %event.skill_id, action = %event.action, error = %e, "webhook delivery failed");
+                    if audit_failures {
+                        audit::record("webhook_delivery", &event.actor, "failure", Some(&event.skill_id), Some(e));
+                    }
+                }
+            })
+        })
+        .collect()
+}
@@ -4,9 +4,16 @@
 //! Protocol Version: 1.0
 //! Spec Version: 3.1
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
+use crate::commands::{LLMProviderConfig, SynapseConfig};
+use crate::isolation::{is_policy_available, IsolationBackendStatus, ISOLATION_POLICIES};
+use crate::providers::ConnectionProbe;
 use crate::{PROTOCOL_VERSION, SPEC_VERSION};
 
 /// Protocol version constant for wizard responses
@@ -22,9 +29,149 @@ pub struct WizardStep {
     pub protocol_version: String,
 }
 
-/// Get wizard steps
-pub fn get_wizard_steps() -> Vec<WizardStep> {
-    vec![
+/// Wizard progress file, relative to the config directory
+pub const WIZARD_STATE_FILE: &str = "wizard_state.json";
+
+/// Step ids in the order the wizard walks them
+pub const WIZARD_STEP_IDS: &[&str] = &["welcome", "language", "llm", "storage", "security", "review"];
+
+/// Persisted wizard progress, so setup resumes where it was left
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WizardState {
+    pub completed_steps: Vec<String>,
+    pub current_step: String,
+    /// Set once the final "review" step completes; the wizard then stays hidden
+    pub finished: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for WizardState {
+    fn default() -> Self {
+        Self {
+            completed_steps: Vec::new(),
+            current_step: WIZARD_STEP_IDS[0].to_string(),
+            finished: false,
+            updated_at: None,
+        }
+    }
+}
+
+impl WizardState {
+    /// Mark a step complete or not. Completing a step moves on to the next
+    /// one; reopening a step makes it current again.
+    pub fn set_step_complete(&mut self, step_id: &str, is_complete: bool, now: DateTime<Utc>) -> Result<(), String> {
+        let index = WIZARD_STEP_IDS
+            .iter()
+            .position(|id| *id == step_id)
+            .ok_or_else(|| format!("Unknown wizard step '{}'; expected one of {}", step_id, WIZARD_STEP_IDS.join(", ")))?;
+        self.completed_steps.retain(|id| id != step_id);
+        if is_complete {
+            self.completed_steps.push(step_id.to_string());
+            self.completed_steps.sort_by_key(|id| WIZARD_STEP_IDS.iter().position(|s| s == id));
+            self.current_step = WIZARD_STEP_IDS.get(index + 1).unwrap_or(&step_id).to_string();
+            if index == WIZARD_STEP_IDS.len() - 1 {
+                self.finished = true;
+            }
+        } else {
+            self.current_step = step_id.to_string();
+            self.finished = false;
+        }
+        self.updated_at = Some(now);
+        Ok(())
+    }
+
+    pub fn is_complete(&self, step_id: &str) -> bool {
+        self.completed_steps.iter().any(|id| id == step_id)
+    }
+}
+
+/// Where the wizard stands and where it may move
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WizardStatus {
+    pub current_step: String,
+    /// Zero-based position of `current_step`
+    pub step_index: usize,
+    pub total_steps: usize,
+    /// Share of steps marked complete, rounded down to a whole percent
+    pub percent_complete: u32,
+    pub can_go_back: bool,
+    /// Whether the current step's input validates; on "review" this
+    /// finishes the wizard
+    pub can_go_next: bool,
+    /// Validation problems blocking `can_go_next`
+    pub errors: Vec<String>,
+    pub finished: bool,
+    pub protocol_version: String,
+}
+
+/// Status of `state`, validating the current step against `payload`
+pub fn wizard_status(state: &WizardState, payload: &serde_json::Value) -> WizardStatus {
+    let step_index = WIZARD_STEP_IDS.iter().position(|id| *id == state.current_step).unwrap_or(0);
+    let completed = WIZARD_STEP_IDS.iter().filter(|id| state.is_complete(id)).count();
+    let errors = validate_wizard_step(WIZARD_STEP_IDS[step_index], payload).unwrap_or_default();
+
+    WizardStatus {
+        current_step: WIZARD_STEP_IDS[step_index].to_string(),
+        step_index,
+        total_steps: WIZARD_STEP_IDS.len(),
+        percent_complete: (completed * 100 / WIZARD_STEP_IDS.len()) as u32,
+        can_go_back: step_index > 0,
+        can_go_next: errors.is_empty(),
+        errors,
+        finished: state.finished,
+        protocol_version: WIZARD_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+/// Complete the current step and advance, unless its input is invalid
+pub fn wizard_next(state: &mut WizardState, payload: &serde_json::Value, now: DateTime<Utc>) -> Result<WizardStatus, String> {
+    let status = wizard_status(state, payload);
+    if !status.can_go_next {
+        return Err(format!("Step '{}' is incomplete: {}", status.current_step, status.errors.join("; ")));
+    }
+    state.set_step_complete(&status.current_step, true, now)?;
+    Ok(wizard_status(state, payload))
+}
+
+/// Return to the previous step; completed steps stay complete
+pub fn wizard_back(state: &mut WizardState, payload: &serde_json::Value, now: DateTime<Utc>) -> Result<WizardStatus, String> {
+    let status = wizard_status(state, payload);
+    if !status.can_go_back {
+        return Err("Already at the first step".to_string());
+    }
+    state.current_step = WIZARD_STEP_IDS[status.step_index - 1].to_string();
+    state.updated_at = Some(now);
+    Ok(wizard_status(state, payload))
+}
+
+/// Progress saved at `path`; a fresh state when the file doesn't exist yet
+pub fn load_wizard_state(path: &Path) -> Result<WizardState, String> {
+    match fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(WizardState::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+pub fn save_wizard_state(path: &Path, state: &WizardState) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let raw = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    fs::write(path, raw).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Record one step's completion in the state file at `path`
+pub fn save_wizard_progress(path: &Path, step_id: &str, is_complete: bool, now: DateTime<Utc>) -> Result<WizardState, String> {
+    let mut state = load_wizard_state(path)?;
+    state.set_step_complete(step_id, is_complete, now)?;
+    save_wizard_state(path, &state)?;
+    Ok(state)
+}
+
+/// Get wizard steps with completion taken from `state`
+pub fn get_wizard_steps(state: &WizardState) -> Vec<WizardStep> {
+    let mut steps = vec![
         WizardStep {
             id: "welcome".to_string(),
             title: "Welcome".to_string(),
@@ -67,23 +214,207 @@ pub fn get_wizard_steps() -> Vec<WizardStep> {
             is_complete: false,
             protocol_version: WIZARD_PROTOCOL_VERSION.to_string(),
         },
-    ]
+    ];
+    for step in steps.iter_mut() {
+        step.is_complete = state.is_complete(&step.id);
+    }
+    steps
 }
 
-/// Supported languages
-pub fn get_supported_languages() -> Vec<HashMap<String, String>> {
-    vec![
-        HashMap::from([
-            ("code".to_string(), "en".to_string()),
-            ("name".to_string(), "English".to_string()),
-            ("protocol_version".to_string(), WIZARD_PROTOCOL_VERSION.to_string()),
-        ]),
-        HashMap::from([
-            ("code".to_string(), "ru".to_string()),
-            ("name".to_string(), "Русский".to_string()),
-            ("protocol_version".to_string(), WIZARD_PROTOCOL_VERSION.to_string()),
-        ]),
-    ]
+// ============================================================================
+// Finalization
+// ============================================================================
+
+/// Security presets the wizard offers, from most to least restrictive.
+/// Each maps to (mode, approval risk threshold, isolation policy).
+pub const SECURITY_PRESETS: &[(&str, u8, &str)] = &[
+    ("safe", 1, "container"),
+    ("supervised", 3, "container"),
+    ("autonomous", 5, "subprocess"),
+];
+
+/// Everything the wizard collected, ready to become a `SynapseConfig`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WizardAnswers {
+    pub language: String,
+    pub llm_providers: Vec<LLMProviderConfig>,
+    /// Overrides for the default storage paths
+    #[serde(default)]
+    pub data_paths: HashMap<String, String>,
+    /// One of `SECURITY_PRESETS`; becomes the config's `mode`
+    pub security_preset: String,
+    /// Isolation chosen on the security step, replacing the preset's
+    #[serde(default)]
+    pub isolation_policy: Option<String>,
+}
+
+/// Build a full config from wizard answers, defaults filling the gaps.
+/// The result still needs `validate_config`.
+pub fn config_from_answers(answers: &WizardAnswers) -> Result<SynapseConfig, String> {
+    let (mode, risk_threshold, isolation) = SECURITY_PRESETS
+        .iter()
+        .find(|(name, _, _)| *name == answers.security_preset)
+        .ok_or_else(|| {
+            let names: Vec<&str> = SECURITY_PRESETS.iter().map(|(name, _, _)| *name).collect();
+            format!("Unknown security preset '{}'; expected one of {}", answers.security_preset, names.join(", "))
+        })?;
+
+    let mut config = SynapseConfig::default();
+    config.language = answers.language.clone();
+    config.mode = mode.to_string();
+    config.llm_providers = answers.llm_providers.clone();
+    config.data_paths.extend(answers.data_paths.clone());
+    config.security_settings.require_approval_for_risk = *risk_threshold;
+    config.security_settings.isolation_policy = answers.isolation_policy.clone().unwrap_or_else(|| isolation.to_string());
+    Ok(config)
+}
+
+// ============================================================================
+// Step Validation
+// ============================================================================
+
+/// Problems with one step's input, as "field: message"; empty when the
+/// step may be left. "review" re-runs every earlier step on the full config.
+pub fn validate_wizard_step(step_id: &str, payload: &serde_json::Value) -> Result<Vec<String>, String> {
+    match step_id {
+        "welcome" => Ok(Vec::new()),
+        "language" => Ok(validate_language(payload)),
+        "llm" => Ok(validate_llm(payload)),
+        "storage" => Ok(validate_storage(payload)),
+        "security" => Ok(validate_security(payload)),
+        "review" => Ok([validate_language(payload), validate_llm(payload), validate_storage(payload), validate_security(payload)].concat()),
+        other => Err(format!("Unknown wizard step '{}'; expected one of {}", other, WIZARD_STEP_IDS.join(", "))),
+    }
+}
+
+fn validate_language(payload: &serde_json::Value) -> Vec<String> {
+    let codes = supported_language_codes();
+    match payload.get("language").and_then(|v| v.as_str()) {
+        Some(code) if codes.contains(&code) => Vec::new(),
+        Some(code) => vec![format!("language: '{}' is not supported; expected one of {}", code, codes.join(", "))],
+        None => vec!["language: a language is required".to_string()],
+    }
+}
+
+fn validate_llm(payload: &serde_json::Value) -> Vec<String> {
+    let providers = match payload.get("llm_providers").and_then(|v| v.as_array()) {
+        Some(providers) if !providers.is_empty() => providers,
+        _ => return vec!["llm_providers: at least one provider is required".to_string()],
+    };
+    let text = |provider: &serde_json::Value, key: &str| {
+        provider.get(key).and_then(|v| v.as_str()).map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+    };
+
+    let mut errors = Vec::new();
+    for (i, provider) in providers.iter().enumerate() {
+        if text(provider, "model").is_none() {
+            errors.push(format!("llm_providers[{}].model: a model is required", i));
+        }
+        // Local Ollama and self-hosted OpenAI-compatible servers run without a key
+        let provider_type = text(provider, "provider_type");
+        if !matches!(provider_type.as_deref(), Some("ollama" | "custom_openai")) && text(provider, "api_key").is_none() {
+            errors.push(format!("llm_providers[{}].api_key: an API key is required", i));
+        }
+        if provider_type.as_deref() == Some("custom_openai") && text(provider, "base_url").is_none() {
+            errors.push(format!("llm_providers[{}].base_url: a base URL is required for OpenAI-compatible providers", i));
+        }
+    }
+    errors
+}
+
+/// Whether a file can be created in `dir`, or in its nearest existing
+/// ancestor when `dir` has yet to be created
+pub fn is_writable(dir: &Path) -> bool {
+    let mut existing: PathBuf = dir.to_path_buf();
+    while !existing.exists() {
+        if !existing.pop() {
+            return false;
+        }
+    }
+    existing.is_dir() && tempfile::tempfile_in(&existing).is_ok()
+}
+
+fn validate_storage(payload: &serde_json::Value) -> Vec<String> {
+    let paths = match payload.get("data_paths").and_then(|v| v.as_object()) {
+        Some(paths) if !paths.is_empty() => paths,
+        _ => return vec!["data_paths: at least one storage path is required".to_string()],
+    };
+
+    let mut keys: Vec<&String> = paths.keys().collect();
+    keys.sort();
+    let mut errors = Vec::new();
+    for key in keys {
+        let field = format!("data_paths.{}", key);
+        let raw = match paths[key].as_str().map(str::trim) {
+            Some(raw) if !raw.is_empty() => raw,
+            _ => {
+                errors.push(format!("{}: a path is required", field));
+                continue;
+            }
+        };
+        match crate::commands::expand_path_template(raw, &|name| std::env::var(name).ok()) {
+            Ok(expanded) if is_writable(Path::new(&expanded)) => {}
+            Ok(expanded) => errors.push(format!("{}: '{}' is not writable", field, expanded)),
+            Err(e) => errors.push(format!("{}: {}", field, e)),
+        }
+    }
+    errors
+}
+
+fn validate_security(payload: &serde_json::Value) -> Vec<String> {
+    let settings = payload.get("security_settings").unwrap_or(payload);
+    match settings.get("isolation_policy").and_then(|v| v.as_str()) {
+        Some(policy) if ISOLATION_POLICIES.contains(&policy) => Vec::new(),
+        Some(policy) => vec![format!(
+            "security_settings.isolation_policy: unknown policy '{}'; expected one of {}",
+            policy,
+            ISOLATION_POLICIES.join(", ")
+        )],
+        None => vec!["security_settings.isolation_policy: an isolation policy is required".to_string()],
+    }
+}
+
+/// A language the UI can be shown in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Language {
+    /// ISO 639-1 code stored in `SynapseConfig.language`
+    pub code: String,
+    /// English name
+    pub name: String,
+    /// Name in the language itself
+    pub native_name: String,
+    /// Written right to left; the frontend mirrors its layout
+    pub rtl: bool,
+    pub protocol_version: String,
+}
+
+/// (code, name, native name, rtl) for every supported language
+const LANGUAGES: &[(&str, &str, &str, bool)] = &[
+    ("en", "English", "English", false),
+    ("ru", "Russian", "Русский", false),
+    ("de", "German", "Deutsch", false),
+    ("es", "Spanish", "Español", false),
+    ("fr", "French", "Français", false),
+    ("zh", "Chinese", "中文", false),
+];
+
+/// Supported languages; the canonical list config validation checks against
+pub fn get_supported_languages() -> Vec<Language> {
+    LANGUAGES
+        .iter()
+        .map(|(code, name, native_name, rtl)| Language {
+            code: code.to_string(),
+            name: name.to_string(),
+            native_name: native_name.to_string(),
+            rtl: *rtl,
+            protocol_version: WIZARD_PROTOCOL_VERSION.to_string(),
+        })
+        .collect()
+}
+
+/// Codes of the supported languages, in display order
+pub fn supported_language_codes() -> Vec<&'static str> {
+    LANGUAGES.iter().map(|(code, ..)| *code).collect()
 }
 
 /// Supported LLM providers
@@ -107,5 +438,120 @@ pub fn get_supported_llm_providers() -> Vec<HashMap<String, String>> {
             ("models".to_string(), "llama3,mistral,codellama".to_string()),
             ("protocol_version".to_string(), WIZARD_PROTOCOL_VERSION.to_string()),
         ]),
+        HashMap::from([
+            ("id".to_string(), "custom_openai".to_string()),
+            ("name".to_string(), "OpenAI-compatible (Custom)".to_string()),
+            ("models".to_string(), String::new()),
+            ("note".to_string(), "Enter the model name manually and the server's base URL".to_string()),
+            ("protocol_version".to_string(), WIZARD_PROTOCOL_VERSION.to_string()),
+        ]),
     ]
 }
+
+/// Built-in model names for a provider, used when it can't be asked live
+pub fn static_models(provider_type: &str) -> Vec<String> {
+    get_supported_llm_providers()
+        .into_iter()
+        .find(|p| p.get("id").map(String::as_str) == Some(provider_type))
+        .and_then(|p| p.get("models").cloned())
+        .map(|models| models.split(',').filter(|m| !m.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Models offered by the LLM step's dropdown
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WizardModelList {
+    pub provider_type: String,
+    pub models: Vec<String>,
+    /// "live" when listed by the provider, "static" for the built-in list
+    pub source: String,
+    /// Why the live list couldn't be used, shown next to the dropdown
+    pub error: Option<String>,
+    pub protocol_version: String,
+}
+
+/// Prefer the models a probe listed; fall back to the static list when the
+/// probe failed or the provider can't list models
+pub fn model_list_from_probe(provider_type: &str, probe: Result<&ConnectionProbe, &str>) -> WizardModelList {
+    let (models, source, error) = match probe {
+        Ok(probe) if probe.connected && probe.models_listable && !probe.available_models.is_empty() => {
+            (probe.available_models.clone(), "live", None)
+        }
+        Ok(probe) if !probe.connected => (static_models(provider_type), "static", Some(probe.message.clone())),
+        Ok(_) => (static_models(provider_type), "static", Some("Provider does not list its models".to_string())),
+        Err(e) => (static_models(provider_type), "static", Some(e.to_string())),
+    };
+
+    WizardModelList {
+        provider_type: provider_type.to_string(),
+        models,
+        source: source.to_string(),
+        error,
+        protocol_version: WIZARD_PROTOCOL_VERSION.to_string(),
+    }
+}
+
+/// Live model lists fetched this session, keyed by provider, endpoint and
+/// key fingerprint so changed credentials are probed again
+#[derive(Default)]
+pub struct ModelListCache {
+    entries: HashMap<String, WizardModelList>,
+}
+
+impl ModelListCache {
+    pub fn key(provider_type: &str, base_url: Option<&str>, api_key: &str) -> String {
+        format!("{}|{}|{}", provider_type, base_url.unwrap_or(""), crate::security::key_fingerprint(api_key))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&WizardModelList> {
+        self.entries.get(key)
+    }
+
+    /// Remember a list; only live lists are kept so failures are retried
+    pub fn insert(&mut self, key: String, list: &WizardModelList) {
+        if list.source == "live" {
+            self.entries.insert(key, list.clone());
+        }
+    }
+}
+
+pub fn model_list_cache() -> &'static Mutex<ModelListCache> {
+    static CACHE: OnceLock<Mutex<ModelListCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(ModelListCache::default()))
+}
+
+/// Isolation policy option offered by the security step
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IsolationOption {
+    pub policy: String,
+    pub selectable: bool,
+    pub reason: String,
+    pub protocol_version: String,
+}
+
+/// Isolation policies for the security step, disabling those the host can't enforce
+pub fn get_isolation_options(backends: &[IsolationBackendStatus]) -> Vec<IsolationOption> {
+    ISOLATION_POLICIES
+        .iter()
+        .map(|policy| {
+            let selectable = is_policy_available(policy, backends);
+            let reason = if selectable {
+                "Available".to_string()
+            } else {
+                backends
+                    .iter()
+                    .filter(|b| b.isolation_type == *policy)
+                    .map(|b| b.message.clone())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            };
+
+            IsolationOption {
+                policy: policy.to_string(),
+                selectable,
+                reason,
+                protocol_version: WIZARD_PROTOCOL_VERSION.to_string(),
+            }
+        })
+        .collect()
+}
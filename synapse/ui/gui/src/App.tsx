@@ -2,12 +2,19 @@ import React, { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
 
 // Types
+interface ApiError {
+  code: 'not_found' | 'validation' | 'unauthorized' | 'rate_limited' | 'upstream' | 'internal' | 'conflict' | 'quota_exceeded' | 'offline'
+  message: string
+}
+
 interface ApiResponse {
   protocol_version: string
   spec_version: string
+  // Quote this in bug reports to find the matching backend log lines
+  request_id: string
   success: boolean
   data?: any
-  error?: string
+  error?: ApiError
 }
 
 interface SkillInfo {
@@ -141,7 +148,7 @@ const SkillsPage: React.FC = () => {
       try {
         const response = await invoke<ApiResponse>('get_skills')
         if (response.success && response.data) {
-          setSkills(response.data)
+          setSkills(response.data.items)
         }
       } catch (error) {
         console.error('Failed to fetch skills:', error)
@@ -158,7 +165,7 @@ const SkillsPage: React.FC = () => {
       // Refresh skills
       const response = await invoke<ApiResponse>('get_skills')
       if (response.success && response.data) {
-        setSkills(response.data)
+        setSkills(response.data.items)
       }
     } catch (error) {
       console.error('Failed to approve skill:', error)
@@ -247,9 +254,29 @@ const WizardPage: React.FC = () => {
     language: 'en',
     llmProvider: 'openai',
     apiKey: '',
+    baseUrl: '',
     model: 'gpt-4o',
     mode: 'supervised',
   })
+  const [models, setModels] = useState<string[]>(['gpt-4o', 'gpt-4-turbo', 'gpt-3.5-turbo'])
+  const [modelsError, setModelsError] = useState<string | null>(null)
+
+  // Live list from the provider when the credentials work, else the built-in one
+  const loadModels = async (providerType: string, apiKey: string, baseUrl: string) => {
+    try {
+      const response = await invoke<ApiResponse>('get_wizard_models', { providerType, apiKey, baseUrl: baseUrl || null })
+      if (response.success && response.data) {
+        const listed: string[] = response.data.models
+        setModels(listed)
+        setModelsError(response.data.error ?? null)
+        setConfig((current) => (listed.includes(current.model) ? current : { ...current, model: listed[0] ?? '' }))
+      } else {
+        setModelsError(`${response.error?.message ?? 'Failed to load models'} (request ${response.request_id})`)
+      }
+    } catch (error) {
+      setModelsError(String(error))
+    }
+  }
 
   const steps = [
     { title: 'Welcome', description: 'Welcome to Synapse Configurator' },
@@ -309,6 +336,10 @@ const WizardPage: React.FC = () => {
               >
                 <option value="en">English</option>
                 <option value="ru">Русский</option>
+                <option value="de">Deutsch</option>
+                <option value="es">Español</option>
+                <option value="fr">Français</option>
+                <option value="zh">中文</option>
               </select>
             </div>
           )}
@@ -319,12 +350,29 @@ const WizardPage: React.FC = () => {
               <select 
                 className="input"
                 value={config.llmProvider}
-                onChange={(e) => setConfig({ ...config, llmProvider: e.target.value })}
+                onChange={(e) => {
+                  setConfig({ ...config, llmProvider: e.target.value })
+                  loadModels(e.target.value, config.apiKey, config.baseUrl)
+                }}
               >
                 <option value="openai">OpenAI</option>
                 <option value="anthropic">Anthropic</option>
                 <option value="ollama">Ollama (Local)</option>
+                <option value="custom_openai">OpenAI-compatible (Custom)</option>
               </select>
+
+              {config.llmProvider === 'custom_openai' && (
+                <>
+                  <label className="label" style={{ marginTop: '16px' }}>Base URL</label>
+                  <input
+                    className="input"
+                    value={config.baseUrl}
+                    onChange={(e) => setConfig({ ...config, baseUrl: e.target.value })}
+                    onBlur={() => loadModels(config.llmProvider, config.apiKey, config.baseUrl)}
+                    placeholder="http://localhost:8000/v1"
+                  />
+                </>
+              )}
               
               <label className="label" style={{ marginTop: '16px' }}>API Key</label>
               <input 
@@ -332,8 +380,33 @@ const WizardPage: React.FC = () => {
                 className="input"
                 value={config.apiKey}
                 onChange={(e) => setConfig({ ...config, apiKey: e.target.value })}
+                onBlur={() => loadModels(config.llmProvider, config.apiKey, config.baseUrl)}
                 placeholder="Enter your API key"
               />
+
+              <label className="label" style={{ marginTop: '16px' }}>Model</label>
+              {models.length === 0 ? (
+                // Servers that can't list their models take a typed name
+                <input
+                  className="input"
+                  value={config.model}
+                  onChange={(e) => setConfig({ ...config, model: e.target.value })}
+                  placeholder="Enter the model name"
+                />
+              ) : (
+                <select 
+                  className="input"
+                  value={config.model}
+                  onChange={(e) => setConfig({ ...config, model: e.target.value })}
+                >
+                  {models.map((model) => (
+                    <option key={model} value={model}>{model}</option>
+                  ))}
+                </select>
+              )}
+              {modelsError && (
+                <p style={{ color: '#DC2626', fontSize: '0.875rem', marginTop: '8px' }}>{modelsError}</p>
+              )}
             </div>
           )}
 
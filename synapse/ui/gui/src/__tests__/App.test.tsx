@@ -34,19 +34,22 @@ describe('App', () => {
             protocol_version: '1.0',
             spec_version: '3.1',
             success: true,
-            data: [
-              {
-                id: 'skill-001',
-                name: 'read_file',
-                version: '1.0.0',
-                status: 'active',
-                trust_level: 'trusted',
-                risk_level: 1,
-                isolation_type: 'subprocess',
-                required_capabilities: ['fs:read'],
-                created_at: '2026-02-20T00:00:00Z',
-              },
-            ],
+            data: {
+              skills: [
+                {
+                  id: 'skill-001',
+                  name: 'read_file',
+                  version: '1.0.0',
+                  status: 'active',
+                  trust_level: 'trusted',
+                  risk_level: 1,
+                  isolation_type: 'subprocess',
+                  required_capabilities: ['fs:read'],
+                  created_at: '2026-02-20T00:00:00Z',
+                },
+              ],
+              total_count: 1,
+            },
           }
         case 'get_security_settings':
           return {
@@ -191,30 +194,33 @@ describe('Skills Page', () => {
           protocol_version: '1.0',
           spec_version: '3.1',
           success: true,
-          data: [
-            {
-              id: 'skill-001',
-              name: 'read_file',
-              version: '1.0.0',
-              status: 'active',
-              trust_level: 'trusted',
-              risk_level: 1,
-              isolation_type: 'subprocess',
-              required_capabilities: ['fs:read'],
-              created_at: '2026-02-20T00:00:00Z',
-            },
-            {
-              id: 'skill-002',
-              name: 'write_file',
-              version: '1.0.0',
-              status: 'pending',
-              trust_level: 'unverified',
-              risk_level: 3,
-              isolation_type: 'container',
-              required_capabilities: ['fs:write'],
-              created_at: '2026-02-20T00:00:00Z',
-            },
-          ],
+          data: {
+            skills: [
+              {
+                id: 'skill-001',
+                name: 'read_file',
+                version: '1.0.0',
+                status: 'active',
+                trust_level: 'trusted',
+                risk_level: 1,
+                isolation_type: 'subprocess',
+                required_capabilities: ['fs:read'],
+                created_at: '2026-02-20T00:00:00Z',
+              },
+              {
+                id: 'skill-002',
+                name: 'write_file',
+                version: '1.0.0',
+                status: 'pending',
+                trust_level: 'unverified',
+                risk_level: 3,
+                isolation_type: 'container',
+                required_capabilities: ['fs:write'],
+                created_at: '2026-02-20T00:00:00Z',
+              },
+            ],
+            total_count: 2,
+          },
         }
       }
       return { protocol_version: '1.0', success: true, data: null }
@@ -236,19 +242,22 @@ describe('Skills Page', () => {
           protocol_version: '1.0',
           spec_version: '3.1',
           success: true,
-          data: [
-            {
-              id: 'skill-002',
-              name: 'write_file',
-              version: '1.0.0',
-              status: 'pending',
-              trust_level: 'unverified',
-              risk_level: 3,
-              isolation_type: 'container',
-              required_capabilities: ['fs:write'],
-              created_at: '2026-02-20T00:00:00Z',
-            },
-          ],
+          data: {
+            skills: [
+              {
+                id: 'skill-002',
+                name: 'write_file',
+                version: '1.0.0',
+                status: 'pending',
+                trust_level: 'unverified',
+                risk_level: 3,
+                isolation_type: 'container',
+                required_capabilities: ['fs:write'],
+                created_at: '2026-02-20T00:00:00Z',
+              },
+            ],
+            total_count: 1,
+          },
         }
       }
       return { protocol_version: '1.0', success: true, data: null }